@@ -5,9 +5,6 @@ use quote::quote;
 use darling::FromMeta;
 use crate::utils;
 
-/// Default user agent for automate bots
-const USER_AGENT: &str = concat!("DiscordBot (https://github.com/mbenoukaiss/automate, ", env!("CARGO_PKG_VERSION"), ")");
-
 /// Generates the code that should build
 /// the route URL based on the parameters.
 
@@ -22,19 +19,6 @@ fn generate_request(item: &ItemFn, args: Args, major_parameter: TokenStream2) ->
     let body = args.body();
     let status = args.status;
 
-    // hyper does not set content-length to 0 when the body is
-    // empty and method is POST, PUT or PATCH, but discord
-    // requires a content-length
-    let zero_content_length = match method.to_string().as_str() {
-        "POST" | "PUT" | "PATCH" if !args.multipart => Some(quote! {
-            match ::hyper::body::HttpBody::size_hint(&#body).exact() {
-                Some(0) | None => request = request.header("Content-Length", 0),
-                _ => ()
-            };
-        }),
-        _ => None
-    };
-
     let return_value = if args.empty {
         quote!(Ok(()))
     } else if cfg!(feature = "trace-endpoints") {
@@ -57,70 +41,26 @@ fn generate_request(item: &ItemFn, args: Args, major_parameter: TokenStream2) ->
     };
 
     Ok(quote! {
-        use tokio::sync::RwLock;
-        use rate_limit::{Key, Bucket, BUCKETS};
-
-        ::lazy_static::lazy_static! {
-            static ref BUCKET_ID: RwLock<Option<String>> = RwLock::default();
-        }
-
-        if let Some(bucket_id) = BUCKET_ID.read().await.as_ref() {
-            if let Some(bucket) = BUCKETS.lock().await.get(&Key::lookup(&self.token, &bucket_id, #major_parameter)) {
-                trace!("Endpoint {}#{} allows for {} more calls (limit {})", stringify!(#fn_name), bucket.id, bucket.remaining, bucket.limit);
-
-                if bucket.remaining == 0 && ::chrono::Utc::now().naive_utc() < bucket.reset {
-                    return Error::rate_limited(stringify!(#fn_name), bucket.reset, true);
-                }
-            }
-        }
-
-        let uri = #uri;
-
-        let mut request = ::hyper::Request::builder()
-            .uri(uri.clone())
-            .method(::hyper::Method::#method)
-            .header("Content-Type", #content_type)
-            .header("Authorization", &self.token)
-            .header("User-Agent", #USER_AGENT)
-            .header("X-RateLimit-Precision", "millisecond");
-
-        #zero_content_length
-
-        let response = self.client.request(request.body(#body).unwrap()).await?;
-
-        if let Some(bucket) = Bucket::new(response.headers())? {
-            BUCKET_ID.write().await.replace(bucket.id.clone());
-            BUCKETS.lock().await.insert(Key::insert(self.token.clone(), bucket.id.clone(), #major_parameter), bucket);
-        }
+        let response = self.send(request::ApiRequest {
+            #[cfg(feature = "metrics")]
+            endpoint: stringify!(#fn_name),
+            route: stringify!(#fn_name),
+            major: #major_parameter,
+            method: ::hyper::Method::#method,
+            uri: #uri,
+            content_type: #content_type,
+            body: #body,
+        }).await?;
 
         let code = response.status().as_u16();
 
         match code {
             #status => #return_value,
-            400 => Error::http(format!("Bad request (endpoint `̀{}`)", stringify!(#fn_name))),
             401 => Error::invalid_token(stringify!(#fn_name), &self.token),
-            403 => Error::no_permission(stringify!(#fn_name), &self.token),
-            404 => Error::http(format!("Endpoint  ̀{}` not found", stringify!(#fn_name))),
-            405 => Error::http(format!("Method {} not allowed (endpoint `̀{}`)", stringify!(#method), stringify!(#fn_name))),
-            429 => {
-                use chrono::NaiveDateTime;
-
-                let reset = {
-                    let reset = response.headers().get("x-ratelimit-reset").unwrap().to_str().unwrap();
-                    let mut split_reset = reset.split('.');
-                    let secs = split_reset.next().unwrap().parse::<i64>().unwrap();
-
-                    if let Some(m) = split_reset.next() {
-                        NaiveDateTime::from_timestamp(secs, m.parse::<u32>().unwrap() * 1_000_000)
-                    } else {
-                        NaiveDateTime::from_timestamp(secs, 0)
-                    }
-                };
-
-                Error::rate_limited(stringify!(#fn_name), reset, false)
+            _ => {
+                let body = ::hyper::body::to_bytes(response.into_body()).await.unwrap_or_default();
+                Error::http_response(stringify!(#fn_name), code, &body)
             },
-            502 => Error::http(format!("Gateway unavailable (endpoint `̀{}`)", stringify!(#fn_name))),
-            _ => Error::http(format!("Expected status code {}, got {} when requesting {}", #status, code, uri)),
         }
     })
 }
@@ -171,10 +111,16 @@ impl Args {
     }
 
     fn route(&self) -> TokenStream2 {
-        let mut quote = quote!(let mut route = String::from("https://discordapp.com/api/v8"););
+        let mut quote = quote!(let mut route = self.base_url.clone(););
 
-        for part in self.route.split(&['{', '}'][..]) {
-            if part.starts_with('#') {
+        //parts at odd indices were surrounded by curly braces
+        for (i, part) in self.route.split(&['{', '}'][..]).enumerate() {
+            if i % 2 == 0 {
+                quote = quote! {
+                    #quote
+                    route.push_str(#part);
+                };
+            } else if part.starts_with('#') {
                 let part = Ident::new(part.strip_prefix('#').unwrap(), Span::call_site());
 
                 quote = quote! {
@@ -190,6 +136,8 @@ impl Args {
                     ::automate::encode::WriteUrl::write_url(#part, &mut route)?;
                 };
             } else {
+                let part: syn::Expr = syn::parse_str(part).expect("Invalid expression in route");
+
                 quote = quote! {
                     #quote
                     ::std::fmt::Write::write_fmt(&mut route, format_args!("{}", #part)).expect("Failed to write api string");
@@ -204,13 +152,13 @@ impl Args {
         match self.body.as_ref() {
             Some(body) if self.multipart => {
                 let body = Ident::new(body, Span::call_site());
-                quote!(::hyper::Body::from(#body))
+                quote!(::bytes::Bytes::from(#body))
             },
             Some(body) => {
                 let body = Ident::new(body, Span::call_site());
-                quote!(::hyper::Body::from(serde_json::to_string(&#body)?))
+                quote!(::bytes::Bytes::from(serde_json::to_string(&#body)?))
             },
-            None => quote!(::hyper::Body::empty()),
+            None => quote!(::bytes::Bytes::new()),
         }
     }
 
@@ -270,7 +218,7 @@ impl Args {
 ///        }
 ///    }
 ///
-///    #[endpoint(get, route = "/channels/{#channel}/messages/{#message}/reactions/{+emoji}/{query}", status = 200)]
+///    #[endpoint(get, route = "/channels/{#channel}/messages/{#message}/reactions/{+emoji}{query}", status = 200)]
 ///    pub async fn reactions<S: ExtractSnowflake, U: WriteUrl>(&self, channel: S, message: S, emoji: &U, reactions: ReactionsPosition) -> Result<Vec<User>, Error> {
 ///        let query = match reactions {
 ///            ReactionsPosition::Default => String::new(),
@@ -301,13 +249,28 @@ pub fn endpoint(metadata: TokenStream, item: TokenStream) -> TokenStream {
         }
     }
 
+    let method = unwrap!(args.method());
     let request = unwrap!(generate_request(&input, args, major_parameter));
+    let fn_name = &input.sig.ident;
 
     TokenStream::from(quote! {
         #[allow(unused_variables)]
         #visibility #signature {
             #(#content)*
-            #request
+
+            let request = async move {
+                #request
+            };
+
+            #[cfg(feature = "tracing")]
+            let request = ::tracing::Instrument::instrument(request, ::tracing::debug_span!(
+                "http",
+                endpoint = stringify!(#fn_name),
+                method = stringify!(#method),
+                status = ::tracing::field::Empty,
+            ));
+
+            request.await
         }
     })
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use proc_macro2::{TokenStream as TokenStream2, Ident, Span};
-use syn::{ItemFn, AttributeArgs, FnArg, Receiver};
+use syn::{ItemFn, AttributeArgs, FnArg, Receiver, ReturnType};
 use quote::quote;
 use darling::FromMeta;
 use crate::utils;
@@ -13,6 +13,12 @@ fn infer_event_type(dispatch_type: &str) -> Option<&'static str> {
         t if t.contains("ChannelUpdateDispatch") => Some("ChannelUpdate"),
         t if t.contains("ChannelDeleteDispatch") => Some("ChannelDelete"),
         t if t.contains("ChannelPinsUpdateDispatch") => Some("ChannelPinsUpdate"),
+        t if t.contains("ThreadCreateDispatch") => Some("ThreadCreate"),
+        t if t.contains("ThreadUpdateDispatch") => Some("ThreadUpdate"),
+        t if t.contains("ThreadDeleteDispatch") => Some("ThreadDelete"),
+        t if t.contains("ThreadListSyncDispatch") => Some("ThreadListSync"),
+        t if t.contains("ThreadMemberUpdateDispatch") => Some("ThreadMemberUpdate"),
+        t if t.contains("ThreadMembersUpdateDispatch") => Some("ThreadMembersUpdate"),
         t if t.contains("GuildCreateDispatch") => Some("GuildCreate"),
         t if t.contains("GuildUpdateDispatch") => Some("GuildUpdate"),
         t if t.contains("GuildDeleteDispatch") => Some("GuildDelete"),
@@ -20,6 +26,16 @@ fn infer_event_type(dispatch_type: &str) -> Option<&'static str> {
         t if t.contains("GuildBanRemoveDispatch") => Some("GuildBanRemove"),
         t if t.contains("GuildEmojisUpdateDispatch") => Some("GuildEmojisUpdate"),
         t if t.contains("GuildIntegrationsUpdateDispatch") => Some("GuildIntegrationsUpdate"),
+        t if t.contains("GuildScheduledEventCreateDispatch") => Some("GuildScheduledEventCreate"),
+        t if t.contains("GuildScheduledEventUpdateDispatch") => Some("GuildScheduledEventUpdate"),
+        t if t.contains("GuildScheduledEventDeleteDispatch") => Some("GuildScheduledEventDelete"),
+        t if t.contains("GuildScheduledEventUserAddDispatch") => Some("GuildScheduledEventUserAdd"),
+        t if t.contains("GuildScheduledEventUserRemoveDispatch") => Some("GuildScheduledEventUserRemove"),
+        t if t.contains("AutoModerationRuleCreateDispatch") => Some("AutoModerationRuleCreate"),
+        t if t.contains("AutoModerationRuleUpdateDispatch") => Some("AutoModerationRuleUpdate"),
+        t if t.contains("AutoModerationRuleDeleteDispatch") => Some("AutoModerationRuleDelete"),
+        t if t.contains("AutoModerationActionExecutionDispatch") => Some("AutoModerationActionExecution"),
+        t if t.contains("InteractionCreateDispatch") => Some("InteractionCreate"),
         t if t.contains("GuildMemberAddDispatch") => Some("GuildMemberAdd"),
         t if t.contains("GuildMemberRemoveDispatch") => Some("GuildMemberRemove"),
         t if t.contains("GuildMemberUpdateDispatch") => Some("GuildMemberUpdate"),
@@ -41,11 +57,38 @@ fn infer_event_type(dispatch_type: &str) -> Option<&'static str> {
         t if t.contains("VoiceStateUpdateDispatch") => Some("VoiceStateUpdate"),
         t if t.contains("VoiceServerUpdateDispatch") => Some("VoiceServerUpdate"),
         t if t.contains("WebhooksUpdateDispatch") => Some("WebhooksUpdate"),
+        t if t.contains("RawDispatch") => Some("Raw"),
+        t if t.contains("CacheResyncDispatch") => Some("CacheResync"),
         _ => None
     }
 }
 
-fn adapt_method(item: &ItemFn, rcv: &Receiver, arguments: (&Ident, &Ident), event: String) -> TokenStream {
+/// The priority given in the attribute arguments
+/// or the default priority.
+fn priority(args: &Args) -> TokenStream2 {
+    match args.priority {
+        Some(priority) => quote!(#priority),
+        None => quote!(::automate::events::DEFAULT_PRIORITY),
+    }
+}
+
+/// Wraps the body of the listener to convert the value it
+/// returns to an `EventFlow`, allowing listeners to return
+/// either `Result<(), Error>` or `Result<EventFlow, Error>`.
+fn wrap_content(item: &ItemFn) -> TokenStream2 {
+    let content = &item.block;
+    let output = match &item.sig.output {
+        ReturnType::Type(_, ty) => quote!(#ty),
+        ReturnType::Default => quote!(Result<(), Error>),
+    };
+
+    quote! {
+        let result: #output = async move #content.await;
+        result.map(::automate::events::EventFlow::from)
+    }
+}
+
+fn adapt_method(item: &ItemFn, rcv: &Receiver, arguments: (&Ident, &Ident), event: String, priority: TokenStream2) -> TokenStream {
     let (ctx_name, data_name) = arguments;
 
     //TODO: don't hardcode this
@@ -58,7 +101,7 @@ fn adapt_method(item: &ItemFn, rcv: &Receiver, arguments: (&Ident, &Ident), even
     let func = &item.sig.ident;
     let reg_name = Ident::new(&format!("__register_{}", item.sig.ident), Span::call_site());
     let dispatch = Ident::new(&format!("{}Dispatch", event), Span::call_site());
-    let content = &item.block;
+    let content = wrap_content(item);
 
     let event = if rcv.mutability.is_some() {
         Ident::new(&format!("{}Mut", event), Span::call_site())
@@ -68,10 +111,10 @@ fn adapt_method(item: &ItemFn, rcv: &Receiver, arguments: (&Ident, &Ident), even
 
     let quote = quote! {
         //generate an instance of ListenerType struct for registering
-        const #reg_name: ::automate::events::StatefulListener<Self> = ::automate::events::StatefulListener::#event(Self::#func);
+        const #reg_name: ::automate::events::StatefulListener<Self> = ::automate::events::StatefulListener::#event(Self::#func, #priority);
 
         //wrapping the function to remove the async and make it compatible with fn pointer by returning a pin
-        fn #func<'a>(#self_tokens, #ctx_name: &'a Context, #data_name: &'a #dispatch) -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<(), Error>> + Send + 'a>> {
+        fn #func<'a>(#self_tokens, #ctx_name: &'a Context, #data_name: &'a #dispatch) -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<::automate::events::EventFlow, Error>> + Send + 'a>> {
             Box::pin(async move {
                 #content
             })
@@ -81,21 +124,21 @@ fn adapt_method(item: &ItemFn, rcv: &Receiver, arguments: (&Ident, &Ident), even
     quote.into()
 }
 
-fn adapt_function(item: &ItemFn, arguments: (&Ident, &Ident), event: String) -> TokenStream {
+fn adapt_function(item: &ItemFn, arguments: (&Ident, &Ident), event: String, priority: TokenStream2) -> TokenStream {
     let (ctx_name, data_name) = arguments;
 
     let func = &item.sig.ident;
     let reg_name = Ident::new(&format!("__register_{}", item.sig.ident), Span::call_site());
     let dispatch = Ident::new(&format!("{}Dispatch", event), Span::call_site());
     let event = Ident::new(&event, Span::call_site());
-    let content = &item.block;
+    let content = wrap_content(item);
 
     let quote = quote! {
         //generate an instance of ListenerType struct for registering
-        const #reg_name: ::automate::events::ListenerType = ::automate::events::ListenerType::#event(#func);
+        const #reg_name: ::automate::events::ListenerType = ::automate::events::ListenerType::#event(#func, #priority);
 
         //wrapping the function to remove the async and make it compatible with fn pointer by returning a pin
-        fn #func<'a>(#ctx_name: &'a Context, #data_name: &'a #dispatch) -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<(), Error>> + Send + 'a>> {
+        fn #func<'a>(#ctx_name: &'a Context, #data_name: &'a #dispatch) -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<::automate::events::EventFlow, Error>> + Send + 'a>> {
             Box::pin(async move {
                 #content
             })
@@ -112,10 +155,10 @@ struct Args {
     /// The name of the event, not in use anymore.
     #[darling(default)]
     event: Option<String>,
-    /// The priority of the event. Not currently
-    /// in use.
+    /// The priority of the listener, listeners
+    /// with a higher priority are called first.
     #[darling(default)]
-    _priority: Option<u8>,
+    priority: Option<u8>,
 }
 
 pub fn listener(metadata: TokenStream, item: TokenStream) -> TokenStream {
@@ -167,8 +210,8 @@ pub fn listener(metadata: TokenStream, item: TokenStream) -> TokenStream {
             compile_error!(rcv, "Listener methods must take self by reference")
         }
 
-        adapt_method(&input, rcv, (ctx_name, data_name), event.unwrap())
+        adapt_method(&input, rcv, (ctx_name, data_name), event.unwrap(), priority(&args))
     } else {
-        adapt_function(&input, (ctx_name, data_name), event.unwrap())
+        adapt_function(&input, (ctx_name, data_name), event.unwrap(), priority(&args))
     }
 }
\ No newline at end of file
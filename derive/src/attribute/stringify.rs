@@ -20,7 +20,7 @@ pub fn stringify(metadata: TokenStream, item: TokenStream) -> TokenStream {
     };
 
     let mut output: TokenStream2 = quote!(#[derive(Clone, Debug, Serialize, Deserialize)]);
-    output.extend(quote!(#[serde(rename_all(deserialize = #serde_case))]));
+    output.extend(quote!(#[serde(rename_all = #serde_case)]));
     item.to_tokens(&mut output);
 
     TokenStream::from(output)
@@ -9,6 +9,12 @@ static EVENTS_LIST: &[(&str, &str)] = &[
     ("channel_update", "ChannelUpdateDispatch"),
     ("channel_delete", "ChannelDeleteDispatch"),
     ("channel_pins_update", "ChannelPinsUpdateDispatch"),
+    ("thread_create", "ThreadCreateDispatch"),
+    ("thread_update", "ThreadUpdateDispatch"),
+    ("thread_delete", "ThreadDeleteDispatch"),
+    ("thread_list_sync", "ThreadListSyncDispatch"),
+    ("thread_member_update", "ThreadMemberUpdateDispatch"),
+    ("thread_members_update", "ThreadMembersUpdateDispatch"),
     ("guild_create", "GuildCreateDispatch"),
     ("guild_update", "GuildUpdateDispatch"),
     ("guild_delete", "GuildDeleteDispatch"),
@@ -16,6 +22,16 @@ static EVENTS_LIST: &[(&str, &str)] = &[
     ("guild_ban_remove", "GuildBanRemoveDispatch"),
     ("guild_emojis_update", "GuildEmojisUpdateDispatch"),
     ("guild_integrations_update", "GuildIntegrationsUpdateDispatch"),
+    ("guild_scheduled_event_create", "GuildScheduledEventCreateDispatch"),
+    ("guild_scheduled_event_update", "GuildScheduledEventUpdateDispatch"),
+    ("guild_scheduled_event_delete", "GuildScheduledEventDeleteDispatch"),
+    ("guild_scheduled_event_user_add", "GuildScheduledEventUserAddDispatch"),
+    ("guild_scheduled_event_user_remove", "GuildScheduledEventUserRemoveDispatch"),
+    ("auto_moderation_rule_create", "AutoModerationRuleCreateDispatch"),
+    ("auto_moderation_rule_update", "AutoModerationRuleUpdateDispatch"),
+    ("auto_moderation_rule_delete", "AutoModerationRuleDeleteDispatch"),
+    ("auto_moderation_action_execution", "AutoModerationActionExecutionDispatch"),
+    ("interaction_create", "InteractionCreateDispatch"),
     ("guild_member_add", "GuildMemberAddDispatch"),
     ("guild_member_remove", "GuildMemberRemoveDispatch"),
     ("guild_member_update", "GuildMemberUpdateDispatch"),
@@ -39,65 +55,105 @@ static EVENTS_LIST: &[(&str, &str)] = &[
     ("voice_state_update", "VoiceStateUpdateDispatch"),
     ("voice_server_update", "VoiceServerUpdateDispatch"),
     ("webhooks_update", "WebhooksUpdateDispatch"),
+    ("raw", "RawDispatch"),
+    ("cache_resync", "CacheResyncDispatch"),
 ];
 
-fn events_list() -> (Vec<Ident>, Vec<Ident>, Vec<Ident>, Vec<Ident>) {
+fn events_list() -> (Vec<Ident>, Vec<Ident>, Vec<Ident>) {
     let functions = EVENTS_LIST.iter()
         .map(|(f, _)| Ident::new(&format!("on_{}", f), Span::call_site()))
         .collect::<Vec<Ident>>();
 
-    let immutables = EVENTS_LIST.iter()
+    let events = EVENTS_LIST.iter()
         .map(|(f, _)| Ident::new(f, Span::call_site()))
         .collect::<Vec<Ident>>();
 
-    let mutables = EVENTS_LIST.iter()
-        .map(|(f, _)| Ident::new(&format!("{}_mut", f), Span::call_site()))
-        .collect::<Vec<Ident>>();
-
     let dispatches = EVENTS_LIST.iter()
             .map(|(_, d)| Ident::new(d, Span::call_site()))
             .collect::<Vec<Ident>>();
     
-    (functions, immutables, mutables, dispatches)
+    (functions, events, dispatches)
 }
 
 pub fn state(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input);
 
     let name = input.ident.clone();
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
 
-    let methods_storage_name = Ident::new(&format!("__methods_{}", input.ident).to_uppercase(), Span::call_site());
-    let (functions, immutables, mutables, dispatches) = events_list();
-    
-    TokenStream::from(quote! {
-        ::automate::lazy_static::lazy_static! {
-            static ref #methods_storage_name: ::automate::events::StatefulListenerContainer<#name #ty_generics> = {
-                let mut storage: ::automate::events::StatefulListenerContainer<#name #ty_generics> = Default::default();
-                storage.register(<#name::<#ty_generics> as automate::events::Initializable>::initialize());
+    let (functions, events, dispatches) = events_list();
 
-                storage
-            };
-        }
+    //states are stored as trait objects which must be 'static
+    //and their listeners taking `&self` run concurrently
+    let mut generics = input.generics.clone();
+    generics.make_where_clause().predicates.push(syn::parse_quote! {
+        #name #ty_generics: ::std::clone::Clone + ::automate::events::Initializable + Send + Sync + 'static
+    });
+    let where_clause = &generics.where_clause;
 
+    TokenStream::from(quote! {
         #[automatically_derived]
         #[::automate::async_trait]
         impl #impl_generics ::automate::events::State for #name #ty_generics #where_clause {
+            fn listened_events(&self) -> Vec<&'static str> {
+                let listeners = ::automate::events::stateful_listeners::<Self>();
+                let mut events = Vec::new();
+
+                #(
+                    if !listeners.#events.is_empty() {
+                        events.push(stringify!(#events));
+                    }
+                )*
+
+                events
+            }
+
+            fn requirements(&self) -> ::automate::events::Requirements {
+                <Self as ::automate::events::Initializable>::requirements()
+            }
+
+            fn setup(&mut self, dependencies: &mut ::automate::events::Dependencies) -> Result<(), ::automate::Error> {
+                <Self as ::automate::events::Initializable>::setup(self, dependencies)
+            }
+
             #(
-                async fn #functions(&mut self, ctx: &::automate::Context<'_>, event: &::automate::gateway::#dispatches) -> Result<(), Error> {
-                    for listener in &#methods_storage_name.#immutables {
-                        if let Err(error) = listener(self, ctx, event).await {
-                            ::automate::log::error!("Listener to {} failed with: {}", stringify!(#functions), error);
+                async fn #functions(&mut self, ctx: &::automate::Context<'_>, event: &::automate::gateway::#dispatches) -> Result<::automate::events::EventFlow, Error> {
+                    let mut listeners = ::automate::events::stateful_listeners::<Self>().#events.iter().peekable();
+
+                    //listeners are sorted by priority, consecutive listeners
+                    //taking `&self` run concurrently while the ones taking
+                    //`&mut self` have exclusive access to the state
+                    while let Some((handler, _)) = listeners.next() {
+                        let results = match handler {
+                            ::automate::events::StatefulHandler::Exclusive(listener) => vec![listener(self, ctx, event).await],
+                            ::automate::events::StatefulHandler::Shared(listener) => {
+                                let state: &Self = self;
+                                let mut shared = vec![listener(state, ctx, event)];
+
+                                while let Some((::automate::events::StatefulHandler::Shared(listener), _)) = listeners.peek() {
+                                    shared.push(listener(state, ctx, event));
+                                    listeners.next();
+                                }
+
+                                ::automate::events::join_shared(shared).await
+                            }
+                        };
+
+                        let mut stop = false;
+                        for result in results {
+                            match result {
+                                Ok(::automate::events::EventFlow::Stop) => stop = true,
+                                Ok(::automate::events::EventFlow::Continue) => (),
+                                Err(error) => ::automate::log::error!("Listener to {} failed with: {}", stringify!(#functions), error),
+                            }
                         }
-                    }
-            
-                    for listener in &#methods_storage_name.#mutables {
-                        if let Err(error) = listener(self, ctx, event).await {
-                            ::automate::log::error!("Listener to {} failed with: {}", stringify!(#functions), error);
+
+                        if stop {
+                            return Ok(::automate::events::EventFlow::Stop);
                         }
                     }
 
-                    Ok(())
+                    Ok(::automate::events::EventFlow::Continue)
                 }
             )*
         }
@@ -96,13 +96,17 @@ pub fn replace_attributes(input: &mut ItemStruct) {
 
         let mut field_type =  FieldType::Normal;
 
+        //the other attributes such as doc comments must not
+        //override the type, whatever their position
         field.attrs.retain(|attr| {
             let attr_name = attr.path.segments.last().unwrap().ident.to_string();
-            field_type = FieldType::from_str(&attr_name);
 
-            match field_type {
-                FieldType::Nullable | FieldType::OptionNullable => false,
-                FieldType::Normal => true
+            match FieldType::from_str(&attr_name) {
+                FieldType::Normal => true,
+                parsed => {
+                    field_type = parsed;
+                    false
+                }
             }
         });
 
@@ -110,7 +114,9 @@ pub fn replace_attributes(input: &mut ItemStruct) {
         let double_option = quote!(deserialize_with = "automate::encode::json::double_option");
 
         match field_type {
-            //normal optional fields should not be serialized
+            //normal optional fields should not be serialized, which
+            //makes `Option<Option<T>>` fields omitted when `None`
+            //and serialized as null when `Some(None)`
             FieldType::Normal => {
                 let attrs = Attribute::parse_outer
                     .parse2(quote!(#[serde(#skip_none)]))
@@ -64,6 +64,7 @@ async fn invite(ctx: &Context, data: &MessageCreateDispatch) -> Result<(), Error
             max_uses: 1,
             temporary: true,
             unique: false,
+            ..Default::default()
         }).await?.code;
 
         let content = format!("Here's your invite {}: https://discord.gg/{}", message.author.username, invite);
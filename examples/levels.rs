@@ -6,7 +6,7 @@
 extern crate automate;
 
 use automate::{Context, Error, Snowflake, Configuration, Automate};
-use automate::gateway::{MessageCreateDispatch, UpdateStatus, StatusType, User};
+use automate::gateway::{MessageCreateDispatch, Presence, User};
 use automate::http::CreateMessage;
 use automate::log::LevelFilter;
 use std::collections::HashMap;
@@ -132,12 +132,7 @@ fn main() {
     let config = Configuration::from_env("DISCORD_API_TOKEN")
         .enable_logging()
         .level_for("automate", LevelFilter::Trace)
-        .presence(UpdateStatus {
-            status: StatusType::Dnd,
-            game: None,
-            afk: false,
-            since: None,
-        })
+        .presence(Presence::watching("the leaderboard").dnd())
         .add_initializer(|ctn| ctn.initialize::<Count>())
         .register(stateless!(leaderboard_command, count));
 
@@ -0,0 +1,51 @@
+#![no_main]
+
+//! Parses gateway messages the same way the gateway
+//! does before handing them to the listeners: the
+//! opcode and event name are read with `payload_header`
+//! and the payload is then deserialized into the
+//! corresponding dispatch struct.
+
+use libfuzzer_sys::fuzz_target;
+use automate::encode::json;
+use automate::gateway::*;
+
+macro_rules! parse_event {
+    ($event:expr, $data:expr, $($dispatch:ty),*) => {
+        match $event {
+            $(<$dispatch>::EVENT_NAME => {
+                let _ = serde_json::from_str::<Payload<$dispatch>>($data);
+            })*
+            _ => ()
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let data = match std::str::from_utf8(data) {
+        Ok(data) => data,
+        Err(_) => return
+    };
+
+    match json::payload_header(data) {
+        Ok(json::PayloadHeader { op: 0, t: Some(event), .. }) => {
+            parse_event!(&*event, data,
+                ReadyDispatch, ResumedDispatch, ChannelCreateDispatch, ChannelUpdateDispatch,
+                ChannelDeleteDispatch, ChannelPinsUpdateDispatch, GuildCreateDispatch,
+                GuildUpdateDispatch, GuildDeleteDispatch, GuildBanAddDispatch, GuildBanRemoveDispatch,
+                GuildEmojisUpdateDispatch, GuildIntegrationsUpdateDispatch, GuildMemberAddDispatch,
+                GuildMemberRemoveDispatch, GuildMemberUpdateDispatch, GuildMembersChunkDispatch,
+                GuildRoleCreateDispatch, GuildRoleUpdateDispatch, GuildRoleDeleteDispatch,
+                InviteCreateDispatch, InviteDeleteDispatch, MessageCreateDispatch, MessageUpdateDispatch,
+                MessageDeleteDispatch, MessageDeleteBulkDispatch, MessageReactionAddDispatch,
+                MessageReactionRemoveDispatch, MessageReactionRemoveAllDispatch,
+                MessageReactionRemoveEmojiDispatch, PresenceUpdateDispatch, TypingStartDispatch,
+                UserUpdateDispatch, VoiceStateUpdateDispatch, VoiceServerUpdateDispatch,
+                WebhooksUpdateDispatch, InteractionCreateDispatch
+            );
+        },
+        Ok(json::PayloadHeader { op: 9, .. }) => { let _ = serde_json::from_str::<Payload<InvalidSession>>(data); },
+        Ok(json::PayloadHeader { op: 10, .. }) => { let _ = serde_json::from_str::<Payload<Hello>>(data); },
+        _ => ()
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+//! Deserializes arbitrary data into the objects
+//! returned by the HTTP API.
+
+use libfuzzer_sys::fuzz_target;
+use automate::gateway::*;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Guild>(data);
+    let _ = serde_json::from_slice::<Channel>(data);
+    let _ = serde_json::from_slice::<Message>(data);
+    let _ = serde_json::from_slice::<User>(data);
+    let _ = serde_json::from_slice::<GuildMember>(data);
+    let _ = serde_json::from_slice::<AuditLog>(data);
+    let _ = serde_json::from_slice::<Invite>(data);
+    let _ = serde_json::from_slice::<Application>(data);
+});
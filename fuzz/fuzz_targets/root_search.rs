@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use automate::encode::json;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(data) = std::str::from_utf8(data) {
+        let _ = json::root_search::<u8>("op", data);
+        let _ = json::root_search::<i32>("s", data);
+        let _ = json::root_search::<String>("t", data);
+        let _ = json::root_search::<f64>("d", data);
+    }
+});
@@ -0,0 +1,503 @@
+//! Audio sources producing the 20 milliseconds frames
+//! sent through voice connections.
+//!
+//! Frames are either 48kHz stereo PCM samples which still
+//! need to be encoded or Opus packets which can be sent
+//! as they are. Sources can be played one after the other
+//! using an [AudioPlayer](automate::audio::AudioPlayer).
+//!
+//! The library does not have voice connections yet, the
+//! frames are not sent anywhere and PCM frames are never
+//! encoded to Opus.
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use crate::Error;
+
+/// Sample rate expected by Discord.
+pub const SAMPLE_RATE: usize = 48_000;
+
+/// Amount of channels expected by Discord.
+pub const CHANNELS: usize = 2;
+
+/// Amount of samples per channel in a 20 milliseconds frame.
+pub const FRAME_SAMPLES: usize = SAMPLE_RATE / 50;
+
+/// Size in bytes of a frame of 16 bits PCM.
+const PCM_FRAME_SIZE: usize = FRAME_SAMPLES * CHANNELS * 2;
+
+/// 20 milliseconds of audio.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// Interleaved 16 bits stereo samples at 48kHz.
+    Pcm(Vec<i16>),
+    /// An already encoded Opus packet.
+    Opus(Vec<u8>),
+}
+
+/// Source of audio read frame by frame.
+#[async_trait]
+pub trait AudioSource: Send {
+    /// Reads the next frame, returns `None`
+    /// once the source is exhausted.
+    async fn next_frame(&mut self) -> Result<Option<Frame>, Error>;
+}
+
+/// Reads raw 16 bits little endian stereo PCM
+/// sampled at 48kHz.
+pub struct PcmSource<R> {
+    reader: R,
+    finished: bool,
+    #[cfg(feature = "ffmpeg")]
+    processes: Option<ffmpeg::Processes>,
+}
+
+impl<R: AsyncRead + Unpin + Send> PcmSource<R> {
+    pub fn new(reader: R) -> PcmSource<R> {
+        PcmSource {
+            reader,
+            finished: false,
+            #[cfg(feature = "ffmpeg")]
+            processes: None,
+        }
+    }
+}
+
+#[async_trait]
+impl<R: AsyncRead + Unpin + Send> AudioSource for PcmSource<R> {
+    async fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let mut buffer = vec![0u8; PCM_FRAME_SIZE];
+        let mut read = 0;
+
+        while read < PCM_FRAME_SIZE {
+            match self.reader.read(&mut buffer[read..]).await? {
+                0 => {
+                    self.finished = true;
+                    break;
+                }
+                n => read += n,
+            }
+        }
+
+        #[cfg(feature = "ffmpeg")]
+        if self.finished {
+            if let Some(processes) = &mut self.processes {
+                processes.wait().await?;
+            }
+        }
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        //the last frame is padded with silence
+        let samples = buffer.chunks_exact(2)
+            .map(|s| i16::from_le_bytes([s[0], s[1]]))
+            .collect();
+
+        Ok(Some(Frame::Pcm(samples)))
+    }
+}
+
+/// Reads Opus packets from an Ogg Opus file, the
+/// file must be encoded at 48kHz with 20 milliseconds
+/// frames.
+pub struct OpusSource<R> {
+    reader: R,
+    packets: VecDeque<Vec<u8>>,
+    partial: Vec<u8>,
+    headers: u8,
+    finished: bool,
+}
+
+impl<R: AsyncRead + Unpin + Send> OpusSource<R> {
+    pub fn new(reader: R) -> OpusSource<R> {
+        OpusSource {
+            reader,
+            packets: VecDeque::new(),
+            partial: Vec::new(),
+            headers: 0,
+            finished: false,
+        }
+    }
+
+    /// Reads an Ogg page and queues the packets it
+    /// contains. Returns false at the end of the file.
+    async fn read_page(&mut self) -> Result<bool, Error> {
+        let mut header = [0u8; 27];
+        match self.reader.read_exact(&mut header).await {
+            Ok(_) => (),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+            Err(err) => return Err(err.into()),
+        }
+
+        if &header[0..4] != b"OggS" {
+            return Error::err("Invalid Ogg page");
+        }
+
+        let mut segments = vec![0u8; header[26] as usize];
+        self.reader.read_exact(&mut segments).await?;
+
+        let mut data = vec![0u8; segments.iter().map(|s| *s as usize).sum()];
+        self.reader.read_exact(&mut data).await?;
+
+        //packets are split in segments of 255 bytes, a segment
+        //shorter than 255 bytes ends the packet
+        let mut offset = 0;
+        for segment in segments {
+            let segment = segment as usize;
+            self.partial.extend_from_slice(&data[offset..offset + segment]);
+            offset += segment;
+
+            if segment < 255 {
+                let packet = std::mem::take(&mut self.partial);
+
+                //skip the identification and comment headers
+                if self.headers < 2 {
+                    self.headers += 1;
+                } else {
+                    self.packets.push_back(packet);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<R: AsyncRead + Unpin + Send> AudioSource for OpusSource<R> {
+    async fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        while self.packets.is_empty() && !self.finished {
+            if !self.read_page().await? {
+                self.finished = true;
+            }
+        }
+
+        Ok(self.packets.pop_front().map(Frame::Opus))
+    }
+}
+
+/// Creates sources decoded by an ffmpeg child
+/// process, requires ffmpeg to be installed and
+/// youtube-dl for [ytdl](automate::audio::ytdl).
+#[cfg(feature = "ffmpeg")]
+pub use ffmpeg::{ffmpeg, ytdl};
+
+#[cfg(feature = "ffmpeg")]
+mod ffmpeg {
+    use std::process::Stdio;
+    use tokio::process::{Command, Child, ChildStdout};
+    use crate::audio::PcmSource;
+    use crate::Error;
+
+    const FFMPEG_ARGS: &[&str] = &["-f", "s16le", "-ar", "48000", "-ac", "2", "-loglevel", "error", "pipe:1"];
+
+    /// Child processes writing to a source. They are
+    /// reaped once the source is exhausted and killed
+    /// when the source is dropped before its end.
+    pub(crate) struct Processes {
+        ffmpeg: Child,
+        download: Option<std::process::Child>,
+    }
+
+    impl Processes {
+        pub(crate) async fn wait(&mut self) -> Result<(), Error> {
+            self.ffmpeg.wait().await?;
+
+            //youtube-dl exits once ffmpeg read all of its output
+            if let Some(download) = &mut self.download {
+                download.try_wait()?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl Drop for Processes {
+        fn drop(&mut self) {
+            //ffmpeg is killed and reaped by tokio, youtube-dl is
+            //killed first so waiting for it does not block
+            if let Some(download) = &mut self.download {
+                let _ = download.kill();
+                let _ = download.wait();
+            }
+        }
+    }
+
+    fn source(ffmpeg: Child, download: Option<std::process::Child>) -> Result<PcmSource<ChildStdout>, Error> {
+        let mut processes = Processes {
+            ffmpeg,
+            download,
+        };
+
+        let stdout = processes.ffmpeg.stdout.take().ok_or_else(|| Error::new("Failed to read ffmpeg output"))?;
+
+        Ok(PcmSource {
+            reader: stdout,
+            finished: false,
+            processes: Some(processes),
+        })
+    }
+
+    /// Decodes the given file or URL with ffmpeg.
+    pub fn ffmpeg(input: &str) -> Result<PcmSource<ChildStdout>, Error> {
+        let child = Command::new("ffmpeg")
+            .args(&["-i", input])
+            .args(FFMPEG_ARGS)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        source(child, None)
+    }
+
+    /// Downloads the audio of the given URL with
+    /// youtube-dl and decodes it with ffmpeg.
+    pub fn ytdl(url: &str) -> Result<PcmSource<ChildStdout>, Error> {
+        let mut download = std::process::Command::new("youtube-dl")
+            .args(&["-f", "bestaudio", "-q", "-o", "-", url])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let downloaded = match download.stdout.take() {
+            Some(downloaded) => downloaded,
+            None => {
+                let _ = download.kill();
+                let _ = download.wait();
+
+                return Error::err("Failed to read youtube-dl output");
+            }
+        };
+
+        let child = Command::new("ffmpeg")
+            .args(&["-i", "pipe:0"])
+            .args(FFMPEG_ARGS)
+            .stdin(Stdio::from(downloaded))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn();
+
+        match child {
+            Ok(child) => source(child, Some(download)),
+            Err(err) => {
+                let _ = download.kill();
+                let _ = download.wait();
+
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Plays audio sources one after the other, a
+/// voice connection should have its own player.
+///
+/// The player is not wired to any transport since
+/// there are no voice connections yet, the frames
+/// can only be read with [next_frame](automate::audio::AudioPlayer::next_frame)
+/// and [Frame::Pcm](automate::audio::Frame::Pcm) frames
+/// are never encoded to Opus.
+///
+/// The volume is only applied to PCM frames since
+/// Opus packets are sent without being decoded.
+pub struct AudioPlayer {
+    current: Option<Box<dyn AudioSource>>,
+    queue: VecDeque<Box<dyn AudioSource>>,
+    paused: bool,
+    volume: f32,
+}
+
+impl Default for AudioPlayer {
+    fn default() -> Self {
+        AudioPlayer {
+            current: None,
+            queue: VecDeque::new(),
+            paused: false,
+            volume: 1.0,
+        }
+    }
+}
+
+impl AudioPlayer {
+    /// Stops the current source and plays the given one.
+    pub fn play<S: AudioSource + 'static>(&mut self, source: S) {
+        self.current = Some(Box::new(source));
+        self.paused = false;
+    }
+
+    /// Plays the source once the previous ones ended.
+    pub fn enqueue<S: AudioSource + 'static>(&mut self, source: S) {
+        self.queue.push_back(Box::new(source));
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Stops the current source and clears the queue.
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.queue.clear();
+    }
+
+    /// Stops the current source and plays the
+    /// next source of the queue.
+    pub fn skip(&mut self) {
+        self.current = self.queue.pop_front();
+    }
+
+    /// Sets the volume where 1.0 is the original
+    /// volume of the sources.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.max(0.0);
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether the player has a source to play.
+    pub fn is_playing(&self) -> bool {
+        !self.paused && (self.current.is_some() || !self.queue.is_empty())
+    }
+
+    /// Reads the next frame to send, returns `None` if the
+    /// player is paused or has nothing left to play.
+    ///
+    /// Sources failing to read are skipped.
+    pub async fn next_frame(&mut self) -> Option<Frame> {
+        if self.paused {
+            return None;
+        }
+
+        loop {
+            if self.current.is_none() {
+                self.current = Some(self.queue.pop_front()?);
+            }
+
+            match self.current.as_mut().unwrap().next_frame().await {
+                Ok(Some(frame)) => return Some(self.apply_volume(frame)),
+                Ok(None) => self.current = None,
+                Err(err) => {
+                    error!("Failed to read audio source: {}", err);
+                    self.current = None;
+                }
+            }
+        }
+    }
+
+    fn apply_volume(&self, frame: Frame) -> Frame {
+        match frame {
+            Frame::Pcm(samples) if (self.volume - 1.0).abs() > f32::EPSILON => {
+                Frame::Pcm(samples.into_iter()
+                    .map(|s| (s as f32 * self.volume).max(i16::MIN as f32).min(i16::MAX as f32) as i16)
+                    .collect())
+            }
+            frame => frame,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::io::Cursor;
+
+    fn ogg_page(packets: &[&[u8]]) -> Vec<u8> {
+        let mut segments = Vec::new();
+        let mut data = Vec::new();
+
+        for packet in packets {
+            let mut remaining = packet.len();
+            while remaining >= 255 {
+                segments.push(255);
+                remaining -= 255;
+            }
+
+            segments.push(remaining as u8);
+            data.extend_from_slice(packet);
+        }
+
+        let mut page = b"OggS".to_vec();
+        page.extend_from_slice(&[0u8; 22]);
+        page.push(segments.len() as u8);
+        page.extend(segments);
+        page.extend(data);
+        page
+    }
+
+    #[test]
+    fn pcm_frames_are_padded() {
+        let input = vec![1u8; PCM_FRAME_SIZE + 4];
+        let mut source = PcmSource::new(&input[..]);
+
+        match block_on(source.next_frame()).unwrap() {
+            Some(Frame::Pcm(samples)) => assert!(samples.iter().all(|s| *s == 257)),
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+
+        match block_on(source.next_frame()).unwrap() {
+            Some(Frame::Pcm(samples)) => {
+                assert_eq!(samples.len(), FRAME_SAMPLES * CHANNELS);
+                assert_eq!(&samples[..3], &[257, 257, 0]);
+            }
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+
+        assert_eq!(block_on(source.next_frame()).unwrap(), None);
+    }
+
+    #[test]
+    fn opus_packets_are_extracted() {
+        let long = vec![7u8; 300];
+
+        let mut input = ogg_page(&[b"OpusHead"]);
+        input.extend(ogg_page(&[b"OpusTags"]));
+        input.extend(ogg_page(&[&[1, 2, 3], &long]));
+
+        let mut source = OpusSource::new(&input[..]);
+
+        assert_eq!(block_on(source.next_frame()).unwrap(), Some(Frame::Opus(vec![1, 2, 3])));
+        assert_eq!(block_on(source.next_frame()).unwrap(), Some(Frame::Opus(long)));
+        assert_eq!(block_on(source.next_frame()).unwrap(), None);
+    }
+
+    #[test]
+    fn player_plays_queue() {
+        let first = vec![0u8; PCM_FRAME_SIZE];
+        let second = vec![0u8; PCM_FRAME_SIZE * 2];
+
+        let mut player = AudioPlayer::default();
+        player.enqueue(PcmSource::new(Cursor::new(first)));
+        player.enqueue(PcmSource::new(Cursor::new(second)));
+
+        let mut frames = 0;
+        while block_on(player.next_frame()).is_some() {
+            frames += 1;
+        }
+
+        assert_eq!(frames, 3);
+        assert!(!player.is_playing());
+    }
+}
@@ -0,0 +1,149 @@
+//! Posts the amount of guilds of the bot to bot list
+//! websites such as [top.gg](https://top.gg) and
+//! [discord.bots.gg](https://discord.bots.gg).
+//! Requires the `botlists` feature.
+//!
+//! The amount of guilds is computed from the storages of the
+//! shards launched by the [ShardManager](automate::ShardManager)
+//! and posted every 30 minutes by default.
+//!
+//! The statistics are posted once per shard along with its
+//! id since a process may only launch some of the shards of
+//! the bot. The lists add up the counts of the shards posted
+//! by the different processes.
+//!
+//! ```
+//! # use automate::Configuration;
+//! use automate::botlists::BotList;
+//! use std::time::Duration;
+//!
+//! let config = Configuration::new("token")
+//!     .bot_list(BotList::TopGg(String::from("top.gg token")))
+//!     .bot_list(BotList::DiscordBotsGg(String::from("discord.bots.gg token")))
+//!     .bot_list_interval(Duration::from_secs(3600));
+//! ```
+
+use crate::{BotHandle, Error, Snowflake};
+use crate::gateway::Guild;
+use crate::storage::Query;
+use hyper::{Body, Request};
+use std::any::TypeId;
+use std::time::Duration;
+
+/// A bot list website along with the
+/// API token of the bot on this website.
+#[derive(Debug, Clone)]
+pub enum BotList {
+    TopGg(String),
+    DiscordBotsGg(String),
+}
+
+impl BotList {
+    fn name(&self) -> &'static str {
+        match self {
+            BotList::TopGg(_) => "top.gg",
+            BotList::DiscordBotsGg(_) => "discord.bots.gg",
+        }
+    }
+
+    /// The request posting the amount of guilds of a shard.
+    fn request(&self, bot: Snowflake, shard_id: u32, guilds: usize, shards: u32) -> Result<Request<Body>, Error> {
+        let (url, token, body) = match self {
+            BotList::TopGg(token) => (
+                format!("https://top.gg/api/bots/{}/stats", bot),
+                token,
+                serde_json::json!({"server_count": guilds, "shard_id": shard_id, "shard_count": shards}),
+            ),
+            BotList::DiscordBotsGg(token) => (
+                format!("https://discord.bots.gg/api/v1/bots/{}/stats", bot),
+                token,
+                serde_json::json!({"guildCount": guilds, "shardId": shard_id, "shardCount": shards}),
+            ),
+        };
+
+        Ok(Request::post(url)
+            .header("Content-Type", "application/json")
+            .header("Authorization", token.as_str())
+            .body(Body::from(body.to_string()))?)
+    }
+}
+
+/// Posts the statistics of the bot to the lists
+/// every `interval` once the shards are ready.
+pub(crate) async fn post_task(handle: BotHandle, lists: Vec<BotList>, interval: Duration) {
+    let bot = match handle.curent_user().await {
+        Ok(user) => user.id,
+        Err(err) => {
+            error!("Failed to retrieve the bot user, statistics will not be posted to the bot lists: {}", err);
+            return;
+        }
+    };
+
+    let mut timer = tokio::time::interval(interval);
+
+    loop {
+        timer.tick().await;
+
+        let total_shards = handle.total_shards();
+
+        for shard_id in handle.shards() {
+            let guilds = match shard_guilds(&handle, shard_id).await {
+                Some(guilds) => guilds,
+                None => continue,
+            };
+
+            for list in &lists {
+                let result = match list.request(bot, shard_id, guilds, total_shards) {
+                    Ok(request) => handle.client().request(request).await,
+                    Err(err) => Err(err),
+                };
+
+                match result {
+                    Ok(response) if response.status().is_success() => {
+                        trace!("Posted {} guilds of shard {} to {}", guilds, shard_id, list.name());
+                    }
+                    Ok(response) => warn!("Failed to post statistics of shard {} to {}: status {}", shard_id, list.name(), response.status()),
+                    Err(err) => warn!("Failed to post statistics of shard {} to {}: {}", shard_id, list.name(), err),
+                }
+            }
+        }
+    }
+}
+
+/// The amount of guilds of the shard, `None` if
+/// it did not receive its ready event yet.
+async fn shard_guilds(handle: &BotHandle, shard_id: u32) -> Option<usize> {
+    let storage = handle.shard_storage(shard_id).ok()?;
+
+    //the storages are initialized in the ready event
+    if !storage.contains(TypeId::of::<Guild>()) {
+        return None;
+    }
+
+    let guilds = storage.read::<Guild>().await.count();
+    Some(guilds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn builds_requests() {
+        let request = BotList::DiscordBotsGg(String::from("secret")).request(Snowflake(42), 1, 1200, 2).unwrap();
+        assert_eq!(request.uri(), "https://discord.bots.gg/api/v1/bots/42/stats");
+        assert_eq!(request.headers()["Authorization"], "secret");
+
+        let body = block_on(hyper::body::to_bytes(request.into_body())).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({"guildCount": 1200, "shardId": 1, "shardCount": 2}));
+
+        let request = BotList::TopGg(String::from("secret")).request(Snowflake(42), 0, 1200, 2).unwrap();
+        assert_eq!(request.uri(), "https://top.gg/api/bots/42/stats");
+
+        let body = block_on(hyper::body::to_bytes(request.into_body())).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({"server_count": 1200, "shard_id": 0, "shard_count": 2}));
+    }
+}
@@ -0,0 +1,134 @@
+//! Builds the URLs of the images stored on
+//! Discord's CDN from the hashes sent in the
+//! models.
+//!
+//! Hashes starting with `a_` belong to animated
+//! images which are always returned as gifs.
+//!
+//! More information on [Discord's documentation](https://discord.com/developers/docs/reference#image-formatting)
+
+use crate::Snowflake;
+use crate::gateway::StickerFormatType;
+
+pub const CDN_BASE: &str = "https://cdn.discordapp.com";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::WebP => "webp",
+            ImageFormat::Gif => "gif",
+        }
+    }
+}
+
+/// Whether the hash belongs to an animated image.
+pub fn is_animated(hash: &str) -> bool {
+    hash.starts_with("a_")
+}
+
+/// Builds the URL of an image stored under the given path,
+/// the size is rounded to a power of two between 16 and 4096.
+fn image(path: &str, hash: &str, format: ImageFormat, size: u16) -> String {
+    let format = if is_animated(hash) {
+        ImageFormat::Gif
+    } else {
+        format
+    };
+
+    let size = size.max(16).min(4096).next_power_of_two();
+    format!("{}/{}/{}.{}?size={}", CDN_BASE, path, hash, format.extension(), size)
+}
+
+pub fn avatar(user_id: Snowflake, hash: &str, format: ImageFormat, size: u16) -> String {
+    image(&format!("avatars/{}", user_id), hash, format, size)
+}
+
+/// The avatar of users who did not set an avatar,
+/// which depends on the discriminator of the user.
+pub fn default_avatar(discriminator: &str) -> String {
+    let index = discriminator.parse::<u16>().unwrap_or(0) % 5;
+    format!("{}/embed/avatars/{}.png", CDN_BASE, index)
+}
+
+pub fn guild_icon(guild_id: Snowflake, hash: &str, format: ImageFormat, size: u16) -> String {
+    image(&format!("icons/{}", guild_id), hash, format, size)
+}
+
+pub fn guild_splash(guild_id: Snowflake, hash: &str, format: ImageFormat, size: u16) -> String {
+    image(&format!("splashes/{}", guild_id), hash, format, size)
+}
+
+pub fn guild_discovery_splash(guild_id: Snowflake, hash: &str, format: ImageFormat, size: u16) -> String {
+    image(&format!("discovery-splashes/{}", guild_id), hash, format, size)
+}
+
+pub fn guild_banner(guild_id: Snowflake, hash: &str, format: ImageFormat, size: u16) -> String {
+    image(&format!("banners/{}", guild_id), hash, format, size)
+}
+
+pub fn role_icon(role_id: Snowflake, hash: &str, format: ImageFormat, size: u16) -> String {
+    image(&format!("role-icons/{}", role_id), hash, format, size)
+}
+
+/// The image of a custom emoji, animated
+/// emojis are always returned as gifs.
+pub fn emoji(emoji_id: Snowflake, animated: bool, format: ImageFormat) -> String {
+    let format = if animated {
+        ImageFormat::Gif
+    } else {
+        format
+    };
+
+    format!("{}/emojis/{}.{}", CDN_BASE, emoji_id, format.extension())
+}
+
+/// The image of a sticker, lottie stickers
+/// are returned as JSON files.
+pub fn sticker(sticker_id: Snowflake, format: StickerFormatType) -> String {
+    let extension = match format {
+        StickerFormatType::LOTTIE => "json",
+        _ => "png",
+    };
+
+    format!("{}/stickers/{}.{}", CDN_BASE, sticker_id, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animated_hashes() {
+        assert_eq!(
+            avatar(Snowflake(80351110224678912), "a_1269e74af4df7417b13759eae50c83dc", ImageFormat::WebP, 256),
+            "https://cdn.discordapp.com/avatars/80351110224678912/a_1269e74af4df7417b13759eae50c83dc.gif?size=256"
+        );
+
+        assert_eq!(
+            guild_icon(Snowflake(41771983423143937), "1269e74af4df7417b13759eae50c83dc", ImageFormat::WebP, 100),
+            "https://cdn.discordapp.com/icons/41771983423143937/1269e74af4df7417b13759eae50c83dc.webp?size=128"
+        );
+    }
+
+    #[test]
+    fn sizes_are_bounded() {
+        assert!(guild_banner(Snowflake(1), "hash", ImageFormat::Png, 0).ends_with("size=16"));
+        assert!(guild_banner(Snowflake(1), "hash", ImageFormat::Png, 10000).ends_with("size=4096"));
+    }
+
+    #[test]
+    fn default_avatars() {
+        assert_eq!(default_avatar("1337"), "https://cdn.discordapp.com/embed/avatars/2.png");
+        assert_eq!(default_avatar("invalid"), "https://cdn.discordapp.com/embed/avatars/0.png");
+    }
+}
@@ -0,0 +1,126 @@
+//! Futures resolving when the bot receives a
+//! message or a reaction matching a condition.
+//!
+//! Collectors are created through the
+//! [Context](automate::Context):
+//! ```ignore
+//! let collector = ctx.await_message(move |m| m.author.id == user && m.channel_id == channel)
+//!     .timeout(Duration::from_secs(30));
+//!
+//! tokio::spawn(async move {
+//!     if let Some(answer) = collector.await {
+//!         println!("User answered {}", answer.content);
+//!     }
+//! });
+//! ```
+//!
+//! Events are dispatched one at a time, awaiting a
+//! collector directly in a listener would prevent the
+//! awaited event from being received until the listener
+//! finishes. Collectors are thus `'static` and should be
+//! awaited in a spawned task.
+
+use crate::gateway::{Message, MessageCreateDispatch, MessageReactionAddDispatch};
+use futures::channel::oneshot;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Sleep;
+
+type Filter<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+struct Pending<T> {
+    filter: Filter<T>,
+    sender: oneshot::Sender<T>,
+}
+
+/// Collectors waiting for an event, shared
+/// by all the shards of a configuration.
+#[derive(Default)]
+pub(crate) struct Collectors {
+    messages: Mutex<Vec<Pending<Message>>>,
+    reactions: Mutex<Vec<Pending<MessageReactionAddDispatch>>>,
+}
+
+impl Collectors {
+    pub(crate) fn message<F>(&self, filter: F) -> Collector<Message>
+        where F: Fn(&Message) -> bool + Send + Sync + 'static {
+        register(&self.messages, Box::new(filter))
+    }
+
+    pub(crate) fn reaction<F>(&self, filter: F) -> Collector<MessageReactionAddDispatch>
+        where F: Fn(&MessageReactionAddDispatch) -> bool + Send + Sync + 'static {
+        register(&self.reactions, Box::new(filter))
+    }
+
+    pub(crate) fn on_message_create(&self, event: &MessageCreateDispatch) {
+        resolve(&self.messages, &event.0);
+    }
+
+    pub(crate) fn on_reaction_add(&self, reaction: &MessageReactionAddDispatch) {
+        resolve(&self.reactions, reaction);
+    }
+}
+
+fn register<T>(pending: &Mutex<Vec<Pending<T>>>, filter: Filter<T>) -> Collector<T> {
+    let (sender, receiver) = oneshot::channel();
+    pending.lock().unwrap().push(Pending { filter, sender });
+
+    Collector {
+        receiver,
+        timeout: None,
+    }
+}
+
+/// Sends the event to the collectors accepting it and
+/// removes them along with the dropped collectors.
+fn resolve<T: Clone>(pending: &Mutex<Vec<Pending<T>>>, event: &T) {
+    let mut pending = pending.lock().unwrap();
+
+    for collector in std::mem::take(&mut *pending) {
+        if collector.sender.is_canceled() {
+            continue;
+        }
+
+        if (collector.filter)(event) {
+            let _ = collector.sender.send(event.clone());
+        } else {
+            pending.push(collector);
+        }
+    }
+}
+
+/// Resolves to the first event accepted by the
+/// filter or to `None` if the timeout elapsed first.
+pub struct Collector<T> {
+    receiver: oneshot::Receiver<T>,
+    timeout: Option<Pin<Box<Sleep>>>,
+}
+
+impl<T> Collector<T> {
+    /// Gives up waiting after the given duration.
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        self.timeout = Some(Box::pin(tokio::time::sleep(duration)));
+        self
+    }
+}
+
+impl<T> Future for Collector<T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Poll::Ready(result) = Pin::new(&mut self.receiver).poll(cx) {
+            return Poll::Ready(result.ok());
+        }
+
+        if let Some(timeout) = self.timeout.as_mut() {
+            if timeout.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+        }
+
+        Poll::Pending
+    }
+}
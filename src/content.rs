@@ -0,0 +1,130 @@
+//! Helpers to safely send user provided content
+//! back to Discord.
+//!
+//! ```
+//! use automate::content::{escape_markdown, truncate};
+//!
+//! assert_eq!(escape_markdown("**bold**"), "\\*\\*bold\\*\\*");
+//! assert_eq!(truncate("héllo", 2), "hé");
+//! ```
+
+#[cfg(feature = "storage")]
+use crate::mention::Mention;
+#[cfg(feature = "storage")]
+use crate::gateway::Message;
+#[cfg(feature = "storage")]
+use crate::storage::GuildStorage;
+
+/// Maximum amount of characters in the content of a message.
+pub const MESSAGE_LENGTH: usize = 2000;
+
+/// Maximum amount of characters in the content of a
+/// message sent by a user with a nitro subscription.
+pub const PREMIUM_MESSAGE_LENGTH: usize = 4000;
+
+const MARKDOWN: &[char] = &['\\', '*', '_', '~', '`', '|', '>'];
+
+/// Escapes the characters used by Discord's markdown
+/// so that the content is displayed as is.
+pub fn escape_markdown(content: &str) -> String {
+    let mut escaped = String::with_capacity(content.len());
+
+    for c in content.chars() {
+        if MARKDOWN.contains(&c) {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Prevents `@everyone` and `@here` from pinging
+/// by inserting a zero width space after the `@`.
+pub fn neutralize_everyone(content: &str) -> String {
+    content.replace("@everyone", "@\u{200B}everyone")
+        .replace("@here", "@\u{200B}here")
+}
+
+/// Keeps at most `length` characters of the content
+/// without splitting a UTF-8 character.
+pub fn truncate(content: &str, length: usize) -> &str {
+    match content.char_indices().nth(length) {
+        Some((index, _)) => &content[..index],
+        None => content
+    }
+}
+
+/// The content of the message where user, role and
+/// channel mentions are replaced by their names and
+/// `@everyone` and `@here` can not ping anymore.
+///
+/// Users are resolved from the mentions of the message
+/// and then from the members of the guild, roles and
+/// channels from the guild in the storage.
+#[cfg(feature = "storage")]
+pub fn clean_content(message: &Message, guilds: &GuildStorage) -> String {
+    let guild = message.guild_id.and_then(|guild| guilds.get_opt(guild));
+
+    let content = Mention::replace_all(&message.content, |mention| match mention {
+        Mention::User(id) => {
+            let mentioned = message.mentions.iter()
+                .find(|user| user.id == *id)
+                .map(|user| user.member.as_ref().and_then(|m| m.nick.as_ref()).unwrap_or(&user.username));
+
+            let member = guild.and_then(|g| g.members.get(id))
+                .map(|member| member.nick.as_ref().unwrap_or(&member.user.username));
+
+            Some(match mentioned.or(member) {
+                Some(name) => format!("@{}", name),
+                None => String::from("@unknown-user"),
+            })
+        }
+        Mention::Role(id) => Some(match guild.and_then(|g| g.roles.get(id)) {
+            Some(role) => format!("@{}", role.name),
+            None => String::from("@deleted-role"),
+        }),
+        Mention::Channel(id) => Some(match guild.and_then(|g| g.channels.get(id)) {
+            Some(channel) => format!("#{}", channel.name()),
+            None => String::from("#deleted-channel"),
+        }),
+        Mention::Emoji { name, .. } => Some(format!(":{}:", name)),
+        Mention::Timestamp { .. } => None,
+    });
+
+    neutralize_everyone(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape() {
+        assert_eq!(escape_markdown("a_b *c* `d` ~~e~~ ||f|| > g \\"), "a\\_b \\*c\\* \\`d\\` \\~\\~e\\~\\~ \\|\\|f\\|\\| \\> g \\\\");
+        assert_eq!(neutralize_everyone("@everyone @here"), "@\u{200B}everyone @\u{200B}here");
+    }
+
+    #[test]
+    fn truncates() {
+        assert_eq!(truncate("abc", 5), "abc");
+        assert_eq!(truncate("abc", 0), "");
+        assert_eq!(truncate("ééé", 2), "éé");
+        assert_eq!(truncate(&"a".repeat(2500), MESSAGE_LENGTH).len(), MESSAGE_LENGTH);
+    }
+
+    #[test]
+    #[cfg(feature = "storage")]
+    fn cleans() {
+        use crate::testing::{fake_guild, fake_message, fake_user};
+
+        let mut guilds = GuildStorage::default();
+        guilds.insert(fake_guild(1, 2, "guild"));
+
+        let mut message = fake_message(3, 4, fake_user(2, "owner"), "<@2> <#5> <@&6> <:smile:7> @everyone");
+        message.guild_id = Some(crate::Snowflake(1));
+
+        assert_eq!(clean_content(&message, &guilds), "@unknown-user #deleted-channel @deleted-role :smile: @\u{200B}everyone");
+    }
+}
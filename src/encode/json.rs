@@ -1,4 +1,5 @@
 use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use crate::{Snowflake, Identifiable, Error};
 
@@ -30,6 +31,28 @@ pub fn as_hashmap<'de, T, D>(deserializer: D) -> Result<HashMap<Snowflake, T>, D
     Ok(map)
 }
 
+/// The fields of a gateway payload used to dispatch it.
+///
+/// Read by [payload_header](automate::encode::json::payload_header)
+/// in a single pass over the payload, the event data is skipped
+/// without being deserialized or allocated.
+#[derive(Debug, Deserialize)]
+pub struct PayloadHeader<'a> {
+    pub op: u8,
+    #[serde(borrow)]
+    pub t: Option<Cow<'a, str>>,
+    pub s: Option<i32>,
+}
+
+/// Reads the opcode, event name and sequence
+/// number of a gateway payload.
+pub fn payload_header(payload: &str) -> Result<PayloadHeader<'_>, Error> {
+    match serde_json::from_str(payload) {
+        Ok(header) => Ok(header),
+        Err(e) => Error::json(e)
+    }
+}
+
 /// Searches for a key through the root JSON object of the
 /// candidate string and returns a parsed value.
 ///
@@ -207,6 +230,24 @@ mod tests {
         assert!(root_search::<u32>("int", wrong_type).is_err());
     }
 
+    #[test]
+    fn test_payload_header() {
+        let dispatch = r#"{"t":"MESSAGE_CREATE","s":42,"op":0,"d":{"op":7,"t":"READY","content":"{\"op\":1}"}}"#;
+        let header = payload_header(dispatch).unwrap();
+
+        assert_eq!(header.op, 0);
+        assert_eq!(header.t.as_deref(), Some("MESSAGE_CREATE"));
+        assert_eq!(header.s, Some(42));
+
+        let heartbeat_ack = r#"{"op":11,"d":null,"t":null,"s":null}"#;
+        let header = payload_header(heartbeat_ack).unwrap();
+
+        assert_eq!(header.op, 11);
+        assert_eq!(header.t, None);
+
+        assert!(payload_header(r#"{"t":"READY","d":{}}"#).is_err());
+        assert!(payload_header(r#"{"op":0,"d":{"#).is_err());
+    }
 }
 
 #[cfg(test)]
@@ -279,6 +320,18 @@ mod benchmarks {
         });
     }
 
+    #[bench]
+    fn bench_payload_header(b: &mut Bencher) {
+        let payload = format!(
+            r#"{{"d":{},"op":0,"s":12,"t":"MESSAGE_CREATE"}}"#,
+            serde_json::to_string(&Something::create()).unwrap()
+        );
+
+        b.iter(|| {
+            payload_header(&payload).unwrap();
+        });
+    }
+
     #[bench]
     fn bench_root_search_long_str(b: &mut Bencher) {
         let long_str = serde_json::to_string(&LongStr::create()).unwrap();
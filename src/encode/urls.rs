@@ -1,6 +1,6 @@
 use crate::{Error, Snowflake, Identifiable};
 use crate::gateway::*;
-use percent_encoding::NON_ALPHANUMERIC;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
 use std::fmt::Write;
 
 /// Any type which is identified by
@@ -54,7 +54,7 @@ macro_rules! automate_enums {
 
 automate_types! {
     AuditLogEntry,
-    Category, TextChannel, VoiceChannel, NewsChannel, StoreChannel,
+    Category, TextChannel, VoiceChannel, NewsChannel, StoreChannel, StageChannel, ThreadChannel,
     DirectChannel, GroupChannel,
     ChannelMention, MentionnedUser,
     Overwrite,
@@ -62,6 +62,7 @@ automate_types! {
     Guild, PartialGuild, UnavailableGuild,
     Role, PartialRole,
     Message, Attachment, MessageApplication,
+    Application, PartialApplication, ApplicationCommand,
     User, PartialUser,
     GuildEmoji
 }
@@ -126,4 +127,52 @@ impl WriteUrl for PartialEmoji {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl WriteUrl for ReactionEmoji {
+    fn write_url(&self, buf: &mut String) -> Result<(), Error> {
+        match self {
+            ReactionEmoji::Unicode(name) => {
+                let penc = percent_encoding::utf8_percent_encode(name, NON_ALPHANUMERIC);
+                buf.write_fmt(format_args!("{}", penc))?;
+            }
+            ReactionEmoji::Custom { name, id, .. } => {
+                let penc = percent_encoding::utf8_percent_encode(name, NON_ALPHANUMERIC);
+                buf.write_fmt(format_args!("{}:{}", penc, id))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps the colon separating the name
+/// and the id of custom emojis.
+const EMOJI: &AsciiSet = &NON_ALPHANUMERIC.remove(b':');
+
+/// Unicode emojis or custom emojis
+/// in the `name:id` format.
+impl WriteUrl for str {
+    fn write_url(&self, buf: &mut String) -> Result<(), Error> {
+        let penc = percent_encoding::utf8_percent_encode(self, EMOJI);
+
+        buf.reserve(self.len());
+        buf.write_fmt(format_args!("{}", penc))?;
+
+        Ok(())
+    }
+}
+
+impl WriteUrl for String {
+    #[inline]
+    fn write_url(&self, buf: &mut String) -> Result<(), Error> {
+        self.as_str().write_url(buf)
+    }
+}
+
+impl<T: WriteUrl + ?Sized> WriteUrl for &T {
+    #[inline]
+    fn write_url(&self, buf: &mut String) -> Result<(), Error> {
+        (**self).write_url(buf)
+    }
+}
@@ -1,18 +1,110 @@
+//! Errors returned by the library.
+//!
+//! Errors returned by Discord's HTTP API carry the
+//! [JSON error code](https://discord.com/developers/docs/topics/opcodes-and-status-codes#json)
+//! sent by Discord which can be matched against the
+//! constants of the [codes](automate::errors::codes) module.
+//!
+//! ```
+//! # use automate::{Context, Error};
+//! # use automate::errors::{codes, HttpContext};
+//! # use automate::gateway::Message;
+//! async fn delete(ctx: &Context<'_>, message: &Message) -> Result<(), Error> {
+//!     match ctx.delete_message(message.channel_id, message.id).await {
+//!         Err(Error::Http(HttpContext { code: Some(codes::UNKNOWN_MESSAGE), .. })) => Ok(()),
+//!         result => result,
+//!     }
+//! }
+//! ```
+
 use std::{fmt, result};
 use chrono::{NaiveDateTime, Local, DateTime, Utc};
+use serde::Deserialize;
+use crate::gateway::{GatewayCloseCode, Permission};
+use crate::Snowflake;
+
+/// Some of the JSON error codes sent by Discord.
+pub mod codes {
+    pub const UNKNOWN_CHANNEL: u32 = 10003;
+    pub const UNKNOWN_GUILD: u32 = 10004;
+    pub const UNKNOWN_MEMBER: u32 = 10007;
+    pub const UNKNOWN_MESSAGE: u32 = 10008;
+    pub const UNKNOWN_ROLE: u32 = 10011;
+    pub const UNKNOWN_USER: u32 = 10013;
+    pub const UNKNOWN_EMOJI: u32 = 10014;
+    pub const UNKNOWN_WEBHOOK: u32 = 10015;
+    pub const UNKNOWN_INTERACTION: u32 = 10062;
+    pub const MISSING_ACCESS: u32 = 50001;
+    pub const CANNOT_MESSAGE_USER: u32 = 50007;
+    pub const MISSING_PERMISSIONS: u32 = 50013;
+    pub const INVALID_FORM_BODY: u32 = 50035;
+    pub const MESSAGE_TOO_OLD_TO_BULK_DELETE: u32 = 50034;
+    pub const REACTION_BLOCKED: u32 = 90001;
+}
 
 /// Represents an error that occurred while using the library.
 #[derive(Debug)]
 pub enum Error {
     Gateway(String),
-    Http(String),
+    GatewayClosed(CloseContext),
+    Http(HttpContext),
     InvalidToken(TokenContext),
-    NoPermission(TokenContext),
     RateLimited(RlContext),
+    MissingPermission(PermissionContext),
     Json(JsonContext),
     Other(String),
 }
 
+/// Provides context when the gateway closed
+/// the connection.
+#[derive(Debug)]
+pub struct CloseContext {
+    /// The websocket close code
+    pub code: u16,
+    /// The reason given by the gateway
+    pub reason: String,
+}
+
+impl CloseContext {
+    /// The Discord specific close code if the
+    /// connection was closed by Discord.
+    pub fn close_code(&self) -> Option<GatewayCloseCode> {
+        GatewayCloseCode::from_code(self.code)
+    }
+
+    /// Whether reconnecting after this close is pointless.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self.close_code(), Some(code) if code.is_fatal())
+    }
+}
+
+/// Provides context when a request to the HTTP
+/// API failed.
+#[derive(Debug)]
+pub struct HttpContext {
+    /// The endpoint on which this error occurred if
+    /// the error was returned by an endpoint
+    pub endpoint: Option<String>,
+    /// The status code of the response if
+    /// Discord responded
+    pub status: Option<u16>,
+    /// The JSON error code sent by Discord, see
+    /// the [codes](automate::errors::codes) module
+    pub code: Option<u32>,
+    pub message: String,
+    /// The invalid fields of the request body
+    /// when the request was malformed
+    pub errors: Option<serde_json::Value>,
+}
+
+/// Error body sent by Discord's HTTP API.
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: u32,
+    message: String,
+    errors: Option<serde_json::Value>,
+}
+
 /// Provides context when an error related to the
 /// token is emitted.
 #[derive(Debug)]
@@ -35,6 +127,25 @@ pub struct RlContext {
     /// you from getting rate-limited or if it was sent by discord
     /// because of a real rate-limit
     pub prevented: bool,
+    /// Whether the global rate-limit was hit, in
+    /// which case all the endpoints are paused
+    pub global: bool,
+}
+
+/// Context for errors emitted by the [preflight](automate::preflight)
+/// checks when the cached permissions of the bot do not allow
+/// an action, in which case no request is sent.
+#[derive(Debug)]
+pub struct PermissionContext {
+    /// The permissions the bot lacks, see
+    /// [Permission::from_bits](automate::gateway::Permission::from_bits)
+    pub needed: u32,
+    /// The channel in which the permissions are needed
+    /// or `None` for guild-wide permissions
+    pub channel: Option<Snowflake>,
+    /// Whether the bot has the permissions but its highest
+    /// role is not above the role or member it acts on
+    pub hierarchy: bool,
 }
 
 /// Errors spawned in the [json](automate::encode::json) module.
@@ -58,8 +169,39 @@ impl Error {
         Err(Error::Gateway(msg.to_string()))
     }
 
+    pub(crate) fn gateway_closed<S, T>(code: u16, reason: S) -> Result<T, Error> where S: ToString {
+        Err(Error::GatewayClosed(CloseContext {
+            code,
+            reason: reason.to_string(),
+        }))
+    }
+
     pub(crate) fn http<S, T>(msg: S) -> Result<T, Error> where S: ToString {
-        Err(Error::Http(msg.to_string()))
+        Err(Error::Http(HttpContext {
+            endpoint: None,
+            status: None,
+            code: None,
+            message: msg.to_string(),
+            errors: None,
+        }))
+    }
+
+    /// Creates the error from the unexpected response of
+    /// an endpoint and the JSON error sent by Discord in
+    /// its body if there is one.
+    pub(crate) fn http_response<T>(endpoint: &str, status: u16, body: &[u8]) -> Result<T, Error> {
+        let (code, message, errors) = match serde_json::from_slice::<ErrorBody>(body) {
+            Ok(body) => (Some(body.code), body.message, body.errors),
+            Err(_) => (None, format!("Unexpected status code {}", status), None),
+        };
+
+        Err(Error::Http(HttpContext {
+            endpoint: Some(endpoint.to_owned()),
+            status: Some(status),
+            code,
+            message,
+            errors,
+        }))
     }
 
     pub(crate) fn invalid_token<T>(endpoint: &str, token: &str) -> Result<T, Error> {
@@ -69,18 +211,59 @@ impl Error {
         }))
     }
 
-    pub(crate) fn no_permission<T>(endpoint: &str, token: &str) -> Result<T, Error> {
-        Err(Error::NoPermission(TokenContext {
+    pub(crate) fn rate_limited<T>(endpoint: &str, until: NaiveDateTime, prevented: bool) -> Result<T, Error> {
+        Err(Error::RateLimited(RlContext {
             endpoint: endpoint.to_owned(),
-            token: token.to_owned(),
+            until,
+            prevented,
+            global: false,
         }))
     }
 
-    pub(crate) fn rate_limited<T>(endpoint: &str, until: NaiveDateTime, prevented: bool) -> Result<T, Error> {
+    pub(crate) fn global_rate_limited<T>(endpoint: &str, until: NaiveDateTime) -> Result<T, Error> {
         Err(Error::RateLimited(RlContext {
             endpoint: endpoint.to_owned(),
             until,
-            prevented,
+            prevented: false,
+            global: true,
+        }))
+    }
+
+    /// The status code of the response if the error
+    /// was returned by an endpoint.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Http(ctx) => ctx.status,
+            Error::InvalidToken(_) => Some(401),
+            Error::RateLimited(ctx) if !ctx.prevented => Some(429),
+            _ => None,
+        }
+    }
+
+    /// The JSON error code sent by Discord, see
+    /// the [codes](automate::errors::codes) module.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            Error::Http(ctx) => ctx.code,
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "storage")]
+    pub(crate) fn missing_permission<T>(needed: u32, channel: Option<Snowflake>) -> Result<T, Error> {
+        Err(Error::MissingPermission(PermissionContext {
+            needed,
+            channel,
+            hierarchy: false,
+        }))
+    }
+
+    #[cfg(feature = "storage")]
+    pub(crate) fn hierarchy<T>(needed: u32) -> Result<T, Error> {
+        Err(Error::MissingPermission(PermissionContext {
+            needed,
+            channel: None,
+            hierarchy: true,
         }))
     }
 
@@ -97,19 +280,37 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
         match self {
             Error::Gateway(s) => write!(f, "{}", s),
-            Error::Http(s) => write!(f, "{}", s),
+            Error::GatewayClosed(ctx) => match ctx.close_code() {
+                Some(code) => write!(f, "Gateway closed with code {} ({:?}): {}", ctx.code, code, ctx.reason),
+                None => write!(f, "Gateway closed with code {}: {}", ctx.code, ctx.reason),
+            },
+            Error::Http(ctx) => match (&ctx.endpoint, ctx.status, ctx.code) {
+                (Some(endpoint), Some(status), Some(code)) => write!(f, "{} (status {}, code {}, endpoint `{}`)", ctx.message, status, code, endpoint),
+                (Some(endpoint), Some(status), None) => write!(f, "{} (status {}, endpoint `{}`)", ctx.message, status, endpoint),
+                _ => write!(f, "{}", ctx.message),
+            },
             Error::InvalidToken(ctx) => write!(f, "Invalid token `{}`", ctx.token),
-            Error::NoPermission(ctx) => write!(f, "Token `{}` does not have the permission to call `{}`", ctx.token, ctx.endpoint),
             Error::RateLimited(ctx) => {
                 let datetime: DateTime<Utc> = DateTime::from_utc(ctx.until, Utc);
                 let local = datetime.with_timezone(&Local);
 
-                if ctx.prevented {
+                if ctx.global {
+                    write!(f, "Reached global rate limit when requesting endpoint `̀{}` until {}", ctx.endpoint, local)
+                } else if ctx.prevented {
                     write!(f, "Cancelled to avoid reaching rate limit for endpoint `̀{}` wait until {}", ctx.endpoint, local)
                 } else {
                     write!(f, "Reached rate limit for endpoint `̀{}` until {}", ctx.endpoint, local)
                 }
             },
+            Error::MissingPermission(ctx) => {
+                let needed = Permission::from_bits(ctx.needed);
+
+                match (ctx.hierarchy, ctx.channel) {
+                    (true, _) => write!(f, "Highest role of the bot is not high enough to use its permissions {:?}", needed),
+                    (false, Some(channel)) => write!(f, "Missing permissions {:?} in channel {}", needed, channel),
+                    (false, None) => write!(f, "Missing permissions {:?}", needed),
+                }
+            },
             Error::Json(s) =>  {
                 #[cfg(feature = "backtrace")] write!(f, "{}\n{}", s.message, s.backtrace)?;
                 #[cfg(not(feature = "backtrace"))] write!(f, "{}", s.message)?;
@@ -126,3 +327,26 @@ impl<T: std::error::Error> From<T> for Error {
         Error::new(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_discord_errors() {
+        let body = br#"{"code": 50013, "message": "Missing Permissions"}"#;
+
+        match Error::http_response::<()>("create_message", 403, body) {
+            Err(err @ Error::Http(_)) => {
+                assert_eq!(err.status(), Some(403));
+                assert_eq!(err.code(), Some(codes::MISSING_PERMISSIONS));
+                assert_eq!(err.to_string(), "Missing Permissions (status 403, code 50013, endpoint `create_message`)");
+            }
+            _ => panic!("Expected an HTTP error"),
+        }
+
+        let err = Error::http_response::<()>("create_message", 500, b"<html></html>").unwrap_err();
+        assert_eq!(err.status(), Some(500));
+        assert_eq!(err.code(), None);
+    }
+}
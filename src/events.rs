@@ -7,6 +7,42 @@ use crate::gateway::*;
 use crate::{Context, Error};
 use std::pin::Pin;
 use std::future::Future;
+use std::collections::HashMap;
+use std::any::{Any, TypeId, type_name};
+use std::sync::RwLock;
+use crate::Snowflake;
+#[cfg(feature = "storage")]
+use crate::storage::{StorageContainer, Stored};
+
+/// Priority of the listeners that do not specify one.
+pub const DEFAULT_PRIORITY: u8 = 128;
+
+/// Returned by listeners to decide whether the
+/// event should be sent to the next listeners.
+///
+/// Listeners returning `Ok(())` let the event
+/// propagate to the next listeners.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventFlow {
+    Continue,
+    Stop,
+}
+
+impl From<()> for EventFlow {
+    fn from(_: ()) -> Self {
+        EventFlow::Continue
+    }
+}
+
+/// Inserts the listener after the listeners with a higher
+/// or equal priority to keep the list sorted.
+fn insert_sorted<L>(listeners: &mut Vec<(L, u8)>, listener: L, priority: u8) {
+    let index = listeners.iter()
+        .position(|(_, p)| *p < priority)
+        .unwrap_or(listeners.len());
+
+    listeners.insert(index, (listener, priority));
+}
 
 /// Parses a list of state structs before sending them to the
 /// [Configuration::register](automate::Configuration::register) method.
@@ -17,12 +53,168 @@ macro_rules! stateful {
     }
 }
 
+/// Parses a list of state structs before sending them to the
+/// [Configuration::register](automate::Configuration::register) method,
+/// creating a separate instance of each state for every guild.
+/// See [PerGuild](automate::events::PerGuild).
+#[macro_export]
+macro_rules! stateful_per_guild {
+    ($($listener:expr),*) => {
+        vec![$(::automate::events::ListenerType::Stateful(Box::new(::automate::events::PerGuild::new($listener)))),*]
+    }
+}
+
 /// Provides the initialize method for the state
 /// structs which define the stateful listener functions.
 pub trait Initializable {
     /// Returns the list of listeners. Gets called once
     /// when the library is setting up listeners.
     fn initialize() -> Vec<StatefulListener<Self>> where Self: Sized;
+
+    /// The values the state provides to the other states
+    /// and the ones it needs from them, see
+    /// [Requirements](automate::events::Requirements).
+    fn requirements() -> Requirements where Self: Sized {
+        Requirements::default()
+    }
+
+    /// Called on the instance of the state of each shard
+    /// before it receives any event, after the states
+    /// providing its requirements.
+    fn setup(&mut self, dependencies: &mut Dependencies) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Declares the dependencies of a state, allowing
+/// states to be published as reusable modules.
+///
+/// A state providing a value must give it to
+/// [Dependencies::provide](automate::events::Dependencies::provide)
+/// in its setup, the states requiring it are set up
+/// afterwards and receive the value with
+/// [Dependencies::get](automate::events::Dependencies::get).
+///
+/// ```
+/// # #[macro_use] extern crate automate;
+/// #
+/// # use automate::Error;
+/// # use automate::events::{Initializable, StatefulListener, Requirements, Dependencies};
+/// # use std::sync::{Arc, Mutex};
+/// # use std::collections::HashMap;
+/// #
+/// type Levels = Arc<Mutex<HashMap<u64, u32>>>;
+///
+/// #[derive(State, Clone, Default)]
+/// struct Rewards {
+///     levels: Levels,
+/// }
+///
+/// impl Initializable for Rewards {
+///     fn initialize() -> Vec<StatefulListener<Self>> {
+///         methods!(Rewards)
+///     }
+///
+///     fn requirements() -> Requirements {
+///         Requirements::new().requires::<Levels>()
+///     }
+///
+///     fn setup(&mut self, dependencies: &mut Dependencies) -> Result<(), Error> {
+///         self.levels = dependencies.get::<Levels>()?;
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Requirements {
+    provides: Vec<(TypeId, &'static str)>,
+    requires: Vec<(TypeId, &'static str)>,
+    #[cfg(feature = "storage")]
+    storages: Vec<(TypeId, &'static str)>,
+}
+
+impl Requirements {
+    pub fn new() -> Requirements {
+        Requirements::default()
+    }
+
+    /// The state provides a value of the given type.
+    pub fn provides<T: Any>(mut self) -> Self {
+        self.provides.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    /// The state needs a value of the given type
+    /// provided by another state.
+    pub fn requires<T: Any>(mut self) -> Self {
+        self.requires.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    /// The state reads a custom storage which must be
+    /// added with [Configuration::add_initializer](automate::Configuration::add_initializer).
+    #[cfg(feature = "storage")]
+    pub fn storage<T: Stored + 'static>(mut self) -> Self {
+        self.storages.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+}
+
+/// Values provided by the states of a shard
+/// during their setup.
+#[derive(Default)]
+pub struct Dependencies {
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Dependencies {
+    /// Gives a value to the states requiring it, it is
+    /// usually an [Arc](std::sync::Arc) shared with the state.
+    pub fn provide<T: Any + Send + Sync>(&mut self, value: T) {
+        self.values.insert(TypeId::of::<T>(), Box::new(value));
+    }
+
+    /// A copy of the value provided by another state.
+    pub fn get<T: Any + Clone>(&self) -> Result<T, Error> {
+        match self.values.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref::<T>()) {
+            Some(value) => Ok(value.clone()),
+            None => Error::err(format!("No state provided a value of type {}", type_name::<T>())),
+        }
+    }
+}
+
+/// Orders the states so each of them comes after
+/// the states providing its requirements, keeping
+/// the order of registration otherwise.
+fn dependency_order(requirements: &[Requirements]) -> Result<Vec<usize>, Error> {
+    for (i, requirement) in requirements.iter().enumerate() {
+        for (id, name) in &requirement.requires {
+            let provided = requirements.iter().enumerate()
+                .any(|(j, other)| i != j && other.provides.iter().any(|(p, _)| p == id));
+
+            if !provided {
+                return Error::err(format!("No registered state provides {}", name));
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = Vec::with_capacity(requirements.len());
+    let mut remaining: Vec<usize> = (0..requirements.len()).collect();
+
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|&i| {
+            requirements[i].requires.iter().all(|(id, _)| {
+                order.iter().any(|&j| requirements[j].provides.iter().any(|(p, _)| p == id))
+            })
+        });
+
+        match ready {
+            Some(position) => order.push(remaining.remove(position)),
+            None => return Error::err("The requirements of the states are circular"),
+        }
+    }
+
+    Ok(order)
 }
 
 /// A stateful event listener.
@@ -37,41 +229,72 @@ pub trait Initializable {
 /// [Initializable](automate::events::Initializable) in order to be registered.
 #[async_trait]
 pub trait State: StateClone + Send + 'static {
-    async fn on_ready(&mut self, ctx: &Context<'_>, event: &ReadyDispatch) -> Result<(), Error>;
-    async fn on_channel_create(&mut self, ctx: &Context<'_>, event: &ChannelCreateDispatch) -> Result<(), Error>;
-    async fn on_channel_update(&mut self, ctx: &Context<'_>, event: &ChannelUpdateDispatch) -> Result<(), Error>;
-    async fn on_channel_delete(&mut self, ctx: &Context<'_>, event: &ChannelDeleteDispatch) -> Result<(), Error>;
-    async fn on_channel_pins_update(&mut self, ctx: &Context<'_>, event: &ChannelPinsUpdateDispatch) -> Result<(), Error>;
-    async fn on_guild_create(&mut self, ctx: &Context<'_>, event: &GuildCreateDispatch) -> Result<(), Error>;
-    async fn on_guild_update(&mut self, ctx: &Context<'_>, event: &GuildUpdateDispatch) -> Result<(), Error>;
-    async fn on_guild_delete(&mut self, ctx: &Context<'_>, event: &GuildDeleteDispatch) -> Result<(), Error>;
-    async fn on_guild_ban_add(&mut self, ctx: &Context<'_>, event: &GuildBanAddDispatch) -> Result<(), Error>;
-    async fn on_guild_ban_remove(&mut self, ctx: &Context<'_>, event: &GuildBanRemoveDispatch) -> Result<(), Error>;
-    async fn on_guild_emojis_update(&mut self, ctx: &Context<'_>, event: &GuildEmojisUpdateDispatch) -> Result<(), Error>;
-    async fn on_guild_integrations_update(&mut self, ctx: &Context<'_>, event: &GuildIntegrationsUpdateDispatch) -> Result<(), Error>;
-    async fn on_guild_member_add(&mut self, ctx: &Context<'_>, event: &GuildMemberAddDispatch) -> Result<(), Error>;
-    async fn on_guild_member_remove(&mut self, ctx: &Context<'_>, event: &GuildMemberRemoveDispatch) -> Result<(), Error>;
-    async fn on_guild_member_update(&mut self, ctx: &Context<'_>, event: &GuildMemberUpdateDispatch) -> Result<(), Error>;
-    async fn on_guild_members_chunk(&mut self, ctx: &Context<'_>, event: &GuildMembersChunkDispatch) -> Result<(), Error>;
-    async fn on_guild_role_create(&mut self, ctx: &Context<'_>, event: &GuildRoleCreateDispatch) -> Result<(), Error>;
-    async fn on_guild_role_update(&mut self, ctx: &Context<'_>, event: &GuildRoleUpdateDispatch) -> Result<(), Error>;
-    async fn on_guild_role_delete(&mut self, ctx: &Context<'_>, event: &GuildRoleDeleteDispatch) -> Result<(), Error>;
-    async fn on_invite_create(&mut self, ctx: &Context<'_>, event: &InviteCreateDispatch) -> Result<(), Error>;
-    async fn on_invite_delete(&mut self, ctx: &Context<'_>, event: &InviteDeleteDispatch) -> Result<(), Error>;
-    async fn on_message_create(&mut self, ctx: &Context<'_>, event: &MessageCreateDispatch) -> Result<(), Error>;
-    async fn on_message_update(&mut self, ctx: &Context<'_>, event: &MessageUpdateDispatch) -> Result<(), Error>;
-    async fn on_message_delete(&mut self, ctx: &Context<'_>, event: &MessageDeleteDispatch) -> Result<(), Error>;
-    async fn on_message_delete_bulk(&mut self, ctx: &Context<'_>, event: &MessageDeleteBulkDispatch) -> Result<(), Error>;
-    async fn on_reaction_add(&mut self, ctx: &Context<'_>, event: &MessageReactionAddDispatch) -> Result<(), Error>;
-    async fn on_reaction_remove(&mut self, ctx: &Context<'_>, event: &MessageReactionRemoveDispatch) -> Result<(), Error>;
-    async fn on_reaction_remove_all(&mut self, ctx: &Context<'_>, event: &MessageReactionRemoveAllDispatch) -> Result<(), Error>;
-    async fn on_reaction_remove_emoji(&mut self, ctx: &Context<'_>, event: &MessageReactionRemoveEmojiDispatch) -> Result<(), Error>;
-    async fn on_presence_update(&mut self, ctx: &Context<'_>, event: &PresenceUpdateDispatch) -> Result<(), Error>;
-    async fn on_typing_start(&mut self, ctx: &Context<'_>, event: &TypingStartDispatch) -> Result<(), Error>;
-    async fn on_user_update(&mut self, ctx: &Context<'_>, event: &UserUpdateDispatch) -> Result<(), Error>;
-    async fn on_voice_state_update(&mut self, ctx: &Context<'_>, event: &VoiceStateUpdateDispatch) -> Result<(), Error>;
-    async fn on_voice_server_update(&mut self, ctx: &Context<'_>, event: &VoiceServerUpdateDispatch) -> Result<(), Error>;
-    async fn on_webhooks_update(&mut self, ctx: &Context<'_>, event: &WebhooksUpdateDispatch) -> Result<(), Error>;
+    async fn on_ready(&mut self, ctx: &Context<'_>, event: &ReadyDispatch) -> Result<EventFlow, Error>;
+    async fn on_channel_create(&mut self, ctx: &Context<'_>, event: &ChannelCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_channel_update(&mut self, ctx: &Context<'_>, event: &ChannelUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_channel_delete(&mut self, ctx: &Context<'_>, event: &ChannelDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_channel_pins_update(&mut self, ctx: &Context<'_>, event: &ChannelPinsUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_thread_create(&mut self, ctx: &Context<'_>, event: &ThreadCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_thread_update(&mut self, ctx: &Context<'_>, event: &ThreadUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_thread_delete(&mut self, ctx: &Context<'_>, event: &ThreadDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_thread_list_sync(&mut self, ctx: &Context<'_>, event: &ThreadListSyncDispatch) -> Result<EventFlow, Error>;
+    async fn on_thread_member_update(&mut self, ctx: &Context<'_>, event: &ThreadMemberUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_thread_members_update(&mut self, ctx: &Context<'_>, event: &ThreadMembersUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_create(&mut self, ctx: &Context<'_>, event: &GuildCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_update(&mut self, ctx: &Context<'_>, event: &GuildUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_delete(&mut self, ctx: &Context<'_>, event: &GuildDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_ban_add(&mut self, ctx: &Context<'_>, event: &GuildBanAddDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_ban_remove(&mut self, ctx: &Context<'_>, event: &GuildBanRemoveDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_emojis_update(&mut self, ctx: &Context<'_>, event: &GuildEmojisUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_integrations_update(&mut self, ctx: &Context<'_>, event: &GuildIntegrationsUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_scheduled_event_create(&mut self, ctx: &Context<'_>, event: &GuildScheduledEventCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_scheduled_event_update(&mut self, ctx: &Context<'_>, event: &GuildScheduledEventUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_scheduled_event_delete(&mut self, ctx: &Context<'_>, event: &GuildScheduledEventDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_scheduled_event_user_add(&mut self, ctx: &Context<'_>, event: &GuildScheduledEventUserAddDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_scheduled_event_user_remove(&mut self, ctx: &Context<'_>, event: &GuildScheduledEventUserRemoveDispatch) -> Result<EventFlow, Error>;
+    async fn on_auto_moderation_rule_create(&mut self, ctx: &Context<'_>, event: &AutoModerationRuleCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_auto_moderation_rule_update(&mut self, ctx: &Context<'_>, event: &AutoModerationRuleUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_auto_moderation_rule_delete(&mut self, ctx: &Context<'_>, event: &AutoModerationRuleDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_auto_moderation_action_execution(&mut self, ctx: &Context<'_>, event: &AutoModerationActionExecutionDispatch) -> Result<EventFlow, Error>;
+    async fn on_interaction_create(&mut self, ctx: &Context<'_>, event: &InteractionCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_member_add(&mut self, ctx: &Context<'_>, event: &GuildMemberAddDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_member_remove(&mut self, ctx: &Context<'_>, event: &GuildMemberRemoveDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_member_update(&mut self, ctx: &Context<'_>, event: &GuildMemberUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_members_chunk(&mut self, ctx: &Context<'_>, event: &GuildMembersChunkDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_role_create(&mut self, ctx: &Context<'_>, event: &GuildRoleCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_role_update(&mut self, ctx: &Context<'_>, event: &GuildRoleUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_guild_role_delete(&mut self, ctx: &Context<'_>, event: &GuildRoleDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_invite_create(&mut self, ctx: &Context<'_>, event: &InviteCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_invite_delete(&mut self, ctx: &Context<'_>, event: &InviteDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_message_create(&mut self, ctx: &Context<'_>, event: &MessageCreateDispatch) -> Result<EventFlow, Error>;
+    async fn on_message_update(&mut self, ctx: &Context<'_>, event: &MessageUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_message_delete(&mut self, ctx: &Context<'_>, event: &MessageDeleteDispatch) -> Result<EventFlow, Error>;
+    async fn on_message_delete_bulk(&mut self, ctx: &Context<'_>, event: &MessageDeleteBulkDispatch) -> Result<EventFlow, Error>;
+    async fn on_reaction_add(&mut self, ctx: &Context<'_>, event: &MessageReactionAddDispatch) -> Result<EventFlow, Error>;
+    async fn on_reaction_remove(&mut self, ctx: &Context<'_>, event: &MessageReactionRemoveDispatch) -> Result<EventFlow, Error>;
+    async fn on_reaction_remove_all(&mut self, ctx: &Context<'_>, event: &MessageReactionRemoveAllDispatch) -> Result<EventFlow, Error>;
+    async fn on_reaction_remove_emoji(&mut self, ctx: &Context<'_>, event: &MessageReactionRemoveEmojiDispatch) -> Result<EventFlow, Error>;
+    async fn on_presence_update(&mut self, ctx: &Context<'_>, event: &PresenceUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_typing_start(&mut self, ctx: &Context<'_>, event: &TypingStartDispatch) -> Result<EventFlow, Error>;
+    async fn on_user_update(&mut self, ctx: &Context<'_>, event: &UserUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_voice_state_update(&mut self, ctx: &Context<'_>, event: &VoiceStateUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_voice_server_update(&mut self, ctx: &Context<'_>, event: &VoiceServerUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_webhooks_update(&mut self, ctx: &Context<'_>, event: &WebhooksUpdateDispatch) -> Result<EventFlow, Error>;
+    async fn on_raw(&mut self, ctx: &Context<'_>, event: &RawDispatch) -> Result<EventFlow, Error>;
+    async fn on_cache_resync(&mut self, ctx: &Context<'_>, event: &CacheResyncDispatch) -> Result<EventFlow, Error>;
+
+    /// The events for which the state has at least one listener.
+    fn listened_events(&self) -> Vec<&'static str>;
+
+    /// See [Initializable::requirements](automate::events::Initializable::requirements).
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+    }
+
+    /// See [Initializable::setup](automate::events::Initializable::setup).
+    fn setup(&mut self, dependencies: &mut Dependencies) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 /// Internal type used to allow cloning the
@@ -94,11 +317,143 @@ impl Clone for Box<dyn State> {
     }
 }
 
+/// Keeps one instance of the wrapped state for each
+/// guild, created by cloning the given state when the
+/// first event of the guild is received.
+///
+/// Events that do not belong to any guild, such as the
+/// ready event and events from private channels, are
+/// all sent to a single instance.
+///
+/// Usually created through the `stateful_per_guild!` macro.
+#[derive(Clone)]
+pub struct PerGuild<T> {
+    template: T,
+    states: HashMap<Option<Snowflake>, T>,
+}
+
+impl<T: Clone> PerGuild<T> {
+    pub fn new(template: T) -> PerGuild<T> {
+        PerGuild {
+            template,
+            states: HashMap::new(),
+        }
+    }
+
+    fn state(&mut self, guild: Option<Snowflake>) -> &mut T {
+        let template = &self.template;
+        self.states.entry(guild).or_insert_with(|| template.clone())
+    }
+}
+
+/// The guild an event belongs to, if any.
+pub(crate) trait EventGuild {
+    fn event_guild(&self) -> Option<Snowflake>;
+}
+
+impl EventGuild for ResumedDispatch {
+    fn event_guild(&self) -> Option<Snowflake> {
+        None
+    }
+}
+
+macro_rules! per_guild {
+    ($($fn_name:ident: $dispatch:ty => |$event:ident| $guild:expr),*) => {
+        $(
+            impl EventGuild for $dispatch {
+                fn event_guild(&self) -> Option<Snowflake> {
+                    let $event = self;
+                    $guild
+                }
+            }
+        )*
+
+        #[async_trait]
+        impl<T: State + Clone> State for PerGuild<T> {
+            fn listened_events(&self) -> Vec<&'static str> {
+                self.template.listened_events()
+            }
+
+            fn requirements(&self) -> Requirements {
+                self.template.requirements()
+            }
+
+            /// Sets up the template before it is
+            /// cloned for each guild.
+            fn setup(&mut self, dependencies: &mut Dependencies) -> Result<(), Error> {
+                self.template.setup(dependencies)
+            }
+
+            $(
+                async fn $fn_name(&mut self, ctx: &Context<'_>, $event: &$dispatch) -> Result<EventFlow, Error> {
+                    self.state($event.event_guild()).$fn_name(ctx, $event).await
+                }
+            )*
+        }
+    }
+}
+
+per_guild!(
+    on_ready: ReadyDispatch => |e| None,
+    on_channel_create: ChannelCreateDispatch => |e| e.0.guild_id(),
+    on_channel_update: ChannelUpdateDispatch => |e| e.0.guild_id(),
+    on_channel_delete: ChannelDeleteDispatch => |e| e.0.guild_id(),
+    on_channel_pins_update: ChannelPinsUpdateDispatch => |e| e.guild_id,
+    on_thread_create: ThreadCreateDispatch => |e| e.0.guild_id,
+    on_thread_update: ThreadUpdateDispatch => |e| e.0.guild_id,
+    on_thread_delete: ThreadDeleteDispatch => |e| Some(e.guild_id),
+    on_thread_list_sync: ThreadListSyncDispatch => |e| Some(e.guild_id),
+    on_thread_member_update: ThreadMemberUpdateDispatch => |e| e.0.guild_id,
+    on_thread_members_update: ThreadMembersUpdateDispatch => |e| Some(e.guild_id),
+    on_guild_create: GuildCreateDispatch => |e| Some(e.0.id),
+    on_guild_update: GuildUpdateDispatch => |e| Some(e.0.id),
+    on_guild_delete: GuildDeleteDispatch => |e| Some(e.id),
+    on_guild_ban_add: GuildBanAddDispatch => |e| Some(e.guild_id),
+    on_guild_ban_remove: GuildBanRemoveDispatch => |e| Some(e.guild_id),
+    on_guild_emojis_update: GuildEmojisUpdateDispatch => |e| Some(e.guild_id),
+    on_guild_integrations_update: GuildIntegrationsUpdateDispatch => |e| Some(e.guild_id),
+    on_guild_scheduled_event_create: GuildScheduledEventCreateDispatch => |e| Some(e.0.guild_id),
+    on_guild_scheduled_event_update: GuildScheduledEventUpdateDispatch => |e| Some(e.0.guild_id),
+    on_guild_scheduled_event_delete: GuildScheduledEventDeleteDispatch => |e| Some(e.0.guild_id),
+    on_guild_scheduled_event_user_add: GuildScheduledEventUserAddDispatch => |e| Some(e.guild_id),
+    on_guild_scheduled_event_user_remove: GuildScheduledEventUserRemoveDispatch => |e| Some(e.guild_id),
+    on_auto_moderation_rule_create: AutoModerationRuleCreateDispatch => |e| Some(e.0.guild_id),
+    on_auto_moderation_rule_update: AutoModerationRuleUpdateDispatch => |e| Some(e.0.guild_id),
+    on_auto_moderation_rule_delete: AutoModerationRuleDeleteDispatch => |e| Some(e.0.guild_id),
+    on_auto_moderation_action_execution: AutoModerationActionExecutionDispatch => |e| Some(e.guild_id),
+    on_interaction_create: InteractionCreateDispatch => |e| e.0.guild_id,
+    on_guild_member_add: GuildMemberAddDispatch => |e| Some(e.guild_id),
+    on_guild_member_remove: GuildMemberRemoveDispatch => |e| Some(e.guild_id),
+    on_guild_member_update: GuildMemberUpdateDispatch => |e| Some(e.guild_id),
+    on_guild_members_chunk: GuildMembersChunkDispatch => |e| Some(e.guild_id),
+    on_guild_role_create: GuildRoleCreateDispatch => |e| Some(e.guild_id),
+    on_guild_role_update: GuildRoleUpdateDispatch => |e| Some(e.guild_id),
+    on_guild_role_delete: GuildRoleDeleteDispatch => |e| Some(e.guild_id),
+    on_invite_create: InviteCreateDispatch => |e| Some(e.guild_id),
+    on_invite_delete: InviteDeleteDispatch => |e| Some(e.guild_id),
+    on_message_create: MessageCreateDispatch => |e| e.0.guild_id,
+    on_message_update: MessageUpdateDispatch => |e| e.0.guild_id,
+    on_message_delete: MessageDeleteDispatch => |e| e.guild_id,
+    on_message_delete_bulk: MessageDeleteBulkDispatch => |e| e.guild_id,
+    on_reaction_add: MessageReactionAddDispatch => |e| e.guild_id,
+    on_reaction_remove: MessageReactionRemoveDispatch => |e| e.guild_id,
+    on_reaction_remove_all: MessageReactionRemoveAllDispatch => |e| e.guild_id,
+    on_reaction_remove_emoji: MessageReactionRemoveEmojiDispatch => |e| e.guild_id,
+    on_presence_update: PresenceUpdateDispatch => |e| Some(e.0.guild_id),
+    on_typing_start: TypingStartDispatch => |e| e.guild_id,
+    on_user_update: UserUpdateDispatch => |e| None,
+    on_voice_state_update: VoiceStateUpdateDispatch => |e| e.0.guild_id,
+    on_voice_server_update: VoiceServerUpdateDispatch => |e| Some(e.guild_id),
+    on_webhooks_update: WebhooksUpdateDispatch => |e| Some(e.guild_id),
+    on_raw: RawDispatch => |e| e.data.get("guild_id").and_then(|id| id.as_str()).and_then(|id| id.parse().ok()).map(Snowflake),
+    on_cache_resync: CacheResyncDispatch => |e| None
+);
+
 macro_rules! fn_types {
     (($fn:ident, $slf:ident, $slfmut: ident), $ty:ty) => {
-        pub type $fn = for<'a> fn(&'a Context<'_>, &'a $ty) -> Pin<Box<dyn Future<Output=Result<(), Error>> + Send + 'a>>;
-        pub type $slf<T> = for<'a> fn(&'a T, &'a Context<'_>, &'a $ty) -> Pin<Box<dyn Future<Output=Result<(), Error>> + Send + 'a>>;
-        pub type $slfmut<T> = for<'a> fn(&'a mut T, &'a Context<'_>, &'a $ty) -> Pin<Box<dyn Future<Output=Result<(), Error>> + Send + 'a>>;
+        pub type $fn = for<'a> fn(&'a Context<'_>, &'a $ty) -> Pin<Box<dyn Future<Output=Result<EventFlow, Error>> + Send + 'a>>;
+        pub type $slf<T> = for<'a> fn(&'a T, &'a Context<'_>, &'a $ty) -> Pin<Box<dyn Future<Output=Result<EventFlow, Error>> + Send + 'a>>;
+        pub type $slfmut<T> = for<'a> fn(&'a mut T, &'a Context<'_>, &'a $ty) -> Pin<Box<dyn Future<Output=Result<EventFlow, Error>> + Send + 'a>>;
     }
 }
 
@@ -107,6 +462,12 @@ fn_types!((ChannelCreate, ChannelCreateSelf, ChannelCreateSelfMut), ChannelCreat
 fn_types!((ChannelUpdate, ChannelUpdateSelf, ChannelUpdateSelfMut), ChannelUpdateDispatch);
 fn_types!((ChannelDelete, ChannelDeleteSelf, ChannelDeleteSelfMut), ChannelDeleteDispatch);
 fn_types!((ChannelPinsUpdate, ChannelPinsUpdateSelf, ChannelPinsUpdateSelfMut), ChannelPinsUpdateDispatch);
+fn_types!((ThreadCreate, ThreadCreateSelf, ThreadCreateSelfMut), ThreadCreateDispatch);
+fn_types!((ThreadUpdate, ThreadUpdateSelf, ThreadUpdateSelfMut), ThreadUpdateDispatch);
+fn_types!((ThreadDelete, ThreadDeleteSelf, ThreadDeleteSelfMut), ThreadDeleteDispatch);
+fn_types!((ThreadListSync, ThreadListSyncSelf, ThreadListSyncSelfMut), ThreadListSyncDispatch);
+fn_types!((ThreadMemberUpdate, ThreadMemberUpdateSelf, ThreadMemberUpdateSelfMut), ThreadMemberUpdateDispatch);
+fn_types!((ThreadMembersUpdate, ThreadMembersUpdateSelf, ThreadMembersUpdateSelfMut), ThreadMembersUpdateDispatch);
 fn_types!((GuildCreate, GuildCreateSelf, GuildCreateSelfMut), GuildCreateDispatch);
 fn_types!((GuildUpdate, GuildUpdateSelf, GuildUpdateSelfMut), GuildUpdateDispatch);
 fn_types!((GuildDelete, GuildDeleteSelf, GuildDeleteSelfMut), GuildDeleteDispatch);
@@ -114,6 +475,16 @@ fn_types!((GuildBanAdd, GuildBanAddSelf, GuildBanAddSelfMut), GuildBanAddDispatc
 fn_types!((GuildBanRemove, GuildBanRemoveSelf, GuildBanRemoveSelfMut), GuildBanRemoveDispatch);
 fn_types!((GuildEmojisUpdate, GuildEmojisUpdateSelf, GuildEmojisUpdateSelfMut), GuildEmojisUpdateDispatch);
 fn_types!((GuildIntegrationsUpdate, GuildIntegrationsUpdateSelf, GuildIntegrationsUpdateSelfMut), GuildIntegrationsUpdateDispatch);
+fn_types!((GuildScheduledEventCreate, GuildScheduledEventCreateSelf, GuildScheduledEventCreateSelfMut), GuildScheduledEventCreateDispatch);
+fn_types!((GuildScheduledEventUpdate, GuildScheduledEventUpdateSelf, GuildScheduledEventUpdateSelfMut), GuildScheduledEventUpdateDispatch);
+fn_types!((GuildScheduledEventDelete, GuildScheduledEventDeleteSelf, GuildScheduledEventDeleteSelfMut), GuildScheduledEventDeleteDispatch);
+fn_types!((GuildScheduledEventUserAdd, GuildScheduledEventUserAddSelf, GuildScheduledEventUserAddSelfMut), GuildScheduledEventUserAddDispatch);
+fn_types!((GuildScheduledEventUserRemove, GuildScheduledEventUserRemoveSelf, GuildScheduledEventUserRemoveSelfMut), GuildScheduledEventUserRemoveDispatch);
+fn_types!((AutoModerationRuleCreate, AutoModerationRuleCreateSelf, AutoModerationRuleCreateSelfMut), AutoModerationRuleCreateDispatch);
+fn_types!((AutoModerationRuleUpdate, AutoModerationRuleUpdateSelf, AutoModerationRuleUpdateSelfMut), AutoModerationRuleUpdateDispatch);
+fn_types!((AutoModerationRuleDelete, AutoModerationRuleDeleteSelf, AutoModerationRuleDeleteSelfMut), AutoModerationRuleDeleteDispatch);
+fn_types!((AutoModerationActionExecution, AutoModerationActionExecutionSelf, AutoModerationActionExecutionSelfMut), AutoModerationActionExecutionDispatch);
+fn_types!((InteractionCreate, InteractionCreateSelf, InteractionCreateSelfMut), InteractionCreateDispatch);
 fn_types!((GuildMemberAdd, GuildMemberAddSelf, GuildMemberAddSelfMut), GuildMemberAddDispatch);
 fn_types!((GuildMemberRemove, GuildMemberRemoveSelf, GuildMemberRemoveSelfMut), GuildMemberRemoveDispatch);
 fn_types!((GuildMemberUpdate, GuildMemberUpdateSelf, GuildMemberUpdateSelfMut), GuildMemberUpdateDispatch);
@@ -137,6 +508,8 @@ fn_types!((UserUpdate, UserUpdateSelf, UserUpdateSelfMut), UserUpdateDispatch);
 fn_types!((VoiceStateUpdate, VoiceStateUpdateSelf, VoiceStateUpdateSelfMut), VoiceStateUpdateDispatch);
 fn_types!((VoiceServerUpdate, VoiceServerUpdateSelf, VoiceServerUpdateSelfMut), VoiceServerUpdateDispatch);
 fn_types!((WebhooksUpdate, WebhooksUpdateSelf, WebhooksUpdateSelfMut), WebhooksUpdateDispatch);
+fn_types!((Raw, RawSelf, RawSelfMut), RawDispatch);
+fn_types!((CacheResync, CacheResyncSelf, CacheResyncSelfMut), CacheResyncDispatch);
 
 macro_rules! container {
     ($($ty:ident -> $var:ident),*) => {
@@ -144,7 +517,7 @@ macro_rules! container {
         pub enum ListenerType {
             Stateful(Box<dyn State>),
             $(
-             $ty($ty),
+             $ty($ty, u8),
             )*
         }
 
@@ -153,7 +526,7 @@ macro_rules! container {
             pub(crate) stateful_listeners: Vec<Box<dyn State>>,
 
             $(
-             pub(crate) $var: Vec<$ty>,
+             pub(crate) $var: Vec<($ty, u8)>,
             )*
         }
 
@@ -164,11 +537,57 @@ macro_rules! container {
                         ListenerType::Stateful(l) => self.stateful_listeners.push(l),
 
                         $(
-                         ListenerType::$ty(l) => self.$var.push(l),
+                         ListenerType::$ty(l, p) => insert_sorted(&mut self.$var, l, p),
                         )*
                     }
                 }
             }
+
+            /// Orders the states according to their requirements
+            /// and sets them up, must be called on the listeners
+            /// of each shard before dispatching events.
+            pub(crate) fn setup(&mut self, #[cfg(feature = "storage")] storages: &StorageContainer) -> Result<(), Error> {
+                let requirements: Vec<Requirements> = self.stateful_listeners.iter()
+                    .map(|state| state.requirements())
+                    .collect();
+
+                #[cfg(feature = "storage")]
+                for (id, name) in requirements.iter().flat_map(|r| &r.storages) {
+                    if !storages.contains(*id) {
+                        return Error::err(format!("Storage {} is required by a state but was never initialized", name));
+                    }
+                }
+
+                let order = dependency_order(&requirements)?;
+                let mut states: Vec<Option<Box<dyn State>>> = self.stateful_listeners.drain(..).map(Some).collect();
+                for i in order {
+                    self.stateful_listeners.push(states[i].take().unwrap());
+                }
+
+                let mut dependencies = Dependencies::default();
+                for state in &mut self.stateful_listeners {
+                    state.setup(&mut dependencies)?;
+                }
+
+                Ok(())
+            }
+
+            /// The events for which at least one listener is registered.
+            pub(crate) fn events(&self) -> Vec<&'static str> {
+                let mut events: Vec<&'static str> = self.stateful_listeners.iter()
+                    .flat_map(|l| l.listened_events())
+                    .collect();
+
+                $(
+                 if !self.$var.is_empty() {
+                     events.push(stringify!($var));
+                 }
+                )*
+
+                events.sort_unstable();
+                events.dedup();
+                events
+            }
         }
     }
 }
@@ -179,6 +598,12 @@ container!(
     ChannelUpdate -> channel_update,
     ChannelDelete -> channel_delete,
     ChannelPinsUpdate -> channel_pins_update,
+    ThreadCreate -> thread_create,
+    ThreadUpdate -> thread_update,
+    ThreadDelete -> thread_delete,
+    ThreadListSync -> thread_list_sync,
+    ThreadMemberUpdate -> thread_member_update,
+    ThreadMembersUpdate -> thread_members_update,
     GuildCreate -> guild_create,
     GuildUpdate -> guild_update,
     GuildDelete -> guild_delete,
@@ -186,6 +611,16 @@ container!(
     GuildBanRemove -> guild_ban_remove,
     GuildEmojisUpdate -> guild_emojis_update,
     GuildIntegrationsUpdate -> guild_integrations_update,
+    GuildScheduledEventCreate -> guild_scheduled_event_create,
+    GuildScheduledEventUpdate -> guild_scheduled_event_update,
+    GuildScheduledEventDelete -> guild_scheduled_event_delete,
+    GuildScheduledEventUserAdd -> guild_scheduled_event_user_add,
+    GuildScheduledEventUserRemove -> guild_scheduled_event_user_remove,
+    AutoModerationRuleCreate -> auto_moderation_rule_create,
+    AutoModerationRuleUpdate -> auto_moderation_rule_update,
+    AutoModerationRuleDelete -> auto_moderation_rule_delete,
+    AutoModerationActionExecution -> auto_moderation_action_execution,
+    InteractionCreate -> interaction_create,
     GuildMemberAdd -> guild_member_add,
     GuildMemberRemove -> guild_member_remove,
     GuildMemberUpdate -> guild_member_update,
@@ -208,235 +643,567 @@ container!(
     UserUpdate -> user_update,
     VoiceStateUpdate -> voice_state_update,
     VoiceServerUpdate -> voice_server_update,
-    WebhooksUpdate -> webhooks_update
+    WebhooksUpdate -> webhooks_update,
+    Raw -> raw,
+    CacheResync -> cache_resync
 );
 
 #[doc(hidden)]
 pub enum StatefulListener<T> {
-    Ready(ReadySelf<T>),
-    ChannelCreate(ChannelCreateSelf<T>),
-    ChannelUpdate(ChannelUpdateSelf<T>),
-    ChannelDelete(ChannelDeleteSelf<T>),
-    ChannelPinsUpdate(ChannelPinsUpdateSelf<T>),
-    GuildCreate(GuildCreateSelf<T>),
-    GuildUpdate(GuildUpdateSelf<T>),
-    GuildDelete(GuildDeleteSelf<T>),
-    GuildBanAdd(GuildBanAddSelf<T>),
-    GuildBanRemove(GuildBanRemoveSelf<T>),
-    GuildEmojisUpdate(GuildEmojisUpdateSelf<T>),
-    GuildIntegrationsUpdate(GuildIntegrationsUpdateSelf<T>),
-    GuildMemberAdd(GuildMemberAddSelf<T>),
-    GuildMemberRemove(GuildMemberRemoveSelf<T>),
-    GuildMemberUpdate(GuildMemberUpdateSelf<T>),
-    GuildMembersChunk(GuildMembersChunkSelf<T>),
-    GuildRoleCreate(GuildRoleCreateSelf<T>),
-    GuildRoleUpdate(GuildRoleUpdateSelf<T>),
-    GuildRoleDelete(GuildRoleDeleteSelf<T>),
-    InviteCreate(InviteCreateSelf<T>),
-    InviteDelete(InviteDeleteSelf<T>),
-    MessageCreate(MessageCreateSelf<T>),
-    MessageUpdate(MessageUpdateSelf<T>),
-    MessageDelete(MessageDeleteSelf<T>),
-    MessageDeleteBulk(MessageDeleteBulkSelf<T>),
-    MessageReactionAdd(MessageReactionAddSelf<T>),
-    MessageReactionRemove(MessageReactionRemoveSelf<T>),
-    MessageReactionRemoveAll(MessageReactionRemoveAllSelf<T>),
-    MessageReactionRemoveEmoji(MessageReactionRemoveEmojiSelf<T>),
-    PresenceUpdate(PresenceUpdateSelf<T>),
-    TypingStart(TypingStartSelf<T>),
-    UserUpdate(UserUpdateSelf<T>),
-    VoiceStateUpdate(VoiceStateUpdateSelf<T>),
-    VoiceServerUpdate(VoiceServerUpdateSelf<T>),
-    WebhooksUpdate(WebhooksUpdateSelf<T>),
-
-    ReadyMut(ReadySelfMut<T>),
-    ChannelCreateMut(ChannelCreateSelfMut<T>),
-    ChannelUpdateMut(ChannelUpdateSelfMut<T>),
-    ChannelDeleteMut(ChannelDeleteSelfMut<T>),
-    ChannelPinsUpdateMut(ChannelPinsUpdateSelfMut<T>),
-    GuildCreateMut(GuildCreateSelfMut<T>),
-    GuildUpdateMut(GuildUpdateSelfMut<T>),
-    GuildDeleteMut(GuildDeleteSelfMut<T>),
-    GuildBanAddMut(GuildBanAddSelfMut<T>),
-    GuildBanRemoveMut(GuildBanRemoveSelfMut<T>),
-    GuildEmojisUpdateMut(GuildEmojisUpdateSelfMut<T>),
-    GuildIntegrationsUpdateMut(GuildIntegrationsUpdateSelfMut<T>),
-    GuildMemberAddMut(GuildMemberAddSelfMut<T>),
-    GuildMemberRemoveMut(GuildMemberRemoveSelfMut<T>),
-    GuildMemberUpdateMut(GuildMemberUpdateSelfMut<T>),
-    GuildMembersChunkMut(GuildMembersChunkSelfMut<T>),
-    GuildRoleCreateMut(GuildRoleCreateSelfMut<T>),
-    GuildRoleUpdateMut(GuildRoleUpdateSelfMut<T>),
-    GuildRoleDeleteMut(GuildRoleDeleteSelfMut<T>),
-    InviteCreateMut(InviteCreateSelfMut<T>),
-    InviteDeleteMut(InviteDeleteSelfMut<T>),
-    MessageCreateMut(MessageCreateSelfMut<T>),
-    MessageUpdateMut(MessageUpdateSelfMut<T>),
-    MessageDeleteMut(MessageDeleteSelfMut<T>),
-    MessageDeleteBulkMut(MessageDeleteBulkSelfMut<T>),
-    MessageReactionAddMut(MessageReactionAddSelfMut<T>),
-    MessageReactionRemoveMut(MessageReactionRemoveSelfMut<T>),
-    MessageReactionRemoveAllMut(MessageReactionRemoveAllSelfMut<T>),
-    MessageReactionRemoveEmojiMut(MessageReactionRemoveEmojiSelfMut<T>),
-    PresenceUpdateMut(PresenceUpdateSelfMut<T>),
-    TypingStartMut(TypingStartSelfMut<T>),
-    UserUpdateMut(UserUpdateSelfMut<T>),
-    VoiceStateUpdateMut(VoiceStateUpdateSelfMut<T>),
-    VoiceServerUpdateMut(VoiceServerUpdateSelfMut<T>),
-    WebhooksUpdateMut(WebhooksUpdateSelfMut<T>),
+    Ready(ReadySelf<T>, u8),
+    ChannelCreate(ChannelCreateSelf<T>, u8),
+    ChannelUpdate(ChannelUpdateSelf<T>, u8),
+    ChannelDelete(ChannelDeleteSelf<T>, u8),
+    ChannelPinsUpdate(ChannelPinsUpdateSelf<T>, u8),
+    ThreadCreate(ThreadCreateSelf<T>, u8),
+    ThreadUpdate(ThreadUpdateSelf<T>, u8),
+    ThreadDelete(ThreadDeleteSelf<T>, u8),
+    ThreadListSync(ThreadListSyncSelf<T>, u8),
+    ThreadMemberUpdate(ThreadMemberUpdateSelf<T>, u8),
+    ThreadMembersUpdate(ThreadMembersUpdateSelf<T>, u8),
+    GuildCreate(GuildCreateSelf<T>, u8),
+    GuildUpdate(GuildUpdateSelf<T>, u8),
+    GuildDelete(GuildDeleteSelf<T>, u8),
+    GuildBanAdd(GuildBanAddSelf<T>, u8),
+    GuildBanRemove(GuildBanRemoveSelf<T>, u8),
+    GuildEmojisUpdate(GuildEmojisUpdateSelf<T>, u8),
+    GuildIntegrationsUpdate(GuildIntegrationsUpdateSelf<T>, u8),
+    GuildScheduledEventCreate(GuildScheduledEventCreateSelf<T>, u8),
+    GuildScheduledEventUpdate(GuildScheduledEventUpdateSelf<T>, u8),
+    GuildScheduledEventDelete(GuildScheduledEventDeleteSelf<T>, u8),
+    GuildScheduledEventUserAdd(GuildScheduledEventUserAddSelf<T>, u8),
+    GuildScheduledEventUserRemove(GuildScheduledEventUserRemoveSelf<T>, u8),
+    AutoModerationRuleCreate(AutoModerationRuleCreateSelf<T>, u8),
+    AutoModerationRuleUpdate(AutoModerationRuleUpdateSelf<T>, u8),
+    AutoModerationRuleDelete(AutoModerationRuleDeleteSelf<T>, u8),
+    AutoModerationActionExecution(AutoModerationActionExecutionSelf<T>, u8),
+    InteractionCreate(InteractionCreateSelf<T>, u8),
+    GuildMemberAdd(GuildMemberAddSelf<T>, u8),
+    GuildMemberRemove(GuildMemberRemoveSelf<T>, u8),
+    GuildMemberUpdate(GuildMemberUpdateSelf<T>, u8),
+    GuildMembersChunk(GuildMembersChunkSelf<T>, u8),
+    GuildRoleCreate(GuildRoleCreateSelf<T>, u8),
+    GuildRoleUpdate(GuildRoleUpdateSelf<T>, u8),
+    GuildRoleDelete(GuildRoleDeleteSelf<T>, u8),
+    InviteCreate(InviteCreateSelf<T>, u8),
+    InviteDelete(InviteDeleteSelf<T>, u8),
+    MessageCreate(MessageCreateSelf<T>, u8),
+    MessageUpdate(MessageUpdateSelf<T>, u8),
+    MessageDelete(MessageDeleteSelf<T>, u8),
+    MessageDeleteBulk(MessageDeleteBulkSelf<T>, u8),
+    MessageReactionAdd(MessageReactionAddSelf<T>, u8),
+    MessageReactionRemove(MessageReactionRemoveSelf<T>, u8),
+    MessageReactionRemoveAll(MessageReactionRemoveAllSelf<T>, u8),
+    MessageReactionRemoveEmoji(MessageReactionRemoveEmojiSelf<T>, u8),
+    PresenceUpdate(PresenceUpdateSelf<T>, u8),
+    TypingStart(TypingStartSelf<T>, u8),
+    UserUpdate(UserUpdateSelf<T>, u8),
+    VoiceStateUpdate(VoiceStateUpdateSelf<T>, u8),
+    VoiceServerUpdate(VoiceServerUpdateSelf<T>, u8),
+    WebhooksUpdate(WebhooksUpdateSelf<T>, u8),
+    Raw(RawSelf<T>, u8),
+    CacheResync(CacheResyncSelf<T>, u8),
+
+    ReadyMut(ReadySelfMut<T>, u8),
+    ChannelCreateMut(ChannelCreateSelfMut<T>, u8),
+    ChannelUpdateMut(ChannelUpdateSelfMut<T>, u8),
+    ChannelDeleteMut(ChannelDeleteSelfMut<T>, u8),
+    ChannelPinsUpdateMut(ChannelPinsUpdateSelfMut<T>, u8),
+    ThreadCreateMut(ThreadCreateSelfMut<T>, u8),
+    ThreadUpdateMut(ThreadUpdateSelfMut<T>, u8),
+    ThreadDeleteMut(ThreadDeleteSelfMut<T>, u8),
+    ThreadListSyncMut(ThreadListSyncSelfMut<T>, u8),
+    ThreadMemberUpdateMut(ThreadMemberUpdateSelfMut<T>, u8),
+    ThreadMembersUpdateMut(ThreadMembersUpdateSelfMut<T>, u8),
+    GuildCreateMut(GuildCreateSelfMut<T>, u8),
+    GuildUpdateMut(GuildUpdateSelfMut<T>, u8),
+    GuildDeleteMut(GuildDeleteSelfMut<T>, u8),
+    GuildBanAddMut(GuildBanAddSelfMut<T>, u8),
+    GuildBanRemoveMut(GuildBanRemoveSelfMut<T>, u8),
+    GuildEmojisUpdateMut(GuildEmojisUpdateSelfMut<T>, u8),
+    GuildIntegrationsUpdateMut(GuildIntegrationsUpdateSelfMut<T>, u8),
+    GuildScheduledEventCreateMut(GuildScheduledEventCreateSelfMut<T>, u8),
+    GuildScheduledEventUpdateMut(GuildScheduledEventUpdateSelfMut<T>, u8),
+    GuildScheduledEventDeleteMut(GuildScheduledEventDeleteSelfMut<T>, u8),
+    GuildScheduledEventUserAddMut(GuildScheduledEventUserAddSelfMut<T>, u8),
+    GuildScheduledEventUserRemoveMut(GuildScheduledEventUserRemoveSelfMut<T>, u8),
+    AutoModerationRuleCreateMut(AutoModerationRuleCreateSelfMut<T>, u8),
+    AutoModerationRuleUpdateMut(AutoModerationRuleUpdateSelfMut<T>, u8),
+    AutoModerationRuleDeleteMut(AutoModerationRuleDeleteSelfMut<T>, u8),
+    AutoModerationActionExecutionMut(AutoModerationActionExecutionSelfMut<T>, u8),
+    InteractionCreateMut(InteractionCreateSelfMut<T>, u8),
+    GuildMemberAddMut(GuildMemberAddSelfMut<T>, u8),
+    GuildMemberRemoveMut(GuildMemberRemoveSelfMut<T>, u8),
+    GuildMemberUpdateMut(GuildMemberUpdateSelfMut<T>, u8),
+    GuildMembersChunkMut(GuildMembersChunkSelfMut<T>, u8),
+    GuildRoleCreateMut(GuildRoleCreateSelfMut<T>, u8),
+    GuildRoleUpdateMut(GuildRoleUpdateSelfMut<T>, u8),
+    GuildRoleDeleteMut(GuildRoleDeleteSelfMut<T>, u8),
+    InviteCreateMut(InviteCreateSelfMut<T>, u8),
+    InviteDeleteMut(InviteDeleteSelfMut<T>, u8),
+    MessageCreateMut(MessageCreateSelfMut<T>, u8),
+    MessageUpdateMut(MessageUpdateSelfMut<T>, u8),
+    MessageDeleteMut(MessageDeleteSelfMut<T>, u8),
+    MessageDeleteBulkMut(MessageDeleteBulkSelfMut<T>, u8),
+    MessageReactionAddMut(MessageReactionAddSelfMut<T>, u8),
+    MessageReactionRemoveMut(MessageReactionRemoveSelfMut<T>, u8),
+    MessageReactionRemoveAllMut(MessageReactionRemoveAllSelfMut<T>, u8),
+    MessageReactionRemoveEmojiMut(MessageReactionRemoveEmojiSelfMut<T>, u8),
+    PresenceUpdateMut(PresenceUpdateSelfMut<T>, u8),
+    TypingStartMut(TypingStartSelfMut<T>, u8),
+    UserUpdateMut(UserUpdateSelfMut<T>, u8),
+    VoiceStateUpdateMut(VoiceStateUpdateSelfMut<T>, u8),
+    VoiceServerUpdateMut(VoiceServerUpdateSelfMut<T>, u8),
+    WebhooksUpdateMut(WebhooksUpdateSelfMut<T>, u8),
+    RawMut(RawSelfMut<T>, u8),
+    CacheResyncMut(CacheResyncSelfMut<T>, u8),
+}
+
+lazy_static::lazy_static! {
+    /// Listeners of each state type, created the first
+    /// time an event is sent to a state of the type.
+    static ref STATEFUL_LISTENERS: RwLock<HashMap<TypeId, &'static (dyn Any + Send + Sync)>> = RwLock::default();
 }
 
-#[derive(Default, Clone)]
+/// The listeners of the given state type, kept by type
+/// since statics can not be generic over the state.
+#[doc(hidden)]
+pub fn stateful_listeners<T: Initializable + 'static>() -> &'static StatefulListenerContainer<T> {
+    let id = TypeId::of::<T>();
+
+    if let Some(listeners) = STATEFUL_LISTENERS.read().unwrap().get(&id) {
+        return listeners.downcast_ref().unwrap();
+    }
+
+    let listeners = *STATEFUL_LISTENERS.write().unwrap().entry(id).or_insert_with(|| {
+        let mut listeners = StatefulListenerContainer::<T>::default();
+        listeners.register(T::initialize());
+
+        Box::leak(Box::new(listeners))
+    });
+
+    listeners.downcast_ref().unwrap()
+}
+
+/// A listener method of a state, either taking `&self`
+/// or `&mut self`.
+///
+/// Consecutive shared listeners of an event run
+/// concurrently since they can all borrow the state,
+/// exclusive listeners run alone.
+#[derive(Clone)]
+pub enum StatefulHandler<S, M> {
+    Shared(S),
+    Exclusive(M),
+}
+
+/// Listeners of an event sorted by priority.
+pub type StatefulHandlers<S, M> = Vec<(StatefulHandler<S, M>, u8)>;
+
+/// Runs the shared listeners of a state concurrently.
+#[doc(hidden)]
+pub async fn join_shared<F: Future<Output=Result<EventFlow, Error>>>(listeners: Vec<F>) -> Vec<Result<EventFlow, Error>> {
+    futures::future::join_all(listeners).await
+}
+
+#[derive(Clone)]
 pub struct StatefulListenerContainer<T> {
-    pub ready: Vec<ReadySelf<T>>,
-    pub channel_create: Vec<ChannelCreateSelf<T>>,
-    pub channel_update: Vec<ChannelUpdateSelf<T>>,
-    pub channel_delete: Vec<ChannelDeleteSelf<T>>,
-    pub channel_pins_update: Vec<ChannelPinsUpdateSelf<T>>,
-    pub guild_create: Vec<GuildCreateSelf<T>>,
-    pub guild_update: Vec<GuildUpdateSelf<T>>,
-    pub guild_delete: Vec<GuildDeleteSelf<T>>,
-    pub guild_ban_add: Vec<GuildBanAddSelf<T>>,
-    pub guild_ban_remove: Vec<GuildBanRemoveSelf<T>>,
-    pub guild_emojis_update: Vec<GuildEmojisUpdateSelf<T>>,
-    pub guild_integrations_update: Vec<GuildIntegrationsUpdateSelf<T>>,
-    pub guild_member_add: Vec<GuildMemberAddSelf<T>>,
-    pub guild_member_remove: Vec<GuildMemberRemoveSelf<T>>,
-    pub guild_member_update: Vec<GuildMemberUpdateSelf<T>>,
-    pub guild_members_chunk: Vec<GuildMembersChunkSelf<T>>,
-    pub guild_role_create: Vec<GuildRoleCreateSelf<T>>,
-    pub guild_role_update: Vec<GuildRoleUpdateSelf<T>>,
-    pub guild_role_delete: Vec<GuildRoleDeleteSelf<T>>,
-    pub invite_create: Vec<InviteCreateSelf<T>>,
-    pub invite_delete: Vec<InviteDeleteSelf<T>>,
-    pub message_create: Vec<MessageCreateSelf<T>>,
-    pub message_update: Vec<MessageUpdateSelf<T>>,
-    pub message_delete: Vec<MessageDeleteSelf<T>>,
-    pub message_delete_bulk: Vec<MessageDeleteBulkSelf<T>>,
-    pub reaction_add: Vec<MessageReactionAddSelf<T>>,
-    pub reaction_remove: Vec<MessageReactionRemoveSelf<T>>,
-    pub reaction_remove_all: Vec<MessageReactionRemoveAllSelf<T>>,
-    pub reaction_remove_emoji: Vec<MessageReactionRemoveEmojiSelf<T>>,
-    pub presence_update: Vec<PresenceUpdateSelf<T>>,
-    pub typing_start: Vec<TypingStartSelf<T>>,
-    pub user_update: Vec<UserUpdateSelf<T>>,
-    pub voice_state_update: Vec<VoiceStateUpdateSelf<T>>,
-    pub voice_server_update: Vec<VoiceServerUpdateSelf<T>>,
-    pub webhooks_update: Vec<WebhooksUpdateSelf<T>>,
-
-    pub ready_mut: Vec<ReadySelfMut<T>>,
-    pub channel_create_mut: Vec<ChannelCreateSelfMut<T>>,
-    pub channel_update_mut: Vec<ChannelUpdateSelfMut<T>>,
-    pub channel_delete_mut: Vec<ChannelDeleteSelfMut<T>>,
-    pub channel_pins_update_mut: Vec<ChannelPinsUpdateSelfMut<T>>,
-    pub guild_create_mut: Vec<GuildCreateSelfMut<T>>,
-    pub guild_update_mut: Vec<GuildUpdateSelfMut<T>>,
-    pub guild_delete_mut: Vec<GuildDeleteSelfMut<T>>,
-    pub guild_ban_add_mut: Vec<GuildBanAddSelfMut<T>>,
-    pub guild_ban_remove_mut: Vec<GuildBanRemoveSelfMut<T>>,
-    pub guild_emojis_update_mut: Vec<GuildEmojisUpdateSelfMut<T>>,
-    pub guild_integrations_update_mut: Vec<GuildIntegrationsUpdateSelfMut<T>>,
-    pub guild_member_add_mut: Vec<GuildMemberAddSelfMut<T>>,
-    pub guild_member_remove_mut: Vec<GuildMemberRemoveSelfMut<T>>,
-    pub guild_member_update_mut: Vec<GuildMemberUpdateSelfMut<T>>,
-    pub guild_members_chunk_mut: Vec<GuildMembersChunkSelfMut<T>>,
-    pub guild_role_create_mut: Vec<GuildRoleCreateSelfMut<T>>,
-    pub guild_role_update_mut: Vec<GuildRoleUpdateSelfMut<T>>,
-    pub guild_role_delete_mut: Vec<GuildRoleDeleteSelfMut<T>>,
-    pub invite_create_mut: Vec<InviteCreateSelfMut<T>>,
-    pub invite_delete_mut: Vec<InviteDeleteSelfMut<T>>,
-    pub message_create_mut: Vec<MessageCreateSelfMut<T>>,
-    pub message_update_mut: Vec<MessageUpdateSelfMut<T>>,
-    pub message_delete_mut: Vec<MessageDeleteSelfMut<T>>,
-    pub message_delete_bulk_mut: Vec<MessageDeleteBulkSelfMut<T>>,
-    pub reaction_add_mut: Vec<MessageReactionAddSelfMut<T>>,
-    pub reaction_remove_mut: Vec<MessageReactionRemoveSelfMut<T>>,
-    pub reaction_remove_all_mut: Vec<MessageReactionRemoveAllSelfMut<T>>,
-    pub reaction_remove_emoji_mut: Vec<MessageReactionRemoveEmojiSelfMut<T>>,
-    pub presence_update_mut: Vec<PresenceUpdateSelfMut<T>>,
-    pub typing_start_mut: Vec<TypingStartSelfMut<T>>,
-    pub user_update_mut: Vec<UserUpdateSelfMut<T>>,
-    pub voice_state_update_mut: Vec<VoiceStateUpdateSelfMut<T>>,
-    pub voice_server_update_mut: Vec<VoiceServerUpdateSelfMut<T>>,
-    pub webhooks_update_mut: Vec<WebhooksUpdateSelfMut<T>>,
+    pub ready: StatefulHandlers<ReadySelf<T>, ReadySelfMut<T>>,
+    pub channel_create: StatefulHandlers<ChannelCreateSelf<T>, ChannelCreateSelfMut<T>>,
+    pub channel_update: StatefulHandlers<ChannelUpdateSelf<T>, ChannelUpdateSelfMut<T>>,
+    pub channel_delete: StatefulHandlers<ChannelDeleteSelf<T>, ChannelDeleteSelfMut<T>>,
+    pub channel_pins_update: StatefulHandlers<ChannelPinsUpdateSelf<T>, ChannelPinsUpdateSelfMut<T>>,
+    pub thread_create: StatefulHandlers<ThreadCreateSelf<T>, ThreadCreateSelfMut<T>>,
+    pub thread_update: StatefulHandlers<ThreadUpdateSelf<T>, ThreadUpdateSelfMut<T>>,
+    pub thread_delete: StatefulHandlers<ThreadDeleteSelf<T>, ThreadDeleteSelfMut<T>>,
+    pub thread_list_sync: StatefulHandlers<ThreadListSyncSelf<T>, ThreadListSyncSelfMut<T>>,
+    pub thread_member_update: StatefulHandlers<ThreadMemberUpdateSelf<T>, ThreadMemberUpdateSelfMut<T>>,
+    pub thread_members_update: StatefulHandlers<ThreadMembersUpdateSelf<T>, ThreadMembersUpdateSelfMut<T>>,
+    pub guild_create: StatefulHandlers<GuildCreateSelf<T>, GuildCreateSelfMut<T>>,
+    pub guild_update: StatefulHandlers<GuildUpdateSelf<T>, GuildUpdateSelfMut<T>>,
+    pub guild_delete: StatefulHandlers<GuildDeleteSelf<T>, GuildDeleteSelfMut<T>>,
+    pub guild_ban_add: StatefulHandlers<GuildBanAddSelf<T>, GuildBanAddSelfMut<T>>,
+    pub guild_ban_remove: StatefulHandlers<GuildBanRemoveSelf<T>, GuildBanRemoveSelfMut<T>>,
+    pub guild_emojis_update: StatefulHandlers<GuildEmojisUpdateSelf<T>, GuildEmojisUpdateSelfMut<T>>,
+    pub guild_integrations_update: StatefulHandlers<GuildIntegrationsUpdateSelf<T>, GuildIntegrationsUpdateSelfMut<T>>,
+    pub guild_scheduled_event_create: StatefulHandlers<GuildScheduledEventCreateSelf<T>, GuildScheduledEventCreateSelfMut<T>>,
+    pub guild_scheduled_event_update: StatefulHandlers<GuildScheduledEventUpdateSelf<T>, GuildScheduledEventUpdateSelfMut<T>>,
+    pub guild_scheduled_event_delete: StatefulHandlers<GuildScheduledEventDeleteSelf<T>, GuildScheduledEventDeleteSelfMut<T>>,
+    pub guild_scheduled_event_user_add: StatefulHandlers<GuildScheduledEventUserAddSelf<T>, GuildScheduledEventUserAddSelfMut<T>>,
+    pub guild_scheduled_event_user_remove: StatefulHandlers<GuildScheduledEventUserRemoveSelf<T>, GuildScheduledEventUserRemoveSelfMut<T>>,
+    pub auto_moderation_rule_create: StatefulHandlers<AutoModerationRuleCreateSelf<T>, AutoModerationRuleCreateSelfMut<T>>,
+    pub auto_moderation_rule_update: StatefulHandlers<AutoModerationRuleUpdateSelf<T>, AutoModerationRuleUpdateSelfMut<T>>,
+    pub auto_moderation_rule_delete: StatefulHandlers<AutoModerationRuleDeleteSelf<T>, AutoModerationRuleDeleteSelfMut<T>>,
+    pub auto_moderation_action_execution: StatefulHandlers<AutoModerationActionExecutionSelf<T>, AutoModerationActionExecutionSelfMut<T>>,
+    pub interaction_create: StatefulHandlers<InteractionCreateSelf<T>, InteractionCreateSelfMut<T>>,
+    pub guild_member_add: StatefulHandlers<GuildMemberAddSelf<T>, GuildMemberAddSelfMut<T>>,
+    pub guild_member_remove: StatefulHandlers<GuildMemberRemoveSelf<T>, GuildMemberRemoveSelfMut<T>>,
+    pub guild_member_update: StatefulHandlers<GuildMemberUpdateSelf<T>, GuildMemberUpdateSelfMut<T>>,
+    pub guild_members_chunk: StatefulHandlers<GuildMembersChunkSelf<T>, GuildMembersChunkSelfMut<T>>,
+    pub guild_role_create: StatefulHandlers<GuildRoleCreateSelf<T>, GuildRoleCreateSelfMut<T>>,
+    pub guild_role_update: StatefulHandlers<GuildRoleUpdateSelf<T>, GuildRoleUpdateSelfMut<T>>,
+    pub guild_role_delete: StatefulHandlers<GuildRoleDeleteSelf<T>, GuildRoleDeleteSelfMut<T>>,
+    pub invite_create: StatefulHandlers<InviteCreateSelf<T>, InviteCreateSelfMut<T>>,
+    pub invite_delete: StatefulHandlers<InviteDeleteSelf<T>, InviteDeleteSelfMut<T>>,
+    pub message_create: StatefulHandlers<MessageCreateSelf<T>, MessageCreateSelfMut<T>>,
+    pub message_update: StatefulHandlers<MessageUpdateSelf<T>, MessageUpdateSelfMut<T>>,
+    pub message_delete: StatefulHandlers<MessageDeleteSelf<T>, MessageDeleteSelfMut<T>>,
+    pub message_delete_bulk: StatefulHandlers<MessageDeleteBulkSelf<T>, MessageDeleteBulkSelfMut<T>>,
+    pub reaction_add: StatefulHandlers<MessageReactionAddSelf<T>, MessageReactionAddSelfMut<T>>,
+    pub reaction_remove: StatefulHandlers<MessageReactionRemoveSelf<T>, MessageReactionRemoveSelfMut<T>>,
+    pub reaction_remove_all: StatefulHandlers<MessageReactionRemoveAllSelf<T>, MessageReactionRemoveAllSelfMut<T>>,
+    pub reaction_remove_emoji: StatefulHandlers<MessageReactionRemoveEmojiSelf<T>, MessageReactionRemoveEmojiSelfMut<T>>,
+    pub presence_update: StatefulHandlers<PresenceUpdateSelf<T>, PresenceUpdateSelfMut<T>>,
+    pub typing_start: StatefulHandlers<TypingStartSelf<T>, TypingStartSelfMut<T>>,
+    pub user_update: StatefulHandlers<UserUpdateSelf<T>, UserUpdateSelfMut<T>>,
+    pub voice_state_update: StatefulHandlers<VoiceStateUpdateSelf<T>, VoiceStateUpdateSelfMut<T>>,
+    pub voice_server_update: StatefulHandlers<VoiceServerUpdateSelf<T>, VoiceServerUpdateSelfMut<T>>,
+    pub webhooks_update: StatefulHandlers<WebhooksUpdateSelf<T>, WebhooksUpdateSelfMut<T>>,
+    pub raw: StatefulHandlers<RawSelf<T>, RawSelfMut<T>>,
+    pub cache_resync: StatefulHandlers<CacheResyncSelf<T>, CacheResyncSelfMut<T>>,
+}
+
+/// Implemented manually since deriving it would
+/// require the state to implement `Default`.
+impl<T> Default for StatefulListenerContainer<T> {
+    fn default() -> Self {
+        StatefulListenerContainer {
+            ready: Vec::new(),
+            channel_create: Vec::new(),
+            channel_update: Vec::new(),
+            channel_delete: Vec::new(),
+            channel_pins_update: Vec::new(),
+            thread_create: Vec::new(),
+            thread_update: Vec::new(),
+            thread_delete: Vec::new(),
+            thread_list_sync: Vec::new(),
+            thread_member_update: Vec::new(),
+            thread_members_update: Vec::new(),
+            guild_create: Vec::new(),
+            guild_update: Vec::new(),
+            guild_delete: Vec::new(),
+            guild_ban_add: Vec::new(),
+            guild_ban_remove: Vec::new(),
+            guild_emojis_update: Vec::new(),
+            guild_integrations_update: Vec::new(),
+            guild_scheduled_event_create: Vec::new(),
+            guild_scheduled_event_update: Vec::new(),
+            guild_scheduled_event_delete: Vec::new(),
+            guild_scheduled_event_user_add: Vec::new(),
+            guild_scheduled_event_user_remove: Vec::new(),
+            auto_moderation_rule_create: Vec::new(),
+            auto_moderation_rule_update: Vec::new(),
+            auto_moderation_rule_delete: Vec::new(),
+            auto_moderation_action_execution: Vec::new(),
+            interaction_create: Vec::new(),
+            guild_member_add: Vec::new(),
+            guild_member_remove: Vec::new(),
+            guild_member_update: Vec::new(),
+            guild_members_chunk: Vec::new(),
+            guild_role_create: Vec::new(),
+            guild_role_update: Vec::new(),
+            guild_role_delete: Vec::new(),
+            invite_create: Vec::new(),
+            invite_delete: Vec::new(),
+            message_create: Vec::new(),
+            message_update: Vec::new(),
+            message_delete: Vec::new(),
+            message_delete_bulk: Vec::new(),
+            reaction_add: Vec::new(),
+            reaction_remove: Vec::new(),
+            reaction_remove_all: Vec::new(),
+            reaction_remove_emoji: Vec::new(),
+            presence_update: Vec::new(),
+            typing_start: Vec::new(),
+            user_update: Vec::new(),
+            voice_state_update: Vec::new(),
+            voice_server_update: Vec::new(),
+            webhooks_update: Vec::new(),
+            raw: Vec::new(),
+            cache_resync: Vec::new(),
+        }
+    }
 }
 
 impl<T> StatefulListenerContainer<T> {
     pub fn register(&mut self, listeners: Vec<StatefulListener<T>>) {
         for l in listeners {
             match l {
-                StatefulListener::Ready(l) => self.ready.push(l),
-                StatefulListener::ChannelCreate(l) => self.channel_create.push(l),
-                StatefulListener::ChannelUpdate(l) => self.channel_update.push(l),
-                StatefulListener::ChannelDelete(l) => self.channel_delete.push(l),
-                StatefulListener::ChannelPinsUpdate(l) => self.channel_pins_update.push(l),
-                StatefulListener::GuildCreate(l) => self.guild_create.push(l),
-                StatefulListener::GuildUpdate(l) => self.guild_update.push(l),
-                StatefulListener::GuildDelete(l) => self.guild_delete.push(l),
-                StatefulListener::GuildBanAdd(l) => self.guild_ban_add.push(l),
-                StatefulListener::GuildBanRemove(l) => self.guild_ban_remove.push(l),
-                StatefulListener::GuildEmojisUpdate(l) => self.guild_emojis_update.push(l),
-                StatefulListener::GuildIntegrationsUpdate(l) => self.guild_integrations_update.push(l),
-                StatefulListener::GuildMemberAdd(l) => self.guild_member_add.push(l),
-                StatefulListener::GuildMemberRemove(l) => self.guild_member_remove.push(l),
-                StatefulListener::GuildMemberUpdate(l) => self.guild_member_update.push(l),
-                StatefulListener::GuildMembersChunk(l) => self.guild_members_chunk.push(l),
-                StatefulListener::GuildRoleCreate(l) => self.guild_role_create.push(l),
-                StatefulListener::GuildRoleUpdate(l) => self.guild_role_update.push(l),
-                StatefulListener::GuildRoleDelete(l) => self.guild_role_delete.push(l),
-                StatefulListener::InviteCreate(l) => self.invite_create.push(l),
-                StatefulListener::InviteDelete(l) => self.invite_delete.push(l),
-                StatefulListener::MessageCreate(l) => self.message_create.push(l),
-                StatefulListener::MessageUpdate(l) => self.message_update.push(l),
-                StatefulListener::MessageDelete(l) => self.message_delete.push(l),
-                StatefulListener::MessageDeleteBulk(l) => self.message_delete_bulk.push(l),
-                StatefulListener::MessageReactionAdd(l) => self.reaction_add.push(l),
-                StatefulListener::MessageReactionRemove(l) => self.reaction_remove.push(l),
-                StatefulListener::MessageReactionRemoveAll(l) => self.reaction_remove_all.push(l),
-                StatefulListener::MessageReactionRemoveEmoji(l) => self.reaction_remove_emoji.push(l),
-                StatefulListener::PresenceUpdate(l) => self.presence_update.push(l),
-                StatefulListener::TypingStart(l) => self.typing_start.push(l),
-                StatefulListener::UserUpdate(l) => self.user_update.push(l),
-                StatefulListener::VoiceStateUpdate(l) => self.voice_state_update.push(l),
-                StatefulListener::VoiceServerUpdate(l) => self.voice_server_update.push(l),
-                StatefulListener::WebhooksUpdate(l) => self.webhooks_update.push(l),
-
-                StatefulListener::ReadyMut(l) => self.ready_mut.push(l),
-                StatefulListener::ChannelCreateMut(l) => self.channel_create_mut.push(l),
-                StatefulListener::ChannelUpdateMut(l) => self.channel_update_mut.push(l),
-                StatefulListener::ChannelDeleteMut(l) => self.channel_delete_mut.push(l),
-                StatefulListener::ChannelPinsUpdateMut(l) => self.channel_pins_update_mut.push(l),
-                StatefulListener::GuildCreateMut(l) => self.guild_create_mut.push(l),
-                StatefulListener::GuildUpdateMut(l) => self.guild_update_mut.push(l),
-                StatefulListener::GuildDeleteMut(l) => self.guild_delete_mut.push(l),
-                StatefulListener::GuildBanAddMut(l) => self.guild_ban_add_mut.push(l),
-                StatefulListener::GuildBanRemoveMut(l) => self.guild_ban_remove_mut.push(l),
-                StatefulListener::GuildEmojisUpdateMut(l) => self.guild_emojis_update_mut.push(l),
-                StatefulListener::GuildIntegrationsUpdateMut(l) => self.guild_integrations_update_mut.push(l),
-                StatefulListener::GuildMemberAddMut(l) => self.guild_member_add_mut.push(l),
-                StatefulListener::GuildMemberRemoveMut(l) => self.guild_member_remove_mut.push(l),
-                StatefulListener::GuildMemberUpdateMut(l) => self.guild_member_update_mut.push(l),
-                StatefulListener::GuildMembersChunkMut(l) => self.guild_members_chunk_mut.push(l),
-                StatefulListener::GuildRoleCreateMut(l) => self.guild_role_create_mut.push(l),
-                StatefulListener::GuildRoleUpdateMut(l) => self.guild_role_update_mut.push(l),
-                StatefulListener::GuildRoleDeleteMut(l) => self.guild_role_delete_mut.push(l),
-                StatefulListener::InviteCreateMut(l) => self.invite_create_mut.push(l),
-                StatefulListener::InviteDeleteMut(l) => self.invite_delete_mut.push(l),
-                StatefulListener::MessageCreateMut(l) => self.message_create_mut.push(l),
-                StatefulListener::MessageUpdateMut(l) => self.message_update_mut.push(l),
-                StatefulListener::MessageDeleteMut(l) => self.message_delete_mut.push(l),
-                StatefulListener::MessageDeleteBulkMut(l) => self.message_delete_bulk_mut.push(l),
-                StatefulListener::MessageReactionAddMut(l) => self.reaction_add_mut.push(l),
-                StatefulListener::MessageReactionRemoveMut(l) => self.reaction_remove_mut.push(l),
-                StatefulListener::MessageReactionRemoveAllMut(l) => self.reaction_remove_all_mut.push(l),
-                StatefulListener::MessageReactionRemoveEmojiMut(l) => self.reaction_remove_emoji_mut.push(l),
-                StatefulListener::PresenceUpdateMut(l) => self.presence_update_mut.push(l),
-                StatefulListener::TypingStartMut(l) => self.typing_start_mut.push(l),
-                StatefulListener::UserUpdateMut(l) => self.user_update_mut.push(l),
-                StatefulListener::VoiceStateUpdateMut(l) => self.voice_state_update_mut.push(l),
-                StatefulListener::VoiceServerUpdateMut(l) => self.voice_server_update_mut.push(l),
-                StatefulListener::WebhooksUpdateMut(l) => self.webhooks_update_mut.push(l),
+                StatefulListener::Ready(l, p) => insert_sorted(&mut self.ready, StatefulHandler::Shared(l), p),
+                StatefulListener::ChannelCreate(l, p) => insert_sorted(&mut self.channel_create, StatefulHandler::Shared(l), p),
+                StatefulListener::ChannelUpdate(l, p) => insert_sorted(&mut self.channel_update, StatefulHandler::Shared(l), p),
+                StatefulListener::ChannelDelete(l, p) => insert_sorted(&mut self.channel_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::ChannelPinsUpdate(l, p) => insert_sorted(&mut self.channel_pins_update, StatefulHandler::Shared(l), p),
+                StatefulListener::ThreadCreate(l, p) => insert_sorted(&mut self.thread_create, StatefulHandler::Shared(l), p),
+                StatefulListener::ThreadUpdate(l, p) => insert_sorted(&mut self.thread_update, StatefulHandler::Shared(l), p),
+                StatefulListener::ThreadDelete(l, p) => insert_sorted(&mut self.thread_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::ThreadListSync(l, p) => insert_sorted(&mut self.thread_list_sync, StatefulHandler::Shared(l), p),
+                StatefulListener::ThreadMemberUpdate(l, p) => insert_sorted(&mut self.thread_member_update, StatefulHandler::Shared(l), p),
+                StatefulListener::ThreadMembersUpdate(l, p) => insert_sorted(&mut self.thread_members_update, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildCreate(l, p) => insert_sorted(&mut self.guild_create, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildUpdate(l, p) => insert_sorted(&mut self.guild_update, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildDelete(l, p) => insert_sorted(&mut self.guild_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildBanAdd(l, p) => insert_sorted(&mut self.guild_ban_add, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildBanRemove(l, p) => insert_sorted(&mut self.guild_ban_remove, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildEmojisUpdate(l, p) => insert_sorted(&mut self.guild_emojis_update, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildIntegrationsUpdate(l, p) => insert_sorted(&mut self.guild_integrations_update, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildScheduledEventCreate(l, p) => insert_sorted(&mut self.guild_scheduled_event_create, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildScheduledEventUpdate(l, p) => insert_sorted(&mut self.guild_scheduled_event_update, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildScheduledEventDelete(l, p) => insert_sorted(&mut self.guild_scheduled_event_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildScheduledEventUserAdd(l, p) => insert_sorted(&mut self.guild_scheduled_event_user_add, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildScheduledEventUserRemove(l, p) => insert_sorted(&mut self.guild_scheduled_event_user_remove, StatefulHandler::Shared(l), p),
+                StatefulListener::AutoModerationRuleCreate(l, p) => insert_sorted(&mut self.auto_moderation_rule_create, StatefulHandler::Shared(l), p),
+                StatefulListener::AutoModerationRuleUpdate(l, p) => insert_sorted(&mut self.auto_moderation_rule_update, StatefulHandler::Shared(l), p),
+                StatefulListener::AutoModerationRuleDelete(l, p) => insert_sorted(&mut self.auto_moderation_rule_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::AutoModerationActionExecution(l, p) => insert_sorted(&mut self.auto_moderation_action_execution, StatefulHandler::Shared(l), p),
+                StatefulListener::InteractionCreate(l, p) => insert_sorted(&mut self.interaction_create, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildMemberAdd(l, p) => insert_sorted(&mut self.guild_member_add, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildMemberRemove(l, p) => insert_sorted(&mut self.guild_member_remove, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildMemberUpdate(l, p) => insert_sorted(&mut self.guild_member_update, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildMembersChunk(l, p) => insert_sorted(&mut self.guild_members_chunk, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildRoleCreate(l, p) => insert_sorted(&mut self.guild_role_create, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildRoleUpdate(l, p) => insert_sorted(&mut self.guild_role_update, StatefulHandler::Shared(l), p),
+                StatefulListener::GuildRoleDelete(l, p) => insert_sorted(&mut self.guild_role_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::InviteCreate(l, p) => insert_sorted(&mut self.invite_create, StatefulHandler::Shared(l), p),
+                StatefulListener::InviteDelete(l, p) => insert_sorted(&mut self.invite_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageCreate(l, p) => insert_sorted(&mut self.message_create, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageUpdate(l, p) => insert_sorted(&mut self.message_update, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageDelete(l, p) => insert_sorted(&mut self.message_delete, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageDeleteBulk(l, p) => insert_sorted(&mut self.message_delete_bulk, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageReactionAdd(l, p) => insert_sorted(&mut self.reaction_add, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageReactionRemove(l, p) => insert_sorted(&mut self.reaction_remove, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageReactionRemoveAll(l, p) => insert_sorted(&mut self.reaction_remove_all, StatefulHandler::Shared(l), p),
+                StatefulListener::MessageReactionRemoveEmoji(l, p) => insert_sorted(&mut self.reaction_remove_emoji, StatefulHandler::Shared(l), p),
+                StatefulListener::PresenceUpdate(l, p) => insert_sorted(&mut self.presence_update, StatefulHandler::Shared(l), p),
+                StatefulListener::TypingStart(l, p) => insert_sorted(&mut self.typing_start, StatefulHandler::Shared(l), p),
+                StatefulListener::UserUpdate(l, p) => insert_sorted(&mut self.user_update, StatefulHandler::Shared(l), p),
+                StatefulListener::VoiceStateUpdate(l, p) => insert_sorted(&mut self.voice_state_update, StatefulHandler::Shared(l), p),
+                StatefulListener::VoiceServerUpdate(l, p) => insert_sorted(&mut self.voice_server_update, StatefulHandler::Shared(l), p),
+                StatefulListener::WebhooksUpdate(l, p) => insert_sorted(&mut self.webhooks_update, StatefulHandler::Shared(l), p),
+                StatefulListener::Raw(l, p) => insert_sorted(&mut self.raw, StatefulHandler::Shared(l), p),
+                StatefulListener::CacheResync(l, p) => insert_sorted(&mut self.cache_resync, StatefulHandler::Shared(l), p),
+
+                StatefulListener::ReadyMut(l, p) => insert_sorted(&mut self.ready, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ChannelCreateMut(l, p) => insert_sorted(&mut self.channel_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ChannelUpdateMut(l, p) => insert_sorted(&mut self.channel_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ChannelDeleteMut(l, p) => insert_sorted(&mut self.channel_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ChannelPinsUpdateMut(l, p) => insert_sorted(&mut self.channel_pins_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ThreadCreateMut(l, p) => insert_sorted(&mut self.thread_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ThreadUpdateMut(l, p) => insert_sorted(&mut self.thread_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ThreadDeleteMut(l, p) => insert_sorted(&mut self.thread_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ThreadListSyncMut(l, p) => insert_sorted(&mut self.thread_list_sync, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ThreadMemberUpdateMut(l, p) => insert_sorted(&mut self.thread_member_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::ThreadMembersUpdateMut(l, p) => insert_sorted(&mut self.thread_members_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildCreateMut(l, p) => insert_sorted(&mut self.guild_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildUpdateMut(l, p) => insert_sorted(&mut self.guild_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildDeleteMut(l, p) => insert_sorted(&mut self.guild_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildBanAddMut(l, p) => insert_sorted(&mut self.guild_ban_add, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildBanRemoveMut(l, p) => insert_sorted(&mut self.guild_ban_remove, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildEmojisUpdateMut(l, p) => insert_sorted(&mut self.guild_emojis_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildIntegrationsUpdateMut(l, p) => insert_sorted(&mut self.guild_integrations_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildScheduledEventCreateMut(l, p) => insert_sorted(&mut self.guild_scheduled_event_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildScheduledEventUpdateMut(l, p) => insert_sorted(&mut self.guild_scheduled_event_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildScheduledEventDeleteMut(l, p) => insert_sorted(&mut self.guild_scheduled_event_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildScheduledEventUserAddMut(l, p) => insert_sorted(&mut self.guild_scheduled_event_user_add, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildScheduledEventUserRemoveMut(l, p) => insert_sorted(&mut self.guild_scheduled_event_user_remove, StatefulHandler::Exclusive(l), p),
+                StatefulListener::AutoModerationRuleCreateMut(l, p) => insert_sorted(&mut self.auto_moderation_rule_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::AutoModerationRuleUpdateMut(l, p) => insert_sorted(&mut self.auto_moderation_rule_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::AutoModerationRuleDeleteMut(l, p) => insert_sorted(&mut self.auto_moderation_rule_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::AutoModerationActionExecutionMut(l, p) => insert_sorted(&mut self.auto_moderation_action_execution, StatefulHandler::Exclusive(l), p),
+                StatefulListener::InteractionCreateMut(l, p) => insert_sorted(&mut self.interaction_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildMemberAddMut(l, p) => insert_sorted(&mut self.guild_member_add, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildMemberRemoveMut(l, p) => insert_sorted(&mut self.guild_member_remove, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildMemberUpdateMut(l, p) => insert_sorted(&mut self.guild_member_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildMembersChunkMut(l, p) => insert_sorted(&mut self.guild_members_chunk, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildRoleCreateMut(l, p) => insert_sorted(&mut self.guild_role_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildRoleUpdateMut(l, p) => insert_sorted(&mut self.guild_role_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::GuildRoleDeleteMut(l, p) => insert_sorted(&mut self.guild_role_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::InviteCreateMut(l, p) => insert_sorted(&mut self.invite_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::InviteDeleteMut(l, p) => insert_sorted(&mut self.invite_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageCreateMut(l, p) => insert_sorted(&mut self.message_create, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageUpdateMut(l, p) => insert_sorted(&mut self.message_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageDeleteMut(l, p) => insert_sorted(&mut self.message_delete, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageDeleteBulkMut(l, p) => insert_sorted(&mut self.message_delete_bulk, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageReactionAddMut(l, p) => insert_sorted(&mut self.reaction_add, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageReactionRemoveMut(l, p) => insert_sorted(&mut self.reaction_remove, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageReactionRemoveAllMut(l, p) => insert_sorted(&mut self.reaction_remove_all, StatefulHandler::Exclusive(l), p),
+                StatefulListener::MessageReactionRemoveEmojiMut(l, p) => insert_sorted(&mut self.reaction_remove_emoji, StatefulHandler::Exclusive(l), p),
+                StatefulListener::PresenceUpdateMut(l, p) => insert_sorted(&mut self.presence_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::TypingStartMut(l, p) => insert_sorted(&mut self.typing_start, StatefulHandler::Exclusive(l), p),
+                StatefulListener::UserUpdateMut(l, p) => insert_sorted(&mut self.user_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::VoiceStateUpdateMut(l, p) => insert_sorted(&mut self.voice_state_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::VoiceServerUpdateMut(l, p) => insert_sorted(&mut self.voice_server_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::WebhooksUpdateMut(l, p) => insert_sorted(&mut self.webhooks_update, StatefulHandler::Exclusive(l), p),
+                StatefulListener::RawMut(l, p) => insert_sorted(&mut self.raw, StatefulHandler::Exclusive(l), p),
+                StatefulListener::CacheResyncMut(l, p) => insert_sorted(&mut self.cache_resync, StatefulHandler::Exclusive(l), p),
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{ContextBuilder, fake_message, fake_user};
+    use futures::executor::block_on;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Poll;
+
+    #[test]
+    fn dependency_order() {
+        let requirements = vec![
+            Requirements::new().requires::<u32>(),
+            Requirements::new(),
+            Requirements::new().provides::<u32>().requires::<u8>(),
+            Requirements::new().provides::<u8>(),
+        ];
+
+        assert_eq!(super::dependency_order(&requirements).unwrap(), vec![1, 3, 2, 0]);
+
+        let missing = vec![Requirements::new().requires::<u32>()];
+        assert!(super::dependency_order(&missing).is_err());
+
+        let circular = vec![
+            Requirements::new().provides::<u32>().requires::<u8>(),
+            Requirements::new().provides::<u8>().requires::<u32>(),
+        ];
+        assert!(super::dependency_order(&circular).is_err());
+    }
+
+    /// Resolves once both shared listeners started or
+    /// after polling a few times, which can only be
+    /// before the other one started if they run one
+    /// after the other.
+    async fn meet(started: &AtomicUsize) -> bool {
+        started.fetch_add(1, Ordering::SeqCst);
+
+        for _ in 0..100 {
+            if started.load(Ordering::SeqCst) == 2 {
+                return true;
+            }
+
+            let mut yielded = false;
+            futures::future::poll_fn(|cx| if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }).await;
+        }
+
+        false
+    }
+
+    #[derive(State, Clone, Default)]
+    struct Concurrent {
+        started: Arc<AtomicUsize>,
+        met: Arc<AtomicUsize>,
+    }
+
+    impl Initializable for Concurrent {
+        fn initialize() -> Vec<StatefulListener<Self>> {
+            methods!(Concurrent: first, second)
+        }
+    }
+
+    impl Concurrent {
+        #[listener]
+        async fn first(&self, _: &Context, _: &MessageCreateDispatch) -> Result<(), Error> {
+            if meet(&self.started).await {
+                self.met.fetch_add(1, Ordering::SeqCst);
+            }
+
+            Ok(())
+        }
+
+        #[listener]
+        async fn second(&self, _: &Context, _: &MessageCreateDispatch) -> Result<(), Error> {
+            if meet(&self.started).await {
+                self.met.fetch_add(1, Ordering::SeqCst);
+            }
+
+            Ok(())
+        }
+    }
+
+    #[derive(State, Clone, Default)]
+    struct Ordered {
+        calls: Arc<Mutex<Vec<&'static str>>>,
+        stop: bool,
+    }
+
+    impl Initializable for Ordered {
+        fn initialize() -> Vec<StatefulListener<Self>> {
+            methods!(Ordered: last, shared_stop, exclusive_first, shared)
+        }
+    }
+
+    impl Ordered {
+        #[listener(priority = 200)]
+        async fn exclusive_first(&mut self, _: &Context, _: &MessageCreateDispatch) -> Result<(), Error> {
+            self.calls.lock().unwrap().push("exclusive");
+            Ok(())
+        }
+
+        #[listener(priority = 150)]
+        async fn shared_stop(&self, _: &Context, _: &MessageCreateDispatch) -> Result<EventFlow, Error> {
+            self.calls.lock().unwrap().push("stop");
+
+            if self.stop {
+                Ok(EventFlow::Stop)
+            } else {
+                Ok(EventFlow::Continue)
+            }
+        }
+
+        #[listener(priority = 150)]
+        async fn shared(&self, _: &Context, _: &MessageCreateDispatch) -> Result<(), Error> {
+            self.calls.lock().unwrap().push("shared");
+            Ok(())
+        }
+
+        #[listener(priority = 100)]
+        async fn last(&mut self, _: &Context, _: &MessageCreateDispatch) -> Result<(), Error> {
+            self.calls.lock().unwrap().push("last");
+            Ok(())
+        }
+    }
+
+    fn message() -> MessageCreateDispatch {
+        MessageCreateDispatch(fake_message(1, 2, fake_user(3, "user"), "hello"))
+    }
+
+    #[test]
+    fn shared_listeners_run_concurrently() {
+        let builder = ContextBuilder::new();
+        let mut state = Concurrent::default();
+
+        let flow = block_on(state.on_message_create(&builder.build(), &message())).unwrap();
+        assert!(matches!(flow, EventFlow::Continue));
+        assert_eq!(state.met.load(Ordering::SeqCst), 2);
+
+        let listeners = &stateful_listeners::<Concurrent>().message_create;
+        assert!(listeners.iter().all(|(handler, _)| matches!(handler, StatefulHandler::Shared(_))));
+    }
+
+    #[test]
+    fn mixed_listeners_follow_priorities() {
+        let builder = ContextBuilder::new();
+
+        //listeners of equal priority keep the order
+        //in which they are registered
+        let mut state = Ordered::default();
+        let flow = block_on(state.on_message_create(&builder.build(), &message())).unwrap();
+        assert!(matches!(flow, EventFlow::Continue));
+        assert_eq!(*state.calls.lock().unwrap(), vec!["exclusive", "stop", "shared", "last"]);
+
+        //the shared listeners running along the one
+        //stopping the event still complete
+        let mut state = Ordered { stop: true, ..Ordered::default() };
+        let flow = block_on(state.on_message_create(&builder.build(), &message())).unwrap();
+        assert!(matches!(flow, EventFlow::Stop));
+        assert_eq!(*state.calls.lock().unwrap(), vec!["exclusive", "stop", "shared"]);
+    }
+}
@@ -4,68 +4,203 @@ pub mod models;
 
 pub use models::*;
 
-use crate::{map, Error, Configuration, logger, http};
-use crate::http::HttpAPI;
+use crate::{Error, Configuration, Decision, Backpressure, PresenceFilter, Snowflake, Identifiable, ZombieHook, logger, http};
+use crate::metrics::{self, Metrics};
+use crate::collectors::{Collectors, Collector};
+use crate::middleware::Event;
+use crate::events::{EventFlow, EventGuild};
+use crate::http::{HttpAPI, ApiVersion, CreateMessage, MessageBuilder, AllowedMentions, ModifyMember, ModifyChannel, MoveChannel, InteractionResponse, FollowupMessage};
+use crate::encode::{ExtractSnowflake, WriteUrl};
 use crate::encode::json;
-use std::env;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::ops::Deref;
-use futures::{stream, future, SinkExt, StreamExt};
+use std::future::Future;
+use std::task::Poll;
+use futures::{stream, future, Stream, SinkExt, StreamExt, TryStreamExt};
+use futures::future::AbortHandle;
 use futures::lock::Mutex;
-use futures::channel::mpsc;
-use futures::channel::mpsc::{SendError, UnboundedSender};
+use tokio::sync::mpsc::{self, error::TrySendError};
 use tktungstenite::tungstenite::Message as TkMessage;
 use chrono::{NaiveDateTime, Utc, Duration as ChronoDuration};
 
 #[cfg(feature = "storage")]
-use crate::storage::{StorageContainer, Stored};
+use crate::storage::{StorageContainer, Stored, Snapshot, Query, MarkedMessage};
 #[cfg(feature = "storage")]
 use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
+#[cfg(feature = "storage")]
+use crate::preflight::Preflight;
+
+/// Maximum amount of guilds a shard leaves because of the
+/// guild policy during [POLICY_LEAVES_PERIOD].
+const MAX_POLICY_LEAVES: usize = 10;
+const POLICY_LEAVES_PERIOD: Duration = Duration::from_secs(600);
+
+/// Forgets the guilds left before the current period and
+/// returns whether another guild can be left.
+fn allow_leave(leaves: &mut VecDeque<Instant>, now: Instant) -> bool {
+    while matches!(leaves.front(), Some(left) if now.duration_since(*left) >= POLICY_LEAVES_PERIOD) {
+        leaves.pop_front();
+    }
+
+    leaves.len() < MAX_POLICY_LEAVES
+}
+
+/// Age under which messages can be bulk deleted, a minute
+/// below Discord's 14 days limit to account for the time
+/// spent fetching the messages and for clock drift.
+const BULK_DELETE_MAX_AGE: u64 = (14 * 24 * 60 - 1) * 60 * 1000;
+
+/// Maximum amount of messages read by [Context::purge] while
+/// looking for the messages accepted by its filter.
+const PURGE_MAX_SCANNED: usize = 10_000;
 
 macro_rules! call_dispatcher {
-    ($data:ident as $payload:ty => $self:ident.$method:ident) => {{
-        let payload: $payload = serde_json::from_str(&$data)?;
+    ($data:ident as Payload<$dispatch:ty> => $self:ident.$method:ident) => {{
+        let payload: Payload<$dispatch> = serde_json::from_str(&$data)?;
 
         if let Some(val) = payload.s {
-            *$self.sequence_number.lock().await = Some(val);
+            $self.track_sequence(val).await?;
+        }
+
+        if !$self.config.dispatches_guild(payload.d.event_guild()) {
+            trace!("Ignoring `{}` event of a filtered guild", <$dispatch>::EVENT_NAME);
+            return Ok(());
         }
 
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "dispatch",
+            event = <$dispatch>::EVENT_NAME,
+            guild_id = ?payload.d.event_guild().map(|guild| guild.0),
+        );
+
+        let dispatch = $self.$method(payload.d, &$data);
+
+        #[cfg(feature = "tracing")]
+        let dispatch = tracing::Instrument::instrument(dispatch, span);
+
+        dispatch.await?
+    }};
+    (op $data:ident as $payload:ty => $self:ident.$method:ident) => {{
+        let payload: $payload = serde_json::from_str(&$data)?;
         $self.$method(payload.d).await?
     }};
 }
 
 macro_rules! dispatcher {
+    ($fn_name:ident: $type:ty => $name:ident, collect) => {
+        dispatcher!(@dispatch $fn_name: $type => $name, |collectors: &Collectors, payload: &$type| collectors.$fn_name(payload));
+    };
     ($fn_name:ident: $type:ty => $name:ident) => {
-        async fn $fn_name(&mut self, payload: $type) -> Result<(), Error> {
+        dispatcher!(@dispatch $fn_name: $type => $name, |_: &Collectors, _: &$type| ());
+    };
+    (@dispatch $fn_name:ident: $type:ty => $name:ident, $collect:expr) => {
+        async fn $fn_name(&mut self, payload: $type, raw: &str) -> Result<(), Error> {
             #[cfg(feature = "storage")]
             self.config.storages.$fn_name(&payload).await;
 
+            //resolve the collectors waiting for this event
+            ($collect)(&self.config.collectors, &payload);
+
+            self.config.metrics.increment(metrics::EVENTS_TOTAL, &[("event", stringify!($name))]);
+
             let context = Context {
                 sender: &self.msg_sender,
+                budget: &self.budget,
                 #[cfg(feature = "storage")]
                 storage: &self.config.storages,
                 http: &self.http,
+                metrics: &self.config.metrics,
+                collectors: &self.config.collectors,
+                shard: self.shard(),
                 bot: self.bot.as_ref().unwrap()
             };
 
-            let stateless = self.config.listeners.$name.iter()
-                .map(|l| (*l)(&context, &payload));
+            run_listeners!(self, context, payload, raw, $fn_name => $name);
 
-            let stateful = self.config.listeners.stateful_listeners.iter_mut()
-                .map(|l| (*l).$fn_name(&context, &payload));
+            Ok(())
+        }
+    }
+}
 
-            future::join_all(stateless.chain(stateful)).await
-                .into_iter()
-                .filter_map(|r| r.err())
-                .for_each(|err| error!("Listener to `{}` failed with: {}", stringify!($name), err));
+/// Sends the payload to the listeners of the event
+/// between the calls to the middlewares.
+macro_rules! run_listeners {
+    ($self:ident, $context:ident, $payload:ident, $raw:ident, $fn_name:ident => $name:ident) => {
+        let event = Event {
+            name: stringify!($name),
+            data: &$payload,
+        };
 
-            Ok(())
+        let mut dispatch = true;
+        for middleware in &$self.config.middlewares {
+            if !middleware.before_event(&$context, &event).await {
+                dispatch = false;
+                break;
+            }
+        }
+
+        if dispatch {
+            let timeout = $self.config.listener_timeout;
+            let mut errors: Vec<Error> = Vec::new();
+            let mut flow = EventFlow::Continue;
+
+            for (listener, _) in &$self.config.listeners.$name {
+                match with_timeout(timeout, stringify!($name), listener(&$context, &$payload)).await {
+                    Ok(result) => flow = result,
+                    Err(err) => errors.push(err),
+                }
+
+                if flow == EventFlow::Stop {
+                    break;
+                }
+            }
+
+            if flow == EventFlow::Continue {
+                for state in $self.config.listeners.stateful_listeners.iter_mut() {
+                    match with_timeout(timeout, stringify!($name), state.$fn_name(&$context, &$payload)).await {
+                        Ok(result) => flow = result,
+                        Err(err) => errors.push(err),
+                    }
+
+                    if flow == EventFlow::Stop {
+                        break;
+                    }
+                }
+            }
+
+            for err in &errors {
+                $self.config.metrics.increment(metrics::LISTENER_ERRORS_TOTAL, &[("event", stringify!($name))]);
+                error!("Listener to `{}` failed with: {}", stringify!($name), err);
+
+                if let Some(hook) = &$self.config.listener_error {
+                    hook(stringify!($name), $raw, err);
+                }
+            }
+
+            for middleware in &$self.config.middlewares {
+                middleware.after_event(&$context, &event, &errors).await;
+            }
         }
     }
 }
 
+/// Cancels the listener and returns an error if it
+/// did not complete within the given duration.
+async fn with_timeout<F>(timeout: Option<Duration>, event: &str, listener: F) -> Result<EventFlow, Error>
+    where F: Future<Output=Result<EventFlow, Error>> {
+    match timeout {
+        Some(duration) => tokio::time::timeout(duration, listener).await
+            .unwrap_or_else(|_| Error::err(format!("Listener to `{}` timed out after {}ms", event, duration.as_millis()))),
+        None => listener.await,
+    }
+}
+
 /// Helps avoid spamming connections to Discord
 /// in case the bots is constantly getting
 /// disconnected by the gateway API since the bot
@@ -120,10 +255,14 @@ impl Delayer {
 /// Provides a way to interact with Discord HTTP API
 /// by dereferencing to [HttpAPI](automate::http::HttpAPI).
 pub struct Context<'a> {
-    sender: &'a UnboundedSender<Instruction>,
+    pub(crate) sender: &'a InstructionSender,
+    pub(crate) budget: &'a CommandBudget,
     #[cfg(feature = "storage")]
-    storage: &'a StorageContainer,
-    http: &'a HttpAPI,
+    pub(crate) storage: &'a StorageContainer,
+    pub(crate) http: &'a HttpAPI,
+    pub(crate) metrics: &'a Metrics,
+    pub(crate) collectors: &'a Collectors,
+    pub(crate) shard: [u32; 2],
     pub bot: &'a User,
 }
 
@@ -132,86 +271,1003 @@ impl<'a> Context<'a> {
     /// The message must be a valid payload.
     #[inline]
     async fn send_command<M: Into<TkMessage>>(&self, msg: M) -> Result<(), Error> {
-        Ok(self.sender.unbounded_send(Instruction::Send(msg.into(), false))?)
+        self.sender.try_send(Instruction::Send(msg.into(), false))
     }
 
     /// Indicate a presence or status update.
+    ///
+    /// When [presence coalescing](automate::Configuration::presence_coalescing)
+    /// is enabled, only the last update sent within
+    /// the window reaches the gateway.
     #[inline]
     pub async fn update_status(&self, data: UpdateStatus) -> Result<(), Error> {
+        self.sender.try_send(Instruction::Presence(data.into()))
+    }
+
+    /// Sets the presence of the bot, for example
+    /// `ctx.set_activity(Presence::playing("with fire"))`.
+    #[inline]
+    pub async fn set_activity(&self, presence: Presence) -> Result<(), Error> {
+        self.update_status(UpdateStatus::from(presence)).await
+    }
+
+    /// Sends a payload with the given opcode and data
+    /// to the gateway, for commands which are not yet
+    /// supported by the library. The payload counts
+    /// towards the rate limit of the connection.
+    ///
+    /// Heartbeats, identify and resume payloads are
+    /// sent by the library and are refused.
+    ///
+    /// ```ignore
+    /// ctx.send_raw_gateway(8, json!({"guild_id": "41771983444115456", "query": "", "limit": 0})).await?;
+    /// ```
+    pub async fn send_raw_gateway(&self, op: u8, payload: serde_json::Value) -> Result<(), Error> {
+        if matches!(op, 1 | 2 | 6) {
+            return Error::err(format!("Opcode {} is managed by the library and can not be sent manually", op));
+        }
+
+        let message = serde_json::json!({
+            "op": op,
+            "d": payload,
+        });
+
+        self.send_command(TkMessage::Text(message.to_string())).await
+    }
+
+    /// Number of commands the bot can still send to the
+    /// gateway before the rate limit of the connection
+    /// resets, without the slots reserved for heartbeats.
+    pub fn gateway_budget(&self) -> u32 {
+        self.budget.remaining()
+    }
+
+    /// Join, move or disconnect from a voice channel.
+    #[inline]
+    pub async fn update_voice_state(&self, data: UpdateVoiceState) -> Result<(), Error> {
+        self.send_command(data).await
+    }
+
+    /// Request members of a guild.
+    #[inline]
+    pub async fn request_guild_members(&self, data: RequestGuildMembers) -> Result<(), Error> {
         self.send_command(data).await
     }
 
-    /// Join, move or disconnect from a voice channel.
-    #[inline]
-    pub async fn update_voice_state(&self, data: UpdateVoiceState) -> Result<(), Error> {
-        self.send_command(data).await
+    /// Read only reference to the storage of the
+    /// specified type.
+    ///
+    /// The returned guard keeps the storage locked and
+    /// prevents the gateway from applying updates until it
+    /// is dropped. Do not hold it across an `.await` point,
+    /// use [snapshot](automate::Context::snapshot) instead.
+    /// This can be enforced with clippy's
+    /// `await_holding_invalid_type` lint by adding
+    /// `tokio::sync::RwLockReadGuard` and
+    /// `tokio::sync::RwLockWriteGuard` to the
+    /// `await-holding-invalid-types` list of your `clippy.toml`.
+    #[inline]
+    #[cfg(feature = "storage")]
+    pub async fn storage<T: Stored + 'static>(&self) -> RwLockReadGuard<'_, T::Storage> {
+        self.storage.read::<T>().await
+    }
+
+    /// Owned copy of the storage of the specified type
+    /// which can safely be iterated and kept across
+    /// `.await` points. Changes made to the storage after
+    /// the snapshot was taken are not visible in it.
+    ///
+    /// ```ignore
+    /// let guilds = ctx.snapshot::<Guild>().await;
+    ///
+    /// for guild in guilds.all() {
+    ///     ctx.create_message(some_channel, CreateMessage {
+    ///         content: Some(guild.name.clone()),
+    ///         ..Default::default()
+    ///     }).await?;
+    /// }
+    /// ```
+    #[inline]
+    #[cfg(feature = "storage")]
+    pub async fn snapshot<T: Stored + 'static>(&self) -> T::Storage where T::Storage: Clone {
+        self.storage.snapshot::<T>().await
+    }
+
+    /// Immutable copy of the storage of the specified type
+    /// shared with the other listeners, made for long
+    /// computations such as leaderboards that should not
+    /// stall the storage updates.
+    ///
+    /// Unlike [snapshot](automate::Context::snapshot), the
+    /// storage is only copied if it was modified since the
+    /// last call and the [generation](automate::storage::Snapshot::generation)
+    /// of the snapshot tells whether two snapshots differ.
+    #[inline]
+    #[cfg(feature = "storage")]
+    pub async fn storage_snapshot<T: Stored + 'static>(&self) -> Snapshot<T::Storage> where T::Storage: Clone + 'static {
+        self.storage.storage_snapshot::<T>().await
+    }
+
+    /// Checks the cached permissions of the bot before
+    /// sending actions, see the [preflight](automate::preflight)
+    /// module.
+    #[inline]
+    #[cfg(feature = "storage")]
+    pub fn preflight(&self) -> Preflight<'a> {
+        Preflight {
+            storage: self.storage,
+            http: self.http,
+            bot: self.bot,
+        }
+    }
+
+    /// Amount of guilds in the storage of the shard, see
+    /// [BotHandle::statistics](automate::BotHandle::statistics)
+    /// for the guilds of all the shards.
+    #[inline]
+    #[cfg(feature = "storage")]
+    pub async fn cached_guild_count(&self) -> usize {
+        self.storage.read::<Guild>().await.count()
+    }
+
+    /// Amount of users in the storage of the shard.
+    #[inline]
+    #[cfg(feature = "storage")]
+    pub async fn cached_user_count(&self) -> usize {
+        self.storage.read::<User>().await.count()
+    }
+
+    /// Writable reference to the storage of the specified
+    /// type. Getting a writable version of
+    /// [GuildStorage](automate::storage::GuildStorage),
+    /// [ChannelStorage](automate::storage::ChannelStorage) or
+    /// [UserStorage](automate::storage::UserStorage)
+    /// is useless since they are not mutable.
+    ///
+    /// Like [storage](automate::Context::storage), the
+    /// guard should not be held across an `.await` point.
+    #[inline]
+    #[cfg(feature = "storage")]
+    pub async fn storage_mut<T: Stored + 'static>(&self) -> RwLockWriteGuard<'_, T::Storage> {
+        self.storage.write::<T>().await
+    }
+
+    /// Id of the shard which received the event.
+    #[inline]
+    pub fn shard_id(&self) -> u32 {
+        self.shard[0]
+    }
+
+    /// Total amount of shards of the bot.
+    #[inline]
+    pub fn total_shards(&self) -> u32 {
+        self.shard[1]
+    }
+
+    /// Registry where the bot can record its own
+    /// counters next to the ones of the library.
+    #[inline]
+    pub fn metrics(&self) -> &Metrics {
+        self.metrics
+    }
+
+    /// Sends a message to the given user, opening a
+    /// direct channel with them if needed.
+    ///
+    /// The direct channel is kept in the storage so
+    /// that it is only opened once per user when the
+    /// `storage` feature is enabled.
+    pub async fn direct_message<S: ExtractSnowflake>(&self, user: S, message: CreateMessage) -> Result<Message, Error> {
+        let user = user.extract_snowflake()?;
+
+        #[cfg(feature = "storage")]
+        let cached = self.storage.read::<Channel>().await.direct_with(user).map(|channel| channel.id);
+        #[cfg(not(feature = "storage"))]
+        let cached = None;
+
+        let channel = match cached {
+            Some(channel) => channel,
+            None => {
+                let channel = self.http.create_dm(user).await?;
+                let id = channel.id;
+
+                #[cfg(feature = "storage")]
+                self.storage.write::<Channel>().await.insert_direct(user, channel);
+
+                id
+            }
+        };
+
+        self.http.create_message(channel, message).await
+    }
+
+    /// Responds to the interaction with a loading state,
+    /// the response can then be sent within 15 minutes
+    /// using [followup](automate::Context::followup).
+    pub async fn defer(&self, interaction: &Interaction) -> Result<(), Error> {
+        self.http.create_interaction_response(interaction.id, &interaction.token, InteractionResponse::deferred()).await
+    }
+
+    /// Same as [defer](automate::Context::defer) but only
+    /// the user who triggered the interaction will see
+    /// the response.
+    pub async fn defer_ephemeral(&self, interaction: &Interaction) -> Result<(), Error> {
+        self.http.create_interaction_response(interaction.id, &interaction.token, InteractionResponse::deferred().ephemeral()).await
+    }
+
+    /// Responds to the interaction.
+    pub async fn respond(&self, interaction: &Interaction, response: InteractionResponse) -> Result<(), Error> {
+        self.http.create_interaction_response(interaction.id, &interaction.token, response).await
+    }
+
+    /// Sends a followup message to an interaction
+    /// which has already been responded to.
+    pub async fn followup(&self, interaction: &Interaction, message: FollowupMessage) -> Result<Message, Error> {
+        self.http.create_followup_message(interaction.application_id, &interaction.token, message).await
+    }
+
+    /// Retrieves a guild from the storage if it is
+    /// cached, else requests it from the HTTP API.
+    ///
+    /// Always requests the HTTP API when the `storage`
+    /// feature is disabled.
+    pub async fn fetch_guild<S: ExtractSnowflake>(&self, guild: S) -> Result<Guild, Error> {
+        let guild = guild.extract_snowflake()?;
+
+        #[cfg(feature = "storage")]
+        {
+            if let Some(cached) = self.storage.read::<Guild>().await.get_opt(guild) {
+                return Ok(Guild::clone(cached));
+            }
+        }
+
+        self.http.guild(guild).await
+    }
+
+    /// Retrieves a channel from the storage if it is
+    /// cached, else requests it from the HTTP API.
+    ///
+    /// Always requests the HTTP API when the `storage`
+    /// feature is disabled.
+    pub async fn fetch_channel<S: ExtractSnowflake>(&self, channel: S) -> Result<Channel, Error> {
+        let channel = channel.extract_snowflake()?;
+
+        #[cfg(feature = "storage")]
+        {
+            if let Some(cached) = self.storage.read::<Channel>().await.get_opt(channel) {
+                return Ok(Channel::clone(cached));
+            }
+        }
+
+        self.http.channel(channel).await
+    }
+
+    /// Retrieves a user from the storage if it is
+    /// cached, else requests it from the HTTP API.
+    ///
+    /// Always requests the HTTP API when the `storage`
+    /// feature is disabled.
+    pub async fn fetch_user<S: ExtractSnowflake>(&self, user: S) -> Result<User, Error> {
+        let user = user.extract_snowflake()?;
+
+        #[cfg(feature = "storage")]
+        {
+            if let Some(cached) = self.storage.read::<User>().await.get_opt(user) {
+                return Ok(User::clone(cached));
+            }
+        }
+
+        self.http.user(user).await
+    }
+
+    /// Retrieves a guild member from the storage if it is
+    /// cached, else requests it from the HTTP API.
+    ///
+    /// Always requests the HTTP API when the `storage`
+    /// feature is disabled.
+    pub async fn fetch_member<S: ExtractSnowflake>(&self, guild: S, user: S) -> Result<GuildMember, Error> {
+        let guild = guild.extract_snowflake()?;
+        let user = user.extract_snowflake()?;
+
+        #[cfg(feature = "storage")]
+        {
+            let guilds = self.storage.read::<Guild>().await;
+            if let Some(member) = guilds.get_opt(guild).and_then(|g| g.members.get(&user)) {
+                return Ok(GuildMember::clone(member));
+            }
+        }
+
+        self.http.member(guild, user).await
+    }
+
+    /// Guild-wide permissions of a user, see
+    /// [Guild::base_permissions](automate::gateway::Guild::base_permissions).
+    ///
+    /// Uses the storage when possible, else falls back
+    /// to the HTTP API.
+    pub async fn permissions<S: ExtractSnowflake>(&self, guild: S, user: S) -> Result<u32, Error> {
+        let guild = self.fetch_guild(guild).await?;
+        let member = self.fetch_member(guild.id, user.extract_snowflake()?).await?;
+
+        Ok(guild.base_permissions(&member))
+    }
+
+    /// Adds and removes roles of a member with a single
+    /// request instead of one request per role. Roles that
+    /// are both added and removed are removed.
+    ///
+    /// The current roles of the member are taken from the
+    /// storage when possible and no request is sent if the
+    /// member already has the resulting roles.
+    pub async fn apply_role_changes<S: ExtractSnowflake>(&self, guild: S, member: S, add: &[Snowflake], remove: &[Snowflake]) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let member = self.fetch_member(guild, member.extract_snowflake()?).await?;
+
+        let mut current = member.roles.clone();
+        current.sort_unstable();
+
+        let mut roles: Vec<Snowflake> = member.roles.iter()
+            .chain(add)
+            .filter(|role| !remove.contains(role))
+            .copied()
+            .collect();
+
+        roles.sort_unstable();
+        roles.dedup();
+
+        if roles == current {
+            return Ok(());
+        }
+
+        self.http.modify_member(guild, member.user.id, ModifyMember {
+            roles: Some(roles),
+            ..Default::default()
+        }).await
+    }
+
+    /// Sets and removes permission overwrites of a channel
+    /// with a single request instead of one request per
+    /// overwrite. The given overwrites replace the existing
+    /// overwrites of the same roles or members.
+    ///
+    /// The current overwrites are taken from the storage
+    /// when possible.
+    pub async fn apply_overwrite_changes<S: ExtractSnowflake>(&self, channel: S, set: &[Overwrite], remove: &[Snowflake]) -> Result<(), Error> {
+        let channel = self.fetch_channel(channel).await?;
+
+        let mut overwrites: Vec<Overwrite> = channel.permission_overwrites().iter()
+            .filter(|o| !remove.contains(&o.id) && !set.iter().any(|s| s.id == o.id))
+            .cloned()
+            .collect();
+
+        overwrites.extend(set.iter().filter(|o| !remove.contains(&o.id)).cloned());
+
+        self.http.modify_channel(channel.id(), ModifyChannel {
+            permission_overwrites: Some(overwrites),
+            ..Default::default()
+        }).await?;
+
+        Ok(())
+    }
+
+    /// Channels of the guild taken from the storage
+    /// when possible, else from the HTTP API.
+    async fn guild_channels(&self, guild: Snowflake) -> Result<Vec<GuildChannel>, Error> {
+        #[cfg(feature = "storage")]
+        {
+            if let Some(cached) = self.storage.read::<Guild>().await.get_opt(guild) {
+                return Ok(cached.channels.values().cloned().collect());
+            }
+        }
+
+        self.http.channels(guild).await
+    }
+
+    /// Moves the channel at the bottom of the channels
+    /// of the same kind in the category.
+    pub async fn move_channel_to_category<S: ExtractSnowflake>(&self, channel: S, category: S) -> Result<(), Error> {
+        let channel = channel.extract_snowflake()?;
+        let category = category.extract_snowflake()?;
+
+        let guild = match self.fetch_channel(channel).await?.guild_id() {
+            Some(guild) => guild,
+            None => return Error::err("Private channels can not be moved to a category"),
+        };
+
+        let channels = self.guild_channels(guild).await?;
+        let moved = match channels.iter().find(|c| c.id() == channel) {
+            Some(moved) if moved.position().is_some() && !matches!(moved, GuildChannel::Category(_)) => moved,
+            _ => return Error::err(format!("Channel {} can not be moved to a category", channel)),
+        };
+
+        if !matches!(channels.iter().find(|c| c.id() == category), Some(GuildChannel::Category(_))) {
+            return Error::err(format!("Channel {} is not a category of the guild", category));
+        }
+
+        if moved.parent_id() == Some(category) {
+            return Ok(());
+        }
+
+        let position = channels.iter()
+            .filter(|c| c.parent_id() == Some(category) && http::sorting_group(c) == http::sorting_group(moved))
+            .filter_map(GuildChannel::position)
+            .max()
+            .map_or(0, |position| position + 1);
+
+        self.http.move_channels(guild, vec![MoveChannel {
+            id: channel.0,
+            position,
+            parent_id: Some(Some(category)),
+            ..MoveChannel::default()
+        }]).await
+    }
+
+    /// Places the channels in the order of the plan, see
+    /// [MoveChannel::reorder](automate::http::MoveChannel::reorder).
+    /// No request is sent if the channels are already in order.
+    ///
+    /// ```
+    /// # use automate::{Context, Error, Snowflake};
+    /// async fn pin_rules(ctx: &Context<'_>, guild: Snowflake, rules: Snowflake, announcements: Snowflake) -> Result<(), Error> {
+    ///     ctx.reorder_channels(guild, &[rules, announcements]).await
+    /// }
+    /// ```
+    pub async fn reorder_channels<S: ExtractSnowflake>(&self, guild: S, plan: &[Snowflake]) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let moves = MoveChannel::reorder(&self.guild_channels(guild).await?, plan)?;
+
+        if moves.is_empty() {
+            return Ok(());
+        }
+
+        self.http.move_channels(guild, moves).await
+    }
+
+    /// Shows the bot as typing in the channel until the
+    /// returned handle is dropped by triggering the typing
+    /// indicator every 8 seconds in a background task.
+    ///
+    /// ```ignore
+    /// let _typing = ctx.start_typing(channel)?;
+    /// let answer = long_computation().await;
+    /// //the indicator stops when `_typing` is dropped
+    /// ```
+    pub fn start_typing<S: ExtractSnowflake>(&self, channel: S) -> Result<TypingHandle, Error> {
+        let channel = channel.extract_snowflake()?;
+        let http = HttpAPI::clone(self.http);
+
+        let (task, handle) = future::abortable(async move {
+            loop {
+                if let Err(err) = http.trigger_typing(channel).await {
+                    warn!("Failed to trigger typing in channel {}: {}", channel, err);
+                }
+
+                tokio::time::sleep(Duration::from_secs(8)).await;
+            }
+        });
+
+        tokio::spawn(task);
+
+        Ok(TypingHandle(handle))
+    }
+
+    /// Replies to the message in the channel it was sent in.
+    /// The author of the message is not pinged, use
+    /// [create_message](automate::HttpAPI::create_message)
+    /// with [CreateMessage::reply_to](automate::http::CreateMessage::reply_to)
+    /// for more control over the reply.
+    pub async fn reply<S: Into<String>>(&self, message: &Message, content: S) -> Result<Message, Error> {
+        let reply = CreateMessage {
+            content: Some(content.into()),
+            allowed_mentions: Some(AllowedMentions::all_but_everyone()),
+            ..Default::default()
+        };
+
+        self.http.create_message(message.channel_id, reply.reply_to(message)).await
+    }
+
+    /// Checks and sends the message built by the
+    /// [MessageBuilder](automate::http::MessageBuilder)
+    /// to the channel.
+    ///
+    /// ```ignore
+    /// ctx.send(channel, MessageBuilder::new().content("Hello").embed(|e| e.title("World"))).await?;
+    /// ```
+    pub async fn send<S: ExtractSnowflake>(&self, channel: S, message: MessageBuilder) -> Result<Message, Error> {
+        self.http.create_message(channel, message.build()?).await
+    }
+
+    /// Edits the message previously sent with the same marker
+    /// in the channel, or sends a new one if there is none or
+    /// if it was deleted. Made for status or leaderboard
+    /// messages which are updated over time.
+    ///
+    /// The marked messages are kept in the
+    /// [MarkerStorage](automate::storage::MarkerStorage)
+    /// until the shard is stopped, a message sent before
+    /// the bot was restarted is not edited.
+    ///
+    /// ```ignore
+    /// let scores = MessageBuilder::new().embed(|e| e.title("Leaderboard").description(table));
+    /// ctx.upsert_message(channel, "leaderboard", scores).await?;
+    /// ```
+    #[cfg(feature = "storage")]
+    pub async fn upsert_message<S: ExtractSnowflake>(&self, channel: S, marker: &str, message: MessageBuilder) -> Result<Message, Error> {
+        let channel = channel.extract_snowflake()?;
+
+        let lock = self.storage.write::<MarkedMessage>().await.lock(channel, marker);
+        let _guard = lock.lock().await;
+
+        let marked = self.storage.read::<MarkedMessage>().await.get(channel, marker);
+
+        if let Some(marked) = marked {
+            match self.http.modify_message(channel, marked.message_id, message.clone().build_modification()?).await {
+                Err(err) if err.status() == Some(404) => (),
+                result => return result,
+            }
+        }
+
+        let sent = self.http.create_message(channel, message.build()?).await?;
+        self.storage.write::<MarkedMessage>().await.insert(channel, marker, sent.id);
+
+        Ok(sent)
+    }
+
+    /// Adds the reactions to the message one after the other
+    /// so they are displayed in the given order. Stops at
+    /// the first reaction that could not be added.
+    ///
+    /// ```ignore
+    /// ctx.add_reactions(channel, message, &["1️⃣", "2️⃣", "3️⃣"]).await?;
+    /// ```
+    pub async fn add_reactions<S: ExtractSnowflake, U: WriteUrl>(&self, channel: S, message: S, emojis: &[U]) -> Result<(), Error> {
+        let channel = channel.extract_snowflake()?;
+        let message = message.extract_snowflake()?;
+
+        for emoji in emojis {
+            self.http.create_reaction(channel, message, emoji).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves all the users who reacted to the
+    /// message with the given emoji.
+    pub async fn all_reaction_users<S: ExtractSnowflake, U: WriteUrl>(&self, channel: S, message: S, emoji: &U) -> Result<Vec<User>, Error> {
+        self.http.reactions_iter(channel, message, emoji).try_collect().await
+    }
+
+    /// Deletes up to `limit` messages of the channel accepted
+    /// by the filter, starting from the most recent one, and
+    /// returns how many messages were deleted.
+    ///
+    /// Only the 10 000 most recent messages of the channel are
+    /// given to the filter. Messages younger than 14 days are bulk
+    /// deleted by batches of 100 while older ones are deleted one
+    /// by one. Requests which are rate-limited are sent again once
+    /// the rate-limit is over.
+    ///
+    /// Stops at the first other error, which is returned if no
+    /// message was deleted yet. Otherwise the error is logged and
+    /// the amount of messages deleted before it is returned.
+    ///
+    /// ```ignore
+    /// let bot = ctx.bot.id;
+    /// let deleted = ctx.purge(channel, |m| m.author.id == bot, 500).await?;
+    /// ```
+    pub async fn purge<S, F>(&self, channel: S, mut filter: F, limit: usize) -> Result<usize, Error>
+        where S: ExtractSnowflake,
+              F: FnMut(&Message) -> bool {
+        let channel = channel.extract_snowflake()?;
+        let messages: Vec<Message> = self.http.messages_iter(channel)
+            .take(PURGE_MAX_SCANNED)
+            .try_filter(|m| future::ready(filter(m)))
+            .take(limit)
+            .try_collect()
+            .await?;
+
+        let now = Utc::now().timestamp_millis() as u64;
+        let (recent, old) = partition_by_age(messages.iter().map(|m| m.id), now);
+
+        let mut deleted = 0;
+
+        for batch in recent.chunks(100) {
+            let result = match batch {
+                [message] => retry_rate_limited(|| self.http.delete_message(channel, *message)).await,
+                batch => retry_rate_limited(|| self.http.delete_message_bulk(channel, batch.to_vec())).await,
+            };
+
+            match result {
+                Ok(()) => deleted += batch.len(),
+                Err(err) => return purge_interrupted(deleted, err),
+            }
+        }
+
+        for message in old {
+            match retry_rate_limited(|| self.http.delete_message(channel, message)).await {
+                Ok(()) => deleted += 1,
+                Err(err) => return purge_interrupted(deleted, err),
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Waits for the next message accepted by the filter.
+    /// See the [collectors](automate::collectors) module.
+    pub fn await_message<F>(&self, filter: F) -> Collector<Message>
+        where F: Fn(&Message) -> bool + Send + Sync + 'static {
+        self.collectors.message(filter)
+    }
+
+    /// Waits for the next reaction with the given emoji on
+    /// the message. The emoji is either the unicode character
+    /// of a standard emoji or the id of a custom emoji.
+    /// See the [collectors](automate::collectors) module.
+    pub fn await_reaction<S: ExtractSnowflake>(&self, message: S, emoji: &str) -> Result<Collector<MessageReactionAddDispatch>, Error> {
+        let message = message.extract_snowflake()?;
+        let emoji = emoji.to_owned();
+
+        Ok(self.collectors.reaction(move |r| {
+            r.message_id == message && match r.emoji.id {
+                Some(id) => id.to_string() == emoji,
+                None => r.emoji.name == emoji
+            }
+        }))
+    }
+
+    /// Waits for the next reaction accepted by the filter.
+    /// See the [collectors](automate::collectors) module.
+    pub fn await_reaction_with<F>(&self, filter: F) -> Collector<MessageReactionAddDispatch>
+        where F: Fn(&MessageReactionAddDispatch) -> bool + Send + Sync + 'static {
+        self.collectors.reaction(filter)
+    }
+
+    /// Creates a link to invite the bot to a discord server
+    /// and give him the specified permissions.
+    #[inline]
+    pub fn invite_bot(&self, permission: u32) -> String {
+        format!("https://discordapp.com/oauth2/authorize?client_id={}&scope=bot&permissions={}", self.bot.id, permission)
+    }
+}
+
+/// Splits the messages which can be bulk deleted
+/// from the ones that are too old to be.
+fn partition_by_age<I: IntoIterator<Item = Snowflake>>(messages: I, now: u64) -> (Vec<Snowflake>, Vec<Snowflake>) {
+    messages.into_iter().partition(|id| now.saturating_sub(id.timestamp_millis()) < BULK_DELETE_MAX_AGE)
+}
+
+/// Result of a purge stopped by an error, the error is
+/// only returned if no message was deleted before it.
+fn purge_interrupted(deleted: usize, err: Error) -> Result<usize, Error> {
+    if deleted == 0 {
+        return Err(err);
+    }
+
+    warn!("Purge stopped after deleting {} messages: {}", deleted, err);
+    Ok(deleted)
+}
+
+/// Sends the request again once the rate-limit
+/// is over for as long as it is rate-limited.
+async fn retry_rate_limited<T, F, R>(mut request: F) -> Result<T, Error>
+    where F: FnMut() -> R,
+          R: Future<Output = Result<T, Error>> {
+    loop {
+        match request().await {
+            Err(Error::RateLimited(ctx)) => {
+                let wait = (ctx.until - Utc::now().naive_utc()).to_std().unwrap_or_default();
+                tokio::time::sleep(wait).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Random number between 0 and `max` included.
+fn random(max: u64) -> u64 {
+    RandomState::new().build_hasher().finish() % (max + 1)
+}
+
+/// Keeps the bot typing in a channel until dropped.
+/// Created by [Context::start_typing](automate::Context::start_typing).
+pub struct TypingHandle(AbortHandle);
+
+impl TypingHandle {
+    /// Stops the typing indicator, same as dropping the handle.
+    pub fn stop(self) {}
+}
+
+impl Drop for TypingHandle {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl<'a> Deref for Context<'a> {
+    type Target = HttpAPI;
+
+    #[inline]
+    fn deref(&self) -> &HttpAPI {
+        &self.http
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Instruction {
+    /// Receive a message sent by the gateway
+    Receive(Result<TkMessage, tktungstenite::tungstenite::Error>),
+    /// The message and whether it is a necessary message or not.
+    /// Necessary messages have reserved slots to keep sending
+    /// them even when it's about to reach rate-limit since they
+    /// are necessary to keep the gateway connection alive.
+    Send(TkMessage, bool),
+    /// A presence update which can be replaced by the
+    /// next one if they are sent in a short interval.
+    Presence(TkMessage),
+    /// Send the last presence update received
+    /// since the start of the coalescing window.
+    FlushPresence,
+    /// Close the connection with the gateway
+    Close,
+    /// Close the connection with the gateway which
+    /// stopped acknowledging the heartbeats
+    Zombie,
+}
+
+/// Why the connection with the gateway was restarted,
+/// recorded in the [reconnects](metrics::RECONNECTS_TOTAL)
+/// metric to tell network issues apart from reconnections
+/// requested by Discord.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ReconnectCause {
+    /// The gateway sent a reconnect payload
+    Requested,
+    /// The gateway invalidated the session
+    InvalidSession,
+    /// The gateway stopped acknowledging the heartbeats
+    Zombie,
+    /// The gateway closed the connection
+    Closed,
+    /// The connection failed
+    Error,
+}
+
+impl ReconnectCause {
+    fn label(self) -> &'static str {
+        match self {
+            ReconnectCause::Requested => "requested",
+            ReconnectCause::InvalidSession => "invalid_session",
+            ReconnectCause::Zombie => "zombie",
+            ReconnectCause::Closed => "closed",
+            ReconnectCause::Error => "error",
+        }
+    }
+}
+
+/// Sending half of the queues of instructions of a gateway
+/// connection. The commands of the listeners go through a
+/// bounded queue which applies the
+/// [backpressure policy](automate::Backpressure) when it is
+/// full while the instructions of the gateway itself, such as
+/// the heartbeats, go through a separate queue so that they
+/// are never delayed by the commands of the listeners.
+#[derive(Clone)]
+pub(crate) struct InstructionSender {
+    sender: mpsc::Sender<Instruction>,
+    control: mpsc::UnboundedSender<Instruction>,
+    policy: Backpressure,
+}
+
+/// Receiving halves of the queues of an [InstructionSender].
+pub(crate) struct InstructionReceiver {
+    commands: mpsc::Receiver<Instruction>,
+    control: mpsc::UnboundedReceiver<Instruction>,
+}
+
+impl InstructionSender {
+    pub(crate) fn channel(capacity: usize, policy: Backpressure) -> (InstructionSender, InstructionReceiver) {
+        let (sender, commands) = mpsc::channel(capacity.max(1));
+        let (control_sender, control) = mpsc::unbounded_channel();
+
+        (InstructionSender { sender, control: control_sender, policy }, InstructionReceiver { commands, control })
+    }
+
+    /// Queues a command of the listeners without waiting since
+    /// the queue is only emptied between two events. Fails if
+    /// the queue is full unless the policy allows to drop it.
+    pub(crate) fn try_send(&self, instruction: Instruction) -> Result<(), Error> {
+        match self.sender.try_send(instruction) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(instruction)) if self.droppable(&instruction) => {
+                trace!("Gateway command queue is full, dropping presence update");
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => Error::err("The gateway command queue is full"),
+            Err(TrySendError::Closed(_)) => Error::err("The gateway connection is closed"),
+        }
+    }
+
+    /// Queues an instruction of the gateway itself, the
+    /// instructions are handled in the order they are pushed.
+    pub(crate) fn push(&self, instruction: Instruction) -> Result<(), Error> {
+        match self.control.send(instruction) {
+            Ok(()) => Ok(()),
+            Err(_) => Error::err("The gateway connection is closed"),
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.control.is_closed()
+    }
+
+    fn droppable(&self, instruction: &Instruction) -> bool {
+        self.policy == Backpressure::DropPresences && matches!(instruction, Instruction::Presence(_))
+    }
+}
+
+impl InstructionReceiver {
+    /// Merges the queues, the instructions of the gateway
+    /// are received before the commands of the listeners.
+    fn into_stream(mut self) -> impl Stream<Item = Instruction> {
+        stream::poll_fn(move |cx| match self.control.poll_recv(cx) {
+            Poll::Ready(Some(instruction)) => Poll::Ready(Some(instruction)),
+            _ => self.commands.poll_recv(cx),
+        })
+    }
+
+    /// Takes the instructions waiting in the queues.
+    pub(crate) fn try_recv(&mut self) -> Option<Instruction> {
+        self.control.try_recv().or_else(|_| self.commands.try_recv()).ok()
+    }
+}
+
+/// Keeps the presence update until the end of the coalescing
+/// window, replacing the one already waiting, and returns the
+/// presence update when it should be sent right away.
+fn coalesce_presence(pending: &mut Option<TkMessage>, message: TkMessage, window: Option<Duration>, sender: &InstructionSender) -> Option<TkMessage> {
+    let window = match window {
+        Some(window) => window,
+        None => return Some(message),
+    };
+
+    //the flush is only scheduled by the first update of the window
+    if pending.replace(message).is_none() {
+        schedule_presence_flush(sender, window);
+    }
+
+    None
+}
+
+/// Sends the last presence update received
+/// once the coalescing window is over.
+fn schedule_presence_flush(sender: &InstructionSender, window: Duration) {
+    let sender = sender.clone();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(window).await;
+        let _ = sender.push(Instruction::FlushPresence);
+    });
+}
+
+/// Keeps track of the commands that can be sent on a
+/// gateway connection which is limited to 120 commands
+/// per minute.
+#[derive(Clone, Default)]
+pub(crate) struct CommandBudget {
+    remaining: Arc<std::sync::Mutex<Option<(i32, NaiveDateTime)>>>,
+}
+
+impl CommandBudget {
+    const COMMANDS: i32 = 120;
+    /// Commands kept for the heartbeats and identify.
+    const RESERVED: i32 = 5;
+
+    fn refresh(remaining: &mut Option<(i32, NaiveDateTime)>) -> &mut (i32, NaiveDateTime) {
+        let remaining = remaining.get_or_insert_with(|| (CommandBudget::COMMANDS, Utc::now().naive_utc() + ChronoDuration::minutes(1)));
+
+        //if now is after the given time, reset the rate-limit
+        if Utc::now().naive_utc() > remaining.1 {
+            remaining.0 = CommandBudget::COMMANDS;
+            remaining.1 += ChronoDuration::minutes(1);
+        }
+
+        remaining
+    }
+
+    /// Takes a slot for a command and returns whether it
+    /// can be sent. Necessary commands can use the slots
+    /// reserved to keep the connection alive.
+    pub(crate) fn consume(&self, necessary: bool) -> bool {
+        let mut guard = self.remaining.lock().unwrap();
+        let remaining = CommandBudget::refresh(&mut guard);
+
+        if necessary || remaining.0 > CommandBudget::RESERVED {
+            remaining.0 -= 1;
+
+            trace!("{} gateway command calls remaining until (reset at {} UTC)", remaining.0, remaining.1.format("%Y-%m-%d %H:%M:%S"));
+            true
+        } else {
+            trace!("Reached gateway rate limit ({} calls left, reset at {} UTC)", remaining.0, remaining.1.format("%Y-%m-%d %H:%M:%S"));
+            false
+        }
+    }
+
+    /// Number of commands that are not necessary
+    /// which can still be sent.
+    pub(crate) fn remaining(&self) -> u32 {
+        let mut guard = self.remaining.lock().unwrap();
+        let remaining = CommandBudget::refresh(&mut guard);
+
+        (remaining.0 - CommandBudget::RESERVED).max(0) as u32
+    }
+}
+
+/// Sends commands to the current gateway connection of
+/// a shard, the sender is replaced every time the shard
+/// reconnects.
+#[derive(Clone, Default)]
+pub(crate) struct ShardSender {
+    sender: Arc<std::sync::Mutex<Option<InstructionSender>>>,
+    ready: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ShardSender {
+    fn replace(&self, sender: InstructionSender) {
+        *self.sender.lock().unwrap() = Some(sender);
     }
 
-    /// Request members of a guild.
-    #[inline]
-    pub async fn request_guild_members(&self, data: RequestGuildMembers) -> Result<(), Error> {
-        self.send_command(data).await
+    pub(crate) fn send_command<M: Into<TkMessage>>(&self, msg: M) -> Result<(), Error> {
+        self.send(Instruction::Send(msg.into(), false))
     }
 
-    /// Read only reference to the storage of the
-    /// specified type.
-    #[inline]
-    #[cfg(feature = "storage")]
-    pub async fn storage<T: Stored + 'static>(&self) -> RwLockReadGuard<'_, T::Storage> {
-        self.storage.read::<T>().await
+    pub(crate) fn send_presence(&self, presence: UpdateStatus) -> Result<(), Error> {
+        self.send(Instruction::Presence(presence.into()))
     }
 
-    /// Writable reference to the storage of the specified
-    /// type. Getting a writable version of
-    /// [GuildStorage](automate::storage::GuildStorage),
-    /// [ChannelStorage](automate::storage::ChannelStorage) or
-    /// [UserStorage](automate::storage::UserStorage)
-    /// is useless since they are not mutable
-    #[inline]
-    #[cfg(feature = "storage")]
-    pub async fn storage_mut<T: Stored + 'static>(&self) -> RwLockWriteGuard<'_, T::Storage> {
-        self.storage.write::<T>().await
+    fn send(&self, instruction: Instruction) -> Result<(), Error> {
+        match &*self.sender.lock().unwrap() {
+            Some(sender) => sender.try_send(instruction),
+            None => Error::err("The shard is not connected to the gateway"),
+        }
     }
 
-    /// Creates a link to invite the bot to a discord server
-    /// and give him the specified permissions.
-    #[inline]
-    pub fn invite_bot(&self, permission: u32) -> String {
-        format!("https://discordapp.com/oauth2/authorize?client_id={}&scope=bot&permissions={}", self.bot.id, permission)
+    /// Whether the shard received its first ready event.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
     }
-}
 
-impl<'a> Deref for Context<'a> {
-    type Target = HttpAPI;
+    /// Closes the connection of the shard which
+    /// will not reconnect.
+    pub(crate) fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
 
-    #[inline]
-    fn deref(&self) -> &HttpAPI {
-        &self.http
+        if let Some(sender) = &*self.sender.lock().unwrap() {
+            let _ = sender.push(Instruction::Close);
+        }
     }
-}
 
-#[derive(Debug)]
-enum Instruction {
-    /// Receive a message sent by the gateway
-    Receive(Result<TkMessage, tktungstenite::tungstenite::Error>),
-    /// The message and whether it is a necessary message or not.
-    /// Necessary messages have reserved slots to keep sending
-    /// them even when it's about to reach rate-limit since they
-    /// are necessary to keep the gateway connection alive.
-    Send(TkMessage, bool),
-    /// Close the connection with the gateway
-    Close,
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
 }
 
 /// Communicates with Discord's gateway
 pub(crate) struct GatewayAPI<'a> {
     config: &'a mut Configuration,
+    shard_sender: &'a ShardSender,
+    unavailable_guilds: &'a mut HashSet<Snowflake>,
+    policy_leaves: &'a mut VecDeque<Instant>,
     session_id: Option<String>,
-    msg_sender: UnboundedSender<Instruction>,
+    msg_sender: InstructionSender,
+    budget: CommandBudget,
     http: &'a HttpAPI,
     bot: Option<User>,
     sequence_number: Arc<Mutex<Option<i32>>>,
     heartbeat_confirmed: Arc<AtomicBool>,
+    reconnect_cause: ReconnectCause,
+    presence_count: u32,
     initialized: bool,
 }
 
@@ -219,51 +1275,88 @@ impl<'a> GatewayAPI<'a> {
     /// Establishes a connection to Discord's
     /// gateway and calls the provided listeners
     /// when receiving an event.
-    pub(crate) async fn connect(mut config: Configuration, url: String) -> ! {
+    ///
+    /// Reconnects indefinitely unless the gateway closes
+    /// the connection with a fatal
+    /// [close code](automate::gateway::GatewayCloseCode)
+    /// in which case the error is returned.
+    pub(crate) async fn connect(mut config: Configuration, url: String, shard_sender: ShardSender) -> Result<(), Error> {
         let mut delayer = Delayer::new();
 
-        let http = HttpAPI::new(&config.token);
+        config.listeners.setup(#[cfg(feature = "storage")] &config.storages)?;
+
+        let url = format!("{}/{}", url.trim_end_matches('/'), config.api_version.gateway_query());
+        let http = HttpAPI::for_config(&config);
         let sequence_number = Arc::new(Mutex::new(None));
         let mut session_id = None;
+        let mut cause = ReconnectCause::Closed;
         let mut initialized = false;
+        let mut unavailable_guilds = HashSet::new();
+        let mut policy_leaves = VecDeque::new();
 
         loop {
+            if shard_sender.is_stopped() {
+                return Ok(());
+            }
+
             let execution: Result<(), Error> = try {
-                let (tx, rx) = mpsc::unbounded();
+                let (tx, rx) = InstructionSender::channel(config.command_queue_capacity, config.backpressure);
+                shard_sender.replace(tx.clone());
+
                 let (socket, _) = tktungstenite::connect_async(&url).await?;
 
-                let mut remaining_commands: Option<(i32, NaiveDateTime)> = None;
+                let mut pending_presence: Option<TkMessage> = None;
 
                 let mut gateway = GatewayAPI {
                     config: &mut config,
-                    session_id: None,
+                    shard_sender: &shard_sender,
+                    unavailable_guilds: &mut unavailable_guilds,
+                    policy_leaves: &mut policy_leaves,
+                    session_id: session_id.clone(),
                     msg_sender: tx,
+                    budget: CommandBudget::default(),
                     http: &http,
                     bot: None,
                     sequence_number: Arc::clone(&sequence_number),
                     heartbeat_confirmed: Arc::new(AtomicBool::new(true)),
+                    reconnect_cause: ReconnectCause::Closed,
+                    presence_count: 0,
                     initialized,
                 };
 
                 let mut select = stream::select(
                     socket.map(Instruction::Receive), //gateway events
-                    rx,                                  //commands and close
+                    Box::pin(rx.into_stream()),       //commands and close
                 );
 
                 while let Some(message) = select.next().await {
                     match message {
                         Instruction::Receive(m) => gateway.on_message(m?).await?,
-                        Instruction::Send(m, n) => if check_remaining(&mut remaining_commands, n).await {
+                        Instruction::Send(m, n) => if gateway.budget.consume(n) {
                             select.get_mut().0.send(m).await?;
                         },
-                        Instruction::Close => break
+                        Instruction::Presence(m) => if let Some(m) = coalesce_presence(&mut pending_presence, m, gateway.config.presence_coalescing, &gateway.msg_sender) {
+                            if gateway.budget.consume(false) {
+                                select.get_mut().0.send(m).await?;
+                            }
+                        },
+                        Instruction::FlushPresence => if let Some(m) = pending_presence.take() {
+                            if gateway.budget.consume(false) {
+                                select.get_mut().0.send(m).await?;
+                            }
+                        },
+                        Instruction::Close => break,
+                        Instruction::Zombie => {
+                            gateway.reconnect_cause = ReconnectCause::Zombie;
+                            break;
+                        }
                     }
                 }
 
                 select.get_mut().0.close().await?;
-                select.get_mut().1.close();
 
                 session_id = gateway.session_id;
+                cause = gateway.reconnect_cause;
                 initialized = gateway.initialized;
             };
 
@@ -278,27 +1371,59 @@ impl<'a> GatewayAPI<'a> {
                 delayer.reset(); //else everything went correctly and it's probably just a reconnect
             }
 
-            if let Err(err) = execution {
-                error!("Connection was interrupted: {}", err.to_string());
+            match execution {
+                Err(Error::GatewayClosed(ctx)) if ctx.is_fatal() => {
+                    error!("Gateway closed the connection with a fatal code, not reconnecting");
+                    return Err(Error::GatewayClosed(ctx));
+                }
+                Err(Error::GatewayClosed(_)) => cause = ReconnectCause::Closed,
+                Err(err) => {
+                    error!("Connection was interrupted: {}", err.to_string());
+                    cause = ReconnectCause::Error;
+                }
+                Ok(()) => ()
+            }
+
+            if shard_sender.is_stopped() {
+                return Ok(());
             }
 
+            let shard = config.shard_id.unwrap_or(0).to_string();
+            config.metrics.increment(metrics::RECONNECTS_TOTAL, &[("shard", &shard), ("cause", cause.label())]);
+
             delayer.delay(&session_id).await
         }
     }
 
+    /// The id of the shard and the total amount of shards.
+    fn shard(&self) -> [u32; 2] {
+        [self.config.shard_id.unwrap(), self.config.total_shards.unwrap()]
+    }
+
     /// Sends a command to the gateway.
     #[inline]
     async fn send_command<M: Into<TkMessage>>(&mut self, msg: M, necessary: bool) -> Result<(), Error> {
-        Ok(self.msg_sender.send(Instruction::Send(msg.into(), necessary)).await?)
+        self.msg_sender.push(Instruction::Send(msg.into(), necessary))
+    }
+
+    /// The properties sent when identifying, which
+    /// are not prefixed by `$` since version 10.
+    fn identify_properties(&self) -> HashMap<String, String> {
+        let properties = self.config.identify_properties.clone();
+
+        if self.config.api_version >= ApiVersion::V10 {
+            properties.into_iter()
+                .map(|(key, value)| (key.trim_start_matches('$').to_owned(), value))
+                .collect()
+        } else {
+            properties
+        }
     }
 
     /// Shuts down the connection with the gateway.
     #[inline]
     async fn disconnect(&mut self) -> Result<(), Error> {
-        self.msg_sender.send(Instruction::Close).await?;
-        self.msg_sender.close().await?;
-
-        Ok(())
+        self.msg_sender.push(Instruction::Close)
     }
 
     async fn on_message(&mut self, msg: TkMessage) -> Result<(), Error> {
@@ -310,7 +1435,7 @@ impl<'a> GatewayAPI<'a> {
             }
             TkMessage::Close(close) => {
                 return if let Some(cf) = close {
-                    Error::gateway(format!("Gateway unexpectedly closed with code {}: {}", Into::<u16>::into(cf.code), cf.reason))
+                    Error::gateway_closed(cf.code.into(), cf.reason)
                 } else {
                     Error::gateway("Gateway unexpectedly closed")
                 };
@@ -322,11 +1447,14 @@ impl<'a> GatewayAPI<'a> {
     }
 
     async fn dispatch_payload(&mut self, data: &str) -> Result<(), Error> {
-        match json::root_search::<u8>("op", data)? {
-            0 => self.dispatch_event(data).await?,
+        let header = json::payload_header(data)?;
+
+        match header.op {
+            0 => self.dispatch_event(data, header.t.as_deref().unwrap_or_default()).await?,
+            1 => self.on_heartbeat_request().await?,
             7 => self.on_reconnect().await?,
-            9 => call_dispatcher!(data as Payload<InvalidSession> => self.on_invalid_session),
-            10 => call_dispatcher!(data as Payload<Hello> => self.on_hello),
+            9 => call_dispatcher!(op data as Payload<InvalidSession> => self.on_invalid_session),
+            10 => call_dispatcher!(op data as Payload<Hello> => self.on_hello),
             11 => self.on_heartbeat_ack().await?,
             unknown_op => warn!("Received unknown opcode '{}': \n{}", unknown_op, data)
         }
@@ -335,26 +1463,41 @@ impl<'a> GatewayAPI<'a> {
     }
 
     /// Takes a full payload, deserializes it and sends
-    /// it to the right method.
-    /// Returns an error when receiving an unknown event.
-    async fn dispatch_event(&mut self, data: &str) -> Result<(), Error> {
-        let event_name = json::root_search::<String>("t", data)?;
+    /// it to the right method. Unknown events are sent
+    /// to the raw listeners.
+    async fn dispatch_event(&mut self, data: &str, event_name: &str) -> Result<(), Error> {
         trace!("Received gateway event `{}`: {}", event_name, data);
 
-        match event_name.as_str() {
+        match event_name {
             ReadyDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ReadyDispatch> => self.on_ready),
             ResumedDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ResumedDispatch> => self.on_resumed),
             ChannelCreateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ChannelCreateDispatch> => self.on_channel_create),
             ChannelUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ChannelUpdateDispatch> => self.on_channel_update),
             ChannelDeleteDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ChannelDeleteDispatch> => self.on_channel_delete),
             ChannelPinsUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ChannelPinsUpdateDispatch> => self.on_channel_pins_update),
-            GuildCreateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildCreateDispatch> => self.on_guild_create),
+            ThreadCreateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ThreadCreateDispatch> => self.on_thread_create),
+            ThreadUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ThreadUpdateDispatch> => self.on_thread_update),
+            ThreadDeleteDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ThreadDeleteDispatch> => self.on_thread_delete),
+            ThreadListSyncDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ThreadListSyncDispatch> => self.on_thread_list_sync),
+            ThreadMemberUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ThreadMemberUpdateDispatch> => self.on_thread_member_update),
+            ThreadMembersUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<ThreadMembersUpdateDispatch> => self.on_thread_members_update),
+            GuildCreateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildCreateDispatch> => self.track_guild_create),
             GuildUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildUpdateDispatch> => self.on_guild_update),
-            GuildDeleteDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildDeleteDispatch> => self.on_guild_delete),
+            GuildDeleteDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildDeleteDispatch> => self.track_guild_delete),
             GuildBanAddDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildBanAddDispatch> => self.on_guild_ban_add),
             GuildBanRemoveDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildBanRemoveDispatch> => self.on_guild_ban_remove),
             GuildEmojisUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildEmojisUpdateDispatch> => self.on_guild_emojis_update),
             GuildIntegrationsUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildIntegrationsUpdateDispatch> => self.on_guild_integrations_update),
+            GuildScheduledEventCreateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildScheduledEventCreateDispatch> => self.on_guild_scheduled_event_create),
+            GuildScheduledEventUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildScheduledEventUpdateDispatch> => self.on_guild_scheduled_event_update),
+            GuildScheduledEventDeleteDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildScheduledEventDeleteDispatch> => self.on_guild_scheduled_event_delete),
+            GuildScheduledEventUserAddDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildScheduledEventUserAddDispatch> => self.on_guild_scheduled_event_user_add),
+            GuildScheduledEventUserRemoveDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildScheduledEventUserRemoveDispatch> => self.on_guild_scheduled_event_user_remove),
+            AutoModerationRuleCreateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<AutoModerationRuleCreateDispatch> => self.on_auto_moderation_rule_create),
+            AutoModerationRuleUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<AutoModerationRuleUpdateDispatch> => self.on_auto_moderation_rule_update),
+            AutoModerationRuleDeleteDispatch::EVENT_NAME => call_dispatcher!(data as Payload<AutoModerationRuleDeleteDispatch> => self.on_auto_moderation_rule_delete),
+            AutoModerationActionExecutionDispatch::EVENT_NAME => call_dispatcher!(data as Payload<AutoModerationActionExecutionDispatch> => self.on_auto_moderation_action_execution),
+            InteractionCreateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<InteractionCreateDispatch> => self.on_interaction_create),
             GuildMemberAddDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildMemberAddDispatch> => self.on_guild_member_add),
             GuildMemberRemoveDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildMemberRemoveDispatch> => self.on_guild_member_remove),
             GuildMemberUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<GuildMemberUpdateDispatch> => self.on_guild_member_update),
@@ -373,13 +1516,35 @@ impl<'a> GatewayAPI<'a> {
             MessageReactionRemoveAllDispatch::EVENT_NAME => call_dispatcher!(data as Payload<MessageReactionRemoveAllDispatch> => self.on_reaction_remove_all),
             MessageReactionRemoveEmojiDispatch::EVENT_NAME => call_dispatcher!(data as Payload<MessageReactionRemoveEmojiDispatch> => self.on_reaction_remove_emoji),
             PresencesReplaceDispatch::EVENT_NAME => trace!("Ignoring presence replace event"),
-            PresenceUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<PresenceUpdateDispatch> => self.on_presence_update),
+            PresenceUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<PresenceUpdateDispatch> => self.filter_presence_update),
             TypingStartDispatch::EVENT_NAME => call_dispatcher!(data as Payload<TypingStartDispatch> => self.on_typing_start),
             UserUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<UserUpdateDispatch> => self.on_user_update),
             VoiceStateUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<VoiceStateUpdateDispatch> => self.on_voice_state_update),
             VoiceServerUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<VoiceServerUpdateDispatch> => self.on_voice_server_update),
             WebhooksUpdateDispatch::EVENT_NAME => call_dispatcher!(data as Payload<WebhooksUpdateDispatch> => self.on_webhooks_update),
-            unknown_event => return Error::gateway(format!("Unknown event {}", unknown_event))
+            unknown_event => {
+                let payload: Payload<serde_json::Value> = serde_json::from_str(data)?;
+
+                if let Some(val) = payload.s {
+                    self.track_sequence(val).await?;
+                }
+
+                let raw = RawDispatch {
+                    name: unknown_event.to_owned(),
+                    data: payload.d,
+                };
+
+                if !self.config.dispatches_guild(raw.event_guild()) {
+                    trace!("Ignoring `{}` event of a filtered guild", unknown_event);
+                    return Ok(());
+                }
+
+                if let Some(hook) = &self.config.unknown_event {
+                    hook(unknown_event, data);
+                }
+
+                self.on_raw(raw, data).await?
+            }
         }
 
         Ok(())
@@ -389,6 +1554,103 @@ impl<'a> GatewayAPI<'a> {
     dispatcher!(on_channel_update: ChannelUpdateDispatch => channel_update);
     dispatcher!(on_channel_delete: ChannelDeleteDispatch => channel_delete);
     dispatcher!(on_channel_pins_update: ChannelPinsUpdateDispatch => channel_pins_update);
+    dispatcher!(on_thread_create: ThreadCreateDispatch => thread_create);
+    dispatcher!(on_thread_update: ThreadUpdateDispatch => thread_update);
+    dispatcher!(on_thread_delete: ThreadDeleteDispatch => thread_delete);
+    dispatcher!(on_thread_list_sync: ThreadListSyncDispatch => thread_list_sync);
+    dispatcher!(on_thread_member_update: ThreadMemberUpdateDispatch => thread_member_update);
+    dispatcher!(on_thread_members_update: ThreadMembersUpdateDispatch => thread_members_update);
+
+    /// Guilds that were unavailable in the ready event or
+    /// went through an outage are expected to be created
+    /// later, any other guild create means the bot joined
+    /// the guild.
+    async fn track_guild_create(&mut self, mut payload: GuildCreateDispatch, raw: &str) -> Result<(), Error> {
+        payload.1 = !self.unavailable_guilds.remove(&payload.0.id);
+
+        let leave = matches!(&self.config.guild_policy, Some(policy) if policy(&payload.0) == Decision::Leave);
+        if leave && self.leave_rejected_guild(&payload.0).await {
+            return Ok(());
+        }
+
+        self.on_guild_create(payload, raw).await
+    }
+
+    /// Dispatches the presence update to the listeners only
+    /// if it is accepted by the presence filter.
+    async fn filter_presence_update(&mut self, payload: PresenceUpdateDispatch, raw: &str) -> Result<(), Error> {
+        let dispatch = match self.config.presence_filter {
+            PresenceFilter::All => true,
+            PresenceFilter::Changes => self.presence_changed(&payload.0).await,
+            PresenceFilter::Sample(amount) => {
+                self.presence_count = self.presence_count.wrapping_add(1);
+                self.presence_count % amount.max(1) == 0
+            }
+        };
+
+        if dispatch {
+            return self.on_presence_update(payload, raw).await;
+        }
+
+        #[cfg(feature = "storage")]
+        self.config.storages.on_presence_update(&payload).await;
+
+        self.config.metrics.increment(metrics::EVENTS_TOTAL, &[("event", "presence_update")]);
+
+        Ok(())
+    }
+
+    /// Whether the status or the activities of the
+    /// user differ from the cached presence.
+    #[cfg(feature = "storage")]
+    async fn presence_changed(&mut self, update: &PresenceUpdate) -> bool {
+        match self.config.storages.read::<PresenceUpdate>().await.get_opt(update.guild_id, update.user.id) {
+            Some(cached) => !cached.same_activity(update),
+            None => update.status != "offline",
+        }
+    }
+
+    #[cfg(not(feature = "storage"))]
+    async fn presence_changed(&mut self, _update: &PresenceUpdate) -> bool {
+        true
+    }
+
+    /// Leaves a guild rejected by the guild policy unless
+    /// the bot owns it or already left too many guilds.
+    async fn leave_rejected_guild(&mut self, guild: &Guild) -> bool {
+        if self.bot.as_ref().map(|bot| bot.id) == Some(guild.owner_id) {
+            warn!("Guild policy rejected guild {} ({}) which is owned by the bot, staying", guild.name, guild.id);
+            return false;
+        }
+
+        let now = Instant::now();
+
+        if !allow_leave(self.policy_leaves, now) {
+            error!("Guild policy rejected guild {} ({}) but {} guilds were already left recently, staying", guild.name, guild.id, MAX_POLICY_LEAVES);
+            return false;
+        }
+
+        match self.http.leave_guild(guild.id).await {
+            Ok(()) => {
+                info!("Left guild {} ({}) rejected by the guild policy", guild.name, guild.id);
+                self.policy_leaves.push_back(now);
+                true
+            }
+            Err(err) => {
+                error!("Failed to leave guild {} ({}) rejected by the guild policy: {}", guild.name, guild.id, err);
+                false
+            }
+        }
+    }
+
+    async fn track_guild_delete(&mut self, payload: GuildDeleteDispatch, raw: &str) -> Result<(), Error> {
+        if payload.unavailable == Some(true) {
+            self.unavailable_guilds.insert(payload.id);
+        }
+
+        self.on_guild_delete(payload, raw).await
+    }
+
     dispatcher!(on_guild_create: GuildCreateDispatch => guild_create);
     dispatcher!(on_guild_update: GuildUpdateDispatch => guild_update);
     dispatcher!(on_guild_delete: GuildDeleteDispatch => guild_delete);
@@ -396,6 +1658,16 @@ impl<'a> GatewayAPI<'a> {
     dispatcher!(on_guild_ban_remove: GuildBanRemoveDispatch => guild_ban_remove);
     dispatcher!(on_guild_emojis_update: GuildEmojisUpdateDispatch => guild_emojis_update);
     dispatcher!(on_guild_integrations_update: GuildIntegrationsUpdateDispatch => guild_integrations_update);
+    dispatcher!(on_guild_scheduled_event_create: GuildScheduledEventCreateDispatch => guild_scheduled_event_create);
+    dispatcher!(on_guild_scheduled_event_update: GuildScheduledEventUpdateDispatch => guild_scheduled_event_update);
+    dispatcher!(on_guild_scheduled_event_delete: GuildScheduledEventDeleteDispatch => guild_scheduled_event_delete);
+    dispatcher!(on_guild_scheduled_event_user_add: GuildScheduledEventUserAddDispatch => guild_scheduled_event_user_add);
+    dispatcher!(on_guild_scheduled_event_user_remove: GuildScheduledEventUserRemoveDispatch => guild_scheduled_event_user_remove);
+    dispatcher!(on_auto_moderation_rule_create: AutoModerationRuleCreateDispatch => auto_moderation_rule_create);
+    dispatcher!(on_auto_moderation_rule_update: AutoModerationRuleUpdateDispatch => auto_moderation_rule_update);
+    dispatcher!(on_auto_moderation_rule_delete: AutoModerationRuleDeleteDispatch => auto_moderation_rule_delete);
+    dispatcher!(on_auto_moderation_action_execution: AutoModerationActionExecutionDispatch => auto_moderation_action_execution);
+    dispatcher!(on_interaction_create: InteractionCreateDispatch => interaction_create);
     dispatcher!(on_guild_member_add: GuildMemberAddDispatch => guild_member_add);
     dispatcher!(on_guild_member_remove: GuildMemberRemoveDispatch => guild_member_remove);
     dispatcher!(on_guild_member_update: GuildMemberUpdateDispatch => guild_member_update);
@@ -405,11 +1677,11 @@ impl<'a> GatewayAPI<'a> {
     dispatcher!(on_guild_role_delete: GuildRoleDeleteDispatch => guild_role_delete);
     dispatcher!(on_invite_create: InviteCreateDispatch => invite_create);
     dispatcher!(on_invite_delete: InviteDeleteDispatch => invite_delete);
-    dispatcher!(on_message_create: MessageCreateDispatch => message_create);
+    dispatcher!(on_message_create: MessageCreateDispatch => message_create, collect);
     dispatcher!(on_message_update: MessageUpdateDispatch => message_update);
     dispatcher!(on_message_delete: MessageDeleteDispatch => message_delete);
     dispatcher!(on_message_delete_bulk: MessageDeleteBulkDispatch => message_delete_bulk);
-    dispatcher!(on_reaction_add: MessageReactionAddDispatch => reaction_add);
+    dispatcher!(on_reaction_add: MessageReactionAddDispatch => reaction_add, collect);
     dispatcher!(on_reaction_remove: MessageReactionRemoveDispatch => reaction_remove);
     dispatcher!(on_reaction_remove_all: MessageReactionRemoveAllDispatch => reaction_remove_all);
     dispatcher!(on_reaction_remove_emoji: MessageReactionRemoveEmojiDispatch => reaction_remove_emoji);
@@ -419,66 +1691,110 @@ impl<'a> GatewayAPI<'a> {
     dispatcher!(on_voice_state_update: VoiceStateUpdateDispatch => voice_state_update);
     dispatcher!(on_voice_server_update: VoiceServerUpdateDispatch => voice_server_update);
     dispatcher!(on_webhooks_update: WebhooksUpdateDispatch => webhooks_update);
+    dispatcher!(on_raw: RawDispatch => raw);
+    dispatcher!(on_cache_resync: CacheResyncDispatch => cache_resync);
+
+    /// Saves the sequence number of the last event
+    /// and resynchronizes the storages if events
+    /// were skipped since the previous one.
+    async fn track_sequence(&mut self, sequence: i32) -> Result<(), Error> {
+        let previous = self.sequence_number.lock().await.replace(sequence);
+
+        match previous {
+            Some(previous) if sequence > previous + 1 => self.on_sequence_gap(previous + 1, sequence).await,
+            _ => Ok(())
+        }
+    }
 
-    async fn on_hello(&mut self, payload: Hello) -> Result<(), Error> {
-        if self.session_id.is_some() {
-            let resume = Resume {
-                token: self.http.token().clone(),
-                session_id: self.session_id.as_ref().unwrap().clone(),
-                seq: self.sequence_number.lock().await.unwrap(),
-            };
+    async fn on_sequence_gap(&mut self, expected: i32, received: i32) -> Result<(), Error> {
+        let strategy = self.config.resync_strategy;
+        warn!("Missed events {} to {}, resynchronizing with strategy {:?}", expected, received - 1, strategy);
+
+        match strategy {
+            ResyncStrategy::Refetch => {
+                //the refetch takes a few requests per guild, it
+                //runs beside the shard to not delay the events
+                #[cfg(feature = "storage")]
+                {
+                    let [shard_id, total_shards] = self.shard();
+                    let task = refetch_guilds(self.http.clone(), self.config.storages.share());
+                    tokio::spawn(logger::setup_for_task(format!("resync-{}/{}", shard_id, total_shards), task));
+                }
+            }
+            ResyncStrategy::Reidentify => {
+                self.session_id = None;
+                self.disconnect().await?;
+            }
+            ResyncStrategy::Ignore => ()
+        }
+
+        let raw = serde_json::json!({"expected": expected, "received": received}).to_string();
+        self.on_cache_resync(CacheResyncDispatch { expected, received, strategy }, &raw).await
+    }
 
+    async fn on_hello(&mut self, payload: Hello) -> Result<(), Error> {
+        if let Some(resume) = self.resume_payload().await {
             self.send_command(resume, true).await?;
             trace!("Requested to resume session");
         } else {
+            //a new session starts counting from the beginning
+            *self.sequence_number.lock().await = None;
+
             let identify = Identify {
                 token: self.http.token().clone(),
-                properties: map! {
-                    "$os" => env::consts::OS,
-                    "$browser" => "automate",
-                    "$device" => "automate"
-                },
+                properties: self.identify_properties(),
                 compress: false,
-                shard: [self.config.shard_id.unwrap(), self.config.total_shards.unwrap()],
+                shard: self.shard(),
                 large_threshold: self.config.member_threshold,
                 presence: self.config.presence.clone(),
                 guild_subscriptions: self.config.guild_subscriptions,
-                intents: self.config.intents,
+                intents: self.config.intents.bits(),
             };
 
             self.send_command(identify, true).await?;
         }
 
-        let sender: UnboundedSender<Instruction> = self.msg_sender.clone();
+        let sender = self.msg_sender.clone();
         let sequence_number = self.sequence_number.clone();
         let heartbeat_confirmed = self.heartbeat_confirmed.clone();
-        let shard_id = self.config.shard_id.clone().unwrap();
+        let [shard_id, total_shards] = self.shard();
+        let threshold = self.config.zombie_threshold;
+        let hook = self.config.zombie_hook.clone();
 
-        tokio::spawn(logger::setup_for_task(format!("hearbeat-{}", shard_id), async move {
-            heartbeat_task(sender, sequence_number, payload.heartbeat_interval as u64, heartbeat_confirmed).await;
+        tokio::spawn(logger::setup_for_task(format!("heartbeat-{}/{}", shard_id, total_shards), async move {
+            let zombie = Zombie::new(shard_id, threshold, hook);
+            heartbeat_task(sender, sequence_number, payload.heartbeat_interval as u64, heartbeat_confirmed, zombie).await;
         }));
 
         let interval = self.config.collector_period;
 
-        tokio::spawn(logger::setup_for_task(format!("collector-{}", shard_id), async move {
+        tokio::spawn(logger::setup_for_task(format!("collector-{}/{}", shard_id, total_shards), async move {
             bucket_collector_task(interval).await;
         }));
 
         Ok(())
     }
 
-    async fn on_ready(&mut self, payload: ReadyDispatch) -> Result<(), Error> {
+    async fn on_ready(&mut self, payload: ReadyDispatch, raw: &str) -> Result<(), Error> {
         self.bot = Some(payload.user.clone());
         self.session_id.replace(payload.session_id.clone());
+        *self.unavailable_guilds = payload.guilds.iter().map(|guild| guild.id).collect();
+        self.shard_sender.ready.store(true, Ordering::Relaxed);
 
         #[cfg(feature = "storage")]
             self.config.storages.on_ready(&payload).await;
 
+        self.config.metrics.increment(metrics::EVENTS_TOTAL, &[("event", "ready")]);
+
         let context = Context {
             sender: &self.msg_sender,
+            budget: &self.budget,
             #[cfg(feature = "storage")]
             storage: &self.config.storages,
             http: &self.http,
+            metrics: &self.config.metrics,
+            collectors: &self.config.collectors,
+            shard: self.shard(),
             bot: &payload.user,
         };
 
@@ -490,86 +1806,133 @@ impl<'a> GatewayAPI<'a> {
 
         trace!("Established connection for shard {}", shard_id);
 
-        let stateless = self.config.listeners.ready.iter()
-            .map(|l| (*l)(&context, &payload));
-
-        let stateful = self.config.listeners.stateful_listeners.iter_mut()
-            .map(|l| (*l).on_ready(&context, &payload));
-
-        future::join_all(stateless.chain(stateful)).await
-            .into_iter()
-            .filter_map(|r| r.err())
-            .for_each(|err| error!("Listener to `ready` failed with: {}", err));
+        run_listeners!(self, context, payload, raw, on_ready => ready);
 
         Ok(())
     }
 
-    async fn on_resumed(&mut self, _payload: ResumedDispatch) -> Result<(), Error> {
+    async fn on_resumed(&mut self, _payload: ResumedDispatch, _raw: &str) -> Result<(), Error> {
         trace!("Successfully resumed session");
         Ok(())
     }
 
     async fn on_reconnect(&mut self) -> Result<(), Error> {
         trace!("Received reconnect payload, disconnecting");
+        self.reconnect_cause = ReconnectCause::Requested;
         self.disconnect().await?;
 
         Ok(())
     }
 
+    /// The payload resuming the current session, if
+    /// there is a session that can be resumed.
+    async fn resume_payload(&mut self) -> Option<Resume> {
+        let seq = (*self.sequence_number.lock().await)?;
+
+        self.session_id.as_ref().map(|session_id| Resume {
+            token: self.http.token().clone(),
+            session_id: session_id.clone(),
+            seq,
+        })
+    }
+
     async fn on_invalid_session(&mut self, payload: InvalidSession) -> Result<(), Error> {
-        if !payload.0 {
-            self.session_id = None;
+        let resume = if payload.0 {
+            self.resume_payload().await
+        } else {
+            None
+        };
+
+        match resume {
+            //the session and sequence number are kept and the gateway
+            //expects to receive the resume after 1 to 5 seconds
+            Some(resume) => {
+                let delay = 1000 + random(4000);
+                let sender = self.msg_sender.clone();
+
+                warn!("Invalid session, resuming in {}ms", delay);
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    let _ = sender.push(Instruction::Send(resume.into(), true));
+                });
+            }
+            None => {
+                self.session_id = None;
 
-            warn!("Invalid session, shutting down connection");
-            self.disconnect().await?;
+                warn!("Invalid session, shutting down connection");
+                self.reconnect_cause = ReconnectCause::InvalidSession;
+                self.disconnect().await?;
+            }
         }
 
         Ok(())
     }
 
+    /// The gateway may request a heartbeat at any time,
+    /// it has to be sent immediately.
+    async fn on_heartbeat_request(&mut self) -> Result<(), Error> {
+        let sequence_number = *self.sequence_number.lock().await;
+        self.send_command(Heartbeat(sequence_number), true).await?;
+
+        trace!("Sent heartbeat requested by the gateway");
+        Ok(())
+    }
+
     async fn on_heartbeat_ack(&mut self) -> Result<(), Error> {
         self.heartbeat_confirmed.store(true, Ordering::Relaxed);
 
+        #[cfg(all(feature = "metrics", feature = "storage"))]
+        self.config.storages.record_sizes(&self.config.metrics, self.config.shard_id.unwrap_or(0)).await;
+
         trace!("Received heartbeat acknowledgement");
         Ok(())
     }
 }
 
-async fn check_remaining(remaining_commands: &mut Option<(i32, NaiveDateTime)>, necessary: bool) -> bool {
-    if remaining_commands.is_none() {
-        let until: NaiveDateTime = Utc::now().naive_utc() + ChronoDuration::minutes(1);
-
-        *remaining_commands = Some((120, until));
-    }
-
-    let mut remaining_commands = remaining_commands.as_mut().unwrap();
+/// How the heartbeat task detects and reports zombied connections.
+struct Zombie {
+    shard_id: u32,
+    threshold: u32,
+    hook: Option<ZombieHook>,
+    /// Heartbeats not acknowledged in a row.
+    missed: u32,
+}
 
-    //if now is after the given time, reset the rate-limit
-    if ::chrono::Utc::now().naive_utc() > remaining_commands.1 {
-        remaining_commands.0 = 120;
-        remaining_commands.1 += ChronoDuration::minutes(1);
+impl Zombie {
+    fn new(shard_id: u32, threshold: u32, hook: Option<ZombieHook>) -> Zombie {
+        Zombie { shard_id, threshold, hook, missed: 0 }
     }
 
-    if necessary || remaining_commands.0 > 5 {
-        remaining_commands.0 -= 1;
+    /// Counts the heartbeats that were not acknowledged
+    /// before the next one and returns whether the
+    /// connection should be considered zombied.
+    fn missed(&mut self, acknowledged: bool) -> bool {
+        if acknowledged {
+            self.missed = 0;
+            return false;
+        }
 
-        trace!("{} gateway command calls remaining until (reset at {} UTC)", remaining_commands.0, remaining_commands.1.format("%Y-%m-%d %H:%M:%S"));
-        true
-    } else {
-        trace!("Reached gateway rate limit ({} calls left, reset at {} UTC)", remaining_commands.0, remaining_commands.1.format("%Y-%m-%d %H:%M:%S"));
-        false
+        self.missed += 1;
+        self.missed >= self.threshold
     }
 }
 
 async fn heartbeat_task(
-    mut sender: UnboundedSender<Instruction>,
+    sender: InstructionSender,
     sequence_number: Arc<Mutex<Option<i32>>>,
     interval: u64,
     heartbeat_confirmed: Arc<AtomicBool>,
+    mut zombie: Zombie,
 ) {
-    let rs: Result<(), SendError> = try {
+    //the first heartbeat is sent after a random fraction of the
+    //interval so that shards reconnecting at the same time do
+    //not all send their heartbeats at the same time
+    let mut delay = random(interval);
+
+    let rs: Result<(), Error> = try {
         loop {
-            tokio::time::sleep(Duration::from_millis(interval)).await;
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            delay = interval;
 
             //if the channel was closed, it means the shard closed and dropped the receiver
             //therefore this heartbeat task is not needed anymore and a new one will be created
@@ -579,28 +1942,34 @@ async fn heartbeat_task(
                 return;
             }
 
-            if !heartbeat_confirmed.load(Ordering::Relaxed) {
-                warn!("Zombied connection detected, shutting down connection");
+            if zombie.missed(heartbeat_confirmed.load(Ordering::Relaxed)) {
+                warn!("Zombied connection detected after {} unacknowledged heartbeats, shutting down connection", zombie.missed);
+
+                if let Some(hook) = &zombie.hook {
+                    hook(zombie.shard_id, zombie.missed);
+                }
+
                 break;
+            } else if zombie.missed > 0 {
+                warn!("Heartbeat was not acknowledged ({}/{})", zombie.missed, zombie.threshold);
             }
 
-            sender.send(Instruction::Send(Heartbeat(*sequence_number.lock().await).into(), true)).await?;
+            sender.push(Instruction::Send(Heartbeat(*sequence_number.lock().await).into(), true))?;
             heartbeat_confirmed.store(false, Ordering::Relaxed);
 
             trace!("Successfully sent heartbeat");
         }
     };
 
-    if let Err(err) = rs {
-        error!("Heartbeat thread failed ({}), shutting down connection", err.to_string());
-    }
-
-    let close: Result<(), SendError> = try {
-        sender.send(Instruction::Close).await?;
-        sender.close().await?;
+    let instruction = match rs {
+        Ok(()) => Instruction::Zombie,
+        Err(err) => {
+            error!("Heartbeat thread failed ({}), shutting down connection", err.to_string());
+            Instruction::Close
+        }
     };
 
-    if let Err(err) = close {
+    if let Err(err) = sender.push(instruction) {
         error!("Failed to close channel: {}", err.to_string());
     }
 }
@@ -610,4 +1979,377 @@ async fn bucket_collector_task(interval: u64) {
         tokio::time::sleep(Duration::from_millis(interval * 1000)).await;
         http::collect_outdated_buckets().await;
     }
-}
\ No newline at end of file
+}
+
+/// Amount of guilds refetched at the same time
+/// after events were missed.
+#[cfg(feature = "storage")]
+const REFETCH_CONCURRENCY: usize = 4;
+
+/// Updates the guilds of the shard and their
+/// channels from the HTTP API.
+#[cfg(feature = "storage")]
+async fn refetch_guilds(http: HttpAPI, mut storages: StorageContainer) {
+    let guilds: Vec<Snowflake> = storages.read::<Guild>().await.iter().map(|g| g.id).collect();
+
+    let mut fetched = stream::iter(guilds)
+        .map(|guild| {
+            let http = &http;
+            async move { (guild, http.guild(guild).await, http.channels(guild).await) }
+        })
+        .buffer_unordered(REFETCH_CONCURRENCY);
+
+    while let Some((guild, fetched_guild, channels)) = fetched.next().await {
+        match fetched_guild {
+            Ok(fetched) => storages.on_guild_update(&GuildUpdateDispatch(fetched)).await,
+            Err(err) => warn!("Failed to refetch guild {}: {}", guild, err),
+        }
+
+        let channels = match channels {
+            Ok(channels) => channels,
+            Err(err) => {
+                warn!("Failed to refetch the channels of guild {}: {}", guild, err);
+                continue;
+            }
+        };
+
+        //threads are not returned with the channels of the guild
+        let deleted: Vec<Channel> = storages.read::<Channel>().await.guild(guild).into_iter()
+            .filter(|c| !matches!(c, Channel::Thread(_)) && !channels.iter().any(|fetched| fetched.id() == c.id()))
+            .cloned()
+            .collect();
+
+        for channel in deleted {
+            storages.on_channel_delete(&ChannelDeleteDispatch(channel)).await;
+        }
+
+        for channel in &channels {
+            storages.on_channel_update(&ChannelUpdateDispatch(Channel::from_guild(channel))).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, ContextBuilder};
+    use crate::gateway::Presence;
+    use futures::executor::block_on;
+
+    #[test]
+    fn full_command_queue_does_not_block_listeners() {
+        let config = Configuration::new("token").command_queue_capacity(2);
+        let mut builder = ContextBuilder::with_config(config);
+
+        {
+            let ctx = builder.build();
+
+            for _ in 0..2 {
+                block_on(ctx.send_raw_gateway(3, serde_json::json!({}))).unwrap();
+            }
+
+            //the queue is only emptied between two events, a
+            //listener waiting for room would never return
+            assert!(block_on(ctx.send_raw_gateway(3, serde_json::json!({}))).is_err());
+            assert!(block_on(ctx.set_activity(Presence::playing("dropped"))).is_ok());
+        }
+
+        assert_eq!(builder.commands().len(), 2);
+
+        let config = Configuration::new("token").command_queue_capacity(1).backpressure(Backpressure::Reject);
+        let builder = ContextBuilder::with_config(config);
+        let ctx = builder.build();
+
+        assert!(block_on(ctx.set_activity(Presence::playing("queued"))).is_ok());
+        assert!(block_on(ctx.set_activity(Presence::playing("rejected"))).is_err());
+    }
+
+    #[test]
+    fn control_instructions_bypass_the_command_queue() {
+        let (sender, mut receiver) = InstructionSender::channel(1, Backpressure::default());
+
+        sender.try_send(Instruction::Send(TkMessage::Text(String::from("command")), false)).unwrap();
+        assert!(sender.try_send(Instruction::Send(TkMessage::Text(String::from("full")), false)).is_err());
+
+        sender.push(Instruction::Send(TkMessage::Text(String::from("heartbeat")), true)).unwrap();
+        sender.push(Instruction::Close).unwrap();
+
+        assert!(matches!(receiver.try_recv(), Some(Instruction::Send(TkMessage::Text(m), true)) if m == "heartbeat"));
+        assert!(matches!(receiver.try_recv(), Some(Instruction::Close)));
+        assert!(matches!(receiver.try_recv(), Some(Instruction::Send(TkMessage::Text(m), false)) if m == "command"));
+        assert!(receiver.try_recv().is_none());
+    }
+
+    #[test]
+    fn coalesces_presences_until_the_end_of_the_window() {
+        let (sender, mut receiver) = InstructionSender::channel(1, Backpressure::default());
+        let presence = |status: &str| TkMessage::Text(String::from(status));
+        let mut pending = None;
+
+        let sent = coalesce_presence(&mut pending, presence("online"), None, &sender);
+        assert!(matches!(sent, Some(TkMessage::Text(m)) if m == "online"));
+        assert!(pending.is_none());
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let window = Some(Duration::from_millis(20));
+
+            assert!(coalesce_presence(&mut pending, presence("idle"), window, &sender).is_none());
+            assert!(coalesce_presence(&mut pending, presence("dnd"), window, &sender).is_none());
+            assert!(receiver.try_recv().is_none());
+
+            //a single flush for every update of the window
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(matches!(receiver.try_recv(), Some(Instruction::FlushPresence)));
+            assert!(receiver.try_recv().is_none());
+            assert!(matches!(pending.take(), Some(TkMessage::Text(m)) if m == "dnd"));
+        });
+    }
+
+    #[test]
+    fn command_budget_keeps_a_reserve() {
+        let budget = CommandBudget::default();
+        assert_eq!(budget.remaining(), 115);
+
+        for _ in 0..115 {
+            assert!(budget.consume(false));
+        }
+
+        assert!(!budget.consume(false));
+        assert_eq!(budget.remaining(), 0);
+
+        //heartbeats and identify can still be sent
+        for _ in 0..5 {
+            assert!(budget.consume(true));
+        }
+
+        assert!(!budget.consume(false));
+    }
+
+    #[test]
+    fn zombies_after_consecutive_missed_acks() {
+        let mut zombie = Zombie::new(0, 3, None);
+
+        assert!(!zombie.missed(false));
+        assert!(!zombie.missed(false));
+        assert!(!zombie.missed(true));
+        assert_eq!(zombie.missed, 0);
+
+        assert!(!zombie.missed(false));
+        assert!(!zombie.missed(false));
+        assert!(zombie.missed(false));
+
+        assert!(Zombie::new(0, 1, None).missed(false));
+    }
+
+    #[test]
+    fn heartbeat_task_reports_zombies() {
+        let (sender, mut receiver) = InstructionSender::channel(1, Backpressure::default());
+        let reported = Arc::new(std::sync::Mutex::new(None));
+
+        let hook_reported = Arc::clone(&reported);
+        let hook: ZombieHook = Arc::new(move |shard, missed| *hook_reported.lock().unwrap() = Some((shard, missed)));
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let confirmed = Arc::new(AtomicBool::new(true));
+            heartbeat_task(sender, Arc::new(Mutex::new(Some(1))), 1, confirmed, Zombie::new(2, 2, Some(hook))).await;
+        });
+
+        //nothing acknowledges the heartbeats sent by the task
+        assert!(matches!(receiver.try_recv(), Some(Instruction::Send(_, true))));
+        assert!(matches!(receiver.try_recv(), Some(Instruction::Send(_, true))));
+        assert!(matches!(receiver.try_recv(), Some(Instruction::Zombie)));
+        assert!(receiver.try_recv().is_none());
+        assert_eq!(*reported.lock().unwrap(), Some((2, 2)));
+    }
+
+    #[test]
+    fn reconnect_causes_have_distinct_labels() {
+        let causes = [
+            ReconnectCause::Requested,
+            ReconnectCause::InvalidSession,
+            ReconnectCause::Zombie,
+            ReconnectCause::Closed,
+            ReconnectCause::Error,
+        ];
+
+        let labels: HashSet<&str> = causes.iter().map(|cause| cause.label()).collect();
+        assert_eq!(labels.len(), causes.len());
+        assert_eq!(ReconnectCause::Zombie.label(), "zombie");
+        assert_eq!(ReconnectCause::InvalidSession.label(), "invalid_session");
+    }
+
+    #[test]
+    fn policy_leaves_are_limited_per_period() {
+        let start = Instant::now();
+        let mut leaves = VecDeque::new();
+
+        for minute in 0..MAX_POLICY_LEAVES as u64 {
+            let now = start + Duration::from_secs(minute * 60);
+            assert!(allow_leave(&mut leaves, now));
+            leaves.push_back(now);
+        }
+
+        assert!(!allow_leave(&mut leaves, start + Duration::from_secs(599)));
+        assert_eq!(leaves.len(), MAX_POLICY_LEAVES);
+
+        //the first guild was left more than ten minutes ago
+        assert!(allow_leave(&mut leaves, start + POLICY_LEAVES_PERIOD));
+        assert_eq!(leaves.len(), MAX_POLICY_LEAVES - 1);
+    }
+
+    /// Counts the guilds left through the HTTP API.
+    struct Leaves(Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl crate::http::HttpClient for Leaves {
+        async fn request(&self, request: hyper::Request<hyper::Body>) -> Result<hyper::Response<hyper::Body>, Error> {
+            assert_eq!(request.method(), hyper::Method::DELETE);
+            self.0.fetch_add(1, Ordering::SeqCst);
+
+            Ok(hyper::Response::builder().status(204).body(hyper::Body::empty()).unwrap())
+        }
+    }
+
+    #[test]
+    fn rejected_guilds_owned_by_the_bot_are_kept() {
+        let left = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let http = HttpAPI::new("policy leaves").with_client(Leaves(Arc::clone(&left)));
+
+        let mut config = Configuration::new("token");
+        let shard_sender = ShardSender::default();
+        let mut unavailable_guilds = HashSet::new();
+        let mut policy_leaves = VecDeque::new();
+        let (msg_sender, _receiver) = InstructionSender::channel(1, Backpressure::default());
+
+        let mut gateway = GatewayAPI {
+            config: &mut config,
+            shard_sender: &shard_sender,
+            unavailable_guilds: &mut unavailable_guilds,
+            policy_leaves: &mut policy_leaves,
+            session_id: None,
+            msg_sender,
+            budget: CommandBudget::default(),
+            http: &http,
+            bot: Some(testing::fake_user(1, "bot")),
+            sequence_number: Arc::new(Mutex::new(None)),
+            heartbeat_confirmed: Arc::new(AtomicBool::new(true)),
+            reconnect_cause: ReconnectCause::Closed,
+            presence_count: 0,
+            initialized: true,
+        };
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            assert!(!gateway.leave_rejected_guild(&testing::fake_guild(10, 1, "owned")).await);
+            assert!(gateway.leave_rejected_guild(&testing::fake_guild(11, 2, "joined")).await);
+        });
+
+        assert_eq!(left.load(Ordering::SeqCst), 1);
+        assert_eq!(policy_leaves.len(), 1);
+    }
+
+    /// Sends the messages a few milliseconds after
+    /// receiving them and counts them.
+    #[cfg(feature = "storage")]
+    struct SlowMessages(Arc<std::sync::atomic::AtomicUsize>);
+
+    #[cfg(feature = "storage")]
+    #[async_trait::async_trait]
+    impl crate::http::HttpClient for SlowMessages {
+        async fn request(&self, request: hyper::Request<hyper::Body>) -> Result<hyper::Response<hyper::Body>, Error> {
+            let body = if request.method() == hyper::Method::POST {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                let sent = self.0.fetch_add(1, Ordering::SeqCst) as u64;
+
+                testing::message_json(100 + sent, 1, "sent")
+            } else {
+                let id = request.uri().path().rsplit('/').next().unwrap().parse::<u64>().unwrap();
+                testing::message_json(id, 1, "edited")
+            };
+
+            Ok(hyper::Response::builder().status(200).body(hyper::Body::from(body.to_string())).unwrap())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "storage")]
+    fn concurrent_upserts_send_one_message() {
+        let sent = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let http = HttpAPI::new("upsert").with_client(SlowMessages(Arc::clone(&sent)));
+        let builder = ContextBuilder::new().http(http);
+        let ctx = builder.build();
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let (first, second) = future::join(
+                ctx.upsert_message(Snowflake(1), "status", MessageBuilder::new().content("first")),
+                ctx.upsert_message(Snowflake(1), "status", MessageBuilder::new().content("second")),
+            ).await;
+
+            assert_eq!(first.unwrap().id, second.unwrap().id);
+        });
+
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn purged_messages_partition_by_age() {
+        const DAY: u64 = 24 * 60 * 60 * 1000;
+
+        let now = 1_600_000_000_000;
+        let sent = |age: u64| Snowflake((now - age - 1_420_070_400_000) << 22);
+
+        let messages = vec![sent(0), sent(13 * DAY), sent(14 * DAY - 30_000), sent(14 * DAY), sent(100 * DAY)];
+        let (recent, old) = partition_by_age(messages.clone(), now);
+
+        assert_eq!(recent, messages[..2]);
+        assert_eq!(old, messages[2..]);
+        assert!(recent.iter().all(|id| now - id.timestamp_millis() < BULK_DELETE_MAX_AGE));
+
+        assert!(purge_interrupted(0, Error::new("forbidden")).is_err());
+        assert!(matches!(purge_interrupted(150, Error::new("forbidden")), Ok(150)));
+    }
+
+    /// Answers the requests refetching the guild 1.
+    #[cfg(feature = "storage")]
+    struct RefetchApi;
+
+    #[cfg(feature = "storage")]
+    #[async_trait::async_trait]
+    impl crate::http::HttpClient for RefetchApi {
+        async fn request(&self, request: hyper::Request<hyper::Body>) -> Result<hyper::Response<hyper::Body>, Error> {
+            let body = match request.uri().path().trim_start_matches("/api/v8") {
+                "/guilds/1" => testing::guild_json(1, 2, "renamed"),
+                "/guilds/1/channels" => serde_json::json!([testing::channel_json(11, 1, ChannelType::GuildText, None, 0)]),
+                _ => return Ok(hyper::Response::builder().status(404).body(hyper::Body::from("{}")).unwrap()),
+            };
+
+            Ok(hyper::Response::builder().status(200).body(hyper::Body::from(body.to_string())).unwrap())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "storage")]
+    fn refetches_guilds_through_the_http_api() {
+        let mut storages = StorageContainer::for_use(6);
+        storages.initialize::<Guild>();
+        storages.initialize::<Channel>();
+        storages.initialize::<User>();
+        storages.initialize::<PresenceUpdate>();
+        storages.initialize::<VoiceState>();
+        storages.initialize::<crate::storage::Settings>();
+
+        let stale = testing::fake_channel(10, 1, ChannelType::GuildText, None, 0);
+        block_on(storages.on_guild_create(&GuildCreateDispatch::from(testing::fake_guild(1, 2, "guild"))));
+        block_on(storages.on_channel_create(&ChannelCreateDispatch(Channel::from_guild(&stale))));
+
+        let http = HttpAPI::new("refetch").with_client(RefetchApi);
+        tokio::runtime::Runtime::new().unwrap().block_on(refetch_guilds(http, storages.share()));
+
+        block_on(async {
+            assert_eq!(storages.read::<Guild>().await.get(Snowflake(1)).name, "renamed");
+
+            let channels = storages.read::<Channel>().await;
+            assert!(channels.get_opt(Snowflake(10)).is_none());
+            assert!(channels.get_opt(Snowflake(11)).is_some());
+        });
+    }
+}
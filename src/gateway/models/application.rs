@@ -0,0 +1,148 @@
+use crate::gateway::{User, PartialUser, Intent, IntentSet};
+use crate::Snowflake;
+
+/// The application of a bot.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/topics/oauth2#application-object)
+#[object(server)]
+pub struct Application {
+    pub id: Snowflake,
+    pub name: String,
+    #[nullable]
+    pub icon: Option<String>,
+    pub description: String,
+    pub bot_public: bool,
+    pub bot_require_code_grant: bool,
+    pub owner: Option<User>,
+    /// The team owning the application if
+    /// it belongs to a team.
+    #[nullable]
+    pub team: Option<Team>,
+    pub flags: Option<u32>,
+}
+
+/// A group of developers owning applications.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/topics/teams#data-models-team-object)
+#[object(server)]
+pub struct Team {
+    pub id: Snowflake,
+    pub name: String,
+    #[nullable]
+    pub icon: Option<String>,
+    pub members: Vec<TeamMember>,
+    pub owner_user_id: Snowflake,
+}
+
+#[object(server)]
+pub struct TeamMember {
+    pub membership_state: MembershipState,
+    pub permissions: Vec<String>,
+    pub team_id: Snowflake,
+    pub user: PartialUser,
+}
+
+#[convert(u8)]
+pub enum MembershipState {
+    Invited = 1,
+    Accepted = 2,
+}
+
+/// An [Application](automate::gateway::Application)
+/// with only its `id` and `flags` as sent in the
+/// [ReadyDispatch](automate::gateway::ReadyDispatch).
+#[object(server)]
+pub struct PartialApplication {
+    pub id: Snowflake,
+    pub flags: Option<u32>,
+}
+
+/// Flags of an application, they mostly indicate
+/// which privileged intents the bot is allowed to use.
+#[convert(u32)]
+pub enum ApplicationFlag {
+    /// Verified bot allowed to use the
+    /// [GuildPresences](automate::Intent::GuildPresences) intent.
+    GatewayPresence = 1 << 12,
+    /// Unverified bot with the presence intent
+    /// toggle enabled in the developer portal.
+    GatewayPresenceLimited = 1 << 13,
+    /// Verified bot allowed to use the
+    /// [GuildMembers](automate::Intent::GuildMembers) intent.
+    GatewayGuildMembers = 1 << 14,
+    /// Unverified bot with the server members intent
+    /// toggle enabled in the developer portal.
+    GatewayGuildMembersLimited = 1 << 15,
+    VerificationPendingGuildLimit = 1 << 16,
+    Embedded = 1 << 17,
+    /// Verified bot allowed to use the
+    /// [MessageContent](automate::Intent::MessageContent) intent.
+    GatewayMessageContent = 1 << 18,
+    /// Unverified bot with the message content intent
+    /// toggle enabled in the developer portal.
+    GatewayMessageContentLimited = 1 << 19,
+}
+
+impl ApplicationFlag {
+    /// Checks if the flag is in the given flags.
+    pub fn is_set(self, flags: u32) -> bool {
+        flags & self as u32 != 0
+    }
+}
+
+impl Application {
+    /// The users owning the application, which are the
+    /// members of the team who accepted the invitation
+    /// if the application belongs to a team.
+    pub fn owners(&self) -> Vec<Snowflake> {
+        match (&self.team, &self.owner) {
+            (Some(team), _) => team.members.iter()
+                .filter(|member| matches!(member.membership_state, MembershipState::Accepted))
+                .map(|member| member.user.id)
+                .collect(),
+            (None, Some(owner)) => vec![owner.id],
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// Whether the user owns the application, useful
+    /// for commands reserved to the bot's owners.
+    pub fn is_owner(&self, user: Snowflake) -> bool {
+        self.owners().contains(&user)
+    }
+
+    /// Makes sure the application is allowed to use every
+    /// privileged intent in the given intents. Returns the
+    /// intents that need to be enabled in the developer portal.
+    pub fn missing_intents<I: Into<IntentSet>>(&self, intents: I) -> Vec<Intent> {
+        missing_intents(self.flags.unwrap_or(0), intents.into())
+    }
+}
+
+impl PartialApplication {
+    /// See [Application::missing_intents](automate::gateway::Application::missing_intents).
+    pub fn missing_intents<I: Into<IntentSet>>(&self, intents: I) -> Vec<Intent> {
+        missing_intents(self.flags.unwrap_or(0), intents.into())
+    }
+}
+
+fn missing_intents(flags: u32, intents: IntentSet) -> Vec<Intent> {
+    let mut missing = Vec::new();
+
+    let presences = ApplicationFlag::GatewayPresence.is_set(flags) || ApplicationFlag::GatewayPresenceLimited.is_set(flags);
+    if intents.contains(Intent::GuildPresences) && !presences {
+        missing.push(Intent::GuildPresences);
+    }
+
+    let members = ApplicationFlag::GatewayGuildMembers.is_set(flags) || ApplicationFlag::GatewayGuildMembersLimited.is_set(flags);
+    if intents.contains(Intent::GuildMembers) && !members {
+        missing.push(Intent::GuildMembers);
+    }
+
+    let content = ApplicationFlag::GatewayMessageContent.is_set(flags) || ApplicationFlag::GatewayMessageContentLimited.is_set(flags);
+    if intents.contains(Intent::MessageContent) && !content {
+        missing.push(Intent::MessageContent);
+    }
+
+    missing
+}
@@ -0,0 +1,92 @@
+use crate::{Snowflake, Identifiable};
+
+/// A rule of the automatic moderation of a guild
+/// which triggers actions when its trigger matches
+/// the content of a message.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/resources/auto-moderation)
+#[object(server)]
+pub struct AutoModerationRule {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub name: String,
+    pub creator_id: Snowflake,
+    pub event_type: AutoModerationEventType,
+    pub trigger_type: AutoModerationTriggerType,
+    pub trigger_metadata: AutoModerationTriggerMetadata,
+    pub actions: Vec<AutoModerationAction>,
+    pub enabled: bool,
+    pub exempt_roles: Vec<Snowflake>,
+    pub exempt_channels: Vec<Snowflake>,
+}
+
+impl Identifiable for AutoModerationRule {
+    fn id(&self) -> Snowflake {
+        self.id
+    }
+}
+
+#[convert(u8)]
+pub enum AutoModerationEventType {
+    MessageSend = 1,
+}
+
+#[convert(u8)]
+pub enum AutoModerationTriggerType {
+    Keyword = 1,
+    Spam = 3,
+    KeywordPreset = 4,
+    MentionSpam = 5,
+}
+
+/// Additional data of the trigger, the fields
+/// that can be set depend on the trigger type.
+#[object(both, default)]
+pub struct AutoModerationTriggerMetadata {
+    /// Words to match, for keyword triggers
+    pub keyword_filter: Option<Vec<String>>,
+    /// Rust flavored regular expressions to
+    /// match, for keyword triggers
+    pub regex_patterns: Option<Vec<String>>,
+    /// Predefined word lists to match, for
+    /// keyword preset triggers
+    pub presets: Option<Vec<KeywordPresetType>>,
+    /// Words that should not trigger the rule, for
+    /// keyword and keyword preset triggers
+    pub allow_list: Option<Vec<String>>,
+    /// Amount of unique role and user mentions
+    /// allowed per message, for mention spam triggers
+    pub mention_total_limit: Option<i32>,
+}
+
+#[convert(u8)]
+pub enum KeywordPresetType {
+    Profanity = 1,
+    SexualContent = 2,
+    Slurs = 3,
+}
+
+/// An action taken when a rule is triggered.
+#[object(both)]
+pub struct AutoModerationAction {
+    #[serde(rename = "type")]
+    pub _type: AutoModerationActionType,
+    pub metadata: Option<AutoModerationActionMetadata>,
+}
+
+#[convert(u8)]
+pub enum AutoModerationActionType {
+    BlockMessage = 1,
+    SendAlertMessage = 2,
+    Timeout = 3,
+}
+
+#[object(both, default)]
+pub struct AutoModerationActionMetadata {
+    /// Channel where the alert messages are
+    /// sent, for send alert message actions
+    pub channel_id: Option<Snowflake>,
+    /// Duration of the timeout in seconds, up to
+    /// 4 weeks, for timeout actions
+    pub duration_seconds: Option<i32>,
+}
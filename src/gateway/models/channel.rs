@@ -1,4 +1,4 @@
-use crate::gateway::{User, PartialUser, PartialGuild};
+use crate::gateway::{User, PartialUser, PartialGuild, PartialApplication};
 use crate::Snowflake;
 use std::collections::HashMap;
 
@@ -10,6 +10,7 @@ pub enum Channel {
     News(NewsChannel),
     Store(StoreChannel),
     Stage(StageChannel),
+    Thread(ThreadChannel),
     Direct(DirectChannel),
     Group(GroupChannel),
 }
@@ -22,6 +23,7 @@ pub enum GuildChannel {
     News(NewsChannel),
     Store(StoreChannel),
     Stage(StageChannel),
+    Thread(ThreadChannel),
 }
 
 #[derive(Debug, Clone)]
@@ -68,6 +70,9 @@ pub struct VoiceChannel {
     pub name: String,
     pub bitrate: i32,
     pub user_limit: i32,
+    /// The [voice region](automate::HttpAPI::voice_regions)
+    /// of the channel, automatic when `None`.
+    pub rtc_region: Option<String>,
     #[serde(default)]
     pub video_quality_mode: VideoQualityMode,
 }
@@ -110,6 +115,59 @@ pub struct StageChannel {
     pub name: String,
     pub bitrate: i32,
     pub user_limit: i32,
+    /// The [voice region](automate::HttpAPI::voice_regions)
+    /// of the channel, automatic when `None`.
+    pub rtc_region: Option<String>,
+}
+
+/// A public or private thread in a text channel
+/// or a thread in a news channel.
+#[object(server)]
+pub struct ThreadChannel {
+    pub id: Snowflake,
+    #[serde(rename = "type")]
+    pub _type: ChannelType,
+    pub guild_id: Option<Snowflake>,
+    #[nullable]
+    pub parent_id: Option<Snowflake>,
+    pub owner_id: Option<Snowflake>,
+    pub name: String,
+    #[option_nullable]
+    pub last_message_id: Option<Option<Snowflake>>,
+    pub last_pin_timestamp: Option<String>,
+    pub rate_limit_per_user: Option<i32>,
+    pub message_count: Option<i32>,
+    pub member_count: Option<i32>,
+    pub thread_metadata: ThreadMetadata,
+    /// Thread member object of the bot if
+    /// it has joined the thread.
+    pub member: Option<ThreadMember>,
+    /// Only sent in thread create events when
+    /// the thread was just created.
+    pub newly_created: Option<bool>,
+}
+
+#[object(server)]
+pub struct ThreadMetadata {
+    pub archived: bool,
+    /// Duration in minutes after which the thread will
+    /// stop showing in the channel list, can be
+    /// 60, 1440, 4320 or 10080.
+    pub auto_archive_duration: i32,
+    pub archive_timestamp: String,
+    pub locked: Option<bool>,
+    pub invitable: Option<bool>,
+}
+
+/// A user that joined a thread. The id and user id are
+/// omitted when the member is sent in a guild create event.
+#[object(server)]
+pub struct ThreadMember {
+    pub id: Option<Snowflake>,
+    pub user_id: Option<Snowflake>,
+    pub guild_id: Option<Snowflake>,
+    pub join_timestamp: String,
+    pub flags: u32,
 }
 
 #[object(server)]
@@ -142,8 +200,16 @@ pub struct Invite {
     pub guild: Option<PartialGuild>,
     pub channel: InviteChannel,
     pub inviter: Option<User>,
+    /// The user whose stream is displayed
+    /// by a [stream](automate::gateway::InviteTargetType::Stream)
+    /// invite.
     pub target_user: Option<PartialUser>,
     pub target_user_type: Option<i32>,
+    pub target_type: Option<InviteTargetType>,
+    /// The activity opened by an
+    /// [embedded application](automate::gateway::InviteTargetType::EmbeddedApplication)
+    /// invite.
+    pub target_application: Option<PartialApplication>,
     pub approximate_presence_count: Option<i32>,
     pub approximate_member_count: Option<i32>,
 
@@ -155,6 +221,16 @@ pub struct Invite {
     pub created_at: Option<String>,
 }
 
+/// What a voice channel invite opens
+/// once the user has joined the channel.
+#[convert(u8)]
+pub enum InviteTargetType {
+    /// The stream of the target user.
+    Stream = 1,
+    /// An activity such as Watch Together.
+    EmbeddedApplication = 2,
+}
+
 #[object(server)]
 pub struct InviteChannel {
     pub id: Snowflake,
@@ -169,8 +245,16 @@ pub struct PartialInvite {
     pub guild: Option<PartialGuild>,
     pub channel: Option<Channel>,
     pub inviter: Option<User>,
+    /// The user whose stream is displayed
+    /// by a [stream](automate::gateway::InviteTargetType::Stream)
+    /// invite.
     pub target_user: Option<PartialUser>,
     pub target_user_type: Option<i32>,
+    pub target_type: Option<InviteTargetType>,
+    /// The activity opened by an
+    /// [embedded application](automate::gateway::InviteTargetType::EmbeddedApplication)
+    /// invite.
+    pub target_application: Option<PartialApplication>,
     pub approximate_presence_count: Option<i32>,
     pub approximate_member_count: Option<i32>,
 
@@ -191,6 +275,9 @@ pub enum ChannelType {
     GuildCategory = 4,
     GuildNews = 5,
     GuildStore = 6,
+    GuildNewsThread = 10,
+    GuildPublicThread = 11,
+    GuildPrivateThread = 12,
     GuildStageVoice = 13,
 }
 
@@ -253,7 +340,13 @@ pub enum WebhookType {
 
 mod channels {
     use crate::{Snowflake, Identifiable, Error};
-    use super::{Channel, GuildChannel, PrivateChannel};
+    use super::{Channel, GuildChannel, PrivateChannel, ThreadChannel, Overwrite};
+
+    impl Identifiable for ThreadChannel {
+        fn id(&self) -> Snowflake {
+            self.id
+        }
+    }
 
     impl Identifiable for Channel {
         fn id(&self) -> Snowflake {
@@ -264,6 +357,7 @@ mod channels {
                 Channel::News(c) => c.id,
                 Channel::Store(c) => c.id,
                 Channel::Stage(c) => c.id,
+                Channel::Thread(c) => c.id,
                 Channel::Direct(c) => c.id,
                 Channel::Group(c) => c.id,
             }
@@ -279,6 +373,7 @@ mod channels {
                 GuildChannel::News(c) => c.id,
                 GuildChannel::Store(c) => c.id,
                 GuildChannel::Stage(c) => c.id,
+                GuildChannel::Thread(c) => c.id,
             }
         }
     }
@@ -301,6 +396,36 @@ mod channels {
                 GuildChannel::News(c) => Channel::News(Clone::clone(c)),
                 GuildChannel::Store(c) => Channel::Store(Clone::clone(c)),
                 GuildChannel::Stage(c) => Channel::Stage(Clone::clone(c)),
+                GuildChannel::Thread(c) => Channel::Thread(Clone::clone(c)),
+            }
+        }
+
+        /// Guild the channel belongs to or `None`
+        /// for private channels.
+        pub fn guild_id(&self) -> Option<Snowflake> {
+            match self {
+                Channel::Category(c) => c.guild_id,
+                Channel::Text(c) => c.guild_id,
+                Channel::Voice(c) => c.guild_id,
+                Channel::News(c) => c.guild_id,
+                Channel::Store(c) => c.guild_id,
+                Channel::Stage(c) => c.guild_id,
+                Channel::Thread(c) => c.guild_id,
+                Channel::Direct(_) | Channel::Group(_) => None,
+            }
+        }
+
+        /// Permission overwrites of the channel, empty
+        /// for threads and private channels.
+        pub fn permission_overwrites(&self) -> &[Overwrite] {
+            match self {
+                Channel::Category(c) => &c.permission_overwrites,
+                Channel::Text(c) => &c.permission_overwrites,
+                Channel::Voice(c) => c.permission_overwrites.as_deref().unwrap_or(&[]),
+                Channel::News(c) => &c.permission_overwrites,
+                Channel::Store(c) => &c.permission_overwrites,
+                Channel::Stage(c) => c.permission_overwrites.as_deref().unwrap_or(&[]),
+                Channel::Thread(_) | Channel::Direct(_) | Channel::Group(_) => &[],
             }
         }
 
@@ -313,6 +438,58 @@ mod channels {
     }
 
     impl GuildChannel {
+        pub fn name(&self) -> &str {
+            match self {
+                GuildChannel::Category(c) => &c.name,
+                GuildChannel::Text(c) => &c.name,
+                GuildChannel::Voice(c) => &c.name,
+                GuildChannel::News(c) => &c.name,
+                GuildChannel::Store(c) => &c.name,
+                GuildChannel::Stage(c) => &c.name,
+                GuildChannel::Thread(c) => &c.name,
+            }
+        }
+
+        /// Position of the channel among the channels of the
+        /// same kind in its category, `None` for threads.
+        pub fn position(&self) -> Option<i32> {
+            match self {
+                GuildChannel::Category(c) => Some(c.position),
+                GuildChannel::Text(c) => Some(c.position),
+                GuildChannel::Voice(c) => Some(c.position),
+                GuildChannel::News(c) => Some(c.position),
+                GuildChannel::Store(c) => Some(c.position),
+                GuildChannel::Stage(c) => Some(c.position),
+                GuildChannel::Thread(_) => None,
+            }
+        }
+
+        /// Category of the channel or parent channel of a thread.
+        pub fn parent_id(&self) -> Option<Snowflake> {
+            match self {
+                GuildChannel::Category(_) => None,
+                GuildChannel::Text(c) => c.parent_id,
+                GuildChannel::Voice(c) => c.parent_id,
+                GuildChannel::News(c) => c.parent_id,
+                GuildChannel::Store(c) => c.parent_id,
+                GuildChannel::Stage(c) => c.parent_id,
+                GuildChannel::Thread(c) => c.parent_id,
+            }
+        }
+
+        /// Permission overwrites of the channel, empty for threads.
+        pub fn permission_overwrites(&self) -> &[Overwrite] {
+            match self {
+                GuildChannel::Category(c) => &c.permission_overwrites,
+                GuildChannel::Text(c) => &c.permission_overwrites,
+                GuildChannel::Voice(c) => c.permission_overwrites.as_deref().unwrap_or(&[]),
+                GuildChannel::News(c) => &c.permission_overwrites,
+                GuildChannel::Store(c) => &c.permission_overwrites,
+                GuildChannel::Stage(c) => c.permission_overwrites.as_deref().unwrap_or(&[]),
+                GuildChannel::Thread(_) => &[],
+            }
+        }
+
         pub fn from_channel(channel: &Channel) -> Result<Self, Error> {
             match channel {
                 Channel::Category(c) => Ok(GuildChannel::Category(Clone::clone(c))),
@@ -320,6 +497,8 @@ mod channels {
                 Channel::Voice(c) => Ok(GuildChannel::Voice(Clone::clone(c))),
                 Channel::News(c) => Ok(GuildChannel::News(Clone::clone(c))),
                 Channel::Store(c) => Ok(GuildChannel::Store(Clone::clone(c))),
+                Channel::Stage(c) => Ok(GuildChannel::Stage(Clone::clone(c))),
+                Channel::Thread(c) => Ok(GuildChannel::Thread(Clone::clone(c))),
                 _ => Error::err("Can't convert private channel to guild channel")
             }
         }
@@ -338,10 +517,10 @@ mod channels {
 
 mod deserialize {
     use serde::{Deserialize, Deserializer};
-    use serde::__private::de::{ContentDeserializer, TaggedContentVisitor};
+    use serde::__private::de::{Content, ContentDeserializer, TaggedContentVisitor};
     use serde::de::{Visitor, Error, Unexpected};
     use std::fmt::{self, Formatter};
-    use super::{Category, TextChannel, VoiceChannel, NewsChannel, StoreChannel, StageChannel, DirectChannel, GroupChannel};
+    use super::{Category, TextChannel, VoiceChannel, NewsChannel, StoreChannel, StageChannel, ThreadChannel, DirectChannel, GroupChannel};
     use super::{Channel, GuildChannel, PrivateChannel};
 
     enum ChannelTag {
@@ -351,6 +530,7 @@ mod deserialize {
         News,
         Store,
         Stage,
+        Thread(u8),
         Direct,
         Group,
     }
@@ -373,12 +553,26 @@ mod deserialize {
                 4 => Ok(ChannelTag::Category),
                 5 => Ok(ChannelTag::News),
                 6 => Ok(ChannelTag::Store),
+                10..=12 => Ok(ChannelTag::Thread(value as u8)),
                 13 => Ok(ChannelTag::Stage),
-                _ => Err(Error::invalid_value(Unexpected::Unsigned(value), &"variant index 0 <= i < 7")),
+                _ => Err(Error::invalid_value(Unexpected::Unsigned(value), &"variant index 0 <= i < 7, 10 <= i < 14")),
             }
         }
     }
 
+    /// Threads keep their type since it tells whether the thread
+    /// is public or private, so the tag consumed by the tagged
+    /// content visitor is put back before deserializing.
+    fn thread_content(kind: u8, content: Content<'_>) -> Content<'_> {
+        match content {
+            Content::Map(mut entries) => {
+                entries.push((Content::Str("type"), Content::U8(kind)));
+                Content::Map(entries)
+            }
+            content => content
+        }
+    }
+
     impl<'de> Deserialize<'de> for ChannelTag {
         #[inline]
         fn deserialize<D>(d: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
@@ -418,6 +612,10 @@ mod deserialize {
                     StageChannel::deserialize(ContentDeserializer::<D::Error>::new(tagged.content)),
                     Channel::Stage,
                 ),
+                ChannelTag::Thread(kind) => Result::map(
+                    ThreadChannel::deserialize(ContentDeserializer::<D::Error>::new(thread_content(kind, tagged.content))),
+                    Channel::Thread,
+                ),
                 ChannelTag::Direct => Result::map(
                     DirectChannel::deserialize(ContentDeserializer::<D::Error>::new(tagged.content)),
                     Channel::Direct,
@@ -462,6 +660,10 @@ mod deserialize {
                     StageChannel::deserialize(ContentDeserializer::<D::Error>::new(tagged.content)),
                     GuildChannel::Stage,
                 ),
+                ChannelTag::Thread(kind) => Result::map(
+                    ThreadChannel::deserialize(ContentDeserializer::<D::Error>::new(thread_content(kind, tagged.content))),
+                    GuildChannel::Thread,
+                ),
                 _ => Err(Error::custom("disallowed type for guild channel"))
             }
         }
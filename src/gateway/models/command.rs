@@ -0,0 +1,84 @@
+use crate::Snowflake;
+use std::collections::HashMap;
+
+/// A command registered by the application which
+/// users can invoke from their client, either as a
+/// slash command or from the context menu of a user
+/// or a message.
+///
+/// `name_localizations` and `description_localizations`
+/// associate a Discord locale (such as `fr` or `en-US`)
+/// to the translated name or description which is shown
+/// to users with the corresponding client language.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/interactions/application-commands#application-command-object)
+#[object(server)]
+pub struct ApplicationCommand {
+    pub id: Snowflake,
+    #[serde(rename = "type")]
+    pub _type: Option<ApplicationCommandType>,
+    pub application_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub name: String,
+    #[nullable]
+    pub name_localizations: Option<HashMap<String, String>>,
+    pub description: String,
+    #[nullable]
+    pub description_localizations: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub options: Vec<ApplicationCommandOption>,
+    pub default_permission: Option<bool>,
+    pub version: Snowflake,
+}
+
+/// Where the command is invoked from, commands
+/// without a type are slash commands.
+#[convert(u8)]
+pub enum ApplicationCommandType {
+    /// A slash command typed in the message box.
+    ChatInput = 1,
+    /// A command in the context menu of a user,
+    /// the target is in [InteractionData::target_user](automate::gateway::InteractionData::target_user).
+    User = 2,
+    /// A command in the context menu of a message,
+    /// the target is in [InteractionData::target_message](automate::gateway::InteractionData::target_message).
+    Message = 3,
+}
+
+/// A parameter of an [ApplicationCommand](automate::gateway::ApplicationCommand)
+/// or a subcommand.
+#[object(both)]
+pub struct ApplicationCommandOption {
+    #[serde(rename = "type")]
+    pub _type: ApplicationCommandOptionType,
+    pub name: String,
+    pub name_localizations: Option<HashMap<String, String>>,
+    pub description: String,
+    pub description_localizations: Option<HashMap<String, String>>,
+    pub required: Option<bool>,
+    pub choices: Option<Vec<ApplicationCommandOptionChoice>>,
+    pub options: Option<Vec<ApplicationCommandOption>>,
+}
+
+#[convert(u8)]
+pub enum ApplicationCommandOptionType {
+    SubCommand = 1,
+    SubCommandGroup = 2,
+    String = 3,
+    Integer = 4,
+    Boolean = 5,
+    User = 6,
+    Channel = 7,
+    Role = 8,
+    Mentionable = 9,
+    Number = 10,
+}
+
+/// A predefined value the user can pick
+/// for an option.
+#[object(both)]
+pub struct ApplicationCommandOptionChoice {
+    pub name: String,
+    pub name_localizations: Option<HashMap<String, String>>,
+    pub value: serde_json::Value,
+}
@@ -1,6 +1,12 @@
-use crate::gateway::{User, PartialUser, PartialVoiceState, GuildChannel};
-use crate::{Snowflake, Identifiable};
+use crate::gateway::{User, PartialUser, PartialVoiceState, GuildChannel, ThreadChannel, GuildScheduledEvent, OverwriteType};
+use crate::{Snowflake, Identifiable, Error, cdn};
+use crate::mention::Mention;
+use crate::cdn::ImageFormat;
 use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
 use chrono::NaiveDateTime;
 
 #[object(server)]
@@ -49,6 +55,13 @@ pub struct Guild {
     #[serde(deserialize_with = "automate::encode::json::as_hashmap")]
     #[serde(default)]
     pub channels: HashMap<Snowflake, GuildChannel>,
+    /// Active threads the bot has access to.
+    #[serde(deserialize_with = "automate::encode::json::as_hashmap")]
+    #[serde(default)]
+    pub threads: HashMap<Snowflake, ThreadChannel>,
+    #[serde(deserialize_with = "automate::encode::json::as_hashmap")]
+    #[serde(default)]
+    pub guild_scheduled_events: HashMap<Snowflake, GuildScheduledEvent>,
     pub presences: Option<Vec<PartialPresenceUpdate>>,
     #[option_nullable]
     pub max_presences: Option<Option<i32>>,
@@ -117,6 +130,146 @@ pub enum Permission {
     ManageEmojis = 1 << 30,
 }
 
+impl Permission {
+    /// Every permission.
+    pub fn all() -> u32 {
+        0b0111_1111_1111_0111_1111_1111_1111_1111
+    }
+
+    /// The permissions contained in the bitset.
+    pub fn from_bits(bits: u32) -> Vec<Permission> {
+        (0..32)
+            .map(|bit| 1 << bit)
+            .filter(|permission| bits & permission != 0)
+            .filter_map(|permission: u32| {
+                Permission::deserialize(IntoDeserializer::<serde::de::value::Error>::into_deserializer(permission)).ok()
+            })
+            .collect()
+    }
+}
+
+impl Guild {
+    /// Guild-wide permissions of the member, computed from
+    /// the `@everyone` role and the roles of the member.
+    /// Channel overwrites are not taken into account.
+    pub fn base_permissions(&self, member: &GuildMember) -> u32 {
+        if member.user.id == self.owner_id {
+            return Permission::all();
+        }
+
+        let mut permissions = self.roles.get(&self.id).map_or(0, |r| r.permissions);
+        for role in &member.roles {
+            if let Some(role) = self.roles.get(role) {
+                permissions |= role.permissions;
+            }
+        }
+
+        if permissions & Permission::Administrator as u32 != 0 {
+            Permission::all()
+        } else {
+            permissions
+        }
+    }
+
+    /// Permissions of the member in a channel or thread of
+    /// the guild, computed from the guild-wide permissions
+    /// and the overwrites of the channel. Threads use the
+    /// overwrites of their parent channel.
+    ///
+    /// Returns `None` if the channel is not in the guild.
+    pub fn channel_permissions(&self, member: &GuildMember, channel: Snowflake) -> Option<u32> {
+        let channel = match self.channels.get(&channel) {
+            Some(channel) => channel,
+            None => self.threads.get(&channel)?.parent_id.and_then(|parent| self.channels.get(&parent))?,
+        };
+
+        let base = self.base_permissions(member);
+        if base & Permission::Administrator as u32 != 0 {
+            return Some(base);
+        }
+
+        let overwrites = channel.permission_overwrites();
+        let mut permissions = base;
+
+        if let Some(everyone) = overwrites.iter().find(|o| o.id == self.id) {
+            permissions = (permissions & !everyone.deny) | everyone.allow;
+        }
+
+        let (allow, deny) = overwrites.iter()
+            .filter(|o| matches!(o._type, OverwriteType::Role) && o.id != self.id && member.roles.contains(&o.id))
+            .fold((0, 0), |(allow, deny), o| (allow | o.allow, deny | o.deny));
+
+        permissions = (permissions & !deny) | allow;
+
+        if let Some(own) = overwrites.iter().find(|o| matches!(o._type, OverwriteType::Member) && o.id == member.user.id) {
+            permissions = (permissions & !own.deny) | own.allow;
+        }
+
+        //members that can not see a channel have no permission in it
+        if permissions & Permission::ViewChannel as u32 == 0 {
+            Some(0)
+        } else {
+            Some(permissions)
+        }
+    }
+
+    /// Position of the highest role of the member,
+    /// 0 for members without any role.
+    pub fn highest_role_position(&self, member: &GuildMember) -> i32 {
+        member.roles.iter()
+            .filter_map(|role| self.roles.get(role))
+            .map(|role| role.position)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether the role hierarchy allows the moderator
+    /// to kick, ban or change the nickname of the target.
+    /// Permissions are not taken into account.
+    pub fn outranks(&self, moderator: &GuildMember, target: &GuildMember) -> bool {
+        if target.user.id == self.owner_id {
+            false
+        } else if moderator.user.id == self.owner_id {
+            true
+        } else {
+            self.highest_role_position(moderator) > self.highest_role_position(target)
+        }
+    }
+
+    /// The amount of boosts the guild received.
+    pub fn boosts(&self) -> i32 {
+        self.premium_subscription_count.unwrap_or(0)
+    }
+
+    /// The amount of boosts missing to reach the next
+    /// tier, `None` if the guild is at the highest tier.
+    pub fn boosts_to_next_tier(&self) -> Option<i32> {
+        self.premium_tier.next().map(|tier| (tier.required_boosts() - self.boosts()).max(0))
+    }
+
+    pub fn icon_url(&self, format: ImageFormat, size: u16) -> Option<String> {
+        self.icon.as_ref().map(|hash| cdn::guild_icon(self.id, hash, format, size))
+    }
+
+    pub fn splash_url(&self, format: ImageFormat, size: u16) -> Option<String> {
+        self.splash.as_ref().map(|hash| cdn::guild_splash(self.id, hash, format, size))
+    }
+
+    pub fn discovery_splash_url(&self, format: ImageFormat, size: u16) -> Option<String> {
+        self.discovery_splash.as_ref().map(|hash| cdn::guild_discovery_splash(self.id, hash, format, size))
+    }
+
+    pub fn banner_url(&self, format: ImageFormat, size: u16) -> Option<String> {
+        self.banner.as_ref().map(|hash| cdn::guild_banner(self.id, hash, format, size))
+    }
+}
+
+impl PartialGuild {
+    pub fn icon_url(&self, format: ImageFormat, size: u16) -> Option<String> {
+        self.icon.as_ref().map(|hash| cdn::guild_icon(self.id, hash, format, size))
+    }
+}
+
 #[convert(u8)]
 pub enum VerificationLevel {
     None = 0,
@@ -158,6 +311,41 @@ pub enum GuildFeature {
     WelcomeScreenEnabled,
     PreviewEnabled,
     MemberVerificationGateEnabled,
+    AnimatedBanner,
+    AutoModeration,
+    DeveloperSupportServer,
+    InvitesDisabled,
+    MonetizationEnabled,
+    MoreStickers,
+    PrivateThreads,
+    RoleIcons,
+    TicketedEventsEnabled,
+    /// Features added by Discord after this
+    /// version of the library.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Public information about a guild, which can be
+/// retrieved for lurkable guilds the bot is not in.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/resources/guild#guild-preview-object)
+#[object(server)]
+pub struct GuildPreview {
+    pub id: Snowflake,
+    pub name: String,
+    #[nullable]
+    pub icon: Option<String>,
+    #[nullable]
+    pub splash: Option<String>,
+    #[nullable]
+    pub discovery_splash: Option<String>,
+    pub emojis: Vec<GuildEmoji>,
+    pub features: Vec<GuildFeature>,
+    pub approximate_member_count: i32,
+    pub approximate_presence_count: i32,
+    #[nullable]
+    pub description: Option<String>,
 }
 
 #[convert(u8)]
@@ -174,6 +362,68 @@ pub enum PremiumTier {
     Tier3 = 3,
 }
 
+impl PremiumTier {
+    /// The tier unlocked by the given amount of boosts.
+    pub fn from_boosts(boosts: i32) -> PremiumTier {
+        match boosts {
+            b if b >= 14 => PremiumTier::Tier3,
+            b if b >= 7 => PremiumTier::Tier2,
+            b if b >= 2 => PremiumTier::Tier1,
+            _ => PremiumTier::None,
+        }
+    }
+
+    /// The amount of boosts needed to unlock the tier.
+    pub fn required_boosts(self) -> i32 {
+        match self {
+            PremiumTier::None => 0,
+            PremiumTier::Tier1 => 2,
+            PremiumTier::Tier2 => 7,
+            PremiumTier::Tier3 => 14,
+        }
+    }
+
+    /// The tier following this one, `None` for
+    /// the highest tier.
+    pub fn next(self) -> Option<PremiumTier> {
+        match self {
+            PremiumTier::None => Some(PremiumTier::Tier1),
+            PremiumTier::Tier1 => Some(PremiumTier::Tier2),
+            PremiumTier::Tier2 => Some(PremiumTier::Tier3),
+            PremiumTier::Tier3 => None,
+        }
+    }
+
+    /// Maximum amount of custom emojis in the guild.
+    pub fn emoji_limit(self) -> u32 {
+        match self {
+            PremiumTier::None => 50,
+            PremiumTier::Tier1 => 100,
+            PremiumTier::Tier2 => 150,
+            PremiumTier::Tier3 => 250,
+        }
+    }
+
+    /// Maximum bitrate of the voice channels in bits per second.
+    pub fn bitrate_limit(self) -> u32 {
+        match self {
+            PremiumTier::None => 96_000,
+            PremiumTier::Tier1 => 128_000,
+            PremiumTier::Tier2 => 256_000,
+            PremiumTier::Tier3 => 384_000,
+        }
+    }
+
+    /// Maximum size of the uploaded files in bytes.
+    pub fn upload_limit(self) -> u64 {
+        match self {
+            PremiumTier::None | PremiumTier::Tier1 => 8 * 1024 * 1024,
+            PremiumTier::Tier2 => 50 * 1024 * 1024,
+            PremiumTier::Tier3 => 100 * 1024 * 1024,
+        }
+    }
+}
+
 #[object(both)]
 pub struct Role {
     pub id: Snowflake,
@@ -184,6 +434,9 @@ pub struct Role {
     pub permissions: u32,
     pub managed: bool,
     pub mentionable: bool,
+    pub icon: Option<String>,
+    pub unicode_emoji: Option<String>,
+    pub tags: Option<RoleTags>,
 }
 
 impl Identifiable for Role {
@@ -192,6 +445,42 @@ impl Identifiable for Role {
     }
 }
 
+impl Role {
+    /// The custom icon of the role, `None` if the role
+    /// has no icon or uses a unicode emoji instead.
+    pub fn icon_url(&self, format: ImageFormat, size: u16) -> Option<String> {
+        self.icon.as_ref().map(|hash| cdn::role_icon(self.id, hash, format, size))
+    }
+
+    /// Whether the role is managed by a bot, the
+    /// server boosts or another integration.
+    pub fn is_integration(&self) -> bool {
+        self.tags.as_ref().map_or(false, |tags| {
+            tags.bot_id.is_some() || tags.integration_id.is_some() || tags.is_premium_subscriber()
+        })
+    }
+}
+
+/// Describes what manages a role.
+#[object(both)]
+pub struct RoleTags {
+    /// The bot this role belongs to.
+    pub bot_id: Option<Snowflake>,
+    /// The integration this role belongs to.
+    pub integration_id: Option<Snowflake>,
+    /// Present as `null` on the booster role of the guild,
+    /// use [RoleTags::is_premium_subscriber](automate::gateway::RoleTags::is_premium_subscriber).
+    #[option_nullable]
+    pub premium_subscriber: Option<Option<()>>,
+}
+
+impl RoleTags {
+    /// Whether the role is given to the members boosting the guild.
+    pub fn is_premium_subscriber(&self) -> bool {
+        self.premium_subscriber.is_some()
+    }
+}
+
 #[object(server)]
 pub struct PartialRole {
     pub id: Snowflake,
@@ -211,6 +500,13 @@ pub struct Emoji {
     pub available: Option<bool>,
 }
 
+impl Emoji {
+    /// The image of the emoji, `None` for unicode emojis.
+    pub fn url(&self, format: ImageFormat) -> Option<String> {
+        self.id.map(|id| cdn::emoji(id, self.animated.unwrap_or(false), format))
+    }
+}
+
 #[object(both)]
 pub struct GuildEmoji {
     pub id: Snowflake,
@@ -241,6 +537,127 @@ pub struct PartialEmoji {
     pub animated: Option<bool>,
 }
 
+/// An emoji used to react to a message.
+///
+/// Can be given to the reaction endpoints of the
+/// [HttpAPI](automate::HttpAPI) and parsed from
+/// a unicode emoji, a custom emoji as written in
+/// a message (`<:name:id>`) or `name:id`.
+///
+/// ```
+/// use automate::Snowflake;
+/// use automate::gateway::ReactionEmoji;
+///
+/// let emoji: ReactionEmoji = "<:rust:123>".parse().unwrap();
+/// assert_eq!(emoji, ReactionEmoji::Custom { name: String::from("rust"), id: Snowflake(123), animated: false });
+/// assert_eq!("👍".parse::<ReactionEmoji>().unwrap(), ReactionEmoji::Unicode(String::from("👍")));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReactionEmoji {
+    Unicode(String),
+    Custom {
+        name: String,
+        id: Snowflake,
+        animated: bool,
+    },
+}
+
+impl ReactionEmoji {
+    /// The custom emojis used in the given content
+    /// of a message in the order they appear.
+    pub fn custom_in(content: &str) -> Vec<ReactionEmoji> {
+        Mention::parse_all(content)
+            .into_iter()
+            .filter_map(|mention| match mention {
+                Mention::Emoji { animated, name, id } => Some(ReactionEmoji::Custom { name, id, animated }),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Whether the emoji of a reaction
+    /// is this emoji.
+    pub fn matches(&self, emoji: &PartialEmoji) -> bool {
+        match self {
+            ReactionEmoji::Unicode(name) => emoji.id.is_none() && emoji.name == *name,
+            ReactionEmoji::Custom { id, .. } => emoji.id == Some(*id),
+        }
+    }
+}
+
+impl FromStr for ReactionEmoji {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ReactionEmoji, Error> {
+        if s.is_empty() {
+            return Error::err("An emoji can not be empty");
+        }
+
+        if let Ok(Mention::Emoji { animated, name, id }) = s.parse::<Mention>() {
+            return Ok(ReactionEmoji::Custom { name, id, animated });
+        }
+
+        let mut parts = s.rsplitn(2, ':');
+        let id = parts.next().and_then(|id| id.parse::<u64>().ok());
+
+        match (id, parts.next()) {
+            (Some(id), Some(name)) if !name.is_empty() => Ok(ReactionEmoji::Custom {
+                name: name.to_owned(),
+                id: Snowflake(id),
+                animated: false,
+            }),
+            _ => Ok(ReactionEmoji::Unicode(s.to_owned()))
+        }
+    }
+}
+
+impl Display for ReactionEmoji {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReactionEmoji::Unicode(name) => f.write_str(name),
+            ReactionEmoji::Custom { name, id, animated: true } => write!(f, "<a:{}:{}>", name, id),
+            ReactionEmoji::Custom { name, id, animated: false } => write!(f, "<:{}:{}>", name, id),
+        }
+    }
+}
+
+impl From<&PartialEmoji> for ReactionEmoji {
+    fn from(emoji: &PartialEmoji) -> Self {
+        match emoji.id {
+            Some(id) => ReactionEmoji::Custom {
+                name: emoji.name.clone(),
+                id,
+                animated: emoji.animated.unwrap_or(false),
+            },
+            None => ReactionEmoji::Unicode(emoji.name.clone()),
+        }
+    }
+}
+
+impl From<PartialEmoji> for ReactionEmoji {
+    fn from(emoji: PartialEmoji) -> Self {
+        ReactionEmoji::from(&emoji)
+    }
+}
+
+impl From<&GuildEmoji> for ReactionEmoji {
+    fn from(emoji: &GuildEmoji) -> Self {
+        ReactionEmoji::Custom {
+            name: emoji.name.clone(),
+            id: emoji.id,
+            animated: emoji.animated,
+        }
+    }
+}
+
+impl PartialEmoji {
+    /// The emoji in a form that can be given
+    /// to the reaction endpoints.
+    pub fn to_reaction(&self) -> ReactionEmoji {
+        ReactionEmoji::from(self)
+    }
+}
+
 #[object(server)]
 pub struct GuildMember {
     pub user: User,
@@ -260,6 +677,18 @@ impl Identifiable for GuildMember {
     }
 }
 
+impl GuildMember {
+    /// Whether the member is currently boosting the guild.
+    pub fn is_boosting(&self) -> bool {
+        self.boosting_since().is_some()
+    }
+
+    /// When the member started boosting the guild.
+    pub fn boosting_since(&self) -> Option<NaiveDateTime> {
+        self.premium_since.flatten()
+    }
+}
+
 #[object(server)]
 pub struct PartialGuildMember {
     pub user: Option<User>,
@@ -330,6 +759,44 @@ pub struct PartialPresenceUpdate {
     pub premium_since: Option<Option<String>>,
 }
 
+impl PresenceUpdate {
+    /// Completes a presence received in a guild create event,
+    /// returns `None` if the user or the status is missing.
+    /// The premium since date is not kept.
+    pub fn from_partial(guild: Snowflake, presence: &PartialPresenceUpdate) -> Option<PresenceUpdate> {
+        Some(PresenceUpdate {
+            user: presence.user.clone()?,
+            nick: presence.nick.clone(),
+            roles: presence.roles.clone().unwrap_or_default(),
+            game: presence.game.clone(),
+            guild_id: guild,
+            status: presence.status.clone()?,
+            activities: presence.activities.clone().unwrap_or_default(),
+            client_status: presence.client_status.clone().unwrap_or(ClientStatus {
+                desktop: None,
+                mobile: None,
+                web: None,
+            }),
+            premium_since: None,
+        })
+    }
+
+    /// Whether both presences have the same status and
+    /// activities, ignoring the timestamps and assets
+    /// of the activities.
+    pub fn same_activity(&self, other: &PresenceUpdate) -> bool {
+        let same = |a: &Activity, b: &Activity| a.name == b.name
+            && a._type as u8 == b._type as u8
+            && a.url == b.url
+            && a.details == b.details
+            && a.state == b.state;
+
+        self.status == other.status
+            && self.activities.len() == other.activities.len()
+            && self.activities.iter().zip(&other.activities).all(|(a, b)| same(a, b))
+    }
+}
+
 /// Active sessions are indicated with an "online",
 /// "idle", or "dnd" string per platform. If a user
 /// is offline or invisible, the corresponding
@@ -387,6 +854,7 @@ pub enum ActivityType {
     Listening = 2,
     Watching = 3,
     Custom = 4,
+    Competing = 5,
 }
 
 #[convert(u32)]
@@ -438,4 +906,132 @@ pub struct Ban {
     pub user: User,
     #[nullable]
     pub reason: Option<String>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::{Channel, ChannelType};
+    use crate::encode::WriteUrl;
+    use crate::testing::{fake_guild, fake_member, fake_presence, fake_role, channel_json};
+
+    #[test]
+    fn compares_presences() {
+        let presence = |status: &str, activity: &str, created_at: u64| -> PresenceUpdate {
+            let mut presence = fake_presence(2, 1, status);
+            presence.activities = serde_json::from_value(serde_json::json!([
+                {"name": activity, "type": 0, "created_at": created_at},
+            ])).unwrap();
+
+            presence
+        };
+
+        assert!(presence("online", "Chess", 1).same_activity(&presence("online", "Chess", 2)));
+        assert!(!presence("online", "Chess", 1).same_activity(&presence("idle", "Chess", 1)));
+        assert!(!presence("online", "Chess", 1).same_activity(&presence("online", "Go", 1)));
+    }
+
+    #[test]
+    fn role_tags() {
+        let role = |tags: serde_json::Value| -> Role {
+            let mut role = fake_role(1, 1, 0);
+            role.managed = true;
+            role.icon = Some(String::from("hash"));
+            role.tags = serde_json::from_value(tags).unwrap();
+
+            role
+        };
+
+        let booster = role(serde_json::json!({"premium_subscriber": null}));
+        assert!(booster.tags.as_ref().unwrap().is_premium_subscriber());
+        assert!(booster.is_integration());
+        assert!(booster.unicode_emoji.is_none());
+
+        let bot = role(serde_json::json!({"bot_id": "2"}));
+        assert!(!bot.tags.as_ref().unwrap().is_premium_subscriber());
+        assert!(bot.is_integration());
+        assert!(!role(serde_json::json!({})).is_integration());
+
+        assert_eq!(
+            bot.icon_url(ImageFormat::Png, 64).unwrap(),
+            "https://cdn.discordapp.com/role-icons/1/hash.png?size=64"
+        );
+    }
+
+    #[test]
+    fn channel_permissions() {
+        let role = |id: u64, position: i32, permissions: Permission| -> Role {
+            fake_role(id, position, permissions as u32 | Permission::ViewChannel as u32)
+        };
+
+        let mut guild = fake_guild(1, 2, "guild");
+        guild.roles.insert(Snowflake(1), role(1, 0, Permission::SendMessages));
+        guild.roles.insert(Snowflake(10), role(10, 1, Permission::ManageMessages));
+        guild.roles.insert(Snowflake(11), role(11, 2, Permission::BanMembers));
+
+        let mut channel = channel_json(20, 1, ChannelType::GuildText, None, 0);
+        channel["permission_overwrites"] = serde_json::json!([
+            {"id": "1", "type": "role", "allow": 0, "deny": Permission::SendMessages as u32},
+            {"id": "10", "type": "role", "allow": Permission::SendMessages as u32, "deny": 0},
+            {"id": "3", "type": "member", "allow": 0, "deny": Permission::ViewChannel as u32},
+        ]);
+
+        let channel: Channel = serde_json::from_value(channel).unwrap();
+        guild.channels.insert(Snowflake(20), GuildChannel::from_channel(&channel).unwrap());
+
+        let moderator = fake_member(3, vec![10]);
+        let banner = fake_member(4, vec![11]);
+        let everyone = fake_member(5, vec![]);
+
+        let send = Permission::SendMessages as u32;
+        assert_eq!(guild.channel_permissions(&everyone, Snowflake(20)).unwrap() & send, 0);
+        assert_eq!(guild.channel_permissions(&banner, Snowflake(20)).unwrap() & send, 0);
+        assert_eq!(guild.channel_permissions(&moderator, Snowflake(20)), Some(0));
+        assert_ne!(guild.channel_permissions(&fake_member(6, vec![10]), Snowflake(20)).unwrap() & send, 0);
+        assert_eq!(guild.channel_permissions(&everyone, Snowflake(21)), None);
+        assert_eq!(guild.channel_permissions(&fake_member(2, vec![]), Snowflake(20)), Some(Permission::all()));
+
+        assert!(guild.outranks(&banner, &moderator));
+        assert!(!guild.outranks(&moderator, &banner));
+        assert!(!guild.outranks(&banner, &fake_member(2, vec![])));
+
+        let missing = Permission::from_bits(Permission::BanMembers as u32 | Permission::ManageRoles as u32);
+        assert!(matches!(missing[..], [Permission::BanMembers, Permission::ManageRoles]));
+    }
+
+    #[test]
+    fn unknown_guild_features() {
+        let features: Vec<GuildFeature> = serde_json::from_str(r#"["COMMUNITY","ROLE_ICONS","SOME_NEW_FEATURE"]"#).unwrap();
+
+        assert!(matches!(features[0], GuildFeature::Community));
+        assert!(matches!(features[1], GuildFeature::RoleIcons));
+        assert!(matches!(features[2], GuildFeature::Unknown));
+    }
+
+    #[test]
+    fn premium_tiers() {
+        assert!(matches!(PremiumTier::from_boosts(1), PremiumTier::None));
+        assert!(matches!(PremiumTier::from_boosts(7), PremiumTier::Tier2));
+        assert!(matches!(PremiumTier::from_boosts(30), PremiumTier::Tier3));
+        assert_eq!(PremiumTier::Tier1.next().map(PremiumTier::required_boosts), Some(7));
+        assert!(PremiumTier::Tier3.next().is_none());
+    }
+
+    #[test]
+    fn reaction_emojis() {
+        let custom = ReactionEmoji::Custom { name: String::from("rust"), id: Snowflake(1), animated: true };
+
+        assert_eq!("<a:rust:1>".parse::<ReactionEmoji>().unwrap(), custom);
+        assert_eq!(custom.to_string(), "<a:rust:1>");
+        assert!(matches!("rust:1".parse::<ReactionEmoji>().unwrap(), ReactionEmoji::Custom { animated: false, .. }));
+        assert_eq!(":1".parse::<ReactionEmoji>().unwrap(), ReactionEmoji::Unicode(String::from(":1")));
+        assert!("".parse::<ReactionEmoji>().is_err());
+
+        assert_eq!(ReactionEmoji::custom_in("hello <:a:1> <@2> <a:b:3>").len(), 2);
+
+        let mut url = String::new();
+        custom.write_url(&mut url).unwrap();
+        ReactionEmoji::Unicode(String::from("👍")).write_url(&mut url).unwrap();
+        assert_eq!(url, "rust:1%F0%9F%91%8D");
+    }
+}
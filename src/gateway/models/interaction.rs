@@ -0,0 +1,551 @@
+use crate::gateway::{User, GuildMember, Message, Role, ChannelType, ApplicationCommandType, ApplicationCommandOptionType, PartialEmoji};
+use crate::{Snowflake, Error};
+use std::collections::HashMap;
+
+/// Sent when a user uses an application command, clicks
+/// on a message component or submits a modal.
+///
+/// An interaction must be responded to within 3 seconds
+/// using [HttpAPI::create_interaction_response](automate::HttpAPI::create_interaction_response)
+/// or [Context::defer](automate::Context::defer).
+/// The `token` then stays valid for 15 minutes to send
+/// followup messages.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-object)
+#[object(server)]
+pub struct Interaction {
+    pub id: Snowflake,
+    pub application_id: Snowflake,
+    #[serde(rename = "type")]
+    pub _type: InteractionType,
+    pub data: Option<InteractionData>,
+    pub guild_id: Option<Snowflake>,
+    pub channel_id: Option<Snowflake>,
+    /// The member who triggered the interaction
+    /// when it was triggered in a guild.
+    pub member: Option<GuildMember>,
+    /// The user who triggered the interaction
+    /// when it was triggered in a direct message.
+    pub user: Option<User>,
+    pub token: String,
+    pub version: u8,
+    /// The message the component was attached to.
+    pub message: Option<Message>,
+    pub locale: Option<String>,
+    pub guild_locale: Option<String>,
+}
+
+impl Interaction {
+    /// The user who triggered the interaction,
+    /// either in a guild or in a direct message.
+    pub fn author(&self) -> Option<&User> {
+        match &self.member {
+            Some(member) => Some(&member.user),
+            None => self.user.as_ref(),
+        }
+    }
+}
+
+#[convert(u8)]
+pub enum InteractionType {
+    Ping = 1,
+    ApplicationCommand = 2,
+    MessageComponent = 3,
+    ApplicationCommandAutocomplete = 4,
+    ModalSubmit = 5,
+}
+
+/// The data of an interaction, which fields are
+/// present depends on the type of the interaction.
+#[object(server)]
+pub struct InteractionData {
+    /// The id of the invoked command.
+    pub id: Option<Snowflake>,
+    /// The name of the invoked command.
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub _type: Option<ApplicationCommandType>,
+    /// The users, members and messages referenced
+    /// by the options or targeted by the command.
+    pub resolved: Option<ResolvedData>,
+    pub options: Option<Vec<InteractionDataOption>>,
+    pub guild_id: Option<Snowflake>,
+    /// The id of the user or the message the context
+    /// menu command was invoked on.
+    pub target_id: Option<Snowflake>,
+    /// The custom id of the component or the modal.
+    pub custom_id: Option<String>,
+    pub component_type: Option<ComponentType>,
+    /// The values selected in a select menu.
+    pub values: Option<Vec<String>>,
+    /// The components of the submitted modal.
+    pub components: Option<Vec<Component>>,
+}
+
+impl InteractionData {
+    /// The user a [user command](automate::gateway::ApplicationCommandType::User)
+    /// was invoked on.
+    pub fn target_user(&self) -> Option<&User> {
+        match self._type {
+            Some(ApplicationCommandType::User) => self.resolved.as_ref()?.users.get(&self.target_id?),
+            _ => None,
+        }
+    }
+
+    /// The guild member a [user command](automate::gateway::ApplicationCommandType::User)
+    /// was invoked on, when invoked in a guild.
+    pub fn target_member(&self) -> Option<&ResolvedMember> {
+        match self._type {
+            Some(ApplicationCommandType::User) => self.resolved.as_ref()?.members.get(&self.target_id?),
+            _ => None,
+        }
+    }
+
+    /// The message a [message command](automate::gateway::ApplicationCommandType::Message)
+    /// was invoked on.
+    pub fn target_message(&self) -> Option<&Message> {
+        match self._type {
+            Some(ApplicationCommandType::Message) => self.resolved.as_ref()?.messages.get(&self.target_id?),
+            _ => None,
+        }
+    }
+
+    /// The ids selected in a user, role,
+    /// mentionable or channel select menu.
+    pub fn selected_ids(&self) -> Vec<Snowflake> {
+        self.values.iter()
+            .flatten()
+            .filter_map(|value| value.parse().ok().map(Snowflake))
+            .collect()
+    }
+
+    /// The users selected in a user or
+    /// mentionable select menu.
+    pub fn selected_users(&self) -> Vec<&User> {
+        self.selected(|resolved| &resolved.users)
+    }
+
+    /// The roles selected in a role or
+    /// mentionable select menu.
+    pub fn selected_roles(&self) -> Vec<&Role> {
+        self.selected(|resolved| &resolved.roles)
+    }
+
+    /// The channels selected in a channel select menu.
+    pub fn selected_channels(&self) -> Vec<&ResolvedChannel> {
+        self.selected(|resolved| &resolved.channels)
+    }
+
+    fn selected<T, F>(&self, entities: F) -> Vec<&T> where F: Fn(&ResolvedData) -> &HashMap<Snowflake, T> {
+        match &self.resolved {
+            Some(resolved) => {
+                let entities = entities(resolved);
+                self.selected_ids().iter().filter_map(|id| entities.get(id)).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The entities referenced by an interaction
+/// mapped by their id.
+#[object(server)]
+pub struct ResolvedData {
+    #[serde(default)]
+    pub users: HashMap<Snowflake, User>,
+    #[serde(default)]
+    pub members: HashMap<Snowflake, ResolvedMember>,
+    #[serde(default)]
+    pub roles: HashMap<Snowflake, Role>,
+    #[serde(default)]
+    pub channels: HashMap<Snowflake, ResolvedChannel>,
+    #[serde(default)]
+    pub messages: HashMap<Snowflake, Message>,
+}
+
+/// A guild member referenced by an interaction,
+/// the user is in [ResolvedData::users](automate::gateway::ResolvedData::users).
+#[object(server)]
+pub struct ResolvedMember {
+    pub nick: Option<String>,
+    pub roles: Vec<Snowflake>,
+    pub joined_at: String,
+    pub premium_since: Option<String>,
+    /// The permissions of the member in the
+    /// channel of the interaction.
+    pub permissions: Option<String>,
+}
+
+/// A channel referenced by an interaction.
+#[object(server)]
+pub struct ResolvedChannel {
+    pub id: Snowflake,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub _type: ChannelType,
+    /// The permissions of the bot in the channel.
+    pub permissions: Option<String>,
+    pub parent_id: Option<Snowflake>,
+}
+
+/// The value given by the user for an option of
+/// the command or the subcommand and its options.
+#[object(server)]
+pub struct InteractionDataOption {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub _type: ApplicationCommandOptionType,
+    pub value: Option<serde_json::Value>,
+    pub options: Option<Vec<InteractionDataOption>>,
+    /// Whether the user is typing this option
+    /// in an autocomplete interaction.
+    pub focused: Option<bool>,
+}
+
+/// An interactive element of a message or a modal.
+///
+/// Which fields are used depends on the type of the
+/// component, more information on [Discord's documentation](https://discord.com/developers/docs/interactions/message-components#component-object)
+#[object(both, default)]
+pub struct Component {
+    #[serde(rename = "type")]
+    pub _type: ComponentType,
+    pub custom_id: Option<String>,
+    pub disabled: Option<bool>,
+    pub style: Option<u8>,
+    pub label: Option<String>,
+    pub emoji: Option<PartialEmoji>,
+    pub url: Option<String>,
+    pub placeholder: Option<String>,
+    pub min_length: Option<u16>,
+    pub max_length: Option<u16>,
+    pub required: Option<bool>,
+    pub value: Option<String>,
+    pub components: Option<Vec<Component>>,
+    /// The choices of a string select menu.
+    pub options: Option<Vec<SelectOption>>,
+    /// The types of channels shown in a channel select menu.
+    pub channel_types: Option<Vec<ChannelType>>,
+    /// The entities selected by default in a user, role,
+    /// mentionable or channel select menu.
+    pub default_values: Option<Vec<SelectDefaultValue>>,
+    pub min_values: Option<u8>,
+    pub max_values: Option<u8>,
+}
+
+impl Component {
+    /// A row containing other components.
+    pub fn action_row(components: Vec<Component>) -> Component {
+        Component {
+            _type: ComponentType::ActionRow,
+            components: Some(components),
+            ..Default::default()
+        }
+    }
+
+    /// A blurple button sending an interaction
+    /// with the given custom id when clicked.
+    pub fn button<S: Into<String>, L: Into<String>>(custom_id: S, label: L) -> Component {
+        Component {
+            _type: ComponentType::Button,
+            custom_id: Some(custom_id.into()),
+            label: Some(label.into()),
+            style: Some(1),
+            ..Default::default()
+        }
+    }
+
+    /// A grey button opening the given url
+    /// when clicked.
+    pub fn link_button<L: Into<String>, U: Into<String>>(label: L, url: U) -> Component {
+        Component {
+            _type: ComponentType::Button,
+            label: Some(label.into()),
+            url: Some(url.into()),
+            style: Some(5),
+            ..Default::default()
+        }
+    }
+
+    /// A single line text input of a modal.
+    pub fn short_input<S: Into<String>, L: Into<String>>(custom_id: S, label: L) -> Component {
+        Component {
+            _type: ComponentType::TextInput,
+            custom_id: Some(custom_id.into()),
+            label: Some(label.into()),
+            style: Some(1),
+            ..Default::default()
+        }
+    }
+
+    /// A multi line text input of a modal.
+    pub fn paragraph_input<S: Into<String>, L: Into<String>>(custom_id: S, label: L) -> Component {
+        Component {
+            _type: ComponentType::TextInput,
+            custom_id: Some(custom_id.into()),
+            label: Some(label.into()),
+            style: Some(2),
+            ..Default::default()
+        }
+    }
+
+    /// A select menu with predefined choices.
+    pub fn string_select<S: Into<String>>(custom_id: S, options: Vec<SelectOption>) -> Component {
+        Component {
+            _type: ComponentType::StringSelect,
+            custom_id: Some(custom_id.into()),
+            options: Some(options),
+            ..Default::default()
+        }
+    }
+
+    /// A select menu listing the members of the guild.
+    pub fn user_select<S: Into<String>>(custom_id: S) -> Component {
+        Component::select(ComponentType::UserSelect, custom_id)
+    }
+
+    /// A select menu listing the roles of the guild.
+    pub fn role_select<S: Into<String>>(custom_id: S) -> Component {
+        Component::select(ComponentType::RoleSelect, custom_id)
+    }
+
+    /// A select menu listing both the members
+    /// and the roles of the guild.
+    pub fn mentionable_select<S: Into<String>>(custom_id: S) -> Component {
+        Component::select(ComponentType::MentionableSelect, custom_id)
+    }
+
+    /// A select menu listing the channels of the
+    /// guild, restricted to the given types unless
+    /// the list is empty.
+    pub fn channel_select<S: Into<String>>(custom_id: S, channel_types: Vec<ChannelType>) -> Component {
+        Component {
+            channel_types: if channel_types.is_empty() { None } else { Some(channel_types) },
+            ..Component::select(ComponentType::ChannelSelect, custom_id)
+        }
+    }
+
+    fn select<S: Into<String>>(_type: ComponentType, custom_id: S) -> Component {
+        Component {
+            _type,
+            custom_id: Some(custom_id.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn placeholder<S: Into<String>>(mut self, placeholder: S) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// Sets the minimum and maximum number of values
+    /// the user can select, between 0 and 25 and not
+    /// more than the number of choices of a string
+    /// select menu.
+    pub fn values(mut self, min: u8, max: u8) -> Result<Self, Error> {
+        if !self._type.is_select() {
+            return Error::err("Only select menus have a number of values");
+        }
+
+        if max == 0 || max > MAX_SELECT_VALUES {
+            return Error::err(format!("Maximum number of values must be between 1 and {}", MAX_SELECT_VALUES));
+        }
+
+        if min > max {
+            return Error::err("Minimum number of values can not be greater than the maximum");
+        }
+
+        if let Some(options) = &self.options {
+            if max as usize > options.len() {
+                return Error::err(format!("Maximum number of values is greater than the {} choices", options.len()));
+            }
+        }
+
+        self.min_values = Some(min);
+        self.max_values = Some(max);
+        Ok(self)
+    }
+
+    /// Sets the entities selected by default in a user, role,
+    /// mentionable or channel select menu, which can not be
+    /// more than the maximum number of values.
+    pub fn default_values(mut self, values: Vec<SelectDefaultValue>) -> Result<Self, Error> {
+        if !self._type.is_select() || matches!(self._type, ComponentType::StringSelect) {
+            return Error::err("Only user, role, mentionable and channel select menus have default values");
+        }
+
+        let max = self.max_values.unwrap_or(1);
+        if values.len() > max as usize {
+            return Error::err(format!("Can not select more than {} values by default", max));
+        }
+
+        self.default_values = Some(values);
+        Ok(self)
+    }
+}
+
+/// Maximum number of values and choices
+/// of a select menu.
+pub const MAX_SELECT_VALUES: u8 = 25;
+
+/// A choice of a string select menu.
+#[object(both, default)]
+pub struct SelectOption {
+    pub label: String,
+    pub value: String,
+    pub description: Option<String>,
+    pub emoji: Option<PartialEmoji>,
+    pub default: Option<bool>,
+}
+
+impl SelectOption {
+    pub fn new<L: Into<String>, V: Into<String>>(label: L, value: V) -> SelectOption {
+        SelectOption {
+            label: label.into(),
+            value: value.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn emoji(mut self, emoji: PartialEmoji) -> Self {
+        self.emoji = Some(emoji);
+        self
+    }
+
+    /// Selects the choice by default.
+    pub fn selected(mut self) -> Self {
+        self.default = Some(true);
+        self
+    }
+}
+
+/// An entity selected by default in a
+/// select menu.
+#[object(both)]
+pub struct SelectDefaultValue {
+    pub id: Snowflake,
+    #[serde(rename = "type")]
+    pub _type: SelectDefaultValueType,
+}
+
+impl SelectDefaultValue {
+    pub fn user<S: Into<Snowflake>>(id: S) -> SelectDefaultValue {
+        SelectDefaultValue { id: id.into(), _type: SelectDefaultValueType::User }
+    }
+
+    pub fn role<S: Into<Snowflake>>(id: S) -> SelectDefaultValue {
+        SelectDefaultValue { id: id.into(), _type: SelectDefaultValueType::Role }
+    }
+
+    pub fn channel<S: Into<Snowflake>>(id: S) -> SelectDefaultValue {
+        SelectDefaultValue { id: id.into(), _type: SelectDefaultValueType::Channel }
+    }
+}
+
+#[stringify(snake_case)]
+pub enum SelectDefaultValueType {
+    User,
+    Role,
+    Channel,
+}
+
+#[convert(u8)]
+pub enum ComponentType {
+    ActionRow = 1,
+    Button = 2,
+    StringSelect = 3,
+    TextInput = 4,
+    UserSelect = 5,
+    RoleSelect = 6,
+    MentionableSelect = 7,
+    ChannelSelect = 8,
+}
+
+impl ComponentType {
+    pub fn is_select(self) -> bool {
+        matches!(self, ComponentType::StringSelect
+            | ComponentType::UserSelect
+            | ComponentType::RoleSelect
+            | ComponentType::MentionableSelect
+            | ComponentType::ChannelSelect)
+    }
+}
+
+impl Default for ComponentType {
+    fn default() -> Self {
+        ComponentType::ActionRow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn context_menu_target() {
+        let data: InteractionData = serde_json::from_value(json!({
+            "id": "1",
+            "name": "Show profile",
+            "type": 2,
+            "target_id": "3",
+            "resolved": {
+                "users": {
+                    "3": {"id": "3", "username": "user", "discriminator": "0001", "avatar": null}
+                },
+                "members": {
+                    "3": {"nick": null, "roles": [], "joined_at": "2021-01-01T00:00:00.000000+00:00", "premium_since": null}
+                }
+            }
+        })).unwrap();
+
+        assert_eq!(data.target_user().map(|user| user.username.as_str()), Some("user"));
+        assert!(data.target_member().is_some());
+        assert!(data.target_message().is_none());
+    }
+
+    #[test]
+    fn select_menus() {
+        let options = vec![SelectOption::new("Red", "red"), SelectOption::new("Blue", "blue").selected()];
+        assert!(Component::string_select("color", options.clone()).values(1, 2).is_ok());
+        assert!(Component::string_select("color", options).values(1, 3).is_err());
+        assert!(Component::role_select("roles").values(2, 1).is_err());
+        assert!(Component::role_select("roles").values(0, 26).is_err());
+        assert!(Component::short_input("name", "Name").values(1, 1).is_err());
+
+        assert!(Component::user_select("users").default_values(vec![SelectDefaultValue::user(1), SelectDefaultValue::user(2)]).is_err());
+
+        let select = Component::channel_select("channels", vec![ChannelType::GuildText])
+            .values(0, 2)
+            .and_then(|select| select.default_values(vec![SelectDefaultValue::channel(1)]))
+            .unwrap();
+
+        let select = serde_json::to_value(select).unwrap();
+        assert_eq!(select["type"], 8);
+        assert_eq!(select["default_values"][0]["type"], "channel");
+        assert_eq!(select["channel_types"][0], 0);
+    }
+
+    #[test]
+    fn selected_entities() {
+        let data: InteractionData = serde_json::from_value(json!({
+            "custom_id": "roles",
+            "component_type": 6,
+            "values": ["5"],
+            "resolved": {
+                "roles": {
+                    "5": {"id": "5", "name": "admin", "color": 0, "hoist": false, "position": 1, "permissions": 8, "managed": false, "mentionable": false}
+                }
+            }
+        })).unwrap();
+
+        assert_eq!(data.selected_ids(), vec![Snowflake(5)]);
+        assert_eq!(data.selected_roles().len(), 1);
+        assert!(data.selected_users().is_empty());
+    }
+}
@@ -1,5 +1,5 @@
-use crate::gateway::{User, MentionnedUser, PartialGuildMember, ChannelMention, PartialEmoji};
-use crate::Snowflake;
+use crate::gateway::{User, MentionnedUser, PartialGuildMember, ChannelMention, PartialEmoji, PremiumTier};
+use crate::{Snowflake, cdn};
 
 #[object(server)]
 pub struct Message {
@@ -85,6 +85,28 @@ pub enum MessageType {
     InlineReply = 19,
 }
 
+impl MessageType {
+    /// Whether the message is sent by Discord
+    /// when a member boosts the guild.
+    pub fn is_boost(self) -> bool {
+        matches!(self, MessageType::UserPremiumGuildSubscription
+            | MessageType::UserPremiumGuildSubscriptionTier1
+            | MessageType::UserPremiumGuildSubscriptionTier2
+            | MessageType::UserPremiumGuildSubscriptionTier3)
+    }
+
+    /// The tier unlocked by the boost, `None` if the
+    /// boost did not unlock a new tier.
+    pub fn boost_tier(self) -> Option<PremiumTier> {
+        match self {
+            MessageType::UserPremiumGuildSubscriptionTier1 => Some(PremiumTier::Tier1),
+            MessageType::UserPremiumGuildSubscriptionTier2 => Some(PremiumTier::Tier2),
+            MessageType::UserPremiumGuildSubscriptionTier3 => Some(PremiumTier::Tier3),
+            _ => None,
+        }
+    }
+}
+
 #[convert(u32)]
 pub enum MessageFlags {
     Crossposted = 1 << 0,
@@ -105,7 +127,7 @@ pub struct Attachment {
     pub width: Option<u32>,
 }
 
-#[object(both)]
+#[object(both, default)]
 pub struct Embed {
     pub title: Option<String>,
     #[serde(rename = "type")]
@@ -222,6 +244,35 @@ pub struct MessageReference {
     pub channel_id: Snowflake,
     #[nullable]
     pub guild_id: Option<Snowflake>,
+    /// When sending a reply, whether to error if the referenced
+    /// message doesn't exist instead of sending as a normal message.
+    pub fail_if_not_exists: Option<bool>,
+}
+
+impl Message {
+    /// Whether the content, embeds or attachments of the
+    /// message were received. They are all empty when the
+    /// bot does not have the [MessageContent](automate::Intent::MessageContent)
+    /// intent, except in direct messages, in messages
+    /// mentioning the bot and in its own messages.
+    pub fn content_available(&self) -> bool {
+        !self.content.is_empty()
+            || !self.embeds.is_empty()
+            || !self.attachments.is_empty()
+            || self.stickers.as_ref().map_or(false, |stickers| !stickers.is_empty())
+    }
+}
+
+impl MessageReference {
+    /// Reference to the given message, used to reply to it.
+    pub fn to(message: &Message) -> MessageReference {
+        MessageReference {
+            message_id: Some(message.id),
+            channel_id: message.channel_id,
+            guild_id: message.guild_id,
+            fail_if_not_exists: None,
+        }
+    }
 }
 
 #[object(both)]
@@ -236,6 +287,12 @@ pub struct Sticker {
     pub format_type: StickerFormatType
 }
 
+impl Sticker {
+    pub fn url(&self) -> String {
+        cdn::sticker(self.id, self.format_type)
+    }
+}
+
 #[convert(u8)]
 pub enum StickerFormatType {
     PNG = 1,
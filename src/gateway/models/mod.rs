@@ -1,18 +1,28 @@
 mod payload;
+mod application;
+mod command;
 mod audit_log;
 mod channel;
 mod guild;
 mod message;
 mod user;
 mod voice;
+mod scheduled_event;
+mod auto_moderation;
+mod interaction;
 
 pub use payload::*;
+pub use application::*;
+pub use command::*;
 pub use audit_log::*;
 pub use channel::*;
 pub use guild::*;
 pub use message::*;
 pub use user::*;
 pub use voice::*;
+pub use scheduled_event::*;
+pub use auto_moderation::*;
+pub use interaction::*;
 
 /// An object with a single valid WSS URL, which is used
 /// for connecting. This value should be cached.
@@ -39,5 +49,8 @@ pub struct GatewayBot {
 pub struct SessionStartLimit {
     pub total: i32,
     pub remaining: i32,
-    pub reset_after: i32
+    pub reset_after: i32,
+    /// The amount of shards that can identify
+    /// at the same time.
+    pub max_concurrency: u32,
 }
@@ -1,8 +1,9 @@
 use crate::gateway::*;
-use crate::Snowflake;
+use crate::{Snowflake, Error};
 use std::collections::HashMap;
 use serde_json::Value;
-use std::ops::BitOr;
+use std::ops::{BitOr, Sub, Deref, DerefMut};
+use std::fmt::{self, Debug, Formatter};
 use chrono::NaiveDateTime;
 
 /// A Discord Gateway API Payload.
@@ -27,6 +28,7 @@ pub struct ReadyDispatch {
     pub guilds: Vec<UnavailableGuild>,
     pub session_id: String,
     pub shard: Option<[u32; 2]>,
+    pub application: PartialApplication,
 }
 
 #[payload(op = 0, event = "RESUMED", server)]
@@ -48,8 +50,96 @@ pub struct ChannelPinsUpdateDispatch {
     pub last_pin_timestamp: Option<String>,
 }
 
-#[payload(op = 0, event = "GUILD_CREATE", server)]
-pub struct GuildCreateDispatch(pub Guild);
+#[payload(op = 0, event = "THREAD_CREATE", server)]
+pub struct ThreadCreateDispatch(pub ThreadChannel);
+
+#[payload(op = 0, event = "THREAD_UPDATE", server)]
+pub struct ThreadUpdateDispatch(pub ThreadChannel);
+
+#[payload(op = 0, event = "THREAD_DELETE", server)]
+pub struct ThreadDeleteDispatch {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub parent_id: Snowflake,
+    #[serde(rename = "type")]
+    pub _type: ChannelType,
+}
+
+/// Sent when gaining access to a channel, contains
+/// all the active threads of the channel the bot
+/// can access.
+#[payload(op = 0, event = "THREAD_LIST_SYNC", server)]
+pub struct ThreadListSyncDispatch {
+    pub guild_id: Snowflake,
+    /// Parent channels whose threads are being synced,
+    /// if omitted the threads of the whole guild are synced.
+    pub channel_ids: Option<Vec<Snowflake>>,
+    pub threads: Vec<ThreadChannel>,
+    pub members: Vec<ThreadMember>,
+}
+
+/// Sent when the thread member object
+/// of the bot is updated.
+#[payload(op = 0, event = "THREAD_MEMBER_UPDATE", server)]
+pub struct ThreadMemberUpdateDispatch(pub ThreadMember);
+
+#[payload(op = 0, event = "THREAD_MEMBERS_UPDATE", server)]
+pub struct ThreadMembersUpdateDispatch {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub member_count: i32,
+    pub added_members: Option<Vec<ThreadMember>>,
+    pub removed_member_ids: Option<Vec<Snowflake>>,
+}
+
+/// Sent when a guild becomes available, either when the
+/// guilds of the bot are lazily loaded after connecting,
+/// when a guild recovers from an outage or when the bot
+/// joins a new guild.
+///
+/// Unlike the other dispatches, it can not be created
+/// with its constructor, use [From](std::convert::From).
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(from = "Guild")]
+pub struct GuildCreateDispatch(
+    pub Guild,
+    /// Whether the bot joined the guild, set by
+    /// the gateway after receiving the event.
+    pub(crate) bool,
+);
+
+impl GuildCreateDispatch {
+    pub const EVENT_NAME: &'static str = "GUILD_CREATE";
+
+    /// Whether the bot just joined the guild, false if the
+    /// guild was unavailable in the ready event or
+    /// recovered from an outage.
+    pub fn is_new_join(&self) -> bool {
+        self.1
+    }
+}
+
+impl From<Guild> for GuildCreateDispatch {
+    fn from(guild: Guild) -> Self {
+        GuildCreateDispatch(guild, false)
+    }
+}
+
+impl Deref for GuildCreateDispatch {
+    type Target = Guild;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for GuildCreateDispatch {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
 
 #[payload(op = 0, event = "GUILD_UPDATE", server)]
 pub struct GuildUpdateDispatch(pub Guild);
@@ -84,6 +174,67 @@ pub struct GuildIntegrationsUpdateDispatch {
     pub guild_id: Snowflake,
 }
 
+#[payload(op = 0, event = "GUILD_SCHEDULED_EVENT_CREATE", server)]
+pub struct GuildScheduledEventCreateDispatch(pub GuildScheduledEvent);
+
+#[payload(op = 0, event = "GUILD_SCHEDULED_EVENT_UPDATE", server)]
+pub struct GuildScheduledEventUpdateDispatch(pub GuildScheduledEvent);
+
+#[payload(op = 0, event = "GUILD_SCHEDULED_EVENT_DELETE", server)]
+pub struct GuildScheduledEventDeleteDispatch(pub GuildScheduledEvent);
+
+#[payload(op = 0, event = "GUILD_SCHEDULED_EVENT_USER_ADD", server)]
+pub struct GuildScheduledEventUserAddDispatch {
+    pub guild_scheduled_event_id: Snowflake,
+    pub user_id: Snowflake,
+    pub guild_id: Snowflake,
+}
+
+#[payload(op = 0, event = "GUILD_SCHEDULED_EVENT_USER_REMOVE", server)]
+pub struct GuildScheduledEventUserRemoveDispatch {
+    pub guild_scheduled_event_id: Snowflake,
+    pub user_id: Snowflake,
+    pub guild_id: Snowflake,
+}
+
+#[payload(op = 0, event = "AUTO_MODERATION_RULE_CREATE", server)]
+pub struct AutoModerationRuleCreateDispatch(pub AutoModerationRule);
+
+#[payload(op = 0, event = "AUTO_MODERATION_RULE_UPDATE", server)]
+pub struct AutoModerationRuleUpdateDispatch(pub AutoModerationRule);
+
+#[payload(op = 0, event = "AUTO_MODERATION_RULE_DELETE", server)]
+pub struct AutoModerationRuleDeleteDispatch(pub AutoModerationRule);
+
+/// Sent when a rule was triggered and
+/// one of its actions was executed.
+#[payload(op = 0, event = "AUTO_MODERATION_ACTION_EXECUTION", server)]
+pub struct AutoModerationActionExecutionDispatch {
+    pub guild_id: Snowflake,
+    pub action: AutoModerationAction,
+    pub rule_id: Snowflake,
+    pub rule_trigger_type: AutoModerationTriggerType,
+    pub user_id: Snowflake,
+    pub channel_id: Option<Snowflake>,
+    /// Not sent if the message was blocked
+    pub message_id: Option<Snowflake>,
+    /// The alert message sent by the
+    /// send alert message action
+    pub alert_system_message_id: Option<Snowflake>,
+    /// Only filled with the `MessageContent` privileged intent
+    pub content: String,
+    #[nullable]
+    pub matched_keyword: Option<String>,
+    #[nullable]
+    pub matched_content: Option<String>,
+}
+
+/// Sent when a user uses an application command, clicks
+/// on a message component or submits a modal. Does not
+/// require any intent.
+#[payload(op = 0, event = "INTERACTION_CREATE", server)]
+pub struct InteractionCreateDispatch(pub Interaction);
+
 #[payload(op = 0, event = "GUILD_MEMBER_ADD", server)]
 pub struct GuildMemberAddDispatch {
     pub guild_id: Snowflake,
@@ -140,6 +291,9 @@ pub struct InviteCreateDispatch {
     pub guild_id: Snowflake,
     pub channel_id: Snowflake,
     pub inviter: Option<User>,
+    pub target_type: Option<InviteTargetType>,
+    pub target_user: Option<PartialUser>,
+    pub target_application: Option<PartialApplication>,
     pub uses: i32,
     pub max_uses: i32,
     pub max_age: i32,
@@ -250,6 +404,49 @@ pub struct WebhooksUpdateDispatch {
     pub channel_id: Snowflake,
 }
 
+/// An event the library does not know about
+/// with its data left as raw JSON.
+#[derive(Debug, Clone)]
+pub struct RawDispatch {
+    pub name: String,
+    pub data: Value,
+}
+
+/// What the library does when it notices that
+/// events were missed by the shard, usually
+/// after resuming a session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResyncStrategy {
+    /// Fetches the guilds and channels of the shard
+    /// through the HTTP API to update the storages. The
+    /// guilds are fetched a few at a time in a separate
+    /// task while the shard keeps receiving events.
+    Refetch,
+    /// Starts a new session which sends all the
+    /// guilds of the shard again.
+    Reidentify,
+    /// Only notifies the listeners.
+    Ignore,
+}
+
+impl Default for ResyncStrategy {
+    fn default() -> Self {
+        ResyncStrategy::Refetch
+    }
+}
+
+/// Sent by the library when the sequence number of the
+/// gateway events skipped values, meaning events were
+/// lost and the storages may be stale.
+#[derive(Debug, Clone)]
+pub struct CacheResyncDispatch {
+    /// Sequence number of the first missed event.
+    pub expected: i32,
+    /// Sequence number of the event that was received instead.
+    pub received: i32,
+    pub strategy: ResyncStrategy,
+}
+
 #[payload(op = 1, client)]
 pub struct Heartbeat(pub Option<i32>);
 
@@ -268,13 +465,145 @@ pub struct Identify {
 #[payload(op = 3, client)]
 pub struct UpdateStatus {
     #[nullable]
-    pub since: Option<i32>,
+    pub since: Option<u64>,
     #[nullable]
     pub game: Option<ActivityUpdate>,
     pub status: StatusType,
     pub afk: bool,
 }
 
+/// Builds the presence of the bot, which can be given to
+/// [Configuration::presence](automate::Configuration::presence)
+/// or sent using [Context::set_activity](automate::Context::set_activity).
+///
+/// ```
+/// use automate::gateway::Presence;
+///
+/// let presence = Presence::playing("with fire").idle();
+/// let streaming = Presence::streaming("Speedrun", "https://twitch.tv/automate").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Presence {
+    status: StatusType,
+    activity: Option<ActivityUpdate>,
+    afk: bool,
+    since: Option<u64>,
+}
+
+impl Presence {
+    /// An online presence without any activity.
+    pub fn new() -> Presence {
+        Presence {
+            status: StatusType::Online,
+            activity: None,
+            afk: false,
+            since: None,
+        }
+    }
+
+    /// `Playing {name}`
+    pub fn playing<S: Into<String>>(name: S) -> Presence {
+        Presence::new().activity(ActivityType::Game, name, None)
+    }
+
+    /// `Streaming {name}`, the URL must be a
+    /// Twitch or YouTube URL.
+    pub fn streaming<S: Into<String>, U: Into<String>>(name: S, url: U) -> Result<Presence, Error> {
+        let url = url.into();
+
+        if !is_stream_url(&url) {
+            return Error::err(format!("Streaming URL must be a Twitch or YouTube URL, got {}", url));
+        }
+
+        Ok(Presence::new().activity(ActivityType::Streaming, name, Some(url)))
+    }
+
+    /// `Listening to {name}`
+    pub fn listening<S: Into<String>>(name: S) -> Presence {
+        Presence::new().activity(ActivityType::Listening, name, None)
+    }
+
+    /// `Watching {name}`
+    pub fn watching<S: Into<String>>(name: S) -> Presence {
+        Presence::new().activity(ActivityType::Watching, name, None)
+    }
+
+    /// `Competing in {name}`
+    pub fn competing<S: Into<String>>(name: S) -> Presence {
+        Presence::new().activity(ActivityType::Competing, name, None)
+    }
+
+    fn activity<S: Into<String>>(mut self, kind: ActivityType, name: S, url: Option<String>) -> Self {
+        self.activity = Some(ActivityUpdate {
+            name: name.into(),
+            _type: kind,
+            url: url.map(Some),
+        });
+
+        self
+    }
+
+    pub fn status(mut self, status: StatusType) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn online(self) -> Self {
+        self.status(StatusType::Online)
+    }
+
+    pub fn idle(self) -> Self {
+        self.status(StatusType::Idle)
+    }
+
+    pub fn dnd(self) -> Self {
+        self.status(StatusType::Dnd)
+    }
+
+    pub fn invisible(self) -> Self {
+        self.status(StatusType::Invisible)
+    }
+
+    /// Marks the bot as AFK since the given unix
+    /// time in milliseconds.
+    pub fn afk(mut self, since: u64) -> Self {
+        self.afk = true;
+        self.since = Some(since);
+        self
+    }
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Presence::new()
+    }
+}
+
+impl From<Presence> for UpdateStatus {
+    fn from(presence: Presence) -> Self {
+        UpdateStatus {
+            since: presence.since,
+            game: presence.activity,
+            status: presence.status,
+            afk: presence.afk,
+        }
+    }
+}
+
+/// Discord only displays streaming activities
+/// linking to Twitch or YouTube.
+fn is_stream_url(url: &str) -> bool {
+    let rest = match url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    matches!(host, "twitch.tv" | "youtube.com" | "youtu.be") && path.len() > 1
+}
+
 #[payload(op = 4, client)]
 pub struct UpdateVoiceState {
     pub guild_id: Snowflake,
@@ -335,12 +664,19 @@ pub enum Intent {
     ///  - [ChannelUpdate](automate::gateway::ChannelUpdateDispatch)
     ///  - [ChannelDelete](automate::gateway::ChannelDeleteDispatch)
     ///  - [ChannelPinsUpdate](automate::gateway::ChannelPinsUpdateDispatch)
+    ///  - [ThreadCreate](automate::gateway::ThreadCreateDispatch)
+    ///  - [ThreadUpdate](automate::gateway::ThreadUpdateDispatch)
+    ///  - [ThreadDelete](automate::gateway::ThreadDeleteDispatch)
+    ///  - [ThreadListSync](automate::gateway::ThreadListSyncDispatch)
+    ///  - [ThreadMemberUpdate](automate::gateway::ThreadMemberUpdateDispatch)
+    ///  - [ThreadMembersUpdate](automate::gateway::ThreadMembersUpdateDispatch)
     Guilds = 1 << 0,
 
     /// Subscribe to the following events:
     ///  - [GuildMemberAdd](automate::gateway::GuildMemberAddDispatch)
      ///  - [GuildMemberUpdate](automate::gateway::GuildMemberUpdateDispatch)
      ///  - [GuildMemberRemove](automate::gateway::GuildMemberRemoveDispatch)
+     ///  - [ThreadMembersUpdate](automate::gateway::ThreadMembersUpdateDispatch)
     GuildMembers = 1 << 1,
 
     /// Subscribe to the following events:
@@ -401,53 +737,362 @@ pub enum Intent {
 
     /// Subscribe to the [TypingStart](automate::gateway::TypingStartDispatch) event.
     DirectMessageTyping = 1 << 14,
+
+    /// Receive the content, embeds, attachments and components
+    /// of the messages. Without it they are empty except in
+    /// direct messages, in messages mentioning the bot and in
+    /// the messages sent by the bot.
+    ///
+    /// Privileged intent which must be enabled in
+    /// your Discord settings.
+    MessageContent = 1 << 15,
+
+    /// Subscribe to the following events:
+    ///  - [GuildScheduledEventCreate](automate::gateway::GuildScheduledEventCreateDispatch)
+    ///  - [GuildScheduledEventUpdate](automate::gateway::GuildScheduledEventUpdateDispatch)
+    ///  - [GuildScheduledEventDelete](automate::gateway::GuildScheduledEventDeleteDispatch)
+    ///  - [GuildScheduledEventUserAdd](automate::gateway::GuildScheduledEventUserAddDispatch)
+    ///  - [GuildScheduledEventUserRemove](automate::gateway::GuildScheduledEventUserRemoveDispatch)
+    GuildScheduledEvents = 1 << 16,
+
+    /// Subscribe to the following events:
+    ///  - [AutoModerationRuleCreate](automate::gateway::AutoModerationRuleCreateDispatch)
+    ///  - [AutoModerationRuleUpdate](automate::gateway::AutoModerationRuleUpdateDispatch)
+    ///  - [AutoModerationRuleDelete](automate::gateway::AutoModerationRuleDeleteDispatch)
+    AutoModerationConfiguration = 1 << 20,
+
+    /// Subscribe to the [AutoModerationActionExecution](automate::gateway::AutoModerationActionExecutionDispatch) event.
+    AutoModerationExecution = 1 << 21,
 }
 
 impl Intent {
-    /// Return all intents including `GuildPresence` and
-    /// `GuildMembers` which both require to be enabled in
+    /// Return all intents including `GuildPresence`, `GuildMembers`
+    /// and `MessageContent` which all require to be enabled in
     /// your Discord settings or the bot will fail to
     /// connect with error "Disallowed intent(s)".
-    pub fn all() -> u32 {
-        Intent::Guilds | Intent::GuildMembers | Intent::GuildBans | Intent::GuildEmojis |
-            Intent::GuildIntegrations | Intent::GuildWebhooks | Intent::GuildInvites |
-            Intent::GuildVoiceStates | Intent::GuildPresences | Intent::GuildMessages |
-            Intent::GuildMessageReactions | Intent::GuildMessageTyping | Intent::DirectMessages |
-            Intent::DirectMessageReactions | Intent::DirectMessageTyping
+    pub fn all() -> IntentSet {
+        IntentSet::all()
     }
 
     /// Return all intents except for presence updates, guild
     /// members and the message typing related ones.
     ///
     /// Default used by configuration.
-    pub fn default() -> u32 {
+    pub fn default() -> IntentSet {
         Intent::Guilds | Intent::GuildBans | Intent::GuildEmojis | Intent::GuildIntegrations |
             Intent::GuildWebhooks | Intent::GuildInvites | Intent::GuildVoiceStates |
             Intent::GuildMessages | Intent::GuildMessageReactions | Intent::DirectMessages |
-            Intent::DirectMessageReactions
+            Intent::DirectMessageReactions | Intent::GuildScheduledEvents |
+            Intent::AutoModerationConfiguration | Intent::AutoModerationExecution
+    }
+
+    /// The intents of which at least one is required to
+    /// receive the given event. The event is named after
+    /// the snake case name of its dispatch without the
+    /// `Dispatch` suffix, `message_create` for example.
+    ///
+    /// Returns an empty slice for events that are
+    /// sent regardless of the intents.
+    pub fn required_by(event: &str) -> &'static [Intent] {
+        match event {
+            "channel_create" | "channel_pins_update" => &[Intent::Guilds, Intent::DirectMessages],
+            "channel_update" | "channel_delete" | "thread_create" | "thread_update" | "thread_delete" |
+            "thread_list_sync" | "thread_member_update" | "guild_create" | "guild_update" | "guild_delete" |
+            "guild_role_create" | "guild_role_update" | "guild_role_delete" => &[Intent::Guilds],
+            "thread_members_update" => &[Intent::Guilds, Intent::GuildMembers],
+            "guild_member_add" | "guild_member_update" | "guild_member_remove" => &[Intent::GuildMembers],
+            "guild_ban_add" | "guild_ban_remove" => &[Intent::GuildBans],
+            "guild_emojis_update" => &[Intent::GuildEmojis],
+            "guild_integrations_update" => &[Intent::GuildIntegrations],
+            "webhooks_update" => &[Intent::GuildWebhooks],
+            "invite_create" | "invite_delete" => &[Intent::GuildInvites],
+            "voice_state_update" => &[Intent::GuildVoiceStates],
+            "presence_update" => &[Intent::GuildPresences],
+            "message_create" | "message_update" | "message_delete" => &[Intent::GuildMessages, Intent::DirectMessages],
+            "message_delete_bulk" => &[Intent::GuildMessages],
+            "reaction_add" | "reaction_remove" | "reaction_remove_all" | "reaction_remove_emoji" => &[Intent::GuildMessageReactions, Intent::DirectMessageReactions],
+            "typing_start" => &[Intent::GuildMessageTyping, Intent::DirectMessageTyping],
+            "guild_scheduled_event_create" | "guild_scheduled_event_update" | "guild_scheduled_event_delete" |
+            "guild_scheduled_event_user_add" | "guild_scheduled_event_user_remove" => &[Intent::GuildScheduledEvents],
+            "auto_moderation_rule_create" | "auto_moderation_rule_update" | "auto_moderation_rule_delete" => &[Intent::AutoModerationConfiguration],
+            "auto_moderation_action_execution" => &[Intent::AutoModerationExecution],
+            _ => &[],
+        }
     }
 }
 
 impl BitOr for Intent {
-    type Output = u32;
+    type Output = IntentSet;
 
-    fn bitor(self, rhs: Self) -> u32 {
-        self as u32 | rhs as u32
+    fn bitor(self, rhs: Self) -> IntentSet {
+        IntentSet::from(self) | rhs
     }
 }
 
 impl BitOr<u32> for Intent {
-    type Output = u32;
+    type Output = IntentSet;
 
-    fn bitor(self, rhs: u32) -> u32 {
-        self as u32 | rhs
+    fn bitor(self, rhs: u32) -> IntentSet {
+        IntentSet::from(self) | IntentSet::from(rhs)
     }
 }
 
 impl BitOr<Intent> for u32 {
-    type Output = u32;
+    type Output = IntentSet;
+
+    fn bitor(self, rhs: Intent) -> IntentSet {
+        IntentSet::from(self) | rhs
+    }
+}
+
+/// A set of [intents](automate::Intent) which can be
+/// combined with the `|` operator and from which
+/// intents can be removed with the `-` operator.
+///
+/// ```
+/// use automate::{Intent, IntentSet};
+///
+/// let intents = (IntentSet::unprivileged() | Intent::MessageContent) - Intent::GuildMessageTyping;
+/// assert!(intents.contains(Intent::MessageContent));
+/// assert!(!intents.contains(Intent::GuildMessageTyping));
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct IntentSet(u32);
+
+impl IntentSet {
+    const INTENTS: [Intent; 19] = [
+        Intent::Guilds, Intent::GuildMembers, Intent::GuildBans, Intent::GuildEmojis,
+        Intent::GuildIntegrations, Intent::GuildWebhooks, Intent::GuildInvites,
+        Intent::GuildVoiceStates, Intent::GuildPresences, Intent::GuildMessages,
+        Intent::GuildMessageReactions, Intent::GuildMessageTyping, Intent::DirectMessages,
+        Intent::DirectMessageReactions, Intent::DirectMessageTyping, Intent::MessageContent,
+        Intent::GuildScheduledEvents, Intent::AutoModerationConfiguration, Intent::AutoModerationExecution,
+    ];
+
+    /// A set without any intent.
+    pub fn empty() -> IntentSet {
+        IntentSet(0)
+    }
+
+    /// All the intents, including the privileged ones.
+    pub fn all() -> IntentSet {
+        IntentSet::INTENTS.iter().fold(IntentSet::empty(), |set, intent| set | *intent)
+    }
+
+    /// The intents which must be enabled in the bot tab of the
+    /// developer portal or the bot will fail to connect with
+    /// error "Disallowed intent(s)".
+    pub fn privileged() -> IntentSet {
+        Intent::GuildMembers | Intent::GuildPresences | Intent::MessageContent
+    }
+
+    /// All the intents except the privileged ones.
+    pub fn unprivileged() -> IntentSet {
+        IntentSet::all() - IntentSet::privileged()
+    }
+
+    /// The value sent to Discord when identifying.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(self, intent: Intent) -> bool {
+        self.0 & intent as u32 != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The intents in the set.
+    pub fn iter(self) -> impl Iterator<Item = Intent> {
+        IntentSet::INTENTS.iter().copied().filter(move |intent| self.contains(*intent))
+    }
+}
+
+impl From<Intent> for IntentSet {
+    fn from(intent: Intent) -> Self {
+        IntentSet(intent as u32)
+    }
+}
 
-    fn bitor(self, rhs: Intent) -> u32 {
-        self | rhs as u32
+impl From<u32> for IntentSet {
+    fn from(bits: u32) -> Self {
+        IntentSet(bits)
     }
-}
\ No newline at end of file
+}
+
+impl From<IntentSet> for u32 {
+    fn from(set: IntentSet) -> Self {
+        set.0
+    }
+}
+
+impl BitOr for IntentSet {
+    type Output = IntentSet;
+
+    fn bitor(self, rhs: Self) -> IntentSet {
+        IntentSet(self.0 | rhs.0)
+    }
+}
+
+impl BitOr<Intent> for IntentSet {
+    type Output = IntentSet;
+
+    fn bitor(self, rhs: Intent) -> IntentSet {
+        IntentSet(self.0 | rhs as u32)
+    }
+}
+
+impl Sub for IntentSet {
+    type Output = IntentSet;
+
+    fn sub(self, rhs: Self) -> IntentSet {
+        IntentSet(self.0 & !rhs.0)
+    }
+}
+
+impl Sub<Intent> for IntentSet {
+    type Output = IntentSet;
+
+    fn sub(self, rhs: Intent) -> IntentSet {
+        IntentSet(self.0 & !(rhs as u32))
+    }
+}
+
+impl Debug for IntentSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Codes sent by Discord's gateway when closing
+/// the connection.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/topics/opcodes-and-status-codes#gateway-gateway-close-event-codes)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GatewayCloseCode {
+    UnknownError = 4000,
+    UnknownOpcode = 4001,
+    DecodeError = 4002,
+    NotAuthenticated = 4003,
+    AuthenticationFailed = 4004,
+    AlreadyAuthenticated = 4005,
+    InvalidSequence = 4007,
+    RateLimited = 4008,
+    SessionTimedOut = 4009,
+    InvalidShard = 4010,
+    ShardingRequired = 4011,
+    InvalidApiVersion = 4012,
+    InvalidIntents = 4013,
+    DisallowedIntents = 4014,
+}
+
+impl GatewayCloseCode {
+    /// Finds the close code corresponding to the
+    /// given websocket close code.
+    pub fn from_code(code: u16) -> Option<GatewayCloseCode> {
+        use GatewayCloseCode::*;
+
+        Some(match code {
+            4000 => UnknownError,
+            4001 => UnknownOpcode,
+            4002 => DecodeError,
+            4003 => NotAuthenticated,
+            4004 => AuthenticationFailed,
+            4005 => AlreadyAuthenticated,
+            4007 => InvalidSequence,
+            4008 => RateLimited,
+            4009 => SessionTimedOut,
+            4010 => InvalidShard,
+            4011 => ShardingRequired,
+            4012 => InvalidApiVersion,
+            4013 => InvalidIntents,
+            4014 => DisallowedIntents,
+            _ => return None
+        })
+    }
+
+    /// Whether the connection can not be recovered by
+    /// reconnecting because the configuration of the bot
+    /// (token, shards or intents) is wrong.
+    pub fn is_fatal(self) -> bool {
+        use GatewayCloseCode::*;
+
+        matches!(self, AuthenticationFailed | InvalidShard | ShardingRequired
+            | InvalidApiVersion | InvalidIntents | DisallowedIntents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+
+    #[test]
+    fn presence() {
+        let status: UpdateStatus = Presence::watching("you").dnd().into();
+        assert!(matches!(status.status, StatusType::Dnd));
+        assert!(matches!(status.game.unwrap()._type, ActivityType::Watching));
+
+        assert!(Presence::streaming("stream", "https://www.twitch.tv/automate").is_ok());
+        assert!(Presence::streaming("stream", "https://youtube.com/watch?v=abc").is_ok());
+        assert!(Presence::streaming("stream", "https://twitch.tv/").is_err());
+        assert!(Presence::streaming("stream", "https://example.com/twitch.tv").is_err());
+        assert!(Presence::streaming("stream", "twitch.tv/automate").is_err());
+
+        let status: UpdateStatus = Presence::new().afk(1_600_000_000_000).into();
+        assert!(status.afk);
+        assert_eq!(status.since, Some(1_600_000_000_000));
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned>(value: T, expected: &str) {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, format!("\"{}\"", expected));
+
+        let value: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+    }
+
+    #[test]
+    fn stringified_enums() {
+        round_trip(StatusType::Online, "online");
+        round_trip(StatusType::Dnd, "dnd");
+        round_trip(StatusType::Invisible, "invisible");
+        round_trip(EmbedType::Rich, "rich");
+        round_trip(EmbedType::Gifv, "gifv");
+        round_trip(OverwriteType::Role, "role");
+        round_trip(OverwriteType::Member, "member");
+        round_trip(GuildFeature::VanityUrl, "VANITY_URL");
+        round_trip(GuildFeature::WelcomeScreenEnabled, "WELCOME_SCREEN_ENABLED");
+        round_trip(SelectDefaultValueType::User, "user");
+        round_trip(SelectDefaultValueType::Channel, "channel");
+
+        let unknown: GuildFeature = serde_json::from_str("\"SOME_NEW_FEATURE\"").unwrap();
+        assert!(matches!(unknown, GuildFeature::Unknown));
+    }
+
+    #[test]
+    fn guild_create() {
+        let payload = serde_json::json!({"op": 0, "d": crate::testing::guild_json(1, 2, "guild"), "s": 1, "t": "GUILD_CREATE"});
+        let payload: Payload<GuildCreateDispatch> = serde_json::from_value(payload).unwrap();
+
+        assert_eq!(payload.d.name, "guild");
+        assert!(!payload.d.is_new_join());
+        assert!(!GuildCreateDispatch::from(crate::testing::fake_guild(1, 2, "guild")).is_new_join());
+    }
+
+    #[test]
+    fn intent_set() {
+        let all = IntentSet::all();
+        assert_eq!(all.iter().count(), 19);
+        assert_eq!(IntentSet::unprivileged() | IntentSet::privileged(), all);
+        assert!(!IntentSet::unprivileged().contains(Intent::MessageContent));
+
+        let intents = Intent::GuildMessages | Intent::MessageContent;
+        assert_eq!(intents, IntentSet::from((1 << 9) | (1 << 15)));
+        assert_eq!(format!("{:?}", intents - Intent::GuildMessages), "{MessageContent}");
+        assert_eq!(u32::from(IntentSet::empty() | Intent::Guilds), 1);
+    }
+}
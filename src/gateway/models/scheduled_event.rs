@@ -0,0 +1,86 @@
+use crate::gateway::{User, PartialGuildMember};
+use crate::{Snowflake, Identifiable};
+
+/// An event scheduled in a guild, either in a
+/// stage channel, a voice channel or somewhere
+/// outside of Discord.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/resources/guild-scheduled-event)
+#[object(server)]
+pub struct GuildScheduledEvent {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    /// The channel in which the event will be hosted,
+    /// null for external events.
+    #[nullable]
+    pub channel_id: Option<Snowflake>,
+    #[option_nullable]
+    pub creator_id: Option<Option<Snowflake>>,
+    pub name: String,
+    #[option_nullable]
+    pub description: Option<Option<String>>,
+    /// ISO8601 timestamp of the start of the event.
+    pub scheduled_start_time: String,
+    /// ISO8601 timestamp of the end of the event,
+    /// required for external events.
+    #[nullable]
+    pub scheduled_end_time: Option<String>,
+    pub privacy_level: ScheduledEventPrivacyLevel,
+    pub status: ScheduledEventStatus,
+    pub entity_type: ScheduledEventEntityType,
+    #[nullable]
+    pub entity_id: Option<Snowflake>,
+    #[nullable]
+    pub entity_metadata: Option<ScheduledEventEntityMetadata>,
+    pub creator: Option<User>,
+    /// Amount of users subscribed to the event,
+    /// only sent when requested.
+    pub user_count: Option<i32>,
+    #[option_nullable]
+    pub image: Option<Option<String>>,
+}
+
+impl Identifiable for GuildScheduledEvent {
+    fn id(&self) -> Snowflake {
+        self.id
+    }
+}
+
+/// Additional information for external events.
+#[object(both)]
+pub struct ScheduledEventEntityMetadata {
+    pub location: Option<String>,
+}
+
+#[convert(u8)]
+pub enum ScheduledEventPrivacyLevel {
+    GuildOnly = 2,
+}
+
+/// The status of an event can go from scheduled
+/// to active or canceled and from active to
+/// completed.
+#[convert(u8)]
+pub enum ScheduledEventStatus {
+    Scheduled = 1,
+    Active = 2,
+    Completed = 3,
+    Canceled = 4,
+}
+
+#[convert(u8)]
+pub enum ScheduledEventEntityType {
+    StageInstance = 1,
+    Voice = 2,
+    External = 3,
+}
+
+/// A user subscribed to a scheduled event.
+#[object(server)]
+pub struct GuildScheduledEventUser {
+    pub guild_scheduled_event_id: Snowflake,
+    pub user: User,
+    /// Only sent when requested with
+    /// the member of the user.
+    pub member: Option<PartialGuildMember>,
+}
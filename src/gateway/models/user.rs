@@ -1,5 +1,6 @@
 use crate::gateway::PartialGuildMember;
-use crate::{Snowflake, Identifiable};
+use crate::{Snowflake, Identifiable, cdn};
+use crate::cdn::ImageFormat;
 
 /// Users in Discord are generally considered the
 /// base entity. Users can spawn across the entire
@@ -44,6 +45,23 @@ impl Identifiable for User {
     }
 }
 
+impl User {
+    /// The avatar of the user or the default avatar
+    /// if the user did not set any.
+    pub fn avatar_url(&self, format: ImageFormat, size: u16) -> String {
+        match &self.avatar {
+            Some(hash) => cdn::avatar(self.id, hash, format, size),
+            None => cdn::default_avatar(&self.discriminator),
+        }
+    }
+
+    /// The name and discriminator of the
+    /// user, such as `automate#0001`.
+    pub fn tag(&self) -> String {
+        format!("{}#{}", self.username, self.discriminator)
+    }
+}
+
 /// A [User] object with all fields optional
 /// except for  ̀id`.
 #[object(server)]
@@ -18,6 +18,26 @@ pub struct VoiceState {
     pub suppress: bool
 }
 
+impl VoiceState {
+    /// Completes a voice state received in a guild create event.
+    pub fn from_partial(guild: Snowflake, state: &PartialVoiceState) -> VoiceState {
+        VoiceState {
+            guild_id: Some(guild),
+            channel_id: state.channel_id,
+            user_id: state.user_id,
+            member: state.member.clone(),
+            session_id: state.session_id.clone(),
+            deaf: state.deaf,
+            mute: state.mute,
+            self_deaf: state.self_deaf,
+            self_mute: state.self_mute,
+            self_video: false,
+            self_stream: None,
+            suppress: state.suppress,
+        }
+    }
+}
+
 #[object(server)]
 pub struct PartialVoiceState {
     #[nullable]
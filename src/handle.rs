@@ -0,0 +1,199 @@
+use crate::{HttpAPI, Snowflake, Error, sharding};
+use crate::gateway::{ShardSender, UpdateStatus, UpdateVoiceState, RequestGuildMembers};
+use crate::metrics::Metrics;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+#[cfg(feature = "storage")]
+use crate::storage::{StorageContainer, Query};
+#[cfg(feature = "storage")]
+use crate::encode::ExtractSnowflake;
+#[cfg(feature = "storage")]
+use crate::gateway::{Guild, Channel, User};
+#[cfg(feature = "storage")]
+use std::any::TypeId;
+#[cfg(feature = "storage")]
+use std::collections::HashSet;
+
+pub(crate) struct ShardHandle {
+    pub(crate) sender: ShardSender,
+    #[cfg(feature = "storage")]
+    pub(crate) storage: StorageContainer,
+}
+
+/// Clones the handle by sharing the storages
+/// instead of creating new ones.
+impl Clone for ShardHandle {
+    fn clone(&self) -> Self {
+        ShardHandle {
+            sender: self.sender.clone(),
+            #[cfg(feature = "storage")]
+            storage: self.storage.share(),
+        }
+    }
+}
+
+/// Rough statistics of the bot computed from the storages
+/// of the shards launched by this process with
+/// [BotHandle::statistics](automate::BotHandle::statistics).
+#[cfg(feature = "storage")]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// Shards which received their ready event.
+    pub shards: usize,
+    pub guilds: usize,
+    pub channels: usize,
+    /// Cached users, counted once even if they are
+    /// in guilds handled by different shards.
+    pub users: usize,
+    /// Sum of the member counts of the guilds,
+    /// including members that are not cached.
+    pub members: u64,
+}
+
+/// Gives access to the bot from outside of the
+/// listeners, for example from a web server running
+/// beside the bot.
+///
+/// The handle is obtained with
+/// [ShardManager::handle](automate::ShardManager::handle)
+/// or [Automate::launch_with_handle](automate::Automate::launch_with_handle)
+/// and dereferences to [HttpAPI](automate::HttpAPI).
+/// Gateway commands and storages are only available
+/// once the shards have been launched.
+#[derive(Clone)]
+pub struct BotHandle {
+    http: HttpAPI,
+    metrics: Arc<Metrics>,
+    total_shards: Arc<AtomicU32>,
+    shards: Arc<RwLock<HashMap<u32, ShardHandle>>>,
+}
+
+impl BotHandle {
+    pub(crate) fn new(http: HttpAPI, metrics: Arc<Metrics>, total_shards: u32) -> BotHandle {
+        BotHandle {
+            http,
+            metrics,
+            total_shards: Arc::new(AtomicU32::new(total_shards)),
+            shards: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub(crate) fn register(&self, shard_id: u32, shard: ShardHandle) {
+        self.shards.write().unwrap().insert(shard_id, shard);
+    }
+
+    /// Replaces all the shards after the bot has been
+    /// [resharded](automate::ShardManager::auto_reshard).
+    pub(crate) fn replace(&self, total_shards: u32, shards: HashMap<u32, ShardHandle>) {
+        let mut current = self.shards.write().unwrap();
+        self.total_shards.store(total_shards, Ordering::Relaxed);
+        *current = shards;
+    }
+
+    fn shard(&self, shard_id: u32) -> Result<ShardHandle, Error> {
+        match self.shards.read().unwrap().get(&shard_id) {
+            Some(shard) => Ok(shard.clone()),
+            None => Error::err(format!("Shard {} is not launched by this process", shard_id)),
+        }
+    }
+
+    /// Total amount of shards of the bot, including the
+    /// shards launched by other processes.
+    pub fn total_shards(&self) -> u32 {
+        self.total_shards.load(Ordering::Relaxed)
+    }
+
+    /// The ids of the shards launched by this process.
+    pub fn shards(&self) -> Vec<u32> {
+        let mut shards: Vec<u32> = self.shards.read().unwrap().keys().copied().collect();
+        shards.sort_unstable();
+        shards
+    }
+
+    /// Updates the presence of the bot on all the
+    /// shards launched by this process.
+    pub fn update_status(&self, data: UpdateStatus) -> Result<(), Error> {
+        for shard in self.shards.read().unwrap().values() {
+            shard.sender.send_presence(data.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Join, move or disconnect from a voice channel
+    /// through the shard handling the guild.
+    pub fn update_voice_state(&self, data: UpdateVoiceState) -> Result<(), Error> {
+        self.shard(self.shard_of(data.guild_id))?.sender.send_command(data)
+    }
+
+    /// Request members of a guild through the
+    /// shard handling the guild.
+    pub fn request_guild_members(&self, data: RequestGuildMembers) -> Result<(), Error> {
+        self.shard(self.shard_of(data.guild_id))?.sender.send_command(data)
+    }
+
+    /// The storages of the shard handling the given
+    /// guild, which can be read and written like
+    /// in the listeners.
+    #[cfg(feature = "storage")]
+    pub fn storage<S: ExtractSnowflake>(&self, guild: S) -> Result<StorageContainer, Error> {
+        Ok(self.shard(self.shard_of(guild.extract_snowflake()?))?.storage)
+    }
+
+    /// The storages of the given shard.
+    #[cfg(feature = "storage")]
+    pub fn shard_storage(&self, shard_id: u32) -> Result<StorageContainer, Error> {
+        Ok(self.shard(shard_id)?.storage)
+    }
+
+    /// Aggregates the storages of all the shards launched
+    /// by this process, for example to display the amount
+    /// of guilds in the presence of the bot.
+    #[cfg(feature = "storage")]
+    pub async fn statistics(&self) -> Statistics {
+        let shards: Vec<ShardHandle> = self.shards.read().unwrap().values().cloned().collect();
+        let mut statistics = Statistics::default();
+        let mut users = HashSet::new();
+
+        for shard in shards {
+            //the storages are initialized in the ready event
+            if !shard.storage.contains(TypeId::of::<Guild>()) {
+                continue;
+            }
+
+            let guilds = shard.storage.read::<Guild>().await;
+            statistics.shards += 1;
+            statistics.guilds += guilds.count();
+            statistics.members += guilds.iter().map(|g| g.member_count.unwrap_or(0).max(0) as u64).sum::<u64>();
+            drop(guilds);
+
+            statistics.channels += shard.storage.read::<Channel>().await.count();
+            users.extend(shard.storage.read::<User>().await.iter().map(|u| u.id));
+        }
+
+        statistics.users = users.len();
+        statistics
+    }
+
+    /// Registry where the bot can record its own
+    /// counters next to the ones of the library.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    fn shard_of(&self, guild: Snowflake) -> u32 {
+        sharding::shard_id(self.total_shards.load(Ordering::Relaxed) as u64, guild)
+    }
+}
+
+impl Deref for BotHandle {
+    type Target = HttpAPI;
+
+    #[inline]
+    fn deref(&self) -> &HttpAPI {
+        &self.http
+    }
+}
@@ -0,0 +1,55 @@
+use crate::Error;
+use async_trait::async_trait;
+use hyper::{Body, Client, Request, Response};
+use hyper::client::HttpConnector;
+use hyper_tls::HttpsConnector;
+
+/// Sends the requests built by the endpoints of
+/// [HttpAPI](automate::HttpAPI).
+///
+/// The default client sends them to Discord, another
+/// client can be given to
+/// [HttpAPI::with_client](automate::HttpAPI::with_client)
+/// to record the requests and return canned responses
+/// when testing listeners.
+///
+/// ```
+/// use automate::{async_trait, Error, HttpAPI};
+/// use automate::http::HttpClient;
+/// use hyper::{Body, Request, Response};
+/// use std::sync::{Arc, Mutex};
+///
+/// #[derive(Default)]
+/// struct Recorder(Arc<Mutex<Vec<String>>>);
+///
+/// #[async_trait]
+/// impl HttpClient for Recorder {
+///     async fn request(&self, request: Request<Body>) -> Result<Response<Body>, Error> {
+///         self.0.lock().unwrap().push(format!("{} {}", request.method(), request.uri()));
+///
+///         Ok(Response::builder()
+///             .status(200)
+///             .body(Body::from(r#"{"url": "wss://gateway.discord.gg"}"#))
+///             .unwrap())
+///     }
+/// }
+///
+/// # automate::tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let requests = Arc::new(Mutex::new(Vec::new()));
+/// let http = HttpAPI::new("token").with_client(Recorder(Arc::clone(&requests)));
+///
+/// assert_eq!(http.gateway().await.unwrap().url, "wss://gateway.discord.gg");
+/// assert_eq!(requests.lock().unwrap()[0], "GET https://discordapp.com/api/v8/gateway");
+/// # });
+/// ```
+#[async_trait]
+pub trait HttpClient: Send + Sync + 'static {
+    async fn request(&self, request: Request<Body>) -> Result<Response<Body>, Error>;
+}
+
+#[async_trait]
+impl HttpClient for Client<HttpsConnector<HttpConnector>> {
+    async fn request(&self, request: Request<Body>) -> Result<Response<Body>, Error> {
+        Ok(Client::request(self, request).await?)
+    }
+}
@@ -1,27 +1,86 @@
 //! Tools to interact with Discord's HTTP API
 
+mod client;
 mod models;
 mod rate_limit;
+mod pagination;
+mod retry;
+mod raw;
+mod request;
 
+pub use client::HttpClient;
 pub use models::*;
+pub use retry::RetryPolicy;
 pub use rate_limit::collect_outdated_buckets;
 
 use crate::gateway::*;
-use crate::{Error, Snowflake};
+use crate::{Error, Snowflake, Configuration};
 use crate::encode::{ExtractSnowflake, WriteUrl};
 use hyper::Client;
 use hyper::client::HttpConnector;
 use hyper_tls::HttpsConnector;
 use std::io::Write;
+use futures::Stream;
 use native_tls::TlsConnector;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
+
+/// Base URL of Discord's HTTP API.
+pub const API_BASE: &str = "https://discordapp.com/api/v8";
+
+/// Version of Discord's API used by the HTTP
+/// requests and the gateway connections.
+///
+/// Version 10 requires the
+/// [MessageContent](automate::Intent::MessageContent)
+/// intent to receive the content of messages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiVersion {
+    V8 = 8,
+    V9 = 9,
+    V10 = 10,
+}
+
+impl Default for ApiVersion {
+    fn default() -> Self {
+        ApiVersion::V8
+    }
+}
+
+impl ApiVersion {
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+
+    /// Base URL of the HTTP API for this version.
+    pub fn base_url(self) -> String {
+        format!("https://discordapp.com/api/v{}", self.number())
+    }
+
+    /// Query string of the gateway URL
+    /// for this version.
+    pub(crate) fn gateway_query(self) -> String {
+        format!("?v={}&encoding=json", self.number())
+    }
+}
+
+/// Identifies the traced requests in the logs.
+static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
 const FORMDATA_BOUNDARY: &str = "--XREJRTlhIaFgKOHZvSG5BOGRqNGxVcWpCWEJhOWQKRllaTG10QWhLNld";
 
 /// Struct used to interact with the discord HTTP API.
 #[derive(Clone)]
 pub struct HttpAPI {
-    client: Client<HttpsConnector<HttpConnector>>,
+    client: Arc<dyn HttpClient>,
     token: String,
+    base_url: String,
+    retry: RetryPolicy,
+    tracing: bool,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<Metrics>,
 }
 
 impl HttpAPI {
@@ -41,11 +100,92 @@ impl HttpAPI {
         bot_token.push_str(token);
 
         HttpAPI {
-            client: Client::builder().build(https),
+            client: Arc::new(Client::builder().build(https)),
             token: bot_token,
+            base_url: String::from(API_BASE),
+            retry: RetryPolicy::default(),
+            tracing: false,
+            #[cfg(feature = "metrics")]
+            metrics: Arc::new(Metrics::default()),
         }
     }
 
+    /// Creates the HTTP API used by the shards
+    /// launched with the given configuration.
+    pub(crate) fn for_config(config: &Configuration) -> HttpAPI {
+        let base_url = match &config.api_base {
+            Some(base) => base.clone(),
+            None => config.api_version.base_url(),
+        };
+
+        let http = HttpAPI::new(&config.token)
+            .with_base_url(base_url)
+            .retry_policy(config.retry_policy.clone())
+            .tracing(config.http_tracing);
+
+        #[cfg(feature = "metrics")]
+        let http = HttpAPI {
+            metrics: config.metrics(),
+            ..http
+        };
+
+        http
+    }
+
+    /// Sends the requests to the given base URL instead
+    /// of [API_BASE](automate::http::API_BASE), for example
+    /// to go through a proxy or to a mocked API.
+    pub fn with_base_url<S: Into<String>>(mut self, url: S) -> HttpAPI {
+        let mut url = url.into();
+        while url.ends_with('/') {
+            url.pop();
+        }
+
+        self.base_url = url;
+        self
+    }
+
+    /// Sends the requests to Discord's API
+    /// using the given version.
+    pub fn with_api_version(self, version: ApiVersion) -> HttpAPI {
+        self.with_base_url(version.base_url())
+    }
+
+    /// Sends the requests through the given client
+    /// instead of sending them to Discord, see
+    /// [HttpClient](automate::http::HttpClient).
+    pub fn with_client<C: HttpClient>(mut self, client: C) -> HttpAPI {
+        self.client = Arc::new(client);
+        self
+    }
+
+    /// Sets how requests are retried after
+    /// a connection error or a 502 response.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> HttpAPI {
+        self.retry = policy;
+        self
+    }
+
+    /// Logs every request along with the state of its
+    /// rate-limit bucket, then its status and latency at
+    /// the debug level. Both logs share an identifier to
+    /// match responses to their request.
+    pub fn tracing(mut self, enabled: bool) -> HttpAPI {
+        self.tracing = enabled;
+        self
+    }
+
+    fn next_request_id() -> u64 {
+        REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The client sending the requests, which can also
+    /// send requests to other APIs than Discord's.
+    #[cfg(feature = "botlists")]
+    pub(crate) fn client(&self) -> &dyn HttpClient {
+        self.client.as_ref()
+    }
+
     pub fn token(&self) -> &String {
         &self.token
     }
@@ -56,12 +196,131 @@ impl HttpAPI {
     #[endpoint(get, route = "/gateway/bot", status = 200)]
     pub async fn gateway_bot(&self) -> Result<GatewayBot, Error> {}
 
-    #[endpoint(get, route = "/guilds/{#guild}/audit-logs", status = 200)]
-    pub async fn audit_logs<S: ExtractSnowflake>(&self, guild: S) -> Result<AuditLog, Error> {}
+    /// Retrieves the global commands of the application.
+    #[endpoint(get, route = "/applications/{#application}/commands", status = 200)]
+    pub async fn global_commands<S: ExtractSnowflake>(&self, application: S) -> Result<Vec<ApplicationCommand>, Error> {}
+
+    /// Creates a global command. Creating a command with the
+    /// name of an existing command overwrites it.
+    #[endpoint(post, route = "/applications/{#application}/commands", body = "command", status = 200)]
+    pub async fn create_global_command<S: ExtractSnowflake>(&self, application: S, command: NewApplicationCommand) -> Result<ApplicationCommand, Error> {}
+
+    /// Replaces all the global commands of the application.
+    #[endpoint(put, route = "/applications/{#application}/commands", body = "commands", status = 200)]
+    pub async fn overwrite_global_commands<S: ExtractSnowflake>(&self, application: S, commands: Vec<NewApplicationCommand>) -> Result<Vec<ApplicationCommand>, Error> {}
+
+    #[endpoint(delete, route = "/applications/{#application}/commands/{#command}", status = 204, empty)]
+    pub async fn delete_global_command<S: ExtractSnowflake>(&self, application: S, command: S) -> Result<(), Error> {}
+
+    /// Retrieves the commands of the application
+    /// specific to the given guild.
+    #[endpoint(get, route = "/applications/{#application}/guilds/{#guild}/commands", status = 200)]
+    pub async fn guild_commands<S: ExtractSnowflake>(&self, application: S, guild: S) -> Result<Vec<ApplicationCommand>, Error> {}
+
+    #[endpoint(post, route = "/applications/{#application}/guilds/{#guild}/commands", body = "command", status = 200)]
+    pub async fn create_guild_command<S: ExtractSnowflake>(&self, application: S, guild: S, command: NewApplicationCommand) -> Result<ApplicationCommand, Error> {}
+
+    #[endpoint(put, route = "/applications/{#application}/guilds/{#guild}/commands", body = "commands", status = 200)]
+    pub async fn overwrite_guild_commands<S: ExtractSnowflake>(&self, application: S, guild: S, commands: Vec<NewApplicationCommand>) -> Result<Vec<ApplicationCommand>, Error> {}
+
+    #[endpoint(delete, route = "/applications/{#application}/guilds/{#guild}/commands/{#command}", status = 204, empty)]
+    pub async fn delete_guild_command<S: ExtractSnowflake>(&self, application: S, guild: S, command: S) -> Result<(), Error> {}
+
+    /// Responds to an interaction, must be called within
+    /// 3 seconds after receiving the interaction.
+    #[endpoint(post, route = "/interactions/{#interaction}/{token}/callback", body = "response", status = 204, empty)]
+    pub async fn create_interaction_response<S: ExtractSnowflake>(&self, interaction: S, token: &str, response: InteractionResponse) -> Result<(), Error> {}
+
+    #[endpoint(get, route = "/webhooks/{#application}/{token}/messages/@original", status = 200)]
+    pub async fn original_interaction_response<S: ExtractSnowflake>(&self, application: S, token: &str) -> Result<Message, Error> {}
+
+    /// Edits the initial response, which is also how
+    /// the message of a deferred response is sent.
+    #[endpoint(patch, route = "/webhooks/{#application}/{token}/messages/@original", body = "message", status = 200)]
+    pub async fn edit_original_interaction_response<S: ExtractSnowflake>(&self, application: S, token: &str, message: ModifyInteractionMessage) -> Result<Message, Error> {}
+
+    #[endpoint(delete, route = "/webhooks/{#application}/{token}/messages/@original", status = 204, empty)]
+    pub async fn delete_original_interaction_response<S: ExtractSnowflake>(&self, application: S, token: &str) -> Result<(), Error> {}
+
+    /// Sends a new message after the initial response,
+    /// followup messages can be sent for 15 minutes.
+    #[endpoint(post, route = "/webhooks/{#application}/{token}", body = "message", status = 200)]
+    pub async fn create_followup_message<S: ExtractSnowflake>(&self, application: S, token: &str, message: FollowupMessage) -> Result<Message, Error> {}
+
+    #[endpoint(patch, route = "/webhooks/{#application}/{token}/messages/{#message}", body = "modification", status = 200)]
+    pub async fn edit_followup_message<S: ExtractSnowflake>(&self, application: S, token: &str, message: S, modification: ModifyInteractionMessage) -> Result<Message, Error> {}
+
+    #[endpoint(delete, route = "/webhooks/{#application}/{token}/messages/{#message}", status = 204, empty)]
+    pub async fn delete_followup_message<S: ExtractSnowflake>(&self, application: S, token: &str, message: S) -> Result<(), Error> {}
+
+    /// Retrieves the application of the bot along with
+    /// its owner or the team owning it, see
+    /// [Application::owners](automate::gateway::Application::owners).
+    #[endpoint(get, route = "/oauth2/applications/@me", status = 200)]
+    pub async fn current_application(&self) -> Result<Application, Error> {}
+
+    #[endpoint(get, route = "/guilds/{#guild}/audit-logs{query}", status = 200)]
+    pub async fn audit_logs<S: ExtractSnowflake>(&self, guild: S, filter: AuditLogFilter) -> Result<AuditLog, Error> {
+        let mut params = Vec::new();
+
+        if let Some(user) = filter.user_id {
+            params.push(format!("user_id={}", user));
+        }
+
+        if let Some(action) = filter.action_type {
+            params.push(format!("action_type={}", action as u8));
+        }
+
+        if let Some(before) = filter.before {
+            params.push(format!("before={}", before));
+        }
+
+        if let Some(limit) = filter.limit {
+            params.push(format!("limit={}", limit));
+        }
+
+        let query = if params.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", params.join("&"))
+        };
+    }
+
+    /// Iterates over all the audit log entries matching the
+    /// filter, from the most recent to the oldest, by
+    /// requesting pages of `limit` entries (100 if unset)
+    /// as the stream is consumed.
+    ///
+    /// The stream ends after yielding the first error.
+    pub fn audit_logs_iter<S: ExtractSnowflake>(&self, guild: S, filter: AuditLogFilter) -> impl Stream<Item = Result<AuditLogEntry, Error>> + '_ {
+        let limit = filter.limit.unwrap_or(100);
+
+        pagination::paginate(guild.extract_snowflake(), move |guild, before| {
+            let filter = AuditLogFilter {
+                before: before.or(filter.before),
+                limit: Some(limit),
+                ..filter.clone()
+            };
+
+            async move {
+                let page = self.audit_logs(guild, filter).await?.audit_log_entries;
+                let next = page.last().filter(|_| page.len() >= limit as usize).map(|e| e.id);
+
+                Ok((page, next))
+            }
+        })
+    }
 
     #[endpoint(get, route = "/guilds/{#guild}", status = 200)]
     pub async fn guild<S: ExtractSnowflake>(&self, guild: S) -> Result<Guild, Error> {}
 
+    /// Retrieves the public information of a guild
+    /// along with its approximate member and presence
+    /// counts. Works for guilds the bot is not in if
+    /// they are lurkable.
+    #[endpoint(get, route = "/guilds/{#guild}/preview", status = 200)]
+    pub async fn guild_preview<S: ExtractSnowflake>(&self, guild: S) -> Result<GuildPreview, Error> {}
+
     /// Creates a guild
     /// The first role defined in the roles vector will
     /// be used to define the permissions for `@everyone`.
@@ -90,8 +349,8 @@ impl HttpAPI {
 
     #[endpoint(patch, route = "/guilds/{#guild}/channels", body = "moves", status = 204, empty)]
     pub async fn move_channels<S: ExtractSnowflake>(&self, guild: S, moves: Vec<MoveChannel>) -> Result<(), Error> {
-        if moves.len() < 2 {
-            return Error::http("Expected at least two channels");
+        if moves.is_empty() {
+            return Error::http("Expected at least one channel");
         }
     }
 
@@ -102,7 +361,7 @@ impl HttpAPI {
     #[endpoint(get, route = "/guilds/{#guild}/members/{#user}", status = 200)]
     pub async fn member<S: ExtractSnowflake>(&self, guild: S, user: S) -> Result<GuildMember, Error> {}
 
-    #[endpoint(get, route = "/guilds/{#guild}/members/{query}", status = 200)]
+    #[endpoint(get, route = "/guilds/{#guild}/members{query}", status = 200)]
     pub async fn members<S: ExtractSnowflake>(&self, guild: S, filter: MemberFilter) -> Result<Vec<GuildMember>, Error> {
         let query = match filter {
             MemberFilter::Default => String::new(),
@@ -111,6 +370,26 @@ impl HttpAPI {
         };
     }
 
+    /// Iterates over all the members of a guild by requesting
+    /// pages of 1000 members as the stream is consumed.
+    ///
+    /// Requires the [GuildMembers](automate::Intent::GuildMembers)
+    /// privileged intent to be enabled for the application.
+    /// The stream ends after yielding the first error.
+    pub fn members_iter<S: ExtractSnowflake>(&self, guild: S) -> impl Stream<Item = Result<GuildMember, Error>> + '_ {
+        pagination::paginate(guild.extract_snowflake(), move |guild, after| async move {
+            let filter = match after {
+                Some(after) => MemberFilter::After(after, 1000),
+                None => MemberFilter::Limit(1000),
+            };
+
+            let page = self.members(guild, filter).await?;
+            let next = page.last().filter(|_| page.len() >= 1000).map(|m| m.user.id);
+
+            Ok((page, next))
+        })
+    }
+
     #[endpoint(patch, route = "/guilds/{#guild}/members/{#user}", body = "member", status = 204, empty)]
     pub async fn modify_member<S: ExtractSnowflake>(&self, guild: S, user: S, member: ModifyMember) -> Result<(), Error> {}
 
@@ -130,13 +409,47 @@ impl HttpAPI {
     #[endpoint(delete, route = "/guilds/{#guild}/members/{#user}/roles/{#role}", status = 204, empty)]
     pub async fn member_remove_role<S: ExtractSnowflake>(&self, guild: S, user: S, role: S) -> Result<(), Error> {}
 
-    #[endpoint(get, route = "/guilds/{#guild}/bans", status = 200)]
-    pub async fn bans<S: ExtractSnowflake>(&self, guild: S) -> Result<Vec<Ban>, Error> {}
+    /// Retrieves the bans of a guild, sorted by user id.
+    /// Discord returns at most 1000 bans per request,
+    /// see [bans_iter](automate::HttpAPI::bans_iter) to
+    /// retrieve all of them.
+    #[endpoint(get, route = "/guilds/{#guild}/bans{query}", status = 200)]
+    pub async fn bans<S: ExtractSnowflake>(&self, guild: S, bans: BansPosition) -> Result<Vec<Ban>, Error> {
+        let query = match bans {
+            BansPosition::Default => String::new(),
+            BansPosition::Limit(limit) => format!("?limit={}", limit),
+            BansPosition::Before(s, limit) => format!("?before={}&limit={}", s, limit),
+            BansPosition::After(s, limit) => format!("?after={}&limit={}", s, limit),
+        };
+    }
+
+    /// Iterates over all the bans of a guild by requesting
+    /// pages of 1000 bans as the stream is consumed.
+    ///
+    /// The stream ends after yielding the first error.
+    pub fn bans_iter<S: ExtractSnowflake>(&self, guild: S) -> impl Stream<Item = Result<Ban, Error>> + '_ {
+        pagination::paginate(guild.extract_snowflake(), move |guild, after| async move {
+            let position = match after {
+                Some(after) => BansPosition::After(after, 1000),
+                None => BansPosition::Limit(1000),
+            };
+
+            let page = self.bans(guild, position).await?;
+            let next = page.last().filter(|_| page.len() >= 1000).map(|b| b.user.id);
+
+            Ok((page, next))
+        })
+    }
+
+    /// Bans up to 200 users at once. Requires both the
+    /// `BAN_MEMBERS` and `MANAGE_GUILD` permissions.
+    #[endpoint(post, route = "/guilds/{#guild}/bulk-ban", body = "bans", status = 200)]
+    pub async fn bulk_ban<S: ExtractSnowflake>(&self, guild: S, bans: BulkBan) -> Result<BulkBanResult, Error> {}
 
     #[endpoint(get, route = "/guilds/{#guild}/bans/{#user}", status = 200)]
     pub async fn ban<S: ExtractSnowflake>(&self, guild: S, user: S) -> Result<Ban, Error> {}
 
-    #[endpoint(put, route = "/guilds/{#guild}/bans/{#user}/{query}", status = 204, empty)]
+    #[endpoint(put, route = "/guilds/{#guild}/bans/{#user}{query}", status = 204, empty)]
     pub async fn create_ban<S: ExtractSnowflake>(&self, guild: S, user: S, reason: Option<&str>, delete_days: Option<i8>) -> Result<(), Error> {
         let mut query = String::from("?");
 
@@ -173,14 +486,16 @@ impl HttpAPI {
     #[endpoint(delete, route = "/guilds/{#guild}/roles/{#role}", status = 204, empty)]
     pub async fn remove_role<S: ExtractSnowflake>(&self, guild: S, role: S) -> Result<(), Error> {}
 
-    #[endpoint(get, route = "/guilds/{#guild}/prune?days={days}", status = 200)]
-    pub async fn simulate_prune<S: ExtractSnowflake>(&self, guild: S, days: i32) -> Result<Prune, Error> {}
-
-    #[endpoint(post, route = "/guilds/{#guild}/prune?days={days}&compute_prune_count=false", status = 200, empty)]
-    pub async fn prune<S: ExtractSnowflake>(&self, guild: S, days: i32) -> Result<(), Error> {}
+    /// Counts the members a prune with the given
+    /// options would remove without removing them.
+    #[endpoint(get, route = "/guilds/{#guild}/prune{query}", status = 200)]
+    pub async fn simulate_prune<S: ExtractSnowflake>(&self, guild: S, options: PruneOptions) -> Result<Prune, Error> {
+        let query = options.query();
+    }
 
-    #[endpoint(post, route = "/guilds/{#guild}/prune?days={days}&compute_prune_count=true", status = 200)]
-    pub async fn prune_with_report<S: ExtractSnowflake>(&self, guild: S, days: i32) -> Result<Prune, Error> {}
+    /// Removes the inactive members, see [PruneOptions](automate::http::PruneOptions).
+    #[endpoint(post, route = "/guilds/{#guild}/prune", body = "options", status = 200)]
+    pub async fn prune<S: ExtractSnowflake>(&self, guild: S, options: PruneOptions) -> Result<Prune, Error> {}
 
     #[endpoint(get, route = "/voice/regions", status = 200)]
     pub async fn voice_regions(&self) -> Result<Vec<VoiceRegion>, Error> {}
@@ -205,7 +520,7 @@ impl HttpAPI {
     #[endpoint(get, route = "/channels/{#channel}/messages/{#message}", status = 200)]
     pub async fn message<S: ExtractSnowflake>(&self, channel: S, message: S) -> Result<Message, Error> {}
 
-    #[endpoint(get, route = "/channels/{#channel}/messages/{query}", status = 200)]
+    #[endpoint(get, route = "/channels/{#channel}/messages{query}", status = 200)]
     pub async fn messages<S: ExtractSnowflake>(&self, channel: S, messages: MessagesPosition) -> Result<Vec<Message>, Error> {
         let query = match messages {
             MessagesPosition::Default => String::new(),
@@ -216,6 +531,25 @@ impl HttpAPI {
         };
     }
 
+    /// Iterates over all the messages of a channel, from the
+    /// most recent to the oldest, by requesting pages of 100
+    /// messages as the stream is consumed.
+    ///
+    /// The stream ends after yielding the first error.
+    pub fn messages_iter<S: ExtractSnowflake>(&self, channel: S) -> impl Stream<Item = Result<Message, Error>> + '_ {
+        pagination::paginate(channel.extract_snowflake(), move |channel, before| async move {
+            let position = match before {
+                Some(before) => MessagesPosition::Before(before, 100),
+                None => MessagesPosition::Limit(100),
+            };
+
+            let page = self.messages(channel, position).await?;
+            let next = page.last().filter(|_| page.len() >= 100).map(|m| m.id);
+
+            Ok((page, next))
+        })
+    }
+
     /// Post a message to a guild text or DM channel. If operating on
     /// a guild channel, this endpoint requires the `SEND_MESSAGES`
     /// permission to be present on the current user. If the tts field
@@ -235,6 +569,10 @@ impl HttpAPI {
     pub async fn create_message<S: ExtractSnowflake>(&self, channel: S, mut message: CreateMessage) -> Result<Message, Error> {
         let mut data = Vec::new();
 
+        //files are sent as separate parts instead of in the JSON payload
+        let attachment = message.attachment.take();
+        let files = std::mem::take(&mut message.files);
+
         write!(data, "--{}\r\n", FORMDATA_BOUNDARY)?;
         write!(data, "Content-Disposition: form-data; name=\"payload_json\"\r\n")?;
         write!(data, "Content-Type: application/json\r\n")?;
@@ -242,23 +580,41 @@ impl HttpAPI {
         serde_json::to_writer(&mut data, &message)?;
         write!(data, "\r\n")?;
 
-        if let Some(mut attachment) = message.attachment {
+        let named = attachment.into_iter().map(|a| (String::from("file"), a))
+            .chain(files.into_iter().enumerate().map(|(i, a)| (format!("files[{}]", i), a)));
+
+        for (field, mut attachment) in named {
             write!(data, "--{}\r\n", FORMDATA_BOUNDARY)?;
-            write!(data, "Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n", attachment.name)?;
+            write!(data, "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n", field, attachment.name)?;
             write!(data, "Content-Type: {}\r\n", attachment.mime)?;
             write!(data, "\r\n")?;
             data.append(&mut attachment.content);
             write!(data, "\r\n")?;
-
-            message.attachment = None;
         }
 
         write!(data, "--{}--\r\n", FORMDATA_BOUNDARY)?;
     }
 
-    #[endpoint(post, route = "/channels/{#channel}/messages/{#message}", body = "modification", status = 200)]
+    #[endpoint(patch, route = "/channels/{#channel}/messages/{#message}", body = "modification", status = 200)]
     pub async fn modify_message<S: ExtractSnowflake>(&self, channel: S, message: S, modification: ModifyMessage) -> Result<Message, Error> {}
 
+    /// Crosspost a message in a news channel to the channels
+    /// following it. Requires the `SEND_MESSAGES` permission if
+    /// the current user sent the message, or additionally the
+    /// `MANAGE_MESSAGES` permission for all other messages.
+    #[endpoint(post, route = "/channels/{#channel}/messages/{#message}/crosspost", status = 200)]
+    pub async fn crosspost_message<S: ExtractSnowflake>(&self, channel: S, message: S) -> Result<Message, Error> {}
+
+    /// Follows an announcement channel to send its messages
+    /// to the target channel, which requires the
+    /// `MANAGE_WEBHOOKS` permission in the target channel.
+    #[endpoint(post, route = "/channels/{#channel}/followers", body = "follow", status = 200)]
+    pub async fn follow_news_channel<S: ExtractSnowflake>(&self, channel: S, target: S) -> Result<FollowedChannel, Error> {
+        let follow = FollowChannel {
+            webhook_channel_id: target.extract_snowflake()?
+        };
+    }
+
     #[endpoint(delete, route = "/channels/{#channel}/messages/{#message}", status = 204, empty)]
     pub async fn delete_message<S: ExtractSnowflake>(&self, channel: S, message: S) -> Result<(), Error> {}
 
@@ -269,7 +625,7 @@ impl HttpAPI {
             .collect::<Result<Vec<Snowflake>, Error>>()?;
     }
 
-    #[endpoint(get, route = "/channels/{#channel}/messages/{#message}/reactions/{+emoji}/{query}", status = 200)]
+    #[endpoint(get, route = "/channels/{#channel}/messages/{#message}/reactions/{+emoji}{query}", status = 200)]
     pub async fn reactions<S: ExtractSnowflake, U: WriteUrl>(&self, channel: S, message: S, emoji: &U, reactions: ReactionsPosition) -> Result<Vec<User>, Error> {
         let query = match reactions {
             ReactionsPosition::Default => String::new(),
@@ -279,6 +635,28 @@ impl HttpAPI {
         };
     }
 
+    /// Iterates over all the users who reacted to a message
+    /// with the given emoji by requesting pages of 100 users
+    /// as the stream is consumed.
+    ///
+    /// The stream ends after yielding the first error.
+    pub fn reactions_iter<'a, S: ExtractSnowflake, U: WriteUrl>(&'a self, channel: S, message: S, emoji: &'a U) -> impl Stream<Item = Result<User, Error>> + 'a {
+        let ids = channel.extract_snowflake()
+            .and_then(|channel| Ok((channel, message.extract_snowflake()?)));
+
+        pagination::paginate(ids, move |(channel, message), after| async move {
+            let position = match after {
+                Some(after) => ReactionsPosition::After(after, 100),
+                None => ReactionsPosition::Limit(100),
+            };
+
+            let page = self.reactions(channel, message, emoji, position).await?;
+            let next = page.last().filter(|_| page.len() >= 100).map(|u| u.id);
+
+            Ok((page, next))
+        })
+    }
+
     #[endpoint(put, route = "/channels/{#channel}/messages/{#message}/reactions/{+emoji}/@me", status = 204, empty)]
     pub async fn create_reaction<S: ExtractSnowflake, U: WriteUrl>(&self, channel: S, message: S, emoji: &U) -> Result<(), Error> {}
 
@@ -349,6 +727,111 @@ impl HttpAPI {
     #[endpoint(delete, route = "/channels/{#channel}/pins/{#message}", status = 204, empty)]
     pub async fn unpin_message<S: ExtractSnowflake>(&self, channel: S, message: S) -> Result<(), Error> {}
 
+    /// Creates a thread from an existing message.
+    #[endpoint(post, route = "/channels/{#channel}/messages/{#message}/threads", body = "thread", status = 201)]
+    pub async fn start_thread_with_message<S: ExtractSnowflake>(&self, channel: S, message: S, thread: NewThread) -> Result<ThreadChannel, Error> {}
+
+    /// Creates a thread that is not connected to a message,
+    /// private threads can only be created in text channels.
+    #[endpoint(post, route = "/channels/{#channel}/threads", body = "thread", status = 201)]
+    pub async fn start_thread<S: ExtractSnowflake>(&self, channel: S, thread: NewThread) -> Result<ThreadChannel, Error> {}
+
+    #[endpoint(put, route = "/channels/{#thread}/thread-members/@me", status = 204, empty)]
+    pub async fn join_thread<S: ExtractSnowflake>(&self, thread: S) -> Result<(), Error> {}
+
+    #[endpoint(delete, route = "/channels/{#thread}/thread-members/@me", status = 204, empty)]
+    pub async fn leave_thread<S: ExtractSnowflake>(&self, thread: S) -> Result<(), Error> {}
+
+    #[endpoint(put, route = "/channels/{#thread}/thread-members/{#user}", status = 204, empty)]
+    pub async fn add_thread_member<S: ExtractSnowflake>(&self, thread: S, user: S) -> Result<(), Error> {}
+
+    #[endpoint(delete, route = "/channels/{#thread}/thread-members/{#user}", status = 204, empty)]
+    pub async fn remove_thread_member<S: ExtractSnowflake>(&self, thread: S, user: S) -> Result<(), Error> {}
+
+    /// Requires the `GUILD_MEMBERS` privileged intent.
+    #[endpoint(get, route = "/channels/{#thread}/thread-members", status = 200)]
+    pub async fn thread_members<S: ExtractSnowflake>(&self, thread: S) -> Result<Vec<ThreadMember>, Error> {}
+
+    /// Returns all the active threads of the guild
+    /// the bot can see, ordered by descending id.
+    #[endpoint(get, route = "/guilds/{#guild}/threads/active", status = 200)]
+    pub async fn active_threads<S: ExtractSnowflake>(&self, guild: S) -> Result<ThreadList, Error> {}
+
+    /// Returns the archived public threads of the channel,
+    /// ordered by descending archive timestamp.
+    #[endpoint(get, route = "/channels/{#channel}/threads/archived/public{query}", status = 200)]
+    pub async fn public_archived_threads<S: ExtractSnowflake>(&self, channel: S, position: ArchivedThreadsPosition) -> Result<ThreadList, Error> {
+        let query = archived_threads_query(position);
+    }
+
+    /// Returns the archived private threads of the channel,
+    /// requires the `MANAGE_THREADS` permission.
+    #[endpoint(get, route = "/channels/{#channel}/threads/archived/private{query}", status = 200)]
+    pub async fn private_archived_threads<S: ExtractSnowflake>(&self, channel: S, position: ArchivedThreadsPosition) -> Result<ThreadList, Error> {
+        let query = archived_threads_query(position);
+    }
+
+    /// Returns the archived private threads of the
+    /// channel that the bot has joined.
+    #[endpoint(get, route = "/channels/{#channel}/users/@me/threads/archived/private{query}", status = 200)]
+    pub async fn joined_private_archived_threads<S: ExtractSnowflake>(&self, channel: S, position: ArchivedThreadsPosition) -> Result<ThreadList, Error> {
+        let query = archived_threads_query(position);
+    }
+
+    /// Retrieves the scheduled events of the guild, the
+    /// amount of subscribed users is only set when
+    /// `with_user_count` is true.
+    #[endpoint(get, route = "/guilds/{#guild}/scheduled-events?with_user_count={with_user_count}", status = 200)]
+    pub async fn scheduled_events<S: ExtractSnowflake>(&self, guild: S, with_user_count: bool) -> Result<Vec<GuildScheduledEvent>, Error> {}
+
+    /// Schedules an event in the guild. Requires the
+    /// `MANAGE_EVENTS` permission.
+    #[endpoint(post, route = "/guilds/{#guild}/scheduled-events", body = "event", status = 200)]
+    pub async fn create_scheduled_event<S: ExtractSnowflake>(&self, guild: S, event: NewScheduledEvent) -> Result<GuildScheduledEvent, Error> {}
+
+    #[endpoint(get, route = "/guilds/{#guild}/scheduled-events/{#event}?with_user_count={with_user_count}", status = 200)]
+    pub async fn scheduled_event<S: ExtractSnowflake>(&self, guild: S, event: S, with_user_count: bool) -> Result<GuildScheduledEvent, Error> {}
+
+    /// Modifies a scheduled event, see
+    /// [ModifyScheduledEvent](automate::http::ModifyScheduledEvent)
+    /// to start or end an event.
+    #[endpoint(patch, route = "/guilds/{#guild}/scheduled-events/{#event}", body = "modification", status = 200)]
+    pub async fn modify_scheduled_event<S: ExtractSnowflake>(&self, guild: S, event: S, modification: ModifyScheduledEvent) -> Result<GuildScheduledEvent, Error> {}
+
+    #[endpoint(delete, route = "/guilds/{#guild}/scheduled-events/{#event}", status = 204, empty)]
+    pub async fn delete_scheduled_event<S: ExtractSnowflake>(&self, guild: S, event: S) -> Result<(), Error> {}
+
+    /// Retrieves the users subscribed to a scheduled event,
+    /// along with their guild member when `with_member`
+    /// is true.
+    #[endpoint(get, route = "/guilds/{#guild}/scheduled-events/{#event}/users?with_member={with_member}{query}", status = 200)]
+    pub async fn scheduled_event_users<S: ExtractSnowflake>(&self, guild: S, event: S, position: ScheduledEventUsersPosition, with_member: bool) -> Result<Vec<GuildScheduledEventUser>, Error> {
+        let query = match position {
+            ScheduledEventUsersPosition::Default => String::new(),
+            ScheduledEventUsersPosition::Limit(limit) => format!("&limit={}", limit),
+            ScheduledEventUsersPosition::Before(s, limit) => format!("&before={}&limit={}", s, limit),
+            ScheduledEventUsersPosition::After(s, limit) => format!("&after={}&limit={}", s, limit),
+        };
+    }
+
+    /// Retrieves the automatic moderation rules of the guild.
+    /// Requires the `MANAGE_GUILD` permission, as do the
+    /// other automatic moderation endpoints.
+    #[endpoint(get, route = "/guilds/{#guild}/auto-moderation/rules", status = 200)]
+    pub async fn auto_moderation_rules<S: ExtractSnowflake>(&self, guild: S) -> Result<Vec<AutoModerationRule>, Error> {}
+
+    #[endpoint(get, route = "/guilds/{#guild}/auto-moderation/rules/{#rule}", status = 200)]
+    pub async fn auto_moderation_rule<S: ExtractSnowflake>(&self, guild: S, rule: S) -> Result<AutoModerationRule, Error> {}
+
+    #[endpoint(post, route = "/guilds/{#guild}/auto-moderation/rules", body = "rule", status = 200)]
+    pub async fn create_auto_moderation_rule<S: ExtractSnowflake>(&self, guild: S, rule: NewAutoModerationRule) -> Result<AutoModerationRule, Error> {}
+
+    #[endpoint(patch, route = "/guilds/{#guild}/auto-moderation/rules/{#rule}", body = "modification", status = 200)]
+    pub async fn modify_auto_moderation_rule<S: ExtractSnowflake>(&self, guild: S, rule: S, modification: ModifyAutoModerationRule) -> Result<AutoModerationRule, Error> {}
+
+    #[endpoint(delete, route = "/guilds/{#guild}/auto-moderation/rules/{#rule}", status = 204, empty)]
+    pub async fn delete_auto_moderation_rule<S: ExtractSnowflake>(&self, guild: S, rule: S) -> Result<(), Error> {}
+
     /// Returns the current user.
     #[endpoint(get, route = "/users/@me", status = 200)]
     pub async fn curent_user(&self) -> Result<User, Error> {}
@@ -383,4 +866,12 @@ impl HttpAPI {
     #[endpoint(delete, route = "/channels/{#channel}", status = 200)]
     pub async fn close_dm<S: ExtractSnowflake>(&self, channel: S) -> Result<PrivateChannel, Error> {}
 
-}
\ No newline at end of file
+}
+
+fn archived_threads_query(position: ArchivedThreadsPosition) -> String {
+    match position {
+        ArchivedThreadsPosition::Default => String::new(),
+        ArchivedThreadsPosition::Limit(limit) => format!("?limit={}", limit),
+        ArchivedThreadsPosition::Before(timestamp, limit) => format!("?before={}&limit={}", timestamp, limit),
+    }
+}
@@ -0,0 +1,346 @@
+use crate::gateway::{Embed, EmbedAuthor, EmbedField, EmbedFooter, EmbedImage, EmbedThumbnail, Component, Message};
+use crate::http::{AllowedMentions, CreateAttachment, CreateMessage, ModifyMessage};
+use crate::Error;
+
+/// Maximum amount of characters in the content of a message.
+pub const MAX_CONTENT_LENGTH: usize = 2000;
+/// Maximum amount of embeds in a message.
+pub const MAX_EMBEDS: usize = 10;
+/// Maximum amount of characters in all
+/// the embeds of a message combined.
+pub const MAX_EMBED_LENGTH: usize = 6000;
+/// Maximum amount of fields in an embed.
+pub const MAX_EMBED_FIELDS: usize = 25;
+/// Maximum amount of action rows in a message
+/// and of components in an action row.
+pub const MAX_COMPONENT_ROWS: usize = 5;
+/// Maximum amount of files uploaded with a message.
+pub const MAX_FILES: usize = 10;
+
+/// Builds a [CreateMessage](automate::http::CreateMessage)
+/// and checks the limits of Discord before the message
+/// is sent.
+///
+/// ```
+/// use automate::http::MessageBuilder;
+///
+/// let message = MessageBuilder::new()
+///     .content("The results are in")
+///     .embed(|e| e.title("Votes").field("Yes", "12", true).field("No", "3", true))
+///     .component_row(|r| r.button("recount", "Recount").link_button("Details", "https://example.com"))
+///     .file("votes.csv", "yes,no\n12,3")
+///     .allowed_mentions_none()
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(message.embeds.unwrap().len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MessageBuilder {
+    message: CreateMessage,
+}
+
+impl MessageBuilder {
+    pub fn new() -> MessageBuilder {
+        MessageBuilder::default()
+    }
+
+    pub fn content<S: Into<String>>(mut self, content: S) -> Self {
+        self.message.content = Some(content.into());
+        self
+    }
+
+    pub fn tts(mut self) -> Self {
+        self.message.tts = true;
+        self
+    }
+
+    /// Adds an embed built by the given function.
+    pub fn embed<F: FnOnce(EmbedBuilder) -> EmbedBuilder>(mut self, build: F) -> Self {
+        self.message.embeds.get_or_insert_with(Vec::new).push(build(EmbedBuilder::default()).0);
+        self
+    }
+
+    /// Adds a row of components built by the given function.
+    pub fn component_row<F: FnOnce(RowBuilder) -> RowBuilder>(mut self, build: F) -> Self {
+        let row = Component::action_row(build(RowBuilder::default()).0);
+        self.message.components.get_or_insert_with(Vec::new).push(row);
+        self
+    }
+
+    /// Uploads a file with the message.
+    pub fn file<N: Into<String>, B: Into<Vec<u8>>>(mut self, name: N, content: B) -> Self {
+        self.message.files.push(CreateAttachment {
+            name: name.into(),
+            mime: String::from("application/octet-stream"),
+            content: content.into(),
+        });
+
+        self
+    }
+
+    /// Sends the message as a reply to the given message.
+    pub fn reply_to(mut self, message: &Message) -> Self {
+        self.message = self.message.reply_to(message);
+        self
+    }
+
+    /// See [CreateMessage::suppress_mentions](automate::http::CreateMessage::suppress_mentions).
+    pub fn allowed_mentions_none(mut self) -> Self {
+        self.message.allowed_mentions = Some(AllowedMentions::none());
+        self
+    }
+
+    pub fn allowed_mentions(mut self, allowed_mentions: AllowedMentions) -> Self {
+        self.message.allowed_mentions = Some(allowed_mentions);
+        self
+    }
+
+    /// Checks the limits of the message and returns it,
+    /// fails if it is empty or exceeds a limit.
+    pub fn build(self) -> Result<CreateMessage, Error> {
+        let message = self.message;
+        let embeds = message.embeds.as_deref().unwrap_or_default();
+        let rows = message.components.as_deref().unwrap_or_default();
+
+        if message.content.is_none() && embeds.is_empty() && message.files.is_empty() && message.attachment.is_none() {
+            return Error::err("Message needs a content, an embed or a file");
+        }
+
+        if let Some(content) = &message.content {
+            if content.chars().count() > MAX_CONTENT_LENGTH {
+                return Error::err(format!("Message content can not be longer than {} characters", MAX_CONTENT_LENGTH));
+            }
+        }
+
+        if embeds.len() > MAX_EMBEDS {
+            return Error::err(format!("Message can not have more than {} embeds", MAX_EMBEDS));
+        }
+
+        let mut length = 0;
+        for embed in embeds {
+            length += embed_length(embed)?;
+        }
+
+        if length > MAX_EMBED_LENGTH {
+            return Error::err(format!("Embeds of a message can not have more than {} characters combined", MAX_EMBED_LENGTH));
+        }
+
+        if rows.len() > MAX_COMPONENT_ROWS {
+            return Error::err(format!("Message can not have more than {} rows of components", MAX_COMPONENT_ROWS));
+        }
+
+        for row in rows {
+            match row.components.as_deref() {
+                Some([]) | None => return Error::err("Rows of components can not be empty"),
+                Some(components) if components.len() > MAX_COMPONENT_ROWS => {
+                    return Error::err(format!("Rows can not have more than {} components", MAX_COMPONENT_ROWS));
+                }
+                _ => (),
+            }
+        }
+
+        if message.files.len() + message.attachment.is_some() as usize > MAX_FILES {
+            return Error::err(format!("Message can not have more than {} files", MAX_FILES));
+        }
+
+        Ok(message)
+    }
+
+    /// Checks the limits of the message and returns a
+    /// modification replacing the content, embeds and
+    /// components of an existing message with the ones
+    /// of the builder. Fails if the builder has files since
+    /// they can only be uploaded when creating a message.
+    pub fn build_modification(self) -> Result<ModifyMessage, Error> {
+        let message = self.build()?;
+
+        if !message.files.is_empty() || message.attachment.is_some() {
+            return Error::err("Files can not be uploaded when modifying a message");
+        }
+
+        Ok(ModifyMessage {
+            content: Some(message.content),
+            embeds: Some(message.embeds.unwrap_or_default()),
+            components: Some(message.components.unwrap_or_default()),
+            allowed_mentions: message.allowed_mentions,
+            ..ModifyMessage::default()
+        })
+    }
+}
+
+/// Amount of characters of the embed counting
+/// towards the limit of the message, fails if
+/// a field of the embed is too long.
+fn embed_length(embed: &Embed) -> Result<usize, Error> {
+    fn check(name: &str, text: Option<&str>, max: usize) -> Result<usize, Error> {
+        let length = text.map_or(0, |text| text.chars().count());
+
+        if length > max {
+            Error::err(format!("Embed {} can not be longer than {} characters", name, max))
+        } else {
+            Ok(length)
+        }
+    }
+
+    let fields = embed.fields.as_deref().unwrap_or_default();
+    if fields.len() > MAX_EMBED_FIELDS {
+        return Error::err(format!("Embed can not have more than {} fields", MAX_EMBED_FIELDS));
+    }
+
+    let mut length = check("title", embed.title.as_deref(), 256)?
+        + check("description", embed.description.as_deref(), 4096)?
+        + check("footer", embed.footer.as_ref().map(|f| f.text.as_str()), 2048)?
+        + check("author name", embed.author.as_ref().and_then(|a| a.name.as_deref()), 256)?;
+
+    for field in fields {
+        length += check("field name", Some(&field.name), 256)?;
+        length += check("field value", Some(&field.value), 1024)?;
+    }
+
+    Ok(length)
+}
+
+/// Builds an embed of a [MessageBuilder](automate::http::MessageBuilder).
+#[derive(Debug, Clone, Default)]
+pub struct EmbedBuilder(Embed);
+
+impl EmbedBuilder {
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.0.title = Some(title.into());
+        self
+    }
+
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.0.description = Some(description.into());
+        self
+    }
+
+    pub fn url<S: Into<String>>(mut self, url: S) -> Self {
+        self.0.url = Some(url.into());
+        self
+    }
+
+    /// Color of the left border as `0xRRGGBB`.
+    pub fn color(mut self, color: i32) -> Self {
+        self.0.color = Some(color);
+        self
+    }
+
+    /// Time displayed in the footer in ISO8601 format.
+    pub fn timestamp<S: Into<String>>(mut self, timestamp: S) -> Self {
+        self.0.timestamp = Some(timestamp.into());
+        self
+    }
+
+    pub fn field<N: Into<String>, V: Into<String>>(mut self, name: N, value: V, inline: bool) -> Self {
+        self.0.fields.get_or_insert_with(Vec::new).push(EmbedField {
+            name: name.into(),
+            value: value.into(),
+            inline: Some(inline),
+        });
+
+        self
+    }
+
+    pub fn footer<S: Into<String>>(mut self, text: S) -> Self {
+        self.0.footer = Some(EmbedFooter {
+            text: text.into(),
+            icon_url: None,
+            proxy_icon_url: None,
+        });
+
+        self
+    }
+
+    pub fn author<S: Into<String>>(mut self, name: S) -> Self {
+        self.0.author = Some(EmbedAuthor {
+            name: Some(name.into()),
+            url: None,
+            icon_url: None,
+            proxy_icon_url: None,
+        });
+
+        self
+    }
+
+    pub fn image<S: Into<String>>(mut self, url: S) -> Self {
+        self.0.image = Some(EmbedImage {
+            url: Some(url.into()),
+            proxy_url: None,
+            height: None,
+            width: None,
+        });
+
+        self
+    }
+
+    pub fn thumbnail<S: Into<String>>(mut self, url: S) -> Self {
+        self.0.thumbnail = Some(EmbedThumbnail {
+            url: Some(url.into()),
+            proxy_url: None,
+            height: None,
+            width: None,
+        });
+
+        self
+    }
+}
+
+/// Builds a row of components of a [MessageBuilder](automate::http::MessageBuilder).
+#[derive(Debug, Clone, Default)]
+pub struct RowBuilder(Vec<Component>);
+
+impl RowBuilder {
+    pub fn component(mut self, component: Component) -> Self {
+        self.0.push(component);
+        self
+    }
+
+    /// See [Component::button](automate::gateway::Component::button).
+    pub fn button<S: Into<String>, L: Into<String>>(self, custom_id: S, label: L) -> Self {
+        self.component(Component::button(custom_id, label))
+    }
+
+    /// See [Component::link_button](automate::gateway::Component::link_button).
+    pub fn link_button<L: Into<String>, U: Into<String>>(self, label: L, url: U) -> Self {
+        self.component(Component::link_button(label, url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_limits() {
+        assert!(MessageBuilder::new().build().is_err());
+        assert!(MessageBuilder::new().content("a".repeat(2001)).build().is_err());
+        assert!(MessageBuilder::new().embed(|e| e.title("a".repeat(257))).build().is_err());
+        assert!(MessageBuilder::new().content("hi").component_row(|r| r).build().is_err());
+
+        let mut embeds = MessageBuilder::new();
+        for _ in 0..2 {
+            embeds = embeds.embed(|e| e.description("a".repeat(3001)));
+        }
+        assert!(embeds.build().is_err());
+
+        let message = MessageBuilder::new()
+            .embed(|e| e.title("title").field("name", "value", false))
+            .component_row(|r| r.button("id", "label"))
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["embeds"][0]["fields"][0]["name"], "name");
+        assert_eq!(json["components"][0]["components"][0]["style"], 1);
+        assert!(json.get("files").is_none());
+
+        assert!(MessageBuilder::new().file("a.txt", "a").build_modification().is_err());
+
+        let modification = MessageBuilder::new().content("edited").build_modification().unwrap();
+        let json = serde_json::to_value(&modification).unwrap();
+        assert_eq!(json["content"], "edited");
+        assert_eq!(json["embeds"], serde_json::json!([]));
+    }
+}
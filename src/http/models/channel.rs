@@ -1,5 +1,5 @@
-use crate::gateway::{Overwrite, OverwriteType, ChannelType};
-use crate::Snowflake;
+use crate::gateway::{Overwrite, OverwriteType, ChannelType, InviteTargetType, ThreadChannel, ThreadMember, VideoQualityMode, GuildChannel};
+use crate::{Error, Identifiable, Snowflake};
 
 #[object(client)]
 pub struct NewChannel {
@@ -15,23 +15,106 @@ pub struct NewChannel {
     pub rate_limit_per_user: Option<i32>,
 }
 
-#[object(client)]
+/// Fields left to `None` are not modified, the
+/// `Option<Option<T>>` fields are removed when
+/// set to `Some(None)`.
+#[object(client, default)]
 pub struct ModifyChannel {
     pub name: Option<String>,
     pub position: Option<i32>,
-    pub topic: Option<String>,
+    pub topic: Option<Option<String>>,
     pub nsfw: Option<bool>,
     pub rate_limit_per_user: Option<i32>,
     pub bitrate: Option<i32>,
     pub user_limit: Option<i32>,
     pub permission_overwrites: Option<Vec<Overwrite>>,
-    pub parent_id: Option<Snowflake>,
+    pub parent_id: Option<Option<Snowflake>>,
+    /// Region of a voice or stage channel,
+    /// `Some(None)` sets it back to automatic.
+    pub rtc_region: Option<Option<String>>,
+    pub video_quality_mode: Option<VideoQualityMode>,
 }
 
-#[object(client)]
+/// Fields left to `None` are not modified, the channel is
+/// moved out of its category when `parent_id` is `Some(None)`.
+///
+/// Positions are only compared between channels of the same
+/// kind in the same category: text channels are always above
+/// voice channels and ties are broken by the channel id. See
+/// [MoveChannel::reorder](automate::http::MoveChannel::reorder)
+/// and [Context::reorder_channels](automate::Context::reorder_channels).
+#[object(client, default)]
 pub struct MoveChannel {
     pub id: u64,
-    pub position: i32
+    pub position: i32,
+    pub parent_id: Option<Option<Snowflake>>,
+    /// Syncs the permission overwrites of the
+    /// channel with its new category.
+    pub lock_permissions: Option<bool>,
+}
+
+impl MoveChannel {
+    /// Computes the moves placing the channels in the given
+    /// order. The channels of the plan must be in the same
+    /// category and of the same kind, the other channels of
+    /// this kind in the category keep their order below the
+    /// channels of the plan.
+    ///
+    /// Only the channels whose position changes are moved
+    /// and no move is returned if the channels are already
+    /// in order.
+    pub fn reorder(channels: &[GuildChannel], plan: &[Snowflake]) -> Result<Vec<MoveChannel>, Error> {
+        let mut planned = Vec::with_capacity(plan.len());
+        for id in plan {
+            match channels.iter().find(|c| c.id() == *id) {
+                Some(channel) if channel.position().is_some() => planned.push(channel),
+                _ => return Error::err(format!("Channel {} is not a channel of the guild that can be moved", id)),
+            }
+        }
+
+        let first = match planned.first() {
+            Some(first) => *first,
+            None => return Ok(Vec::new()),
+        };
+
+        if planned.iter().any(|c| c.parent_id() != first.parent_id() || sorting_group(c) != sorting_group(first)) {
+            return Error::err("Channels of the plan must be in the same category and of the same kind");
+        }
+
+        let mut siblings: Vec<&GuildChannel> = channels.iter()
+            .filter(|c| c.position().is_some() && c.parent_id() == first.parent_id() && sorting_group(c) == sorting_group(first))
+            .collect();
+
+        siblings.sort_by_key(|c| (c.position(), c.id()));
+
+        let others = siblings.iter().filter(|c| !plan.contains(&c.id())).copied();
+        let order: Vec<&GuildChannel> = planned.iter().copied().chain(others).collect();
+
+        if order.iter().map(|c| c.id()).eq(siblings.iter().map(|c| c.id())) {
+            return Ok(Vec::new());
+        }
+
+        Ok(order.into_iter()
+            .enumerate()
+            .filter(|(position, channel)| channel.position() != Some(*position as i32))
+            .map(|(position, channel)| MoveChannel {
+                id: channel.id().0,
+                position: position as i32,
+                ..MoveChannel::default()
+            })
+            .collect())
+    }
+}
+
+/// Channels are sorted separately in each group, text
+/// channels are above voice channels in a category and
+/// uncategorized channels are above the categories.
+pub(crate) fn sorting_group(channel: &GuildChannel) -> u8 {
+    match channel {
+        GuildChannel::Voice(_) | GuildChannel::Stage(_) => 1,
+        GuildChannel::Category(_) => 2,
+        _ => 0,
+    }
 }
 
 pub enum MessagesPosition {
@@ -49,6 +132,44 @@ pub enum ReactionsPosition {
     After(Snowflake, i32)
 }
 
+/// See [HttpAPI::start_thread](automate::HttpAPI::start_thread)
+/// for documentation. The type is ignored when
+/// starting a thread from a message.
+#[object(client, default)]
+pub struct NewThread {
+    pub name: String,
+    /// Duration in minutes after which the thread will
+    /// stop showing in the channel list, can be
+    /// 60, 1440, 4320 or 10080.
+    pub auto_archive_duration: Option<i32>,
+    #[serde(rename = "type")]
+    pub _type: Option<ChannelType>,
+    /// Whether non-moderators can add other
+    /// non-moderators to a private thread.
+    pub invitable: Option<bool>,
+    pub rate_limit_per_user: Option<i32>,
+}
+
+/// Threads returned by the active and archived threads
+/// endpoints along with the thread member object of the
+/// bot for each thread it joined.
+#[object(server)]
+pub struct ThreadList {
+    pub threads: Vec<ThreadChannel>,
+    pub members: Vec<ThreadMember>,
+    /// Whether there are more archived threads
+    /// to request, not sent for active threads.
+    pub has_more: Option<bool>,
+}
+
+/// Archived threads to request, the `Before`
+/// variant takes an ISO8601 timestamp.
+pub enum ArchivedThreadsPosition {
+    Default,
+    Limit(i32),
+    Before(String, i32),
+}
+
 #[object(client)]
 pub struct NewOverwrite {
     pub _type: OverwriteType,
@@ -56,10 +177,91 @@ pub struct NewOverwrite {
     pub deny: u32
 }
 
-#[object(client)]
+/// An invite to a channel, voice channel invites can
+/// open a stream or an activity once the user joined.
+///
+/// ```
+/// use automate::http::NewInvite;
+/// use automate::Snowflake;
+///
+/// //Watch Together
+/// let invite = NewInvite::activity(Snowflake(880218394199220334));
+/// ```
+#[object(client, default)]
 pub struct NewInvite {
     pub max_age: i32,
     pub max_uses: i32,
     pub temporary: bool,
-    pub unique: bool
-}
\ No newline at end of file
+    pub unique: bool,
+    pub target_type: Option<InviteTargetType>,
+    pub target_user_id: Option<Snowflake>,
+    pub target_application_id: Option<Snowflake>,
+}
+
+impl NewInvite {
+    /// Invite to watch the stream of the given user
+    /// who must be streaming in the voice channel.
+    pub fn stream(user: Snowflake) -> NewInvite {
+        NewInvite {
+            max_age: 86400,
+            target_type: Some(InviteTargetType::Stream),
+            target_user_id: Some(user),
+            ..Default::default()
+        }
+    }
+
+    /// Invite to an activity of the voice channel, the
+    /// application must have the `EMBEDDED` flag.
+    pub fn activity(application: Snowflake) -> NewInvite {
+        NewInvite {
+            max_age: 86400,
+            target_type: Some(InviteTargetType::EmbeddedApplication),
+            target_application_id: Some(application),
+            ..Default::default()
+        }
+    }
+}
+
+#[object(client)]
+pub struct FollowChannel {
+    pub webhook_channel_id: Snowflake,
+}
+
+/// The webhook created in the target channel when
+/// following an announcement channel.
+#[object(server)]
+pub struct FollowedChannel {
+    /// The announcement channel that was followed.
+    pub channel_id: Snowflake,
+    pub webhook_id: Snowflake,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::ChannelType;
+    use crate::testing::fake_channel;
+
+    #[test]
+    fn reorders_channels() {
+        let channels = vec![
+            fake_channel(10, 1, ChannelType::GuildCategory, None, 0),
+            fake_channel(20, 1, ChannelType::GuildText, Some(10), 0),
+            fake_channel(21, 1, ChannelType::GuildText, Some(10), 1),
+            fake_channel(22, 1, ChannelType::GuildText, Some(10), 2),
+            fake_channel(23, 1, ChannelType::GuildVoice, Some(10), 0),
+        ];
+
+        let ids = |moves: Vec<MoveChannel>| moves.iter().map(|m| (m.id, m.position)).collect::<Vec<_>>();
+
+        assert!(MoveChannel::reorder(&channels, &[Snowflake(20), Snowflake(21)]).unwrap().is_empty());
+        assert_eq!(ids(MoveChannel::reorder(&channels, &[Snowflake(22)]).unwrap()), [(22, 0), (20, 1), (21, 2)]);
+        assert_eq!(ids(MoveChannel::reorder(&channels, &[Snowflake(21), Snowflake(20)]).unwrap()), [(21, 0), (20, 1)]);
+
+        assert!(MoveChannel::reorder(&channels, &[Snowflake(20), Snowflake(23)]).is_err());
+        assert!(MoveChannel::reorder(&channels, &[Snowflake(99)]).is_err());
+
+        let json = serde_json::to_value(&MoveChannel::reorder(&channels, &[Snowflake(22)]).unwrap()[0]).unwrap();
+        assert_eq!(json, serde_json::json!({"id": 22, "position": 0}));
+    }
+}
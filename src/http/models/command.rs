@@ -0,0 +1,77 @@
+use crate::gateway::{ApplicationCommandOption, ApplicationCommandType};
+use std::collections::HashMap;
+
+/// Creates or overwrites an application command.
+///
+/// The localization maps associate a Discord locale
+/// (such as `fr` or `en-US`) to the translated value.
+///
+/// ```
+/// use automate::http::NewApplicationCommand;
+///
+/// let ping = NewApplicationCommand::chat_input("ping", "Replies with pong");
+/// let profile = NewApplicationCommand::user("Show profile");
+/// let report = NewApplicationCommand::message("Report message");
+/// ```
+#[object(client, default)]
+pub struct NewApplicationCommand {
+    #[serde(rename = "type")]
+    pub _type: Option<ApplicationCommandType>,
+    pub name: String,
+    pub name_localizations: Option<HashMap<String, String>>,
+    pub description: String,
+    pub description_localizations: Option<HashMap<String, String>>,
+    pub options: Option<Vec<ApplicationCommandOption>>,
+    pub default_permission: Option<bool>,
+}
+
+impl NewApplicationCommand {
+    /// A slash command typed in the message box.
+    pub fn chat_input<N: Into<String>, D: Into<String>>(name: N, description: D) -> NewApplicationCommand {
+        NewApplicationCommand {
+            _type: Some(ApplicationCommandType::ChatInput),
+            name: name.into(),
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A command shown in the context menu of a user,
+    /// which name can contain spaces and capital letters.
+    pub fn user<N: Into<String>>(name: N) -> NewApplicationCommand {
+        NewApplicationCommand {
+            _type: Some(ApplicationCommandType::User),
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A command shown in the context menu of a message,
+    /// which name can contain spaces and capital letters.
+    pub fn message<N: Into<String>>(name: N) -> NewApplicationCommand {
+        NewApplicationCommand {
+            _type: Some(ApplicationCommandType::Message),
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn option(mut self, option: ApplicationCommandOption) -> Self {
+        self.options.get_or_insert_with(Vec::new).push(option);
+        self
+    }
+
+    /// Adds a translation of the name of the
+    /// command for the given locale.
+    pub fn localize_name<L: Into<String>, S: Into<String>>(mut self, locale: L, name: S) -> Self {
+        self.name_localizations.get_or_insert_with(HashMap::new).insert(locale.into(), name.into());
+        self
+    }
+
+    /// Adds a translation of the description of
+    /// the command for the given locale.
+    pub fn localize_description<L: Into<String>, S: Into<String>>(mut self, locale: L, description: S) -> Self {
+        self.description_localizations.get_or_insert_with(HashMap::new).insert(locale.into(), description.into());
+        self
+    }
+}
@@ -1,12 +1,15 @@
-use crate::Snowflake;
-use crate::gateway::{VerificationLevel, MessageNotificationLevel, ExplicitContentFilterLevel, User};
-use crate::http::NewChannel;
+use crate::{Error, Snowflake};
+use std::path::Path;
+use crate::gateway::{VerificationLevel, MessageNotificationLevel, ExplicitContentFilterLevel, User, AuditLogEvent};
+use crate::gateway::{ScheduledEventPrivacyLevel, ScheduledEventStatus, ScheduledEventEntityType, ScheduledEventEntityMetadata};
+use crate::gateway::{AutoModerationEventType, AutoModerationTriggerType, AutoModerationTriggerMetadata, AutoModerationAction};
+use crate::http::{NewChannel, ImageData};
 
 #[object(client)]
 pub struct NewGuild {
     pub name: String,
     pub region: String,
-    pub icon: String,
+    pub icon: Option<ImageData>,
     pub verification_level: VerificationLevel,
     pub default_message_notifications: MessageNotificationLevel,
     pub explicit_content_filter: ExplicitContentFilterLevel,
@@ -14,20 +17,24 @@ pub struct NewGuild {
     pub channels: Vec<NewChannel>,
 }
 
+/// Fields left to `None` are not modified, the
+/// `Option<Option<T>>` fields are removed when
+/// set to `Some(None)`.
 #[object(client, default)]
 pub struct ModifyGuild {
     pub name: Option<String>,
-    pub region: Option<String>,
-    pub icon: Option<String>,
+    #[deprecated(note = "Voice regions are set per channel, see `ModifyChannel::rtc_region`")]
+    pub region: Option<Option<String>>,
+    pub icon: Option<Option<ImageData>>,
     pub verification_level: Option<VerificationLevel>,
     pub default_message_notifications: Option<MessageNotificationLevel>,
     pub explicit_content_filter: Option<ExplicitContentFilterLevel>,
-    pub afk_channel_id: Option<Snowflake>,
+    pub afk_channel_id: Option<Option<Snowflake>>,
     pub afk_timeout: Option<i32>,
     pub owner_id: Option<Snowflake>,
-    pub splash: Option<String>,
-    pub banner: Option<String>,
-    pub system_channel_id: Option<Snowflake>,
+    pub splash: Option<Option<ImageData>>,
+    pub banner: Option<Option<ImageData>>,
+    pub system_channel_id: Option<Option<Snowflake>>,
 }
 
 #[object(client)]
@@ -39,8 +46,15 @@ pub struct NewRole {
     pub permissions: u32,
     pub managed: bool,
     pub mentionable: bool,
+    /// Requires the `ROLE_ICONS` feature on the guild.
+    pub icon: Option<ImageData>,
+    /// Requires the `ROLE_ICONS` feature on the guild.
+    pub unicode_emoji: Option<String>,
 }
 
+/// Fields left to `None` are not modified, the
+/// `Option<Option<T>>` fields are removed when
+/// set to `Some(None)`.
 #[object(client)]
 pub struct ModifyRole {
     pub name: Option<String>,
@@ -48,6 +62,8 @@ pub struct ModifyRole {
     pub hoist: Option<bool>,
     pub permissions: Option<u32>,
     pub mentionable: Option<bool>,
+    pub icon: Option<Option<ImageData>>,
+    pub unicode_emoji: Option<Option<String>>,
 }
 
 #[object(client)]
@@ -59,29 +75,178 @@ pub struct MoveRole {
 #[object(client)]
 pub struct NewEmoji {
     pub name: String,
-    pub image: String,
+    pub image: ImageData,
     pub roles: Vec<Snowflake>,
 }
 
+/// Maximum size in bytes of the image of an emoji.
+pub const MAX_EMOJI_SIZE: usize = 256 * 1024;
+
+impl NewEmoji {
+    /// Creates an emoji available to everyone from
+    /// the content of an image file.
+    ///
+    /// Fails if the image is larger than 256KB unless
+    /// the `image` feature is enabled in which case
+    /// the image is shrunk to fit.
+    pub fn from_bytes<S: Into<String>, B: Into<Vec<u8>>>(name: S, bytes: B) -> Result<NewEmoji, Error> {
+        #[allow(unused_mut)]
+        let mut image = ImageData::from_bytes(bytes)?;
+
+        #[cfg(feature = "image")]
+        {
+            image = image.downsize(MAX_EMOJI_SIZE)?;
+        }
+
+        if image.len() > MAX_EMOJI_SIZE {
+            return Error::err(format!("Emoji image is {} bytes, larger than the {} bytes allowed", image.len(), MAX_EMOJI_SIZE));
+        }
+
+        Ok(NewEmoji {
+            name: name.into(),
+            image,
+            roles: Vec::new(),
+        })
+    }
+
+    /// Creates an emoji available to everyone from
+    /// the given image file, see [from_bytes](automate::http::NewEmoji::from_bytes).
+    pub fn from_file<S: Into<String>, P: AsRef<Path>>(name: S, path: P) -> Result<NewEmoji, Error> {
+        NewEmoji::from_bytes(name, std::fs::read(path)?)
+    }
+}
+
 #[object(client)]
 pub struct ModifyEmoji {
     pub image: String,
     pub roles: Vec<Snowflake>,
 }
 
+/// See [HttpAPI::create_scheduled_event](automate::HttpAPI::create_scheduled_event).
+/// The channel is required for stage and voice events
+/// while the location and the end time are required
+/// for external events.
+#[object(client)]
+pub struct NewScheduledEvent {
+    pub channel_id: Option<Snowflake>,
+    pub entity_metadata: Option<ScheduledEventEntityMetadata>,
+    pub name: String,
+    pub privacy_level: ScheduledEventPrivacyLevel,
+    /// ISO8601 timestamp
+    pub scheduled_start_time: String,
+    /// ISO8601 timestamp
+    pub scheduled_end_time: Option<String>,
+    pub description: Option<String>,
+    pub entity_type: ScheduledEventEntityType,
+    pub image: Option<ImageData>,
+}
+
+/// Changing the status of the event to active
+/// starts it, changing it to completed or
+/// canceled ends it.
+#[object(client, default)]
+pub struct ModifyScheduledEvent {
+    pub channel_id: Option<Option<Snowflake>>,
+    pub entity_metadata: Option<Option<ScheduledEventEntityMetadata>>,
+    pub name: Option<String>,
+    pub privacy_level: Option<ScheduledEventPrivacyLevel>,
+    pub scheduled_start_time: Option<String>,
+    pub scheduled_end_time: Option<String>,
+    pub description: Option<Option<String>>,
+    pub entity_type: Option<ScheduledEventEntityType>,
+    pub status: Option<ScheduledEventStatus>,
+    pub image: Option<ImageData>,
+}
+
+pub enum ScheduledEventUsersPosition {
+    Default,
+    Limit(i32),
+    Before(Snowflake, i32),
+    After(Snowflake, i32)
+}
+
+/// See [HttpAPI::create_auto_moderation_rule](automate::HttpAPI::create_auto_moderation_rule).
+#[object(client)]
+pub struct NewAutoModerationRule {
+    pub name: String,
+    pub event_type: AutoModerationEventType,
+    pub trigger_type: AutoModerationTriggerType,
+    pub trigger_metadata: Option<AutoModerationTriggerMetadata>,
+    pub actions: Vec<AutoModerationAction>,
+    pub enabled: Option<bool>,
+    pub exempt_roles: Option<Vec<Snowflake>>,
+    pub exempt_channels: Option<Vec<Snowflake>>,
+}
+
+/// The trigger type of a rule can not be modified.
+#[object(client, default)]
+pub struct ModifyAutoModerationRule {
+    pub name: Option<String>,
+    pub event_type: Option<AutoModerationEventType>,
+    pub trigger_metadata: Option<AutoModerationTriggerMetadata>,
+    pub actions: Option<Vec<AutoModerationAction>>,
+    pub enabled: Option<bool>,
+    pub exempt_roles: Option<Vec<Snowflake>>,
+    pub exempt_channels: Option<Vec<Snowflake>>,
+}
+
+/// Filters the entries returned by
+/// [audit_logs](automate::http::HttpAPI::audit_logs).
+/// Entries are returned from the most recent
+/// to the oldest.
+#[derive(Clone, Default, Debug)]
+pub struct AuditLogFilter {
+    /// Only entries of actions made by this user.
+    pub user_id: Option<Snowflake>,
+    /// Only entries of this type of action.
+    pub action_type: Option<AuditLogEvent>,
+    /// Only entries older than this entry.
+    pub before: Option<Snowflake>,
+    /// Maximum amount of entries, between 1 and 100.
+    /// Defaults to 50.
+    pub limit: Option<u8>,
+}
+
 pub enum MemberFilter {
     Default,
     Limit(i32),
     After(Snowflake, i32),
 }
 
+/// Fields left to `None` are not modified, the
+/// nickname is removed and the member disconnected
+/// from its voice channel when set to `Some(None)`.
 #[object(client, default)]
 pub struct ModifyMember {
     pub nick: Option<Option<String>>,
     pub roles: Option<Vec<Snowflake>>,
     pub mute: Option<bool>,
     pub deaf: Option<bool>,
-    pub channel_id: Option<Option<u64>>,
+    pub channel_id: Option<Option<Snowflake>>,
+}
+
+pub enum BansPosition {
+    Default,
+    Limit(i32),
+    Before(Snowflake, i32),
+    After(Snowflake, i32)
+}
+
+/// See [HttpAPI::bulk_ban](automate::HttpAPI::bulk_ban).
+#[object(client, default)]
+pub struct BulkBan {
+    pub user_ids: Vec<Snowflake>,
+    /// Deletes the messages sent by the users in
+    /// the given amount of seconds, up to 7 days.
+    pub delete_message_seconds: Option<u32>,
+}
+
+#[object(server)]
+pub struct BulkBanResult {
+    pub banned_users: Vec<Snowflake>,
+    /// Users who could not be banned, for example
+    /// because they were already banned.
+    pub failed_users: Vec<Snowflake>,
 }
 
 #[object(client, default)]
@@ -90,9 +255,72 @@ pub struct NewBan {
     pub delete_message_days: Option<i8>,
 }
 
+/// Result of a prune, the count is only
+/// returned when it was requested.
 #[object(server)]
 pub struct Prune {
-    pub pruned: i32,
+    pub pruned: Option<i32>,
+}
+
+/// Which members are removed by a prune.
+///
+/// Members are pruned when they have not been seen for the
+/// given number of days and have no role, or only roles
+/// among `include_roles`.
+///
+/// ```
+/// use automate::http::PruneOptions;
+/// use automate::Snowflake;
+///
+/// let options = PruneOptions::new(30)
+///     .include_roles(vec![Snowflake(1234)])
+///     .compute_prune_count(false);
+/// ```
+#[object(client)]
+pub struct PruneOptions {
+    pub days: u8,
+    /// Counting the pruned members is discouraged
+    /// for large guilds.
+    pub compute_prune_count: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_roles: Vec<Snowflake>,
+}
+
+impl PruneOptions {
+    /// Prunes the members inactive for the given number of
+    /// days, between 1 and 30, and returns how many were
+    /// pruned.
+    pub fn new(days: u8) -> PruneOptions {
+        PruneOptions {
+            days,
+            compute_prune_count: true,
+            include_roles: Vec::new(),
+        }
+    }
+
+    /// Also prunes members with these roles.
+    pub fn include_roles(mut self, roles: Vec<Snowflake>) -> Self {
+        self.include_roles = roles;
+        self
+    }
+
+    /// Whether the number of pruned members is returned.
+    pub fn compute_prune_count(mut self, compute: bool) -> Self {
+        self.compute_prune_count = compute;
+        self
+    }
+
+    pub(crate) fn query(&self) -> String {
+        let mut query = format!("?days={}", self.days);
+
+        if !self.include_roles.is_empty() {
+            let roles: Vec<String> = self.include_roles.iter().map(ToString::to_string).collect();
+            query.push_str("&include_roles=");
+            query.push_str(&roles.join(","));
+        }
+
+        query
+    }
 }
 
 #[object(server)]
@@ -122,3 +350,18 @@ pub struct GuildEmbed {
     pub enabled: bool,
     pub channel_id: Option<Snowflake>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_emoji_size() {
+        let mut png = vec![0x89, b'P', b'N', b'G'];
+        png.resize(1024, 0);
+        assert_eq!(NewEmoji::from_bytes("small", png.clone()).unwrap().image.len(), 1024);
+
+        png.resize(MAX_EMOJI_SIZE + 1, 0);
+        assert!(NewEmoji::from_bytes("large", png).is_err());
+    }
+}
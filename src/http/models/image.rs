@@ -0,0 +1,156 @@
+use serde::{Serialize, Serializer};
+use std::fmt::{self, Debug, Formatter};
+use std::path::Path;
+use crate::Error;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// An image sent to Discord, serialized to the
+/// `data:image/png;base64,...` form expected by
+/// the endpoints taking images such as
+/// [HttpAPI::create_emoji](automate::HttpAPI::create_emoji).
+///
+/// Supports PNG, JPEG, GIF and WebP images.
+#[derive(Clone, PartialEq)]
+pub struct ImageData {
+    content_type: &'static str,
+    data: Vec<u8>,
+}
+
+impl ImageData {
+    /// Creates the image from the content of an image
+    /// file, fails if the format is not supported.
+    pub fn from_bytes<B: Into<Vec<u8>>>(bytes: B) -> Result<ImageData, Error> {
+        let data = bytes.into();
+
+        let content_type = match data.as_slice() {
+            [0x89, b'P', b'N', b'G', ..] => "image/png",
+            [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+            [b'G', b'I', b'F', b'8', ..] => "image/gif",
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+            _ => return Error::err("Unsupported image format, expected a PNG, JPEG, GIF or WebP image"),
+        };
+
+        Ok(ImageData {
+            content_type,
+            data,
+        })
+    }
+
+    /// Reads the image from the given file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ImageData, Error> {
+        ImageData::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn content_type(&self) -> &str {
+        self.content_type
+    }
+
+    /// Size of the image in bytes, before
+    /// being encoded in base64.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Shrinks the image until it is no larger than
+    /// `max` bytes. The image is re-encoded as a PNG,
+    /// which drops the animation of GIF images.
+    ///
+    /// Images already small enough are left untouched.
+    #[cfg(feature = "image")]
+    pub fn downsize(self, max: usize) -> Result<ImageData, Error> {
+        use image::imageops::FilterType;
+        use image::{GenericImageView, ImageOutputFormat};
+        use std::io::Cursor;
+
+        if self.data.len() <= max {
+            return Ok(self);
+        }
+
+        let image = image::load_from_memory(&self.data)?;
+        let (width, height) = image.dimensions();
+        let mut side = width.max(height).min(128);
+
+        while side >= 16 {
+            let mut data = Vec::new();
+            image.resize(side, side, FilterType::Lanczos3)
+                .write_to(&mut Cursor::new(&mut data), ImageOutputFormat::Png)?;
+
+            if data.len() <= max {
+                return Ok(ImageData {
+                    content_type: "image/png",
+                    data,
+                });
+            }
+
+            side /= 2;
+        }
+
+        Error::err(format!("Failed to shrink the image under {} bytes", max))
+    }
+
+    pub fn to_data_uri(&self) -> String {
+        format!("data:{};base64,{}", self.content_type, base64(&self.data))
+    }
+}
+
+impl Debug for ImageData {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "ImageData({}, {} bytes)", self.content_type, self.data.len())
+    }
+}
+
+impl Serialize for ImageData {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_str(&self.to_data_uri())
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    let mut output = String::with_capacity((data.len() + 2) / 3 * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(BASE64_ALPHABET[(group >> (18 - i * 6)) as usize & 0x3F] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn detects_content_type() {
+        let png = ImageData::from_bytes(vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A]).unwrap();
+        assert_eq!(png.content_type(), "image/png");
+        assert_eq!(png.to_data_uri(), "data:image/png;base64,iVBORw0K");
+
+        let webp = ImageData::from_bytes(&b"RIFF\0\0\0\0WEBPVP8 "[..]).unwrap();
+        assert_eq!(webp.content_type(), "image/webp");
+
+        assert!(ImageData::from_bytes(&b"not an image"[..]).is_err());
+    }
+}
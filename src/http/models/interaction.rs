@@ -0,0 +1,196 @@
+use crate::gateway::{Embed, Component, ApplicationCommandOptionChoice};
+use crate::http::AllowedMentions;
+
+/// Only the user who triggered the interaction
+/// can see the message.
+pub const EPHEMERAL: u32 = 1 << 6;
+
+/// The response to an interaction.
+///
+/// ```
+/// use automate::http::InteractionResponse;
+///
+/// let response = InteractionResponse::message("Only you can see this").ephemeral();
+/// let deferred = InteractionResponse::deferred().ephemeral();
+/// ```
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-response-object)
+#[object(client)]
+pub struct InteractionResponse {
+    #[serde(rename = "type")]
+    pub _type: InteractionCallbackType,
+    pub data: Option<InteractionCallbackData>,
+}
+
+/// The content of the response, which fields
+/// are used depends on the type of the response.
+#[object(client, default)]
+pub struct InteractionCallbackData {
+    pub tts: Option<bool>,
+    pub content: Option<String>,
+    pub embeds: Option<Vec<Embed>>,
+    pub allowed_mentions: Option<AllowedMentions>,
+    pub flags: Option<u32>,
+    pub components: Option<Vec<Component>>,
+    /// The suggestions of an autocomplete response.
+    pub choices: Option<Vec<ApplicationCommandOptionChoice>>,
+    /// The custom id of a modal.
+    pub custom_id: Option<String>,
+    /// The title of a modal.
+    pub title: Option<String>,
+}
+
+#[convert(u8)]
+pub enum InteractionCallbackType {
+    Pong = 1,
+    ChannelMessageWithSource = 4,
+    DeferredChannelMessageWithSource = 5,
+    DeferredUpdateMessage = 6,
+    UpdateMessage = 7,
+    ApplicationCommandAutocompleteResult = 8,
+    Modal = 9,
+}
+
+impl InteractionResponse {
+    fn with_data(_type: InteractionCallbackType, data: InteractionCallbackData) -> InteractionResponse {
+        InteractionResponse {
+            _type,
+            data: Some(data),
+        }
+    }
+
+    /// Responds with a message.
+    pub fn message<S: Into<String>>(content: S) -> InteractionResponse {
+        InteractionResponse::with_data(InteractionCallbackType::ChannelMessageWithSource, InteractionCallbackData {
+            content: Some(content.into()),
+            ..Default::default()
+        })
+    }
+
+    /// Shows a loading state to the user, the message
+    /// must then be sent by editing the original response
+    /// within 15 minutes.
+    pub fn deferred() -> InteractionResponse {
+        InteractionResponse::with_data(InteractionCallbackType::DeferredChannelMessageWithSource, InteractionCallbackData::default())
+    }
+
+    /// Acknowledges a component interaction, the message
+    /// the component is attached to can be edited later.
+    pub fn deferred_update() -> InteractionResponse {
+        InteractionResponse {
+            _type: InteractionCallbackType::DeferredUpdateMessage,
+            data: None,
+        }
+    }
+
+    /// Edits the message the component is attached to.
+    pub fn update<S: Into<String>>(content: S) -> InteractionResponse {
+        InteractionResponse::with_data(InteractionCallbackType::UpdateMessage, InteractionCallbackData {
+            content: Some(content.into()),
+            ..Default::default()
+        })
+    }
+
+    /// Suggests values for the focused option
+    /// of an autocomplete interaction.
+    pub fn autocomplete(choices: Vec<ApplicationCommandOptionChoice>) -> InteractionResponse {
+        InteractionResponse::with_data(InteractionCallbackType::ApplicationCommandAutocompleteResult, InteractionCallbackData {
+            choices: Some(choices),
+            ..Default::default()
+        })
+    }
+
+    /// Opens a popup form containing text inputs, each
+    /// row must contain a single text input.
+    pub fn modal<S: Into<String>, T: Into<String>>(custom_id: S, title: T, inputs: Vec<Component>) -> InteractionResponse {
+        InteractionResponse::with_data(InteractionCallbackType::Modal, InteractionCallbackData {
+            custom_id: Some(custom_id.into()),
+            title: Some(title.into()),
+            components: Some(inputs.into_iter().map(|input| Component::action_row(vec![input])).collect()),
+            ..Default::default()
+        })
+    }
+
+    /// Only the user who triggered the interaction
+    /// will see the response.
+    pub fn ephemeral(mut self) -> Self {
+        let data = self.data.get_or_insert_with(InteractionCallbackData::default);
+        data.flags = Some(data.flags.unwrap_or(0) | EPHEMERAL);
+        self
+    }
+
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.data.get_or_insert_with(InteractionCallbackData::default)
+            .embeds.get_or_insert_with(Vec::new)
+            .push(embed);
+
+        self
+    }
+
+    pub fn components(mut self, components: Vec<Component>) -> Self {
+        self.data.get_or_insert_with(InteractionCallbackData::default).components = Some(components);
+        self
+    }
+
+    pub fn allowed_mentions(mut self, allowed_mentions: AllowedMentions) -> Self {
+        self.data.get_or_insert_with(InteractionCallbackData::default).allowed_mentions = Some(allowed_mentions);
+        self
+    }
+}
+
+/// A message sent after the initial response
+/// to an interaction.
+#[object(client, default)]
+pub struct FollowupMessage {
+    pub content: Option<String>,
+    pub tts: Option<bool>,
+    pub embeds: Option<Vec<Embed>>,
+    pub allowed_mentions: Option<AllowedMentions>,
+    pub flags: Option<u32>,
+    pub components: Option<Vec<Component>>,
+}
+
+impl FollowupMessage {
+    pub fn new<S: Into<String>>(content: S) -> FollowupMessage {
+        FollowupMessage {
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Only the user who triggered the interaction
+    /// will see the message.
+    pub fn ephemeral(mut self) -> Self {
+        self.flags = Some(self.flags.unwrap_or(0) | EPHEMERAL);
+        self
+    }
+}
+
+/// Edits the original response or a followup message.
+#[object(client, default)]
+pub struct ModifyInteractionMessage {
+    pub content: Option<Option<String>>,
+    pub embeds: Option<Option<Vec<Embed>>>,
+    pub allowed_mentions: Option<AllowedMentions>,
+    pub components: Option<Option<Vec<Component>>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses() {
+        let response = serde_json::to_value(InteractionResponse::message("hello").ephemeral()).unwrap();
+        assert_eq!(response["type"], 4);
+        assert_eq!(response["data"]["flags"], 64);
+
+        let response = serde_json::to_value(InteractionResponse::modal("form", "Form", vec![Component::short_input("name", "Name")])).unwrap();
+        assert_eq!(response["type"], 9);
+        assert_eq!(response["data"]["components"][0]["type"], 1);
+        assert_eq!(response["data"]["components"][0]["components"][0]["type"], 4);
+
+        let response = serde_json::to_value(InteractionResponse::deferred_update()).unwrap();
+        assert_eq!(response["type"], 6);
+    }
+}
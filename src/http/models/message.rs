@@ -1,11 +1,11 @@
-use crate::gateway::Embed;
+use crate::gateway::{Embed, Message, MessageReference, Component};
 use crate::Snowflake;
 
 /// See [HttpApi::create_message](automate::HttpAPI::create_message)
 /// for documentation.
 ///
 /// You may create a message as a reply to another message. To do so,
-/// include a [`message_reference`](#DOCS_RESOURCES_CHANNEL/message-object-message-reference-structure)
+/// include a [`message_reference`](https://discord.com/developers/docs/resources/channel#message-object-message-reference-structure)
 /// with a `message_id`. This requires the `VIEW MESSAGE HISTORY`
 /// permission, and the referenced message must exist and cannot be
 /// a system message. The `channel_id` and `guild_id` in the
@@ -17,8 +17,39 @@ pub struct CreateMessage {
     pub tts: bool,
     pub embed: Option<Embed>,
     pub allowed_mentions: Option<AllowedMentions>,
-    pub message_reference: Option<Snowflake>,
+    pub message_reference: Option<MessageReference>,
     pub attachment: Option<CreateAttachment>,
+    pub embeds: Option<Vec<Embed>>,
+    pub components: Option<Vec<Component>>,
+    /// Files uploaded with the message in addition
+    /// to the [attachment](automate::http::CreateMessage::attachment).
+    #[serde(skip)]
+    pub files: Vec<CreateAttachment>,
+}
+
+impl CreateMessage {
+    /// Prevents the message from pinging anyone, including
+    /// `@everyone`, `@here` and the users and roles mentioned
+    /// in the content. Mentions are still displayed.
+    ///
+    /// Should be used when the content of the message
+    /// comes from users.
+    pub fn suppress_mentions(mut self) -> Self {
+        self.allowed_mentions = Some(AllowedMentions::none());
+        self
+    }
+
+    /// Sets the mentions that will ping users and roles.
+    pub fn allowed_mentions(mut self, allowed_mentions: AllowedMentions) -> Self {
+        self.allowed_mentions = Some(allowed_mentions);
+        self
+    }
+
+    /// Sends the message as a reply to the given message.
+    pub fn reply_to(mut self, message: &Message) -> Self {
+        self.message_reference = Some(MessageReference::to(message));
+        self
+    }
 }
 
 #[stringify(snake_case)]
@@ -44,6 +75,38 @@ pub struct AllowedMentions {
     pub replied_user: bool,
 }
 
+impl AllowedMentions {
+    /// Does not allow any mention to ping.
+    pub fn none() -> AllowedMentions {
+        AllowedMentions::default()
+    }
+
+    /// Only allows the given users to be pinged.
+    pub fn users(users: Vec<Snowflake>) -> AllowedMentions {
+        AllowedMentions {
+            users,
+            ..AllowedMentions::default()
+        }
+    }
+
+    /// Only allows the given roles to be pinged.
+    pub fn roles(roles: Vec<Snowflake>) -> AllowedMentions {
+        AllowedMentions {
+            roles,
+            ..AllowedMentions::default()
+        }
+    }
+
+    /// Allows all mentioned users and roles to be pinged
+    /// but not `@everyone` and `@here`.
+    pub fn all_but_everyone() -> AllowedMentions {
+        AllowedMentions {
+            parse: vec![AllowedMentionType::Users, AllowedMentionType::Roles],
+            ..AllowedMentions::default()
+        }
+    }
+}
+
 #[object(client, default)]
 pub struct CreateAttachment {
     pub name: String,
@@ -53,7 +116,24 @@ pub struct CreateAttachment {
 
 #[object(client, default)]
 pub struct ModifyMessage {
-    pub content: Option<String>,
-    pub embed: Option<Embed>,
-    pub flags: u32
+    pub content: Option<Option<String>>,
+    pub embed: Option<Option<Embed>>,
+    pub embeds: Option<Vec<Embed>>,
+    pub components: Option<Vec<Component>>,
+    pub flags: u32,
+    pub allowed_mentions: Option<AllowedMentions>,
+}
+
+impl ModifyMessage {
+    /// See [CreateMessage::suppress_mentions](automate::http::CreateMessage::suppress_mentions).
+    pub fn suppress_mentions(mut self) -> Self {
+        self.allowed_mentions = Some(AllowedMentions::none());
+        self
+    }
+
+    /// Sets the mentions that will ping users and roles.
+    pub fn allowed_mentions(mut self, allowed_mentions: AllowedMentions) -> Self {
+        self.allowed_mentions = Some(allowed_mentions);
+        self
+    }
 }
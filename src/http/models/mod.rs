@@ -1,9 +1,77 @@
+mod builder;
 mod channel;
+mod command;
 mod guild;
+mod image;
+mod interaction;
 mod message;
 mod user;
 
+pub use builder::*;
 pub use channel::*;
+pub use command::*;
 pub use guild::*;
+pub use image::*;
+pub use interaction::*;
 pub use message::*;
 pub use user::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Snowflake;
+
+    #[test]
+    fn nullable_modifications() {
+        let unchanged = ModifyMember::default();
+        assert_eq!(serde_json::to_string(&unchanged).unwrap(), "{}");
+
+        let cleared = ModifyMember {
+            nick: Some(None),
+            channel_id: Some(None),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_string(&cleared).unwrap(), r#"{"nick":null,"channel_id":null}"#);
+
+        let moved = ModifyChannel {
+            topic: Some(Some(String::from("topic"))),
+            parent_id: Some(None),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_string(&moved).unwrap(), r#"{"topic":"topic","parent_id":null}"#);
+
+        let event = ModifyScheduledEvent {
+            channel_id: Some(Some(Snowflake(1))),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_string(&event).unwrap(), r#"{"channel_id":1}"#);
+    }
+
+    #[test]
+    fn allowed_mention_types() {
+        let kinds = [
+            (AllowedMentionType::Roles, r#""roles""#),
+            (AllowedMentionType::Users, r#""users""#),
+            (AllowedMentionType::Everyone, r#""everyone""#),
+        ];
+
+        for (kind, expected) in kinds.iter() {
+            let json = serde_json::to_string(kind).unwrap();
+            assert_eq!(&json, expected);
+
+            let kind: AllowedMentionType = serde_json::from_str(&json).unwrap();
+            assert_eq!(&serde_json::to_string(&kind).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn prune_options() {
+        let options = PruneOptions::new(7);
+        assert_eq!(options.query(), "?days=7");
+        assert_eq!(serde_json::to_string(&options).unwrap(), r#"{"days":7,"compute_prune_count":true}"#);
+
+        let options = options.include_roles(vec![Snowflake(1), Snowflake(2)]).compute_prune_count(false);
+        assert_eq!(options.query(), "?days=7&include_roles=1,2");
+        assert_eq!(serde_json::to_string(&options).unwrap(), r#"{"days":7,"compute_prune_count":false,"include_roles":[1,2]}"#);
+    }
+}
@@ -1,9 +1,11 @@
 use crate::Snowflake;
+use crate::http::ImageData;
 
-#[object(client)]
+#[object(client, default)]
 pub struct ModifyBot {
-    pub username: String,
-    //pub avatar: Option<???>
+    pub username: Option<String>,
+    /// Removes the avatar when set to `Some(None)`.
+    pub avatar: Option<Option<ImageData>>,
 }
 
 #[object(client)]
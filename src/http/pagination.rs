@@ -0,0 +1,45 @@
+use crate::{Error, Snowflake};
+use std::collections::VecDeque;
+use std::future::Future;
+use futures::{stream, Stream};
+
+/// Creates a stream which yields the items of successive
+/// pages. `fetch` is called with the key and the cursor
+/// returned by the previous call (`None` for the first page)
+/// and returns a page along with the cursor of the next one,
+/// or `None` if it was the last page.
+///
+/// The stream ends after yielding the first error.
+pub(crate) fn paginate<'a, K, T, F, Fut>(key: Result<K, Error>, fetch: F) -> impl Stream<Item = Result<T, Error>> + 'a
+    where K: Copy + 'a,
+          T: 'a,
+          F: Fn(K, Option<Snowflake>) -> Fut + 'a,
+          Fut: Future<Output = Result<(Vec<T>, Option<Snowflake>), Error>> + 'a {
+    let (key, error) = match key {
+        Ok(key) => (Some(key), None),
+        Err(err) => (None, Some(err)),
+    };
+
+    //the cursor is `None` when there are no more pages to fetch
+    let state = (fetch, VecDeque::new(), Some(None), error);
+
+    stream::unfold(state, move |(fetch, mut items, mut cursor, mut error)| async move {
+        loop {
+            if let Some(err) = error.take() {
+                return Some((Err(err), (fetch, items, None, None)));
+            }
+
+            if let Some(item) = items.pop_front() {
+                return Some((Ok(item), (fetch, items, cursor, None)));
+            }
+
+            match fetch(key?, cursor.take()?).await {
+                Ok((page, next)) => {
+                    cursor = next.map(Some);
+                    items.extend(page);
+                }
+                Err(err) => error = Some(err)
+            }
+        }
+    })
+}
@@ -5,7 +5,11 @@ use std::collections::HashMap;
 use futures::lock::Mutex;
 use std::borrow::Cow;
 use crate::{Error, Snowflake};
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+/// Maximum amount of requests per second
+/// allowed by Discord across all the endpoints.
+pub const GLOBAL_LIMIT: u32 = 50;
 
 lazy_static::lazy_static! {
     /// Rate-limit buckets storage.
@@ -13,6 +17,10 @@ lazy_static::lazy_static! {
     /// major parameter to the bucket data provided
     /// by Discord.
     pub static ref BUCKETS: Mutex<HashMap<Key<'static>, Bucket>> = Mutex::default();
+
+    /// Global rate-limit of each bot token, shared
+    /// by all the endpoints.
+    pub static ref GLOBAL: Mutex<HashMap<String, GlobalLimit>> = Mutex::default();
 }
 
 /// Groups the API token, bucket id and major
@@ -98,6 +106,81 @@ impl Bucket {
     }
 }
 
+/// Token bucket refilled at the rate of the
+/// global rate-limit which can be paused when
+/// Discord reports the global limit was hit.
+pub struct GlobalLimit {
+    tokens: f64,
+    refilled: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl GlobalLimit {
+    fn new() -> GlobalLimit {
+        GlobalLimit {
+            tokens: GLOBAL_LIMIT as f64,
+            refilled: Instant::now(),
+            paused_until: None,
+        }
+    }
+
+    /// Takes a token if there is one available,
+    /// otherwise returns how long to wait before
+    /// trying again.
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return Err(until - now);
+            }
+
+            self.paused_until = None;
+        }
+
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * GLOBAL_LIMIT as f64).min(GLOBAL_LIMIT as f64);
+        self.refilled = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / GLOBAL_LIMIT as f64))
+        }
+    }
+}
+
+/// Waits until a request can be sent without
+/// exceeding the global rate-limit.
+pub async fn acquire_global(token: &str) {
+    loop {
+        let wait = {
+            let mut limits = GLOBAL.lock().await;
+
+            if !limits.contains_key(token) {
+                limits.insert(token.to_owned(), GlobalLimit::new());
+            }
+
+            match limits.get_mut(token).unwrap().take(Instant::now()) {
+                Ok(()) => return,
+                Err(wait) => wait,
+            }
+        };
+
+        trace!("Global rate-limit reached, waiting {}ms", wait.as_millis());
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Pauses all the requests made with the
+/// token for the given duration.
+pub async fn pause_global(token: &str, duration: Duration) {
+    let mut limits = GLOBAL.lock().await;
+    let limit = limits.entry(token.to_owned()).or_insert_with(GlobalLimit::new);
+
+    limit.paused_until = Some(Instant::now() + duration);
+    limit.tokens = 0.0;
+}
+
 /// Cleans up the bucket hashmap by removing every bucket
 /// that contains a date inferior to now.
 pub async fn collect_outdated_buckets() {
@@ -124,3 +207,34 @@ pub async fn collect_outdated_buckets() {
 
     trace!("Removed {} outdated rate-limit buckets in {}µs", removes, time);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_limit_refills() {
+        let start = Instant::now();
+        let mut limit = GlobalLimit::new();
+        limit.refilled = start;
+
+        for _ in 0..GLOBAL_LIMIT {
+            assert!(limit.take(start).is_ok());
+        }
+
+        assert_eq!(limit.take(start), Err(Duration::from_millis(20)));
+        assert!(limit.take(start + Duration::from_millis(25)).is_ok());
+        assert!(limit.take(start + Duration::from_millis(25)).is_err());
+    }
+
+    #[test]
+    fn global_limit_pauses() {
+        let start = Instant::now();
+        let mut limit = GlobalLimit::new();
+        limit.refilled = start;
+        limit.paused_until = Some(start + Duration::from_secs(2));
+
+        assert_eq!(limit.take(start + Duration::from_secs(1)), Err(Duration::from_secs(1)));
+        assert!(limit.take(start + Duration::from_secs(2)).is_ok());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{Error, Snowflake};
+use crate::http::HttpAPI;
+use crate::http::request::ApiRequest;
+use bytes::Bytes;
+use hyper::{Method, StatusCode};
+
+/// Splits a route into the key identifying its bucket
+/// and its major parameter, the id following `guilds`,
+/// `channels` or `webhooks`.
+fn route_key(method: &Method, route: &str) -> (String, Option<Snowflake>) {
+    let path = route.split('?').next().unwrap_or_default();
+    let mut key = method.to_string();
+    let mut major = None;
+    let mut previous = "";
+
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        key.push('/');
+
+        match segment.parse::<u64>() {
+            Ok(id) if major.is_none() && matches!(previous, "guilds" | "channels" | "webhooks") => {
+                major = Some(Snowflake(id));
+                key.push_str(":major");
+            }
+            Ok(_) => key.push_str(":id"),
+            Err(_) => key.push_str(segment),
+        }
+
+        previous = segment;
+    }
+
+    (key, major)
+}
+
+impl HttpAPI {
+    /// Sends a request to a route of the API that is not
+    /// yet available as a method, such as `/guilds/{id}/new-feature`.
+    /// The request is authenticated and goes through the
+    /// same rate-limits and retries as the other endpoints.
+    ///
+    /// Any status code is returned along with the body
+    /// of the response, except rate-limits which are
+    /// returned as errors.
+    pub async fn request_raw<B: Into<Bytes>>(&self, method: Method, route: &str, body: B) -> Result<(StatusCode, Bytes), Error> {
+        let (key, major) = route_key(&method, route);
+        let uri = format!("{}/{}", self.base_url, route.trim_start_matches('/'));
+
+        let response = self.send(ApiRequest {
+            #[cfg(feature = "metrics")]
+            endpoint: "raw",
+            route: &key,
+            major,
+            method,
+            uri,
+            content_type: "application/json",
+            body: body.into(),
+        }).await?;
+
+        let status = response.status();
+        Ok((status, hyper::body::to_bytes(response.into_body()).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_keys() {
+        let (key, major) = route_key(&Method::GET, "/guilds/41771983423143937/members/80351110224678912?limit=5");
+        assert_eq!(key, "GET/guilds/:major/members/:id");
+        assert_eq!(major, Some(Snowflake(41771983423143937)));
+
+        let (key, major) = route_key(&Method::POST, "applications/1/commands");
+        assert_eq!(key, "POST/applications/:id/commands");
+        assert_eq!(major, None);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{Error, Snowflake};
+use crate::http::HttpAPI;
+use crate::http::rate_limit::{self, Key, Bucket, BUCKETS};
+use bytes::Bytes;
+use chrono::{Duration as ChronoDuration, Utc};
+use futures::lock::Mutex;
+use hyper::{Body, Method, Response, StatusCode};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Default user agent for automate bots
+const USER_AGENT: &str = concat!("DiscordBot (https://github.com/mbenoukaiss/automate, ", env!("CARGO_PKG_VERSION"), ")");
+
+lazy_static::lazy_static! {
+    /// Bucket id of the routes, identified by the name
+    /// of their endpoint or by their method and their
+    /// path without the ids for raw routes.
+    static ref ROUTE_BUCKETS: Mutex<HashMap<String, String>> = Mutex::default();
+}
+
+/// A request sent by one of the endpoints or by
+/// [request_raw](automate::HttpAPI::request_raw).
+pub(crate) struct ApiRequest<'a> {
+    /// Name of the request in the metrics.
+    #[cfg(feature = "metrics")]
+    pub(crate) endpoint: &'a str,
+    /// Identifies the bucket of the route, also used
+    /// as the name of the route in the logs and errors.
+    pub(crate) route: &'a str,
+    pub(crate) major: Option<Snowflake>,
+    pub(crate) method: Method,
+    pub(crate) uri: String,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: Bytes,
+}
+
+impl HttpAPI {
+    /// Sends the request once its bucket and the global
+    /// rate-limit allow it, retries it according to the
+    /// retry policy and records its bucket.
+    ///
+    /// Responses with a 429 status are returned as
+    /// errors, any other status is left to the caller.
+    pub(crate) async fn send(&self, request: ApiRequest<'_>) -> Result<Response<Body>, Error> {
+        let bucket_id = ROUTE_BUCKETS.lock().await.get(request.route).cloned();
+        let mut bucket_state = None;
+
+        if let Some(bucket_id) = bucket_id {
+            if let Some(bucket) = BUCKETS.lock().await.get(&Key::lookup(&self.token, &bucket_id, request.major)) {
+                trace!("Endpoint {}#{} allows for {} more calls (limit {})", request.route, bucket.id, bucket.remaining, bucket.limit);
+
+                if self.tracing {
+                    bucket_state = Some(format!("bucket {} with {}/{} calls remaining", bucket.id, bucket.remaining, bucket.limit));
+                }
+
+                if bucket.remaining == 0 && Utc::now().naive_utc() < bucket.reset {
+                    #[cfg(feature = "metrics")]
+                    self.metrics.increment(crate::metrics::RATE_LIMIT_HITS_TOTAL, &[("endpoint", request.endpoint)]);
+
+                    return Error::rate_limited(request.route, bucket.reset, true);
+                }
+            }
+        }
+
+        // requests that could not reach discord are only
+        // sent again if sending them twice has no side effect
+        let idempotent = matches!(request.method, Method::GET | Method::PUT | Method::DELETE);
+        let mut attempt = 0;
+
+        let request_id = HttpAPI::next_request_id();
+        let started = Instant::now();
+
+        if self.tracing {
+            debug!(
+                "Request #{} {} {} (endpoint `{}`, {})",
+                request_id, request.method, request.uri, request.route,
+                bucket_state.as_deref().unwrap_or("unknown bucket")
+            );
+        }
+
+        let response = loop {
+            rate_limit::acquire_global(&self.token).await;
+
+            let mut builder = hyper::Request::builder()
+                .uri(request.uri.as_str())
+                .method(request.method.clone())
+                .header("Content-Type", request.content_type)
+                .header("Authorization", &self.token)
+                .header("User-Agent", USER_AGENT)
+                .header("X-RateLimit-Precision", "millisecond");
+
+            // hyper does not set content-length to 0 when the body is
+            // empty and method is POST, PUT or PATCH, but discord
+            // requires a content-length
+            if request.body.is_empty() && matches!(request.method, Method::POST | Method::PUT | Method::PATCH) {
+                builder = builder.header("Content-Length", 0);
+            }
+
+            let result = self.client.request(builder.body(Body::from(request.body.clone()))?).await;
+            let retry = match &result {
+                Ok(response) => response.status() == StatusCode::BAD_GATEWAY,
+                Err(_) => idempotent,
+            };
+
+            if retry && attempt < self.retry.max_retries {
+                let delay = self.retry.delay(attempt);
+                attempt += 1;
+
+                warn!("Endpoint {} failed, retrying in {}ms (attempt {})", request.route, delay.as_millis(), attempt);
+                tokio::time::sleep(delay).await;
+            } else {
+                break result?;
+            }
+        };
+
+        let bucket = Bucket::new(response.headers())?;
+        let reset = bucket.as_ref().map(|bucket| bucket.reset);
+
+        if let Some(bucket) = bucket {
+            ROUTE_BUCKETS.lock().await.insert(request.route.to_owned(), bucket.id.clone());
+            BUCKETS.lock().await.insert(Key::insert(self.token.clone(), bucket.id.clone(), request.major), bucket);
+        }
+
+        let status = response.status();
+
+        #[cfg(feature = "metrics")]
+        self.metrics.increment(crate::metrics::HTTP_REQUESTS_TOTAL, &[("endpoint", request.endpoint), ("status", status.as_str())]);
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("status", &status.as_u16());
+
+        if self.tracing {
+            debug!("Request #{} responded {} in {}ms", request_id, status.as_u16(), started.elapsed().as_millis());
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response.headers().get("retry-after")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.parse::<f64>().ok())
+                .unwrap_or(1.0);
+
+            #[cfg(feature = "metrics")]
+            self.metrics.increment(crate::metrics::RATE_LIMIT_HITS_TOTAL, &[("endpoint", request.endpoint)]);
+
+            return if response.headers().contains_key("x-ratelimit-global") {
+                rate_limit::pause_global(&self.token, Duration::from_secs_f64(retry_after)).await;
+
+                let until = Utc::now().naive_utc() + ChronoDuration::milliseconds((retry_after * 1000.0) as i64);
+                Error::global_rate_limited(request.route, until)
+            } else {
+                let until = reset.unwrap_or_else(|| Utc::now().naive_utc() + ChronoDuration::milliseconds((retry_after * 1000.0) as i64));
+                Error::rate_limited(request.route, until, false)
+            };
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpClient;
+    use async_trait::async_trait;
+    use hyper::Request;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Responds with an exhausted bucket to every request.
+    struct Exhausted(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl HttpClient for Exhausted {
+        async fn request(&self, _: Request<Body>) -> Result<Response<Body>, Error> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+
+            let reset = Utc::now().timestamp() + 60;
+
+            Ok(Response::builder()
+                .status(429)
+                .header("x-ratelimit-bucket", "exhausted")
+                .header("x-ratelimit-limit", "5")
+                .header("x-ratelimit-remaining", "0")
+                .header("x-ratelimit-reset", reset.to_string())
+                .header("retry-after", "60")
+                .body(Body::from("{}"))
+                .unwrap())
+        }
+    }
+
+    #[test]
+    fn endpoints_and_raw_routes_share_rate_limits() {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let http = HttpAPI::new("shared runtime").with_client(Exhausted(Arc::clone(&sent)));
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            assert!(matches!(http.gateway().await, Err(Error::RateLimited(ctx)) if !ctx.prevented && ctx.endpoint == "gateway"));
+            assert!(matches!(http.gateway().await, Err(Error::RateLimited(ctx)) if ctx.prevented));
+            assert_eq!(sent.load(Ordering::Relaxed), 1);
+
+            let raw = http.request_raw(Method::GET, "/guilds/1/new-feature", "").await;
+            assert!(matches!(raw, Err(Error::RateLimited(ctx)) if !ctx.prevented && ctx.endpoint == "GET/guilds/:major/new-feature"));
+            assert!(http.request_raw(Method::GET, "/guilds/1/new-feature", "").await.is_err());
+            assert_eq!(sent.load(Ordering::Relaxed), 2);
+
+            //the bucket is bound to the major parameter
+            assert!(matches!(http.request_raw(Method::GET, "/guilds/2/new-feature", "").await, Err(Error::RateLimited(ctx)) if !ctx.prevented));
+            assert_eq!(sent.load(Ordering::Relaxed), 3);
+        });
+    }
+}
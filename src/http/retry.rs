@@ -0,0 +1,105 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Defines how requests are retried after a
+/// connection error or a 502 response.
+///
+/// Requests failing to reach Discord are only retried
+/// for idempotent methods (GET, PUT and DELETE) while
+/// 502 responses are retried for every method as
+/// recommended by Discord.
+///
+/// By default, requests are retried 3 times with
+/// a delay starting at 500 milliseconds and doubling
+/// after each attempt, up to 10 seconds.
+///
+/// ```
+/// # use automate::Configuration;
+/// use automate::http::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let config = Configuration::new("token")
+///     .retry_policy(RetryPolicy::default()
+///         .max_retries(5)
+///         .max_delay(Duration::from_secs(30)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) base_delay: Duration,
+    pub(crate) max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy which never retries requests.
+    pub fn none() -> Self {
+        RetryPolicy::default().max_retries(0)
+    }
+
+    /// Maximum amount of times a request
+    /// is retried before giving up.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled
+    /// for each of the following retries.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Maximum delay between two attempts.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Delay before the given retry, picked randomly
+    /// between half and the whole of the exponential
+    /// delay so that concurrent requests do not all
+    /// retry at the same time.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self.base_delay
+            .checked_mul(2u32.saturating_pow(attempt))
+            .map_or(self.max_delay, |d| d.min(self.max_delay));
+
+        let half = exponential / 2;
+        let jitter = RandomState::new().build_hasher().finish() % (half.as_millis() as u64 + 1);
+
+        half + Duration::from_millis(jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_is_bounded() {
+        let policy = RetryPolicy::default();
+
+        for _ in 0..50 {
+            let first = policy.delay(0);
+            assert!(first >= Duration::from_millis(250) && first <= Duration::from_millis(500));
+
+            let second = policy.delay(1);
+            assert!(second >= Duration::from_millis(500) && second <= Duration::from_millis(1000));
+
+            let capped = policy.delay(40);
+            assert!(capped >= Duration::from_secs(5) && capped <= Duration::from_secs(10));
+        }
+    }
+}
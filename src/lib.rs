@@ -24,6 +24,9 @@
 //! - [Configuration::enable_logging](automate::Configuration::enable_logging) and
 //! [Configuration::disable_logging](automate::Configuration::disable_logging): Enable or
 //! disable Automate's built in logger. You can disable it and use your own logger if necessary.
+//! - [Configuration::log_to_file](automate::Configuration::log_to_file) and
+//! [Configuration::log_format](automate::Configuration::log_format): Write the logs to a rotated
+//! file and format them as JSON or with a custom function.
 //! - [Configuration::level_for](automate::Configuration::level_for): Sets the minimum log level
 //! for a line to be printed in the console output for the given module.
 //! - [Configuration::intents](automate::Configuration::intents): Sets the events which will
@@ -68,6 +71,16 @@
 //! or DM) was deleted.
 //! - [ChannelPinsUpdateDispatch](automate::gateway::ChannelPinsUpdateDispatch): a message was
 //! pinned or unpinned.
+//! - [ThreadCreateDispatch](automate::gateway::ThreadCreateDispatch): a thread was created or
+//! the bot was added to a private thread.
+//! - [ThreadUpdateDispatch](automate::gateway::ThreadUpdateDispatch): a thread was updated.
+//! - [ThreadDeleteDispatch](automate::gateway::ThreadDeleteDispatch): a thread was deleted.
+//! - [ThreadListSyncDispatch](automate::gateway::ThreadListSyncDispatch): the bot gained access
+//! to a channel and received its active threads.
+//! - [ThreadMemberUpdateDispatch](automate::gateway::ThreadMemberUpdateDispatch): the thread
+//! member object of the bot was updated.
+//! - [ThreadMembersUpdateDispatch](automate::gateway::ThreadMembersUpdateDispatch): users were
+//! added to or removed from a thread.
 //! - [GuildCreateDispatch](automate::gateway::GuildCreateDispatch): a guild was created, became
 //! available or the bot was added to a guild.
 //! - [GuildUpdateDispatch](automate::gateway::GuildUpdateDispatch): a guild was updated.
@@ -80,6 +93,26 @@
 //! guild were updated.
 //! - [GuildIntegrationsUpdateDispatch](automate::gateway::GuildIntegrationsUpdateDispatch):
 //! the integration of a guild was updated.
+//! - [GuildScheduledEventCreateDispatch](automate::gateway::GuildScheduledEventCreateDispatch):
+//! an event was scheduled in a guild.
+//! - [GuildScheduledEventUpdateDispatch](automate::gateway::GuildScheduledEventUpdateDispatch):
+//! a scheduled event was updated, started or ended.
+//! - [GuildScheduledEventDeleteDispatch](automate::gateway::GuildScheduledEventDeleteDispatch):
+//! a scheduled event was canceled.
+//! - [GuildScheduledEventUserAddDispatch](automate::gateway::GuildScheduledEventUserAddDispatch):
+//! a user subscribed to a scheduled event.
+//! - [GuildScheduledEventUserRemoveDispatch](automate::gateway::GuildScheduledEventUserRemoveDispatch):
+//! a user unsubscribed from a scheduled event.
+//! - [AutoModerationRuleCreateDispatch](automate::gateway::AutoModerationRuleCreateDispatch):
+//! an automatic moderation rule was created.
+//! - [AutoModerationRuleUpdateDispatch](automate::gateway::AutoModerationRuleUpdateDispatch):
+//! an automatic moderation rule was updated.
+//! - [AutoModerationRuleDeleteDispatch](automate::gateway::AutoModerationRuleDeleteDispatch):
+//! an automatic moderation rule was deleted.
+//! - [AutoModerationActionExecutionDispatch](automate::gateway::AutoModerationActionExecutionDispatch):
+//! an automatic moderation rule was triggered and one of its actions was executed.
+//! - [InteractionCreateDispatch](automate::gateway::InteractionCreateDispatch):
+//! a user used an application command, a message component or submitted a modal.
 //! - [GuildMemberAddDispatch](automate::gateway::GuildMemberAddDispatch): a user joined a guild.
 //! - [GuildMemberUpdateDispatch](automate::gateway::GuildMemberUpdateDispatch): a guild member was updated.
 //! - [GuildMemberRemoveDispatch](automate::gateway::GuildMemberRemoveDispatch): a user was removed from a guild.
@@ -111,6 +144,9 @@
 //! server was updated.
 //! - [WebhooksUpdateDispatch](automate::gateway::WebhooksUpdateDispatch): guild channel webhook
 //! was created, update, or deleted.
+//! - [RawDispatch](automate::gateway::RawDispatch): an event the library does not
+//! know about, with its data as raw JSON.
+//! - [CacheResyncDispatch](automate::gateway::CacheResyncDispatch): events were missed and resynchronized.
 //!
 //! A listener function can be registered in the library by sending the name of the function to the
 //! [Configuration::register](automate::Configuration::register) method using the `stateless!` macro:
@@ -209,8 +245,59 @@
 //!         .register(stateful!(MessageCounter::default()));
 //! ```
 //!
+//! States can also be instantiated once per guild by registering them with the
+//! `stateful_per_guild!` macro instead, each guild then gets its own clone of the given
+//! state. Events which are not related to a guild are all sent to a separate instance.
+//! ```
+//! # #[macro_use] extern crate automate;
+//! #
+//! # use automate::{methods, stateful_per_guild, Context, Error, Configuration};
+//! # use automate::events::{Initializable, StatefulListener};
+//! #
+//! # #[derive(State, Default, Clone)]
+//! # struct MessageCounter;
+//! #
+//! # impl Initializable for MessageCounter {
+//! #     fn initialize() -> Vec<StatefulListener<Self>> {
+//! #         methods!(MessageCounter)
+//! #     }
+//! # }
+//! #
+//! Configuration::new("token")
+//!         .register(stateful_per_guild!(MessageCounter::default()));
+//! ```
+//!
+//! States can depend on values provided by other states, for example to publish a leveling
+//! module in a crate and let bots plug their own modules on top of it. The dependencies are
+//! declared in the [Initializable](automate::events::Initializable) implementation, see
+//! [Requirements](automate::events::Requirements).
+//!
 //! More advanced examples can be found in the  ̀examples/counter.rs` example file.
 //!
+//! ## Listeners order
+//! Listeners of an event are called one after the other, stateless listeners first then
+//! stateful listeners. Listeners with a higher priority, given in the `#[listener]` attribute,
+//! are called first and default to a priority of 128. A listener can prevent the next
+//! listeners from receiving the event by returning [EventFlow::Stop](automate::events::EventFlow::Stop).
+//! ```
+//! # use automate::{Context, Error, listener};
+//! # use automate::events::EventFlow;
+//! # use automate::gateway::MessageCreateDispatch;
+//! #
+//! #[listener(priority = 200)]
+//! async fn anti_spam(ctx: &Context, data: &MessageCreateDispatch) -> Result<EventFlow, Error> {
+//!     if data.0.content.contains("discord.gg") {
+//!         return Ok(EventFlow::Stop);
+//!     }
+//!
+//!     Ok(EventFlow::Continue)
+//! }
+//! ```
+//!
+//! The methods of a state taking `&self` only read the state, so the ones following each other
+//! in the order of priority run concurrently. Methods taking `&mut self` wait for the previous
+//! ones to complete and run alone.
+//!
 //! # Storage API
 //! When receiving events, you will usually need more data than the event sends you. For
 //! example, you may need to know what the role of the user who sent a message is. This data
@@ -221,11 +308,15 @@
 //! quickly get rate limited. That is why the storage API caches some of the data discord sends.
 //!
 //! ## Caching storages
-//! Automate creates 3 storages which you can **not** mutate, they only get mutated
+//! Automate creates 5 storages which you can **not** mutate, they only get mutated
 //! through gateway events:
 //! - [Guilds](automate::gateway::Guild)
 //! - [Channels](automate::gateway::Channel)
 //! - [Users](automate::gateway::User)
+//! - [Presences](automate::gateway::PresenceUpdate) of the users that are not offline, requires
+//! the [GuildPresences](automate::Intent::GuildPresences) intent
+//! - [Voice states](automate::gateway::VoiceState) of the users connected to a voice channel,
+//! requires the [GuildVoiceStates](automate::Intent::GuildVoiceStates) intent
 //!
 //! ```
 //! # use automate::listener;
@@ -249,6 +340,40 @@
 //! }
 //! ```
 //!
+//! ## Iterating over storages
+//! [Context::storage](automate::Context::storage) returns a guard which keeps the storage
+//! locked. Holding it across an `.await` point blocks every storage update from the gateway
+//! until the guard is dropped and can deadlock if the same listener then asks for
+//! [Context::storage_mut](automate::Context::storage_mut). When you need to await while
+//! iterating, take an owned copy with [Context::snapshot](automate::Context::snapshot) which
+//! holds no lock. Snapshots of the caching storages are cheap since their entries are shared.
+//! For long computations such as leaderboards, [Context::storage_snapshot](automate::Context::storage_snapshot)
+//! returns a copy shared by all the listeners which is only made again once the storage changed.
+//!
+//! The caching storages implement [Query](automate::storage::Query) to iterate over, search and
+//! count their values. They also index the channels of each guild and the users by their tag.
+//!
+//! ```
+//! # use automate::listener;
+//! use automate::{Context, Error};
+//! use automate::gateway::{MessageCreateDispatch, Guild};
+//! use automate::http::CreateMessage;
+//!
+//! #[listener]
+//! async fn list_guilds(ctx: &Context, data: &MessageCreateDispatch) -> Result<(), Error> {
+//!     let guilds = ctx.snapshot::<Guild>().await;
+//!
+//!     for guild in guilds.all() {
+//!         ctx.create_message(data.0.channel_id, CreateMessage {
+//!             content: Some(guild.name.clone()),
+//!             ..Default::default()
+//!         }).await?;
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
 //! ## Custom storages
 //! You can also create your own storages. Having your own custom storages will usually allow you
 //! to store data without using stateful listeners and in a simpler way.
@@ -261,12 +386,59 @@
 //! See [examples/levels.rs](https://github.com/mbenoukaiss/automate/blob/master/examples/levels.rs)
 //! for a detailed example.
 //!
+//! ## Persistent storages
+//! Custom storages live in memory and are lost when the bot restarts. Storing values in a
+//! database instead can be done by implementing [PersistentBackend](automate::storage::PersistentBackend)
+//! for the stored type, setting its storage to [PersistentStorage](automate::storage::PersistentStorage)
+//! and registering the backend with
+//! [Configuration::add_persistent_storage](automate::Configuration::add_persistent_storage).
+//! Since the backend is shared by all the shards, it can also be used to share data between shards
+//! running on different machines.
+//!
+//! ## Guild settings
+//! Values configured per guild such as a command prefix or toggled features can be kept in the
+//! [SettingsStorage](automate::storage::SettingsStorage) instead of a custom storage. Listeners
+//! read and modify them through the [Settings](automate::storage::Settings) storage and can
+//! [subscribe](automate::storage::SettingsStorage::subscribe) to their modifications. The settings
+//! are saved in a backend when one is given to
+//! [Configuration::persistent_settings](automate::Configuration::persistent_settings)
+//! once the modification is [persisted](automate::storage::PendingSave::persist).
+//!
+//! ```
+//! # use automate::listener;
+//! use automate::{Context, Error};
+//! use automate::gateway::MessageCreateDispatch;
+//! use automate::storage::Settings;
+//!
+//! #[listener]
+//! async fn set_prefix(ctx: &Context, data: &MessageCreateDispatch) -> Result<(), Error> {
+//!     if let (Some(guild), Some(prefix)) = (data.0.guild_id, data.0.content.strip_prefix("!prefix ")) {
+//!         //the storage is released before the settings are saved
+//!         let save = ctx.storage_mut::<Settings>().await.set(guild, "prefix", prefix)?;
+//!         save.persist().await?;
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
 //! ## Deactivating storages
 //! The storages API is by default enabled but you might not want it because you simply
 //! do not need to cache the data sent by discord or because you do not have a lot of RAM
 //! available to run the bot. In that case, you can disable the feature by setting the
 //! `default-features` key to `false` for `automate` in your `Cargo.toml` file.
 //!
+//! When only part of the data is needed, a [CachePolicy](automate::storage::CachePolicy) can be
+//! given to [Configuration::cache_policy](automate::Configuration::cache_policy) to stop caching
+//! users, presences or voice states, cap the amount of members cached per guild, only cache
+//! the channels of some guilds or evict users that have not been seen for a while.
+//!
+//! Helpers such as [Context::fetch_guild](automate::Context::fetch_guild),
+//! [Context::fetch_member](automate::Context::fetch_member) or
+//! [Context::permissions](automate::Context::permissions) read from the storages when
+//! the feature is enabled and fall back to the HTTP API otherwise. They keep working without
+//! the storages at the cost of more API calls.
+//!
 //! # Sharding
 //! Automate implements support for sharding through the [ShardManager](automate::ShardManager)
 //! struct. However, you will not need to use the [ShardManager](automate::ShardManager) directly
@@ -274,8 +446,9 @@
 //! create as many shards as Discord recommends.
 //!
 //! The reasons you would need to use the [ShardManager](automate::ShardManager) are if you want
-//! to spread your bot across multiple servers or if you want to launch more or less
-//! shards than what Discord recommends.
+//! to spread your bot across multiple servers, if you want to launch more or less
+//! shards than what Discord recommends or if the shards need
+//! [different configurations](automate::ShardManager::configure_shards).
 //!
 //! # Models
 //! All the data sent by discord is deserialized into [model structs and enums](automate::gateway::models).
@@ -339,12 +512,27 @@ pub mod http;
 pub mod encode;
 pub mod gateway;
 pub mod sharding;
+pub mod metrics;
+pub mod collectors;
+pub mod mention;
+pub mod content;
+pub mod locale;
+pub mod middleware;
+pub mod audio;
+pub mod cdn;
+pub mod scheduler;
+#[cfg(feature = "botlists")]
+pub mod botlists;
+pub mod testing;
+mod handle;
 #[cfg(feature = "storage")]
 pub mod storage;
+#[cfg(feature = "storage")]
+pub mod preflight;
 mod snowflake;
 mod macros;
-mod errors;
-mod logger;
+pub mod errors;
+pub mod logger;
 
 pub use automate_derive::listener;
 
@@ -356,6 +544,35 @@ pub use automate_derive::listener;
 /// It implements the [State](automate::events::State)
 /// in order to relay the events to the methods annotated
 /// with `#[listener]`
+///
+/// States can be generic, which allows sharing a
+/// component between bots.
+/// ```
+/// # #[macro_use] extern crate automate;
+/// #
+/// # use automate::{Context, Error};
+/// # use automate::events::{Initializable, StatefulListener};
+/// # use automate::gateway::MessageCreateDispatch;
+/// #
+/// #[derive(State, Clone)]
+/// struct Logger<T: Clone + Send + Sync + 'static> {
+///     output: T,
+/// }
+///
+/// impl<T: Clone + Send + Sync + 'static> Initializable for Logger<T> {
+///     fn initialize() -> Vec<StatefulListener<Self>> {
+///         methods!(Self: log)
+///     }
+/// }
+///
+/// impl<T: Clone + Send + Sync + 'static> Logger<T> {
+///     #[listener]
+///     async fn log(&self, _: &Context, data: &MessageCreateDispatch) -> Result<(), Error> {
+///         println!("{}", data.0.content);
+///         Ok(())
+///     }
+/// }
+/// ```
 pub use automate_derive::State;
 
 /// Derive macro for a stored struct.
@@ -452,8 +669,12 @@ pub use http::HttpAPI;
 pub use gateway::Context;
 #[doc(inline)]
 pub use gateway::Intent;
+pub use gateway::IntentSet;
 
 pub use sharding::ShardManager;
+pub use handle::BotHandle;
+#[cfg(feature = "storage")]
+pub use handle::Statistics;
 pub use snowflake::{Identifiable, Snowflake};
 pub use errors::Error;
 
@@ -462,9 +683,19 @@ use tokio::runtime::Builder;
 use std::env;
 use log::LevelFilter;
 use std::future::Future;
-use crate::gateway::UpdateStatus;
+use crate::gateway::{UpdateStatus, ResyncStrategy, Guild};
+use crate::http::{RetryPolicy, ApiVersion};
+use crate::metrics::Metrics;
+use crate::collectors::Collectors;
+use crate::middleware::Middleware;
+use crate::scheduler::{Job, JobContext, Schedule};
+use std::sync::Arc;
+use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use crate::logger::{LogOutput, LogFormat, Rotation};
 #[cfg(feature = "storage")]
-use crate::storage::StorageContainer;
+use crate::storage::{StorageContainer, Stored, PersistentBackend, PersistentStorage, CachePolicy, Settings, SettingsStorage};
 
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub enum Threading {
@@ -472,6 +703,62 @@ pub enum Threading {
     Multi,
 }
 
+/// What to do with a guild, returned by the
+/// [guild policy](automate::Configuration::guild_policy).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Stay,
+    Leave,
+}
+
+/// What happens to the commands sent to the gateway when
+/// the [command queue](automate::Configuration::command_queue_capacity)
+/// of the shard is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Presence updates are dropped while the queue is full
+    /// and the other commands fail with an error.
+    DropPresences,
+    /// All the commands fail with an error while the queue is full.
+    Reject,
+}
+
+impl Default for Backpressure {
+    fn default() -> Self {
+        Backpressure::DropPresences
+    }
+}
+
+/// Which presence updates are dispatched to the listeners,
+/// the storages receive all of them to stay up to date and
+/// the `automate_events_total` metric counts all of them. The updates
+/// that are not dispatched are not given to the middlewares.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PresenceFilter {
+    /// Every presence update is dispatched.
+    All,
+    /// Only the presence updates which change the status or
+    /// the activities of the user compared to the presence in
+    /// the [PresenceStorage](automate::storage::PresenceStorage)
+    /// for the same guild are dispatched. Requires the `storage` feature and
+    /// presences to be cached, else every update is dispatched.
+    Changes,
+    /// Only one presence update out of the given amount
+    /// is dispatched.
+    Sample(u32),
+}
+
+impl Default for PresenceFilter {
+    fn default() -> Self {
+        PresenceFilter::All
+    }
+}
+
+type UnknownEventHook = Arc<dyn Fn(&str, &str) + Send + Sync>;
+type GuildPolicy = Arc<dyn Fn(&Guild) -> Decision + Send + Sync>;
+type ListenerErrorHook = Arc<dyn Fn(&str, &str, &Error) + Send + Sync>;
+type ZombieHook = Arc<dyn Fn(u32, u32) + Send + Sync>;
+
 /// Allows specifying API token, registering
 /// stateful and stateless listeners, stating
 /// the shard id, intents and configuring logger.
@@ -502,14 +789,45 @@ pub struct Configuration {
     token: String,
     logging: bool,
     log_levels: Vec<(String, LevelFilter)>,
+    log_output: LogOutput,
+    log_format: LogFormat,
     listeners: ListenerContainer,
     #[cfg(feature = "storage")]
     storages: StorageContainer,
-    intents: u32,
+    intents: IntentSet,
     member_threshold: Option<u32>,
     presence: Option<UpdateStatus>,
+    identify_properties: HashMap<String, String>,
     guild_subscriptions: Option<bool>,
     collector_period: u64,
+    metrics: Arc<Metrics>,
+    collectors: Arc<Collectors>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    unknown_event: Option<UnknownEventHook>,
+    listener_error: Option<ListenerErrorHook>,
+    listener_timeout: Option<Duration>,
+    presence_coalescing: Option<Duration>,
+    resync_strategy: ResyncStrategy,
+    only_guilds: Option<HashSet<Snowflake>>,
+    ignored_guilds: HashSet<Snowflake>,
+    guild_policy: Option<GuildPolicy>,
+    zombie_threshold: u32,
+    zombie_hook: Option<ZombieHook>,
+    command_queue_capacity: usize,
+    backpressure: Backpressure,
+    presence_filter: PresenceFilter,
+    jobs: Vec<Job>,
+    #[cfg(feature = "botlists")]
+    bot_lists: Vec<botlists::BotList>,
+    #[cfg(feature = "botlists")]
+    bot_list_interval: Duration,
+    strict_intents: bool,
+    retry_policy: RetryPolicy,
+    http_tracing: bool,
+    api_base: Option<String>,
+    api_version: ApiVersion,
+    #[cfg(feature = "metrics")]
+    metrics_address: Option<std::net::SocketAddr>,
 }
 
 impl Configuration {
@@ -530,14 +848,49 @@ impl Configuration {
             token: token.into(),
             logging: true,
             log_levels: default_levels,
+            log_output: LogOutput::default(),
+            log_format: LogFormat::default(),
             listeners: ListenerContainer::default(),
             #[cfg(feature = "storage")]
             storages: StorageContainer::for_initialization(),
             intents: Intent::default(),
             member_threshold: None,
             presence: None,
+            identify_properties: map! {
+                "$os" => env::consts::OS,
+                "$browser" => "automate",
+                "$device" => "automate"
+            },
             guild_subscriptions: None,
             collector_period: 3600,
+            metrics: Arc::new(Metrics::default()),
+            collectors: Arc::new(Collectors::default()),
+            middlewares: Vec::new(),
+            unknown_event: None,
+            listener_error: None,
+            listener_timeout: None,
+            presence_coalescing: None,
+            resync_strategy: ResyncStrategy::default(),
+            only_guilds: None,
+            ignored_guilds: HashSet::new(),
+            guild_policy: None,
+            zombie_threshold: 1,
+            zombie_hook: None,
+            command_queue_capacity: 256,
+            backpressure: Backpressure::default(),
+            presence_filter: PresenceFilter::default(),
+            jobs: Vec::new(),
+            #[cfg(feature = "botlists")]
+            bot_lists: Vec::new(),
+            #[cfg(feature = "botlists")]
+            bot_list_interval: Duration::from_secs(1800),
+            strict_intents: false,
+            retry_policy: RetryPolicy::default(),
+            http_tracing: false,
+            api_base: None,
+            api_version: ApiVersion::default(),
+            #[cfg(feature = "metrics")]
+            metrics_address: None,
         }
     }
 
@@ -591,6 +944,20 @@ impl Configuration {
         self
     }
 
+    /// Writes the logs to the given file instead of
+    /// the standard output, see [Rotation](automate::logger::Rotation).
+    pub fn log_to_file<P: Into<PathBuf>>(mut self, path: P, rotation: Rotation) -> Self {
+        self.log_output = LogOutput::File(path.into(), rotation);
+        self
+    }
+
+    /// Sets the format of the lines written by the
+    /// built-in logger, see [LogFormat](automate::logger::LogFormat).
+    pub fn log_format(mut self, format: LogFormat) -> Self {
+        self.log_format = format;
+        self
+    }
+
     /// Sets the minimum log level for the given module.
     ///
     /// By default, automate will be set to
@@ -632,19 +999,57 @@ impl Configuration {
         self
     }
 
+    /// Registers a storage for `T` which relays all its calls
+    /// to the given [backend](automate::storage::PersistentBackend).
+    /// All the shards share the same backend.
+    ///
+    /// The storage of `T` must be a
+    /// [PersistentStorage](automate::storage::PersistentStorage).
+    #[cfg(feature = "storage")]
+    pub fn add_persistent_storage<T, B>(self, backend: B) -> Self
+        where T: Stored<Storage = PersistentStorage<T>> + 'static,
+              B: PersistentBackend<T> + 'static {
+        let backend: Arc<dyn PersistentBackend<T>> = Arc::new(backend);
+
+        self.add_initializer(move |container| {
+            container.existing::<T>(PersistentStorage::new(Arc::clone(&backend)));
+        })
+    }
+
+    /// Saves the [settings](automate::storage::SettingsStorage)
+    /// of the guilds in the given backend so they survive
+    /// restarts. All the shards share the same backend.
+    #[cfg(feature = "storage")]
+    pub fn persistent_settings<B: PersistentBackend<Settings> + 'static>(self, backend: B) -> Self {
+        let backend: Arc<dyn PersistentBackend<Settings>> = Arc::new(backend);
+
+        self.add_initializer(move |container| {
+            container.existing::<Settings>(SettingsStorage::persistent(Arc::clone(&backend)));
+        })
+    }
+
+    /// Defines what the caching storages keep in memory,
+    /// see [CachePolicy](automate::storage::CachePolicy).
+    #[cfg(feature = "storage")]
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.storages.set_policy(policy);
+        self
+    }
+
     /// [Intents](automate::Intent) are a system to help you
     /// lower the amount of data you need to process by
     /// specifying the events Discord should relay to the library.
     ///
     /// An [Intents](automate::Intent) concerns one or more
     /// event. By default, all intents are specified except
-    /// for `GuildPresence` and `GuildMembers` which both
-    /// require to be enabled in your Discord settings or the
-    /// bot will fail to connect with "Disallowed intent(s)".
+    /// for the [privileged](automate::IntentSet::privileged)
+    /// ones and the typing ones. Privileged intents require
+    /// to be enabled in your Discord settings or the bot will
+    /// fail to connect with "Disallowed intent(s)".
     ///
-    /// When specifying a single [], you must explicitly cast
-    /// the intent to u32. When specifying multiple intents,
-    /// you can aggregate them using the bitwise or operator.
+    /// Accepts a single intent, an [IntentSet](automate::IntentSet)
+    /// or the raw bits of the intents. Multiple intents can be
+    /// aggregated using the bitwise or operator.
     ///
     /// # Example
     /// The bot in the following example will only receive events
@@ -657,17 +1062,16 @@ impl Configuration {
     ///         .intents(GuildMessages | GuildMessageTyping);
     /// ```
     ///
-    /// If you want to only listen to one intent type, you must
-    /// explicitly cast the intent to u32 like the following example
-    /// which only listens to events about guild members:
+    /// The following example only listens to
+    /// events about guild members:
     /// ```
     /// use automate::{Configuration, Intent::*};
     ///
     /// Configuration::from_env("DISCORD_API_TOKEN")
-    ///         .intents(GuildMembers as u32);
+    ///         .intents(GuildMembers);
     /// ```
-    pub fn intents(mut self, intents: u32) -> Self {
-        self.intents = intents;
+    pub fn intents<I: Into<IntentSet>>(mut self, intents: I) -> Self {
+        self.intents = intents.into();
         self
     }
 
@@ -684,9 +1088,30 @@ impl Configuration {
     ///
     /// This can later be modified using the
     /// [update_status](automate::Context::update_status)
-    /// gateway command.
-    pub fn presence(mut self, presence: UpdateStatus) -> Self {
-        self.presence = Some(presence);
+    /// gateway command. Accepts either an
+    /// [UpdateStatus](automate::gateway::UpdateStatus) or
+    /// a [Presence](automate::gateway::Presence) builder.
+    pub fn presence<P: Into<UpdateStatus>>(mut self, presence: P) -> Self {
+        self.presence = Some(presence.into());
+        self
+    }
+
+    /// Sets the operating system, browser and device
+    /// sent to Discord when identifying. They default to
+    /// the current operating system and `automate`.
+    pub fn identify_properties<O, B, D>(mut self, os: O, browser: B, device: D) -> Self
+        where O: Into<String>, B: Into<String>, D: Into<String> {
+        self.identify_properties.insert(String::from("$os"), os.into());
+        self.identify_properties.insert(String::from("$browser"), browser.into());
+        self.identify_properties.insert(String::from("$device"), device.into());
+        self
+    }
+
+    /// Adds properties sent to Discord when identifying
+    /// next to the operating system, browser and device,
+    /// such as `$referring_domain`.
+    pub fn extra_identify_properties(mut self, properties: HashMap<String, String>) -> Self {
+        self.identify_properties.extend(properties);
         self
     }
 
@@ -727,6 +1152,308 @@ impl Configuration {
         self.collector_period = period;
         self
     }
+
+    /// Registers a [middleware](automate::middleware::Middleware)
+    /// called before and after the listeners of every event.
+    pub fn add_middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Calls the hook with the name and the raw JSON payload of
+    /// the events the library does not know about. Listeners
+    /// taking a [RawDispatch](automate::gateway::RawDispatch)
+    /// also receive these events.
+    pub fn on_unknown_event<F: Fn(&str, &str) + Send + Sync + 'static>(mut self, hook: F) -> Self {
+        self.unknown_event = Some(Arc::new(hook));
+        self
+    }
+
+    /// Calls the hook with the name of the event, its raw JSON
+    /// payload and the error every time a listener fails.
+    ///
+    /// ```
+    /// # use automate::Configuration;
+    /// let config = Configuration::new("token")
+    ///     .on_listener_error(|event, payload, error| {
+    ///         eprintln!("Listener to {} failed with {} for {}", event, error, payload);
+    ///     });
+    /// ```
+    pub fn on_listener_error<F: Fn(&str, &str, &Error) + Send + Sync + 'static>(mut self, hook: F) -> Self {
+        self.listener_error = Some(Arc::new(hook));
+        self
+    }
+
+    /// Maximum duration a listener can take to handle
+    /// an event. Listeners taking longer are cancelled
+    /// and fail with an error so that a single listener
+    /// can not stall the processing of the next events.
+    ///
+    /// Listeners are not limited by default.
+    pub fn listener_timeout(mut self, timeout: Duration) -> Self {
+        self.listener_timeout = Some(timeout);
+        self
+    }
+
+    /// Delays the presence updates by the given window
+    /// and only sends the last one received during that
+    /// window, which avoids wasting the 120 commands per
+    /// minute the gateway accepts on bots updating their
+    /// presence frequently.
+    ///
+    /// Presence updates are sent immediately by default.
+    pub fn presence_coalescing(mut self, window: Duration) -> Self {
+        self.presence_coalescing = Some(window);
+        self
+    }
+
+    /// Defines how the storages are brought up to date when
+    /// the gateway skipped events, for example while resuming
+    /// a session. Listeners are notified with a
+    /// [CacheResyncDispatch](automate::gateway::CacheResyncDispatch)
+    /// whatever the strategy.
+    ///
+    /// Defaults to [ResyncStrategy::Refetch](automate::gateway::ResyncStrategy::Refetch).
+    pub fn resync_strategy(mut self, strategy: ResyncStrategy) -> Self {
+        self.resync_strategy = strategy;
+        self
+    }
+
+    /// Amount of consecutive heartbeats the gateway can leave
+    /// unacknowledged before the connection is considered
+    /// zombied and restarted. A higher threshold tolerates
+    /// short network hiccups but takes longer to detect
+    /// dead connections.
+    ///
+    /// Defaults to 1, the connection is restarted when a
+    /// heartbeat was not acknowledged before the next one.
+    pub fn zombie_threshold(mut self, missed_acks: u32) -> Self {
+        self.zombie_threshold = missed_acks.max(1);
+        self
+    }
+
+    /// Calls the hook with the shard id and the amount of
+    /// unacknowledged heartbeats when a zombied connection
+    /// is detected, right before it is restarted.
+    pub fn on_zombie<F: Fn(u32, u32) + Send + Sync + 'static>(mut self, hook: F) -> Self {
+        self.zombie_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Maximum amount of commands sent by the listeners
+    /// waiting to be sent to the gateway by a shard. The
+    /// commands are sent between two events, the commands
+    /// sent by a listener while the queue is full are handled
+    /// according to the [backpressure policy](automate::Configuration::backpressure)
+    /// instead of waiting for an event that never ends.
+    ///
+    /// The heartbeats and other commands of the gateway
+    /// itself do not count towards the capacity.
+    ///
+    /// Defaults to 256.
+    pub fn command_queue_capacity(mut self, capacity: usize) -> Self {
+        self.command_queue_capacity = capacity.max(1);
+        self
+    }
+
+    /// Defines what happens to the commands sent while the
+    /// command queue is full.
+    ///
+    /// Defaults to [Backpressure::DropPresences](automate::Backpressure::DropPresences).
+    pub fn backpressure(mut self, policy: Backpressure) -> Self {
+        self.backpressure = policy;
+        self
+    }
+
+    /// Reduces the amount of presence updates dispatched to
+    /// the listeners, which make most of the traffic of bots
+    /// in large guilds.
+    ///
+    /// ```
+    /// # use automate::{Configuration, PresenceFilter};
+    /// let config = Configuration::new("token")
+    ///     .presence_filter(PresenceFilter::Changes);
+    /// ```
+    pub fn presence_filter(mut self, filter: PresenceFilter) -> Self {
+        self.presence_filter = filter;
+        self
+    }
+
+    /// Posts the amount of guilds of the bot to the bot list,
+    /// see the [botlists](automate::botlists) module.
+    #[cfg(feature = "botlists")]
+    pub fn bot_list(mut self, list: botlists::BotList) -> Self {
+        self.bot_lists.push(list);
+        self
+    }
+
+    /// Interval between two posts of the amount
+    /// of guilds to the bot lists.
+    ///
+    /// Defaults to 30 minutes.
+    #[cfg(feature = "botlists")]
+    pub fn bot_list_interval(mut self, interval: Duration) -> Self {
+        self.bot_list_interval = interval;
+        self
+    }
+
+    /// Only dispatches the events of the given guilds to the
+    /// listeners and the storages. Events that are not tied
+    /// to a guild, such as direct messages, are still
+    /// dispatched.
+    ///
+    /// Useful for a staging bot sharing its token with
+    /// the production one or a bot made for a single guild.
+    pub fn only_guilds(mut self, guilds: Vec<Snowflake>) -> Self {
+        self.only_guilds = Some(guilds.into_iter().collect());
+        self
+    }
+
+    /// Drops the events of the given guilds before
+    /// they reach the listeners and the storages.
+    pub fn ignore_guilds(mut self, guilds: Vec<Snowflake>) -> Self {
+        self.ignored_guilds.extend(guilds);
+        self
+    }
+
+    /// Calls the policy with each guild received from the
+    /// gateway and leaves the guild if the policy returns
+    /// [Decision::Leave](automate::Decision::Leave). The events
+    /// of the guilds the bot leaves are not dispatched.
+    ///
+    /// As a safeguard against a faulty policy, the bot never
+    /// leaves the guilds it owns and each shard leaves at most
+    /// 10 guilds every 10 minutes, the other guilds are
+    /// kept and an error is logged.
+    ///
+    /// ```
+    /// use automate::{Configuration, Decision};
+    ///
+    /// let config = Configuration::new("token")
+    ///     .guild_policy(|guild| if guild.member_count.unwrap_or(0) < 10 {
+    ///         Decision::Leave
+    ///     } else {
+    ///         Decision::Stay
+    ///     });
+    /// ```
+    pub fn guild_policy<F: Fn(&Guild) -> Decision + Send + Sync + 'static>(mut self, policy: F) -> Self {
+        self.guild_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Whether the events of the guild should be dispatched
+    /// according to [only_guilds](automate::Configuration::only_guilds)
+    /// and [ignore_guilds](automate::Configuration::ignore_guilds).
+    pub(crate) fn dispatches_guild(&self, guild: Option<Snowflake>) -> bool {
+        match guild {
+            Some(guild) => !self.ignored_guilds.contains(&guild)
+                && self.only_guilds.as_ref().map_or(true, |only| only.contains(&guild)),
+            None => true
+        }
+    }
+
+    /// Runs the job following the given [schedule](automate::scheduler::Schedule)
+    /// once the shards are launched, see the [scheduler](automate::scheduler)
+    /// module.
+    pub fn schedule<F, R>(mut self, schedule: Schedule, job: F) -> Self
+        where F: Fn(JobContext) -> R + Send + Sync + 'static,
+              R: Future<Output = Result<(), Error>> + Send + 'static {
+        self.jobs.push(Job::new(schedule, job));
+        self
+    }
+
+    /// Fails to create the [ShardManager](automate::ShardManager)
+    /// if listeners are registered for events that will never be
+    /// received with the configured intents instead of only
+    /// logging a warning when launching the shards.
+    pub fn strict_intents(mut self) -> Self {
+        self.strict_intents = true;
+        self
+    }
+
+    /// Lists the events with registered listeners along with the
+    /// intents they require when none of them is configured.
+    pub(crate) fn missing_intents(&self) -> Vec<(&'static str, &'static [Intent])> {
+        self.listeners.events()
+            .into_iter()
+            .map(|event| (event, Intent::required_by(event)))
+            .filter(|(_, required)| !required.is_empty() && required.iter().all(|i| !self.intents.contains(*i)))
+            .collect()
+    }
+
+    /// Whether listeners are registered for message events
+    /// while the [MessageContent](automate::Intent::MessageContent)
+    /// intent is not configured.
+    pub(crate) fn lacks_message_content(&self) -> bool {
+        !self.intents.contains(Intent::MessageContent) && self.listeners.events()
+            .into_iter()
+            .any(|event| event == "message_create" || event == "message_update")
+    }
+
+    /// Sets how the HTTP requests are retried after a
+    /// connection error or a 502 response, see
+    /// [RetryPolicy](automate::http::RetryPolicy).
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Logs every HTTP request with its method, route,
+    /// rate-limit bucket, status and latency at the debug
+    /// level, see [HttpAPI::tracing](automate::HttpAPI::tracing).
+    ///
+    /// The logs are only printed if the debug level
+    /// is enabled for the `automate` module.
+    pub fn http_tracing(mut self, enabled: bool) -> Self {
+        self.http_tracing = enabled;
+        self
+    }
+
+    /// Sends the HTTP requests to the given base URL
+    /// instead of Discord's API. Useful to mock the
+    /// API in tests or to share the rate-limits of
+    /// several processes through a proxy.
+    ///
+    /// ```
+    /// # use automate::Configuration;
+    /// let config = Configuration::new("token")
+    ///     .api_base("http://localhost:8080/api/v8");
+    /// ```
+    pub fn api_base<S: Into<String>>(mut self, url: S) -> Self {
+        self.api_base = Some(url.into());
+        self
+    }
+
+    /// Version of Discord's API used by the HTTP requests
+    /// and the gateway, version 8 by default.
+    ///
+    /// The version is not applied to the HTTP requests
+    /// when a custom [api_base](automate::Configuration::api_base)
+    /// is set.
+    pub fn api_version(mut self, version: ApiVersion) -> Self {
+        self.api_version = version;
+        self
+    }
+
+    /// The [metrics](automate::metrics::Metrics) registry shared
+    /// by all the shards launched with this configuration.
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Serves the metrics in Prometheus' text format
+    /// on the given address once the bot is launched.
+    ///
+    /// ```
+    /// # use automate::Configuration;
+    /// let config = Configuration::new("token")
+    ///     .serve_metrics(([127, 0, 0, 1], 9100).into());
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn serve_metrics(mut self, address: std::net::SocketAddr) -> Self {
+        self.metrics_address = Some(address);
+        self
+    }
 }
 
 /// Defines utility functions.
@@ -744,6 +1471,23 @@ impl Automate {
         })
     }
 
+    /// Launches the bot like [launch](automate::Automate::launch)
+    /// and runs the future returned by the given function
+    /// beside the shards. The function receives a
+    /// [BotHandle](automate::BotHandle) giving access to
+    /// the bot outside of the listeners.
+    pub fn launch_with_handle<F, R>(config: Configuration, f: F)
+        where F: FnOnce(BotHandle) -> R,
+              R: Future<Output = ()> + Send + 'static {
+        Automate::block_on(config.threading, async move {
+            let mut manager = ShardManager::with_config(config).await.unwrap();
+            manager.auto_setup();
+
+            tokio::spawn(f(manager.handle()));
+            manager.launch().await
+        })
+    }
+
     /// Creates a tokio runtime and runs the
     /// given future inside.
     pub fn block_on<F: Future>(threading: Threading, future: F) -> F::Output {
@@ -0,0 +1,232 @@
+//! Languages supported by Discord and translation
+//! of the responses of the bot.
+//!
+//! A [Catalog](automate::locale::Catalog) holds the
+//! translations of the bot and picks the one matching
+//! the language of the user who triggered an interaction.
+//!
+//! ```
+//! use automate::locale::{Catalog, Locale};
+//!
+//! let catalog = Catalog::new(Locale::EnglishUS)
+//!     .add(Locale::EnglishUS, "greeting", "Hello!")
+//!     .add(Locale::French, "greeting", "Bonjour !");
+//!
+//! assert_eq!(catalog.get(Some(Locale::French), "greeting"), Some("Bonjour !"));
+//! assert_eq!(catalog.get(Some(Locale::German), "greeting"), Some("Hello!"));
+//! ```
+
+use crate::gateway::Interaction;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+macro_rules! locales {
+    ($($variant:ident => $code:literal),* $(,)?) => {
+        /// A language of the Discord client.
+        ///
+        /// More information on [Discord's documentation](https://discord.com/developers/docs/reference#locales)
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum Locale {
+            $($variant),*
+        }
+
+        impl Locale {
+            /// The code Discord uses for the locale,
+            /// such as `fr` or `en-US`.
+            pub fn code(self) -> &'static str {
+                match self {
+                    $(Locale::$variant => $code),*
+                }
+            }
+        }
+
+        impl FromStr for Locale {
+            type Err = UnknownLocale;
+
+            fn from_str(s: &str) -> Result<Locale, UnknownLocale> {
+                match s {
+                    $($code => Ok(Locale::$variant),)*
+                    _ => Err(UnknownLocale)
+                }
+            }
+        }
+    }
+}
+
+locales! {
+    Indonesian => "id",
+    Danish => "da",
+    German => "de",
+    EnglishUK => "en-GB",
+    EnglishUS => "en-US",
+    Spanish => "es-ES",
+    SpanishLatam => "es-419",
+    French => "fr",
+    Croatian => "hr",
+    Italian => "it",
+    Lithuanian => "lt",
+    Hungarian => "hu",
+    Dutch => "nl",
+    Norwegian => "no",
+    Polish => "pl",
+    PortugueseBrazilian => "pt-BR",
+    Romanian => "ro",
+    Finnish => "fi",
+    Swedish => "sv-SE",
+    Vietnamese => "vi",
+    Turkish => "tr",
+    Czech => "cs",
+    Greek => "el",
+    Bulgarian => "bg",
+    Russian => "ru",
+    Ukrainian => "uk",
+    Hindi => "hi",
+    Thai => "th",
+    ChineseChina => "zh-CN",
+    Japanese => "ja",
+    ChineseTaiwan => "zh-TW",
+    Korean => "ko",
+}
+
+impl Locale {
+    /// The locale sharing the language of this one, used
+    /// when no translation exists for a regional variant.
+    fn sibling(self) -> Option<Locale> {
+        match self {
+            Locale::EnglishUK => Some(Locale::EnglishUS),
+            Locale::EnglishUS => Some(Locale::EnglishUK),
+            Locale::Spanish => Some(Locale::SpanishLatam),
+            Locale::SpanishLatam => Some(Locale::Spanish),
+            Locale::ChineseChina => Some(Locale::ChineseTaiwan),
+            Locale::ChineseTaiwan => Some(Locale::ChineseChina),
+            _ => None
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Allows using locales in the localization maps
+/// of the application commands.
+impl From<Locale> for String {
+    fn from(locale: Locale) -> Self {
+        locale.code().to_owned()
+    }
+}
+
+/// Error returned when a string is not
+/// a locale supported by Discord.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLocale;
+
+impl Display for UnknownLocale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown locale")
+    }
+}
+
+impl std::error::Error for UnknownLocale {}
+
+impl Interaction {
+    /// The language of the user who triggered the interaction,
+    /// not sent for [ping](automate::gateway::InteractionType::Ping)
+    /// interactions.
+    pub fn user_locale(&self) -> Option<Locale> {
+        self.locale.as_deref().and_then(|locale| locale.parse().ok())
+    }
+
+    /// The preferred language of the community guild
+    /// the interaction was triggered in.
+    pub fn guild_preferred_locale(&self) -> Option<Locale> {
+        self.guild_locale.as_deref().and_then(|locale| locale.parse().ok())
+    }
+}
+
+/// Translations of the strings of the bot
+/// identified by a key.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    default: Locale,
+    translations: HashMap<Locale, HashMap<String, String>>,
+}
+
+impl Catalog {
+    /// Creates a catalog falling back to the given
+    /// locale when a string is not translated.
+    pub fn new(default: Locale) -> Catalog {
+        Catalog {
+            default,
+            translations: HashMap::new(),
+        }
+    }
+
+    /// Adds the translation of the string
+    /// for the given locale.
+    pub fn add<K: Into<String>, V: Into<String>>(mut self, locale: Locale, key: K, value: V) -> Self {
+        self.translations.entry(locale).or_default().insert(key.into(), value.into());
+        self
+    }
+
+    /// The translation of the string in the given locale,
+    /// in a regional variant of the same language or in
+    /// the default locale of the catalog.
+    pub fn get(&self, locale: Option<Locale>, key: &str) -> Option<&str> {
+        let lookup = |locale: Locale| self.translations.get(&locale)
+            .and_then(|strings| strings.get(key))
+            .map(String::as_str);
+
+        locale.and_then(|locale| lookup(locale).or_else(|| locale.sibling().and_then(lookup)))
+            .or_else(|| lookup(self.default))
+    }
+
+    /// The translation of the string in the language of the
+    /// user who triggered the interaction, or else in the
+    /// language of the guild.
+    pub fn for_interaction(&self, interaction: &Interaction, key: &str) -> Option<&str> {
+        let locale = interaction.user_locale().or_else(|| interaction.guild_preferred_locale());
+        self.get(locale, key)
+    }
+
+    /// All the translations of the string except the one in
+    /// the default locale, in the format of the localization
+    /// maps of the application commands.
+    pub fn localizations(&self, key: &str) -> HashMap<String, String> {
+        self.translations.iter()
+            .filter(|(locale, _)| **locale != self.default)
+            .filter_map(|(locale, strings)| strings.get(key).map(|value| (String::from(*locale), value.clone())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_locales() {
+        assert_eq!("pt-BR".parse::<Locale>(), Ok(Locale::PortugueseBrazilian));
+        assert_eq!("pt".parse::<Locale>(), Err(UnknownLocale));
+        assert_eq!(Locale::SpanishLatam.to_string(), "es-419");
+    }
+
+    #[test]
+    fn catalog() {
+        let catalog = Catalog::new(Locale::French)
+            .add(Locale::French, "hello", "Bonjour")
+            .add(Locale::EnglishUS, "hello", "Hello")
+            .add(Locale::German, "bye", "Tschüss");
+
+        assert_eq!(catalog.get(Some(Locale::EnglishUK), "hello"), Some("Hello"));
+        assert_eq!(catalog.get(Some(Locale::German), "hello"), Some("Bonjour"));
+        assert_eq!(catalog.get(None, "bye"), None);
+
+        let localizations = catalog.localizations("hello");
+        assert_eq!(localizations.len(), 1);
+        assert_eq!(localizations["en-US"], "Hello");
+    }
+}
@@ -1,6 +1,28 @@
-use chrono::Local;
-use log::{Log, Metadata, Record, LevelFilter};
+//! Output and format of the built-in logger.
+//!
+//! Each line is prefixed with the name of the task which
+//! emitted it, such as `shard-0/2` or `heartbeat-0/2`. Bots
+//! using their own logger can retrieve this name with
+//! [task_name](automate::logger::task_name).
+//!
+//! ```no_run
+//! use automate::Configuration;
+//! use automate::logger::{LogFormat, Rotation};
+//! use std::time::Duration;
+//!
+//! let config = Configuration::from_env("DISCORD_API_TOKEN")
+//!     .log_to_file("bot.log", Rotation::never().max_size(10 * 1024 * 1024).every(Duration::from_secs(86400)))
+//!     .log_format(LogFormat::Json);
+//! ```
+
+use chrono::{DateTime, Local};
+use log::{Log, Metadata, Record, Level, LevelFilter};
+use std::fs::{self, File, OpenOptions};
 use std::future::Future;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 tokio::task_local! {
     static TASK_NAME: String;
@@ -11,9 +33,171 @@ pub(crate) async fn setup_for_task<F: Future>(name: String, future: F) -> F::Out
     TASK_NAME.scope(name, future).await
 }
 
-#[derive(Debug)]
+/// The name of the task currently running, such as
+/// `shard-0/2`, or `None` outside of the tasks
+/// spawned by the library.
+pub fn task_name() -> Option<String> {
+    TASK_NAME.try_with(|name| name.clone()).ok()
+}
+
+/// A line emitted by the library or the bot.
+pub struct LogRecord<'a> {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: &'a str,
+    pub task: Option<&'a str>,
+    pub message: String,
+}
+
+/// Formats the lines written by the built-in logger.
+#[derive(Clone)]
+pub enum LogFormat {
+    /// `2020-01-01 00:00:00 in automate::gateway(shard-0/2) [INFO]: message`
+    Text,
+    /// One JSON object per line with the `time`, `level`,
+    /// `target`, `task` and `message` keys.
+    Json,
+    Custom(Arc<dyn Fn(&LogRecord) -> String + Send + Sync>),
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+impl LogFormat {
+    /// Formats the lines with the given function.
+    pub fn custom<F: Fn(&LogRecord) -> String + Send + Sync + 'static>(format: F) -> LogFormat {
+        LogFormat::Custom(Arc::new(format))
+    }
+
+    fn format(&self, record: &LogRecord) -> String {
+        match self {
+            LogFormat::Text => format!(
+                "{} in {}({}) [{}]: {}",
+                record.time.format("%Y-%m-%d %H:%M:%S"),
+                record.target,
+                record.task.unwrap_or("unknown"),
+                record.level,
+                record.message
+            ),
+            LogFormat::Json => serde_json::json!({
+                "time": record.time.to_rfc3339(),
+                "level": record.level.as_str(),
+                "target": record.target,
+                "task": record.task,
+                "message": record.message,
+            }).to_string(),
+            LogFormat::Custom(format) => format(record),
+        }
+    }
+}
+
+/// Where the built-in logger writes the lines.
+#[derive(Clone, Debug)]
+pub enum LogOutput {
+    Stdout,
+    File(PathBuf, Rotation),
+}
+
+impl Default for LogOutput {
+    fn default() -> Self {
+        LogOutput::Stdout
+    }
+}
+
+/// When the log file is moved aside to start a new one.
+/// The previous file is renamed by appending the time
+/// of the rotation to its name.
+#[derive(Clone, Debug, Default)]
+pub struct Rotation {
+    max_size: Option<u64>,
+    period: Option<Duration>,
+}
+
+impl Rotation {
+    /// Always writes to the same file.
+    pub fn never() -> Rotation {
+        Rotation::default()
+    }
+
+    /// Rotates the file once it reaches the given size in bytes.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Rotates the file after the given duration.
+    pub fn every(mut self, period: Duration) -> Self {
+        self.period = Some(period);
+        self
+    }
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    file: File,
+    size: u64,
+    opened: Instant,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, rotation: Rotation) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(RotatingFile {
+            path,
+            rotation,
+            file,
+            size,
+            opened: Instant::now(),
+        })
+    }
+
+    fn should_rotate(&self, line: usize) -> bool {
+        let too_large = self.rotation.max_size
+            .map_or(false, |max| self.size > 0 && self.size + line as u64 > max);
+
+        let too_old = self.rotation.period
+            .map_or(false, |period| self.opened.elapsed() >= period);
+
+        too_large || too_old
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(Local::now().format(".%Y-%m-%d-%H-%M-%S%.3f").to_string());
+
+        fs::rename(&self.path, rotated)?;
+        *self = RotatingFile::open(self.path.clone(), self.rotation.clone())?;
+
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.should_rotate(line.len() + 1) {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+
+        Ok(())
+    }
+}
+
+enum Writer {
+    Stdout,
+    File(Mutex<RotatingFile>),
+}
+
 struct QuickLogger {
-    levels: Vec<(String, LevelFilter)>
+    levels: Vec<(String, LevelFilter)>,
+    format: LogFormat,
+    writer: Writer,
 }
 
 impl Log for QuickLogger {
@@ -34,36 +218,33 @@ impl Log for QuickLogger {
             return;
         }
 
-        let time = Local::now().format("%Y-%m-%d %H:%M:%S");
-
-        let result = TASK_NAME.try_with(|task_name| {
-            println!(
-                "{} in {}({}) [{}]: {}",
-                time,
-                record.target(),
-                task_name,
-                record.level(),
-                record.args()
-            );
+        let task = task_name();
+        let line = self.format.format(&LogRecord {
+            time: Local::now(),
+            level: record.level(),
+            target: record.target(),
+            task: task.as_deref(),
+            message: record.args().to_string(),
         });
 
-        if result.is_err() {
-            println!(
-                "{} in {}(unknown) [{}]: {}",
-                time,
-                record.target(),
-                record.level(),
-                record.args()
-            );
+        match &self.writer {
+            Writer::Stdout => println!("{}", line),
+            Writer::File(file) => if let Err(err) = file.lock().unwrap().write_line(&line) {
+                eprintln!("Failed to write logs: {}", err);
+            }
         }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Writer::File(file) = &self.writer {
+            let _ = file.lock().unwrap().file.flush();
+        }
+    }
 }
 
 /// Sets up a very basic logger that prints
-/// logs to stdout.
-pub fn __internal_setup_logging(levels: Vec<(String, LevelFilter)>) {
+/// logs to stdout or to a file.
+pub fn __internal_setup_logging(levels: Vec<(String, LevelFilter)>, output: LogOutput, format: LogFormat) {
     let mut max_level = LevelFilter::Off;
     for (_, level) in &levels {
         if *level >= max_level {
@@ -71,10 +252,66 @@ pub fn __internal_setup_logging(levels: Vec<(String, LevelFilter)>) {
         }
     }
 
+    let writer = match output {
+        LogOutput::Stdout => Writer::Stdout,
+        LogOutput::File(path, rotation) => match RotatingFile::open(path.clone(), rotation) {
+            Ok(file) => Writer::File(Mutex::new(file)),
+            Err(err) => {
+                eprintln!("Failed to open log file {}, logging to stdout: {}", path.display(), err);
+                Writer::Stdout
+            }
+        }
+    };
+
     let logger = QuickLogger {
-        levels
+        levels,
+        format,
+        writer,
     };
 
     log::set_boxed_logger(Box::new(logger)).unwrap();
     log::set_max_level(max_level);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(message: &str) -> LogRecord<'static> {
+        LogRecord {
+            time: Local::now(),
+            level: Level::Info,
+            target: "automate::gateway",
+            task: Some("shard-0"),
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn formats() {
+        assert!(LogFormat::Text.format(&record("hello")).ends_with("in automate::gateway(shard-0) [INFO]: hello"));
+
+        let json: serde_json::Value = serde_json::from_str(&LogFormat::Json.format(&record("hello"))).unwrap();
+        assert_eq!(json["task"], "shard-0");
+        assert_eq!(json["level"], "INFO");
+
+        let custom = LogFormat::custom(|record| format!("{} {}", record.level, record.message));
+        assert_eq!(custom.format(&record("hello")), "INFO hello");
+    }
+
+    #[test]
+    fn rotates_on_size() {
+        let dir = std::env::temp_dir().join(format!("automate-logs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("bot.log");
+        let mut file = RotatingFile::open(path.clone(), Rotation::never().max_size(10)).unwrap();
+        file.write_line("12345678").unwrap();
+        file.write_line("12345678").unwrap();
+
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "12345678\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+//! Parsing and formatting of the mentions Discord
+//! recognizes in message content.
+//!
+//! ```
+//! use automate::Snowflake;
+//! use automate::mention::{Mention, Mentionable};
+//!
+//! let content = format!("Hello {}!", Snowflake(80351110224678912).mention());
+//! assert_eq!(content, "Hello <@80351110224678912>!");
+//! assert_eq!(Mention::parse_all(&content), vec![Mention::User(Snowflake(80351110224678912))]);
+//! ```
+
+use crate::Snowflake;
+use crate::gateway::*;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// A mention in a message.
+///
+/// More information on [Discord's documentation](https://discord.com/developers/docs/reference#message-formatting)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mention {
+    /// `<@id>` or `<@!id>`
+    User(Snowflake),
+    /// `<@&id>`
+    Role(Snowflake),
+    /// `<#id>`
+    Channel(Snowflake),
+    /// `<:name:id>` or `<a:name:id>` for animated emojis
+    Emoji {
+        animated: bool,
+        name: String,
+        id: Snowflake,
+    },
+    /// `<t:timestamp>` or `<t:timestamp:style>` where the
+    /// timestamp is in seconds since the unix epoch
+    Timestamp {
+        timestamp: i64,
+        style: Option<char>,
+    },
+}
+
+impl Mention {
+    /// Finds all the mentions in the given content
+    /// in the order they appear.
+    pub fn parse_all(content: &str) -> Vec<Mention> {
+        let mut mentions = Vec::new();
+        let mut rest = content;
+
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+
+            match rest.find('>') {
+                Some(end) => match rest[..=end].parse::<Mention>() {
+                    Ok(mention) => {
+                        mentions.push(mention);
+                        rest = &rest[end + 1..];
+                    }
+                    //another mention may start between the two chevrons
+                    Err(_) => rest = &rest[1..]
+                },
+                None => break
+            }
+        }
+
+        mentions
+    }
+
+    /// Replaces the mentions of the given content with the
+    /// value returned by `f`, mentions for which `f` returns
+    /// `None` are left untouched.
+    pub fn replace_all<F>(content: &str, mut f: F) -> String
+        where F: FnMut(&Mention) -> Option<String> {
+        let mut replaced = String::with_capacity(content.len());
+        let mut rest = content;
+
+        while let Some(start) = rest.find('<') {
+            replaced.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let end = match rest.find('>') {
+                Some(end) => end,
+                None => break
+            };
+
+            match rest[..=end].parse::<Mention>() {
+                Ok(mention) => {
+                    match f(&mention) {
+                        Some(replacement) => replaced.push_str(&replacement),
+                        None => replaced.push_str(&rest[..=end]),
+                    }
+
+                    rest = &rest[end + 1..];
+                }
+                Err(_) => {
+                    replaced.push('<');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        replaced.push_str(rest);
+        replaced
+    }
+}
+
+/// Error returned when a string is not a valid mention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMention;
+
+impl Display for InvalidMention {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid mention")
+    }
+}
+
+impl std::error::Error for InvalidMention {}
+
+fn snowflake(s: &str) -> Result<Snowflake, InvalidMention> {
+    s.parse::<u64>().map(Snowflake).map_err(|_| InvalidMention)
+}
+
+impl FromStr for Mention {
+    type Err = InvalidMention;
+
+    fn from_str(s: &str) -> Result<Mention, InvalidMention> {
+        let inner = s.strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .ok_or(InvalidMention)?;
+
+        if let Some(role) = inner.strip_prefix("@&") {
+            Ok(Mention::Role(snowflake(role)?))
+        } else if let Some(user) = inner.strip_prefix("@!") {
+            Ok(Mention::User(snowflake(user)?))
+        } else if let Some(user) = inner.strip_prefix('@') {
+            Ok(Mention::User(snowflake(user)?))
+        } else if let Some(channel) = inner.strip_prefix('#') {
+            Ok(Mention::Channel(snowflake(channel)?))
+        } else if let Some(timestamp) = inner.strip_prefix("t:") {
+            let mut parts = timestamp.splitn(2, ':');
+            let timestamp = parts.next().unwrap().parse::<i64>().map_err(|_| InvalidMention)?;
+
+            let style = match parts.next() {
+                Some(style) if style.len() == 1 => style.chars().next(),
+                Some(_) => return Err(InvalidMention),
+                None => None
+            };
+
+            Ok(Mention::Timestamp { timestamp, style })
+        } else {
+            let (animated, emoji) = match inner.strip_prefix("a:") {
+                Some(emoji) => (true, emoji),
+                None => (false, inner.strip_prefix(':').ok_or(InvalidMention)?)
+            };
+
+            let mut parts = emoji.rsplitn(2, ':');
+            let id = snowflake(parts.next().unwrap())?;
+
+            match parts.next() {
+                Some(name) if !name.is_empty() => Ok(Mention::Emoji {
+                    animated,
+                    name: name.to_owned(),
+                    id,
+                }),
+                _ => Err(InvalidMention)
+            }
+        }
+    }
+}
+
+impl Display for Mention {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Mention::User(id) => write!(f, "<@{}>", id),
+            Mention::Role(id) => write!(f, "<@&{}>", id),
+            Mention::Channel(id) => write!(f, "<#{}>", id),
+            Mention::Emoji { animated: true, name, id } => write!(f, "<a:{}:{}>", name, id),
+            Mention::Emoji { animated: false, name, id } => write!(f, "<:{}:{}>", name, id),
+            Mention::Timestamp { timestamp, style: Some(style) } => write!(f, "<t:{}:{}>", timestamp, style),
+            Mention::Timestamp { timestamp, style: None } => write!(f, "<t:{}>", timestamp),
+        }
+    }
+}
+
+/// An object that can be mentioned in a message.
+pub trait Mentionable {
+    fn mention(&self) -> Mention;
+}
+
+/// A snowflake is assumed to be the
+/// id of a user when mentioned.
+impl Mentionable for Snowflake {
+    fn mention(&self) -> Mention {
+        Mention::User(*self)
+    }
+}
+
+macro_rules! mentionable {
+    ($variant:ident: $($struct:ty),*) => {
+        $(
+            impl Mentionable for $struct {
+                fn mention(&self) -> Mention {
+                    Mention::$variant(self.id)
+                }
+            }
+        )*
+    }
+}
+
+mentionable!(User: User, PartialUser, MentionnedUser);
+mentionable!(Role: Role, PartialRole);
+mentionable!(Channel: Category, TextChannel, VoiceChannel, NewsChannel, StoreChannel, StageChannel, ThreadChannel, ChannelMention);
+
+impl Mentionable for GuildMember {
+    fn mention(&self) -> Mention {
+        Mention::User(self.user.id)
+    }
+}
+
+impl Mentionable for GuildChannel {
+    fn mention(&self) -> Mention {
+        Mention::Channel(crate::Identifiable::id(self))
+    }
+}
+
+impl Mentionable for GuildEmoji {
+    fn mention(&self) -> Mention {
+        Mention::Emoji {
+            animated: self.animated,
+            name: self.name.clone(),
+            id: self.id,
+        }
+    }
+}
+
+impl Message {
+    /// Finds all the mentions in the content of the message.
+    pub fn parse_mentions(&self) -> Vec<Mention> {
+        Mention::parse_all(&self.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_all() {
+        let content = "<@1> <@!2> <@&3> <#4> <:smile:5> <a:dance:6> <t:1600000000> <t:1600000000:R>";
+
+        assert_eq!(Mention::parse_all(content), vec![
+            Mention::User(Snowflake(1)),
+            Mention::User(Snowflake(2)),
+            Mention::Role(Snowflake(3)),
+            Mention::Channel(Snowflake(4)),
+            Mention::Emoji { animated: false, name: String::from("smile"), id: Snowflake(5) },
+            Mention::Emoji { animated: true, name: String::from("dance"), id: Snowflake(6) },
+            Mention::Timestamp { timestamp: 1600000000, style: None },
+            Mention::Timestamp { timestamp: 1600000000, style: Some('R') },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(Mention::parse_all("a < b <@abc> <@12"), vec![]);
+        assert_eq!(Mention::parse_all("<<@1>>"), vec![Mention::User(Snowflake(1))]);
+        assert_eq!(Mention::parse_all("<::1> <:a:b>"), vec![]);
+    }
+
+    #[test]
+    fn test_replace_all() {
+        let replaced = Mention::replace_all("a <<@1> <#2> <@", |mention| match mention {
+            Mention::User(_) => Some(String::from("@user")),
+            _ => None
+        });
+
+        assert_eq!(replaced, "a <@user <#2> <@");
+    }
+
+    #[test]
+    fn test_format() {
+        for mention in &["<@1>", "<@&3>", "<#4>", "<:smile:5>", "<a:dance:6>", "<t:16:R>", "<t:16>"] {
+            assert_eq!(&mention.parse::<Mention>().unwrap().to_string(), mention);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Counters recorded by the library and by the bot.
+//!
+//! The library counts the events received and the
+//! listeners that failed, labelled by event name. Bots
+//! can record their own counters through
+//! [Context::metrics](automate::Context::metrics).
+//!
+//! With the `metrics` feature, the library also counts the
+//! HTTP requests, rate limit hits and reconnections and
+//! keeps track of the size of the storages. The registry
+//! can then be exported in Prometheus' text format using
+//! [Metrics::gather](automate::metrics::Metrics::gather)
+//! or served by the bot on the address given to
+//! [Configuration::serve_metrics](automate::Configuration::serve_metrics).
+//!
+//! ```
+//! # use automate::{listener, Context, Error};
+//! # use automate::gateway::MessageCreateDispatch;
+//! #[listener]
+//! async fn open_ticket(ctx: &Context, data: &MessageCreateDispatch) -> Result<(), Error> {
+//!     if data.0.content.starts_with("!ticket") {
+//!         ctx.metrics().increment("tickets_opened", &[("channel", &data.0.channel_id.to_string())]);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "metrics")]
+use std::fmt::Write;
+#[cfg(feature = "metrics")]
+use std::net::SocketAddr;
+#[cfg(feature = "metrics")]
+use std::sync::Arc;
+#[cfg(feature = "metrics")]
+use crate::Error;
+
+/// Name of the counter of events received from the gateway.
+pub const EVENTS_TOTAL: &str = "automate_events_total";
+
+/// Name of the counter of listeners that returned an error.
+pub const LISTENER_ERRORS_TOTAL: &str = "automate_listener_errors_total";
+
+/// Name of the counter of connections to the
+/// gateway that were interrupted and restarted,
+/// labelled by shard and cause which is one of
+/// `requested`, `invalid_session`, `zombie`,
+/// `closed` or `error`.
+pub const RECONNECTS_TOTAL: &str = "automate_reconnects_total";
+
+/// Name of the counter of requests sent to the
+/// HTTP API, labelled by endpoint and status code.
+pub const HTTP_REQUESTS_TOTAL: &str = "automate_http_requests_total";
+
+/// Name of the counter of requests that were
+/// rate limited, labelled by endpoint.
+pub const RATE_LIMIT_HITS_TOTAL: &str = "automate_rate_limit_hits_total";
+
+/// Name of the gauge of the amount of objects
+/// in each storage, labelled by storage and shard.
+pub const CACHE_SIZE: &str = "automate_cache_size";
+
+/// A counter identified by its name
+/// and its labels sorted by name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CounterKey {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+}
+
+impl CounterKey {
+    fn new(name: &str, labels: &[(&str, &str)]) -> CounterKey {
+        let mut labels: Vec<(String, String)> = labels.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        labels.sort();
+
+        CounterKey {
+            name: name.to_owned(),
+            labels,
+        }
+    }
+}
+
+/// Registry of the counters of the bot, shared
+/// by all the shards of a configuration.
+#[derive(Default, Debug)]
+pub struct Metrics {
+    counters: RwLock<HashMap<CounterKey, AtomicU64>>,
+    gauges: RwLock<HashMap<CounterKey, AtomicU64>>,
+}
+
+impl Metrics {
+    /// Increments the counter with the given
+    /// name and labels by one.
+    #[inline]
+    pub fn increment(&self, name: &str, labels: &[(&str, &str)]) {
+        self.add(name, labels, 1);
+    }
+
+    /// Adds `value` to the counter with the
+    /// given name and labels.
+    pub fn add(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        let key = CounterKey::new(name, labels);
+
+        if let Some(counter) = self.counters.read().unwrap().get(&key) {
+            counter.fetch_add(value, Ordering::Relaxed);
+            return;
+        }
+
+        self.counters.write().unwrap()
+            .entry(key)
+            .or_default()
+            .fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Current value of the counter with
+    /// the given name and labels.
+    pub fn get(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        self.counters.read().unwrap()
+            .get(&CounterKey::new(name, labels))
+            .map_or(0, |c| c.load(Ordering::Relaxed))
+    }
+
+    /// Values of all the counters, sorted
+    /// by name and labels.
+    pub fn counters(&self) -> Vec<(CounterKey, u64)> {
+        sorted(&self.counters)
+    }
+
+    /// Sets the gauge with the given name and
+    /// labels to `value`.
+    pub fn set(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        let key = CounterKey::new(name, labels);
+
+        if let Some(gauge) = self.gauges.read().unwrap().get(&key) {
+            gauge.store(value, Ordering::Relaxed);
+            return;
+        }
+
+        self.gauges.write().unwrap()
+            .entry(key)
+            .or_default()
+            .store(value, Ordering::Relaxed);
+    }
+
+    /// Current value of the gauge with
+    /// the given name and labels.
+    pub fn gauge(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        self.gauges.read().unwrap()
+            .get(&CounterKey::new(name, labels))
+            .map_or(0, |g| g.load(Ordering::Relaxed))
+    }
+
+    /// Values of all the gauges, sorted
+    /// by name and labels.
+    pub fn gauges(&self) -> Vec<(CounterKey, u64)> {
+        sorted(&self.gauges)
+    }
+
+    /// Exports the counters and gauges in
+    /// Prometheus' text exposition format.
+    #[cfg(feature = "metrics")]
+    pub fn gather(&self) -> String {
+        let mut output = String::new();
+
+        for (kind, values) in &[("counter", self.counters()), ("gauge", self.gauges())] {
+            let mut previous: Option<&str> = None;
+
+            for (key, value) in values {
+                if previous != Some(&key.name) {
+                    writeln!(output, "# TYPE {} {}", key.name, kind).unwrap();
+                    previous = Some(&key.name);
+                }
+
+                output.push_str(&key.name);
+
+                if !key.labels.is_empty() {
+                    let labels: Vec<String> = key.labels.iter()
+                        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                        .collect();
+
+                    write!(output, "{{{}}}", labels.join(",")).unwrap();
+                }
+
+                writeln!(output, " {}", value).unwrap();
+            }
+        }
+
+        output
+    }
+}
+
+fn sorted(map: &RwLock<HashMap<CounterKey, AtomicU64>>) -> Vec<(CounterKey, u64)> {
+    let mut values: Vec<(CounterKey, u64)> = map.read().unwrap()
+        .iter()
+        .map(|(k, v)| (k.clone(), v.load(Ordering::Relaxed)))
+        .collect();
+
+    values.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name).then_with(|| a.labels.cmp(&b.labels)));
+    values
+}
+
+#[cfg(feature = "metrics")]
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serves the metrics in Prometheus' text format
+/// to any HTTP request received on `address`.
+#[cfg(feature = "metrics")]
+pub async fn serve(metrics: Arc<Metrics>, address: SocketAddr) -> Result<(), Error> {
+    use hyper::{Body, Response, Server};
+    use hyper::service::{make_service_fn, service_fn};
+    use std::convert::Infallible;
+
+    let service = make_service_fn(move |_| {
+        let metrics = Arc::clone(&metrics);
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |_| {
+                let body = metrics.gather();
+
+                async move {
+                    Ok::<_, Infallible>(Response::builder()
+                        .header("Content-Type", "text/plain; version=0.0.4")
+                        .body(Body::from(body))
+                        .unwrap())
+                }
+            }))
+        }
+    });
+
+    Ok(Server::try_bind(&address)?.serve(service).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_order_does_not_matter() {
+        let metrics = Metrics::default();
+        metrics.increment("tickets", &[("a", "1"), ("b", "2")]);
+        metrics.add("tickets", &[("b", "2"), ("a", "1")], 2);
+        metrics.increment("tickets", &[]);
+
+        assert_eq!(metrics.get("tickets", &[("a", "1"), ("b", "2")]), 3);
+        assert_eq!(metrics.get("tickets", &[]), 1);
+        assert_eq!(metrics.get("unknown", &[]), 0);
+        assert_eq!(metrics.counters().len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn gather_prometheus_format() {
+        let metrics = Metrics::default();
+        metrics.increment(EVENTS_TOTAL, &[("event", "ready")]);
+        metrics.add(EVENTS_TOTAL, &[("event", "message_create")], 3);
+        metrics.increment("quotes", &[("text", "say \"hi\"")]);
+        metrics.set(CACHE_SIZE, &[("storage", "guilds"), ("shard", "0")], 12);
+
+        assert_eq!(metrics.gather(), "\
+            # TYPE automate_events_total counter\n\
+            automate_events_total{event=\"message_create\"} 3\n\
+            automate_events_total{event=\"ready\"} 1\n\
+            # TYPE quotes counter\n\
+            quotes{text=\"say \\\"hi\\\"\"} 1\n\
+            # TYPE automate_cache_size gauge\n\
+            automate_cache_size{shard=\"0\",storage=\"guilds\"} 12\n");
+    }
+}
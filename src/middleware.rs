@@ -0,0 +1,69 @@
+#![allow(unused_variables)]
+//! Hooks running around the dispatch of every event
+//! received from the gateway.
+//!
+//! Middlewares are registered on the configuration using
+//! [Configuration::add_middleware](automate::Configuration::add_middleware)
+//! and are called in the order they were registered.
+//!
+//! ```
+//! use automate::{async_trait, Context, Error};
+//! use automate::middleware::{Middleware, Event};
+//! use automate::gateway::MessageCreateDispatch;
+//!
+//! struct IgnoreBots;
+//!
+//! #[async_trait]
+//! impl Middleware for IgnoreBots {
+//!     async fn before_event(&self, _ctx: &Context<'_>, event: &Event<'_>) -> bool {
+//!         match event.downcast::<MessageCreateDispatch>() {
+//!             Some(message) => !message.0.author.bot.unwrap_or(false),
+//!             None => true
+//!         }
+//!     }
+//!
+//!     async fn after_event(&self, _ctx: &Context<'_>, event: &Event<'_>, errors: &[Error]) {
+//!         for error in errors {
+//!             println!("A listener to {} failed: {}", event.name, error);
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::{Context, Error};
+use async_trait::async_trait;
+use std::any::Any;
+
+/// An event about to be or that was just
+/// dispatched to the listeners.
+pub struct Event<'a> {
+    /// Name of the event in snake case,
+    /// for example `message_create`.
+    pub name: &'static str,
+    /// The dispatch struct of the event.
+    pub data: &'a (dyn Any + Send + Sync),
+}
+
+impl<'a> Event<'a> {
+    /// Returns the dispatch struct if it is of type `T`.
+    pub fn downcast<T: Any>(&self) -> Option<&'a T> {
+        self.data.downcast_ref::<T>()
+    }
+}
+
+/// Runs code before and after the listeners of every
+/// event, see the [middleware](automate::middleware)
+/// module for an example.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    /// Called before the event is sent to the listeners.
+    /// Returning false prevents the listeners and the
+    /// following middlewares from receiving the event.
+    async fn before_event(&self, ctx: &Context<'_>, event: &Event<'_>) -> bool {
+        true
+    }
+
+    /// Called after all the listeners finished with
+    /// the errors returned by the listeners that failed.
+    async fn after_event(&self, ctx: &Context<'_>, event: &Event<'_>, errors: &[Error]) {}
+}
@@ -0,0 +1,237 @@
+//! Checks the permissions of the bot against the storages
+//! before sending requests to the HTTP API. Requires the
+//! `storage` feature.
+//!
+//! Actions sent through [Context::preflight](automate::Context::preflight)
+//! fail with an [Error::MissingPermission](automate::Error::MissingPermission)
+//! describing the missing permissions instead of sending a request
+//! that would be rejected by Discord with a 403, which does not
+//! use the rate-limit budget of the endpoint.
+//!
+//! The checks are best effort: the action is sent without any check
+//! when the guild, the channel or the bot member are not cached.
+//!
+//! ```
+//! # use automate::{Context, Error};
+//! # use automate::gateway::MessageCreateDispatch;
+//! async fn clean(ctx: &Context<'_>, data: &MessageCreateDispatch) -> Result<(), Error> {
+//!     match ctx.preflight().delete_message(&data.0).await {
+//!         Err(Error::MissingPermission(missing)) => {
+//!             println!("Can not delete the message, missing {:x}", missing.needed);
+//!             Ok(())
+//!         }
+//!         result => result,
+//!     }
+//! }
+//! ```
+
+use crate::{Error, Snowflake};
+use crate::encode::ExtractSnowflake;
+use crate::gateway::{Channel, Guild, Message, Permission, User};
+use crate::http::{HttpAPI, CreateMessage, ModifyChannel};
+use crate::storage::StorageContainer;
+
+/// Sends actions to the HTTP API after checking the
+/// cached permissions of the bot. Created by
+/// [Context::preflight](automate::Context::preflight).
+pub struct Preflight<'a> {
+    pub(crate) storage: &'a StorageContainer,
+    pub(crate) http: &'a HttpAPI,
+    pub(crate) bot: &'a User,
+}
+
+impl<'a> Preflight<'a> {
+    /// Checks that the bot has the given permissions in the
+    /// channel, overwrites included.
+    pub async fn check_channel<S: ExtractSnowflake>(&self, channel: S, needed: u32) -> Result<(), Error> {
+        let channel = channel.extract_snowflake()?;
+
+        let guild = match self.storage.read::<Channel>().await.get_opt(channel).and_then(Channel::guild_id) {
+            Some(guild) => guild,
+            None => return Ok(()),
+        };
+
+        let guilds = self.storage.read::<Guild>().await;
+        let permissions = guilds.get_opt(guild)
+            .and_then(|guild| guild.channel_permissions(guild.members.get(&self.bot.id)?, channel));
+
+        match permissions {
+            Some(permissions) if permissions & needed != needed => Error::missing_permission(needed & !permissions, Some(channel)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the bot has the given guild-wide permissions.
+    pub async fn check_guild<S: ExtractSnowflake>(&self, guild: S, needed: u32) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+
+        let guilds = self.storage.read::<Guild>().await;
+        let permissions = guilds.get_opt(guild)
+            .and_then(|guild| Some(guild.base_permissions(guild.members.get(&self.bot.id)?)));
+
+        match permissions {
+            Some(permissions) if permissions & needed != needed => Error::missing_permission(needed & !permissions, None),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the bot has the given guild-wide permissions
+    /// and that its highest role is above the highest role of
+    /// the member.
+    pub async fn check_member<S: ExtractSnowflake>(&self, guild: S, member: S, needed: u32) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let member = member.extract_snowflake()?;
+
+        self.check_guild(guild, needed).await?;
+
+        let guilds = self.storage.read::<Guild>().await;
+        let outranks = guilds.get_opt(guild)
+            .and_then(|guild| Some(guild.outranks(guild.members.get(&self.bot.id)?, guild.members.get(&member)?)));
+
+        match outranks {
+            Some(false) => Error::hierarchy(needed),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the bot can manage roles and that
+    /// its highest role is above the role.
+    pub async fn check_role<S: ExtractSnowflake>(&self, guild: S, role: S) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let role = role.extract_snowflake()?;
+        let needed = Permission::ManageRoles as u32;
+
+        self.check_guild(guild, needed).await?;
+
+        let guilds = self.storage.read::<Guild>().await;
+        let outranks = guilds.get_opt(guild).and_then(|guild| {
+            let bot = guild.members.get(&self.bot.id)?;
+            let role = guild.roles.get(&role)?;
+
+            Some(bot.user.id == guild.owner_id || guild.highest_role_position(bot) > role.position)
+        });
+
+        match outranks {
+            Some(false) => Error::hierarchy(needed),
+            _ => Ok(()),
+        }
+    }
+
+    /// See [HttpAPI::create_message](automate::HttpAPI::create_message).
+    pub async fn create_message<S: ExtractSnowflake>(&self, channel: S, message: CreateMessage) -> Result<Message, Error> {
+        let channel = channel.extract_snowflake()?;
+        let thread = matches!(self.storage.read::<Channel>().await.get_opt(channel), Some(Channel::Thread(_)));
+
+        //threads require a permission that does not fit in the permissions of the library
+        let mut needed = Permission::ViewChannel as u32;
+        if !thread {
+            needed |= Permission::SendMessages as u32;
+        }
+
+        if !message.files.is_empty() || message.attachment.is_some() {
+            needed |= Permission::AttachFiles as u32;
+        }
+
+        self.check_channel(channel, needed).await?;
+        self.http.create_message(channel, message).await
+    }
+
+    /// Deletes the message, the bot needs to be able to manage
+    /// messages unless it is the author of the message.
+    pub async fn delete_message(&self, message: &Message) -> Result<(), Error> {
+        if message.author.id != self.bot.id {
+            self.check_channel(message.channel_id, Permission::ManageMessages as u32).await?;
+        }
+
+        self.http.delete_message(message.channel_id, message.id).await
+    }
+
+    /// See [HttpAPI::delete_message_bulk](automate::HttpAPI::delete_message_bulk).
+    pub async fn delete_message_bulk<S: ExtractSnowflake>(&self, channel: S, messages: Vec<S>) -> Result<(), Error> {
+        let channel = channel.extract_snowflake()?;
+
+        self.check_channel(channel, Permission::ManageMessages as u32).await?;
+        self.http.delete_message_bulk(channel, messages.iter().map(S::extract_snowflake).collect::<Result<Vec<Snowflake>, Error>>()?).await
+    }
+
+    /// See [HttpAPI::pin_message](automate::HttpAPI::pin_message).
+    pub async fn pin_message<S: ExtractSnowflake>(&self, channel: S, message: S) -> Result<(), Error> {
+        let channel = channel.extract_snowflake()?;
+
+        self.check_channel(channel, Permission::ManageMessages as u32).await?;
+        self.http.pin_message(channel, message.extract_snowflake()?).await
+    }
+
+    /// See [HttpAPI::unpin_message](automate::HttpAPI::unpin_message).
+    pub async fn unpin_message<S: ExtractSnowflake>(&self, channel: S, message: S) -> Result<(), Error> {
+        let channel = channel.extract_snowflake()?;
+
+        self.check_channel(channel, Permission::ManageMessages as u32).await?;
+        self.http.unpin_message(channel, message.extract_snowflake()?).await
+    }
+
+    /// See [HttpAPI::modify_channel](automate::HttpAPI::modify_channel).
+    pub async fn modify_channel<S: ExtractSnowflake>(&self, channel: S, modification: ModifyChannel) -> Result<Channel, Error> {
+        let channel = channel.extract_snowflake()?;
+
+        let mut needed = Permission::ManageChannels as u32;
+        if modification.permission_overwrites.is_some() {
+            needed |= Permission::ManageRoles as u32;
+        }
+
+        self.check_channel(channel, needed).await?;
+        self.http.modify_channel(channel, modification).await
+    }
+
+    /// See [HttpAPI::delete_channel](automate::HttpAPI::delete_channel).
+    pub async fn delete_channel<S: ExtractSnowflake>(&self, channel: S) -> Result<Channel, Error> {
+        let channel = channel.extract_snowflake()?;
+
+        self.check_channel(channel, Permission::ManageChannels as u32).await?;
+        self.http.delete_channel(channel).await
+    }
+
+    /// See [HttpAPI::create_ban](automate::HttpAPI::create_ban).
+    pub async fn create_ban<S: ExtractSnowflake>(&self, guild: S, user: S, reason: Option<&str>, delete_days: Option<i8>) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let user = user.extract_snowflake()?;
+
+        self.check_member(guild, user, Permission::BanMembers as u32).await?;
+        self.http.create_ban(guild, user, reason, delete_days).await
+    }
+
+    /// See [HttpAPI::remove_ban](automate::HttpAPI::remove_ban).
+    pub async fn remove_ban<S: ExtractSnowflake>(&self, guild: S, user: S) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+
+        self.check_guild(guild, Permission::BanMembers as u32).await?;
+        self.http.remove_ban(guild, user.extract_snowflake()?).await
+    }
+
+    /// Kicks the member, see [HttpAPI::remove_member](automate::HttpAPI::remove_member).
+    pub async fn remove_member<S: ExtractSnowflake>(&self, guild: S, user: S) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let user = user.extract_snowflake()?;
+
+        self.check_member(guild, user, Permission::KickMembers as u32).await?;
+        self.http.remove_member(guild, user).await
+    }
+
+    /// See [HttpAPI::member_add_role](automate::HttpAPI::member_add_role).
+    pub async fn member_add_role<S: ExtractSnowflake>(&self, guild: S, user: S, role: S) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let role = role.extract_snowflake()?;
+
+        self.check_role(guild, role).await?;
+        self.http.member_add_role(guild, user.extract_snowflake()?, role).await
+    }
+
+    /// See [HttpAPI::member_remove_role](automate::HttpAPI::member_remove_role).
+    pub async fn member_remove_role<S: ExtractSnowflake>(&self, guild: S, user: S, role: S) -> Result<(), Error> {
+        let guild = guild.extract_snowflake()?;
+        let role = role.extract_snowflake()?;
+
+        self.check_role(guild, role).await?;
+        self.http.member_remove_role(guild, user.extract_snowflake()?, role).await
+    }
+}
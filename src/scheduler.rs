@@ -0,0 +1,167 @@
+//! Jobs running periodically or once alongside the
+//! shards of the bot.
+//!
+//! Jobs are registered on the configuration using
+//! [Configuration::schedule](automate::Configuration::schedule)
+//! and are started by the [ShardManager](automate::ShardManager)
+//! when the shards are launched. By default a job runs once for
+//! the whole bot, [Schedule::per_shard](automate::scheduler::Schedule::per_shard)
+//! runs it once for each shard launched by the manager instead.
+//!
+//! ```
+//! use automate::Configuration;
+//! use automate::scheduler::every;
+//! use std::time::Duration;
+//!
+//! let config = Configuration::new("token")
+//!     .schedule(every(Duration::from_secs(60)), |ctx| async move {
+//!         let guilds = ctx.bot_guilds().await?;
+//!         println!("The bot is in {} guilds", guilds.len());
+//!
+//!         Ok(())
+//!     });
+//! ```
+
+use crate::{HttpAPI, Error, logger};
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+
+type JobFn = Arc<dyn Fn(JobContext) -> BoxFuture<'static, Result<(), Error>> + Send + Sync>;
+
+/// When a job runs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    delay: Duration,
+    period: Option<Duration>,
+    per_shard: bool,
+}
+
+impl Schedule {
+    /// Waits the given duration before the first
+    /// execution of a recurring job instead of
+    /// running it as soon as the job is started.
+    pub fn starting_after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Runs the job once for each shard launched by the
+    /// [ShardManager](automate::ShardManager).
+    pub fn per_shard(mut self) -> Self {
+        self.per_shard = true;
+        self
+    }
+
+    pub fn is_per_shard(&self) -> bool {
+        self.per_shard
+    }
+}
+
+/// Runs the job every `period`, starting
+/// as soon as the shards are launched.
+pub fn every(period: Duration) -> Schedule {
+    Schedule {
+        delay: Duration::from_secs(0),
+        period: Some(period),
+        per_shard: false,
+    }
+}
+
+/// Runs the job once after `delay`.
+pub fn after(delay: Duration) -> Schedule {
+    Schedule {
+        delay,
+        period: None,
+        per_shard: false,
+    }
+}
+
+/// The handle given to the jobs, dereferences
+/// to [HttpAPI](automate::HttpAPI).
+#[derive(Clone)]
+pub struct JobContext {
+    pub http: HttpAPI,
+    /// The shard this job runs for if the job
+    /// was scheduled [per shard](automate::scheduler::Schedule::per_shard).
+    pub shard_id: Option<u32>,
+}
+
+impl Deref for JobContext {
+    type Target = HttpAPI;
+
+    #[inline]
+    fn deref(&self) -> &HttpAPI {
+        &self.http
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Job {
+    pub(crate) schedule: Schedule,
+    run: JobFn,
+}
+
+impl Job {
+    pub(crate) fn new<F, R>(schedule: Schedule, job: F) -> Job
+        where F: Fn(JobContext) -> R + Send + Sync + 'static,
+              R: Future<Output = Result<(), Error>> + Send + 'static {
+        Job {
+            schedule,
+            run: Arc::new(move |ctx| Box::pin(job(ctx))),
+        }
+    }
+
+    /// Spawns a task running the job until its
+    /// schedule ends.
+    pub(crate) fn spawn(&self, ctx: JobContext) {
+        let schedule = self.schedule;
+        let run = Arc::clone(&self.run);
+
+        let name = match ctx.shard_id {
+            Some(id) => format!("scheduler-{}", id),
+            None => String::from("scheduler"),
+        };
+
+        tokio::spawn(logger::setup_for_task(name, async move {
+            tokio::time::sleep(schedule.delay).await;
+
+            match schedule.period {
+                Some(period) => {
+                    let mut interval = tokio::time::interval(period);
+
+                    loop {
+                        interval.tick().await;
+
+                        if let Err(err) = run(ctx.clone()).await {
+                            error!("Scheduled job failed: {}", err);
+                        }
+                    }
+                }
+                None => if let Err(err) = run(ctx).await {
+                    error!("Scheduled job failed: {}", err);
+                }
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules() {
+        let schedule = every(Duration::from_secs(60));
+        assert_eq!(schedule.period, Some(Duration::from_secs(60)));
+        assert_eq!(schedule.delay, Duration::from_secs(0));
+        assert!(!schedule.is_per_shard());
+
+        let schedule = after(Duration::from_secs(5)).per_shard();
+        assert_eq!(schedule.period, None);
+        assert_eq!(schedule.delay, Duration::from_secs(5));
+        assert!(schedule.is_per_shard());
+    }
+}
@@ -1,7 +1,17 @@
-use crate::{HttpAPI, Snowflake, Configuration, logger, Error};
-use crate::gateway::GatewayAPI;
-use futures::future;
-use std::time::Duration;
+use crate::{HttpAPI, Snowflake, Configuration, BotHandle, logger, Error};
+use crate::gateway::{GatewayAPI, ShardSender, Intent};
+use crate::handle::ShardHandle;
+use crate::scheduler::JobContext;
+use futures::future::{self, Either};
+use tokio::task::{JoinHandle, JoinError};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long the new shards have to receive their
+/// ready event once they identified when resharding.
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+type ShardConfigFn = Box<dyn Fn(u32, Configuration) -> Configuration + Send + Sync>;
 
 /// Helps setting up a bot with multiple shards.
 ///
@@ -18,23 +28,78 @@ pub struct ShardManager {
     config: Configuration,
     total_shards: u32,
     recommended_shards: u32,
+    max_concurrency: u32,
     gateway_url: String,
     managed_shards: Vec<u32>,
+    handle: Option<BotHandle>,
+    shard_config: Option<ShardConfigFn>,
+    reshard_interval: Option<Duration>,
+}
+
+/// A shard launched by the manager.
+struct RunningShard {
+    id: u32,
+    handle: ShardHandle,
+    task: JoinHandle<()>,
 }
 
 impl ShardManager {
     /// Creates a shard manager where all the shards
     /// will use the given config.
+    ///
+    /// Fails if the configured intents contain privileged
+    /// intents which are not enabled for the application.
     pub async fn with_config(config: Configuration) -> Result<ShardManager, Error> {
-        let http = HttpAPI::new(&config.token);
+        let http = HttpAPI::for_config(&config);
+
+        let application = http.current_application().await?;
+        let missing = application.missing_intents(config.intents);
+        if !missing.is_empty() {
+            let names = missing.iter()
+                .map(|intent| match intent {
+                    Intent::GuildPresences => "Presence Intent",
+                    Intent::MessageContent => "Message Content Intent",
+                    _ => "Server Members Intent",
+                })
+                .collect::<Vec<&str>>()
+                .join(" and ");
+
+            return Error::err(format!(
+                "The bot requested the privileged intent(s) {:?} but they are not enabled for the application, \
+                enable the {} toggle in the bot tab of https://discord.com/developers/applications/{}/bot \
+                or remove the intent(s) from the configuration",
+                missing, names, application.id
+            ));
+        }
+
+        if config.strict_intents {
+            let missing = config.missing_intents();
+
+            if !missing.is_empty() {
+                let events = missing.iter()
+                    .map(|(event, intents)| format!("`{}` requires one of {:?}", event, intents))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                return Error::err(format!(
+                    "Listeners are registered for events that require intents missing from the configuration: {}",
+                    events
+                ));
+            }
+        }
+
         let gateway_bot = http.gateway_bot().await?;
 
         Ok(ShardManager {
             config,
             total_shards: gateway_bot.shards,
             recommended_shards: gateway_bot.shards,
+            max_concurrency: gateway_bot.session_start_limit.max_concurrency.max(1),
             gateway_url: gateway_bot.url,
             managed_shards: Vec::new(),
+            handle: None,
+            shard_config: None,
+            reshard_interval: None,
         })
     }
 
@@ -51,50 +116,274 @@ impl ShardManager {
         self
     }
 
+    /// Customizes the configuration of each shard. The
+    /// function receives the id of the shard and a copy
+    /// of the manager's configuration and returns the
+    /// configuration the shard will use.
+    ///
+    /// Logging, metrics and scheduled jobs are set up
+    /// once for the whole process from the manager's
+    /// configuration and can not be changed per shard.
+    ///
+    /// ```no_run
+    /// # use automate::{Configuration, ShardManager};
+    /// # use automate::gateway::Presence;
+    /// # automate::tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let mut manager = ShardManager::with_config(Configuration::from_env("DISCORD_API_TOKEN")).await?;
+    ///
+    /// manager.configure_shards(|shard_id, config| {
+    ///     config.presence(Presence::playing(format!("on shard {}", shard_id)))
+    /// });
+    /// # Ok::<(), automate::Error>(())
+    /// # });
+    /// ```
+    pub fn configure_shards<F>(&mut self, f: F) -> &mut Self
+        where F: Fn(u32, Configuration) -> Configuration + Send + Sync + 'static {
+        self.shard_config = Some(Box::new(f));
+        self
+    }
+
+    /// Periodically checks the amount of shards recommended
+    /// by Discord and reshards the bot when it grows beyond
+    /// the current amount of shards.
+    ///
+    /// Resharding launches a new set of shards using the
+    /// recommended amount and closes the current shards once
+    /// all the new ones are ready, which means events received
+    /// during the handover may be dispatched twice. The storages
+    /// of the new shards are filled from scratch by the guild
+    /// create events they receive.
+    ///
+    /// Only available when the manager launches all the
+    /// shards of the bot.
+    pub fn auto_reshard(&mut self, interval: Duration) -> &mut Self {
+        self.reshard_interval = Some(interval);
+        self
+    }
+
     /// Launches all the previously set up shards
     pub async fn launch(&mut self) {
         if self.config.logging {
-            logger::__internal_setup_logging(self.config.log_levels.clone());
+            logger::__internal_setup_logging(self.config.log_levels.clone(), self.config.log_output.clone(), self.config.log_format.clone());
+        }
+
+        for (event, intents) in self.config.missing_intents() {
+            warn!("Listeners to `{}` will never be called without one of the intents {:?}", event, intents);
+        }
+
+        if self.config.lacks_message_content() {
+            warn!("Messages will be received without their content, embeds and attachments except in direct \
+                messages and when mentioning the bot, add the `MessageContent` intent to receive them");
         }
 
         if self.recommended_shards > self.total_shards {
             warn!("Discord recommends using {} shards, you should use at least this many shards", self.recommended_shards);
         }
 
-        let mut handles = Vec::new();
+        #[cfg(feature = "metrics")]
+        if let Some(address) = self.config.metrics_address {
+            let metrics = self.config.metrics();
 
-        for (position, shard_id) in self.managed_shards.iter().enumerate() {
-            let shard_id = *shard_id;
+            tokio::spawn(async move {
+                if let Err(err) = crate::metrics::serve(metrics, address).await {
+                    error!("Failed to serve metrics on {}: {}", address, err);
+                }
+            });
+        }
 
-            let url = self.gateway_url.clone();
-            let mut config = self.config.clone();
-            config.shard(shard_id, self.total_shards);
+        let http = HttpAPI::for_config(&self.config);
 
-            let handle = tokio::spawn(async move {
-                //there must be at least 5 seconds between each identify call
-                //so wait 5.5 seconds to make sure we don't hit rate limit
-                tokio::time::sleep(Duration::from_millis(position as u64 * 5500)).await;
+        for job in self.config.jobs.iter().filter(|job| !job.schedule.is_per_shard()) {
+            job.spawn(JobContext { http: http.clone(), shard_id: None });
+        }
 
-                automate::logger::setup_for_task(format!("shard-{}", shard_id),  async move {
-                    GatewayAPI::connect(config, url).await
-                }).await
+        for shard_id in &self.managed_shards {
+            self.spawn_shard_jobs(&http, *shard_id);
+        }
+
+        let bot_handle = self.handle();
+        let shards = self.start_shards(&self.managed_shards, self.total_shards, self.max_concurrency, &self.gateway_url);
+
+        for shard in &shards {
+            bot_handle.register(shard.id, shard.handle.clone());
+        }
+
+        #[cfg(feature = "botlists")]
+        if !self.config.bot_lists.is_empty() {
+            let task = crate::botlists::post_task(bot_handle.clone(), self.config.bot_lists.clone(), self.config.bot_list_interval);
+            tokio::spawn(logger::setup_for_task(String::from("botlists"), task));
+        }
+
+        match self.reshard_interval {
+            Some(interval) if self.manages_all_shards() => self.reshard_loop(interval, shards, &http).await,
+            Some(_) => {
+                warn!("Automatic resharding requires the manager to launch all the shards, it will be disabled");
+                join_shards(shards).await;
+            }
+            None => join_shards(shards).await,
+        }
+    }
+
+    fn manages_all_shards(&self) -> bool {
+        let mut shards = self.managed_shards.clone();
+        shards.sort_unstable();
+        shards.dedup();
+
+        shards.into_iter().eq(0..self.total_shards)
+    }
+
+    fn spawn_shard_jobs(&self, http: &HttpAPI, shard_id: u32) {
+        for job in self.config.jobs.iter().filter(|job| job.schedule.is_per_shard()) {
+            job.spawn(JobContext { http: http.clone(), shard_id: Some(shard_id) });
+        }
+    }
+
+    /// Spawns a task for each of the given shards, waiting
+    /// for their identify bucket to be available.
+    fn start_shards(&self, shard_ids: &[u32], total_shards: u32, max_concurrency: u32, url: &str) -> Vec<RunningShard> {
+        let delays = identify_delays(shard_ids, max_concurrency);
+        let mut shards = Vec::with_capacity(shard_ids.len());
+
+        for (shard_id, delay) in shard_ids.iter().copied().zip(delays) {
+            let url = url.to_owned();
+            let mut config = match &self.shard_config {
+                Some(shard_config) => shard_config(shard_id, self.config.clone()),
+                None => self.config.clone(),
+            };
+
+            config.shard(shard_id, total_shards);
+
+            let sender = ShardSender::default();
+            let handle = ShardHandle {
+                sender: sender.clone(),
+                #[cfg(feature = "storage")]
+                storage: config.storages.share(),
+            };
+
+            let task = tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+
+                let connection = GatewayAPI::connect(config, url, sender);
+
+                #[cfg(feature = "tracing")]
+                let connection = tracing::Instrument::instrument(connection, tracing::info_span!("shard", shard_id, total_shards));
+
+                let result = automate::logger::setup_for_task(format!("shard-{}/{}", shard_id, total_shards), connection).await;
+
+                if let Err(err) = result {
+                    error!("Shard {} stopped: {}", shard_id, err);
+                }
             });
 
-            handles.push(handle);
+            shards.push(RunningShard { id: shard_id, handle, task });
         }
 
-        for result in future::join_all(handles).await {
-            if let Err(err) = result {
-                error!("Failed to join shard: {}", err);
+        shards
+    }
+
+    /// Runs the shards until they all stop, replacing
+    /// them when Discord recommends more shards.
+    async fn reshard_loop(&mut self, interval: Duration, mut shards: Vec<RunningShard>, http: &HttpAPI) {
+        loop {
+            let tasks = future::join_all(shards.iter_mut().map(|shard| &mut shard.task));
+
+            if let Either::Left((results, _)) = future::select(tasks, Box::pin(tokio::time::sleep(interval))).await {
+                log_join_errors(results);
+                return;
+            }
+
+            let gateway_bot = match http.gateway_bot().await {
+                Ok(gateway_bot) => gateway_bot,
+                Err(err) => {
+                    warn!("Failed to check the amount of shards recommended by Discord: {}", err);
+                    continue;
+                }
+            };
+
+            let total_shards = gateway_bot.shards;
+            if total_shards <= self.total_shards {
+                continue;
+            }
+
+            let limit = gateway_bot.session_start_limit;
+            if limit.remaining < total_shards as i32 {
+                warn!(
+                    "Discord recommends using {} shards but only {} sessions can be started, resharding in {} ms",
+                    total_shards, limit.remaining, limit.reset_after
+                );
+                continue;
+            }
+
+            info!("Discord recommends using {} shards instead of {}, resharding", total_shards, self.total_shards);
+
+            let shard_ids: Vec<u32> = (0..total_shards).collect();
+            let max_concurrency = limit.max_concurrency.max(1);
+            let new_shards = self.start_shards(&shard_ids, total_shards, max_concurrency, &gateway_bot.url);
+
+            let identify_time = identify_delays(&shard_ids, max_concurrency).into_iter().max().unwrap_or_default();
+            if !wait_ready(&new_shards, identify_time + READY_TIMEOUT).await {
+                error!("The new shards did not connect in time, keeping the current {} shards", self.total_shards);
+
+                for shard in new_shards {
+                    shard.handle.sender.stop();
+                }
+
+                continue;
+            }
+
+            for shard_id in self.total_shards..total_shards {
+                self.spawn_shard_jobs(http, shard_id);
             }
+
+            let handles = new_shards.iter()
+                .map(|shard| (shard.id, shard.handle.clone()))
+                .collect();
+
+            self.handle().replace(total_shards, handles);
+
+            for shard in shards {
+                shard.handle.sender.stop();
+            }
+
+            info!("Resharded the bot to {} shards", total_shards);
+
+            shards = new_shards;
+            self.managed_shards = shard_ids;
+            self.total_shards = total_shards;
+            self.recommended_shards = total_shards;
+            self.max_concurrency = max_concurrency;
+            self.gateway_url = gateway_bot.url;
         }
     }
 
+    /// Handle giving access to the HTTP API, the storages
+    /// and the gateway of the shards from outside of the
+    /// listeners. The shards become available in the
+    /// handle once [launched](automate::ShardManager::launch).
+    ///
+    /// The total amount of shards can not be changed
+    /// after the handle has been created.
+    pub fn handle(&mut self) -> BotHandle {
+        if self.handle.is_none() {
+            let http = HttpAPI::for_config(&self.config);
+            self.handle = Some(BotHandle::new(http, self.config.metrics(), self.total_shards));
+        }
+
+        self.handle.clone().unwrap()
+    }
+
     /// The amount of shards recommended by discord
     pub fn recommended_shards(&self) -> u32 {
         self.recommended_shards
     }
 
+    /// The amount of shards that can identify at
+    /// the same time, shards are started in batches
+    /// of this size.
+    pub fn max_concurrency(&self) -> u32 {
+        self.max_concurrency
+    }
+
     /// The amount of shards this bot will have.
     /// Defaults to the recommended shards value.
     pub fn total_shards(&self) -> u32 {
@@ -107,6 +396,10 @@ impl ShardManager {
             panic!("Changing total shards count after a shard has been launched is not possible");
         }
 
+        if self.handle.is_some() {
+            panic!("Changing total shards count after the bot handle has been created is not possible");
+        }
+
         self.total_shards = total_shards;
         self
     }
@@ -117,3 +410,67 @@ impl ShardManager {
 pub fn shard_id(total_shards: u64, guild_id: Snowflake) -> u32 {
     ((guild_id.0 >> 22) % total_shards) as u32
 }
+
+async fn join_shards(shards: Vec<RunningShard>) {
+    log_join_errors(future::join_all(shards.into_iter().map(|shard| shard.task)).await);
+}
+
+fn log_join_errors(results: Vec<Result<(), JoinError>>) {
+    for result in results {
+        if let Err(err) = result {
+            error!("Failed to join shard: {}", err);
+        }
+    }
+}
+
+/// Waits until all the shards received their
+/// ready event or the timeout elapsed.
+async fn wait_ready(shards: &[RunningShard], timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+
+    while Instant::now() < deadline {
+        if shards.iter().all(|shard| shard.handle.sender.is_ready()) {
+            return true;
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    false
+}
+
+/// How long each shard waits before identifying.
+///
+/// Shards identify in rate limit buckets of id
+/// `shard_id % max_concurrency`. There must be
+/// at least 5 seconds between two identify calls
+/// in the same bucket, so shards of a bucket are
+/// spaced by 5.5 seconds to make sure the rate limit
+/// is not hit while the buckets start in parallel.
+fn identify_delays(shards: &[u32], max_concurrency: u32) -> Vec<Duration> {
+    let mut buckets: HashMap<u32, u64> = HashMap::new();
+
+    shards.iter()
+        .map(|shard_id| {
+            let position = buckets.entry(shard_id % max_concurrency).or_insert(0);
+            let delay = Duration::from_millis(*position * 5500);
+            *position += 1;
+
+            delay
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identify_buckets() {
+        let delays = identify_delays(&[0, 1, 2, 3], 1);
+        assert_eq!(delays, vec![0, 5500, 11000, 16500].into_iter().map(Duration::from_millis).collect::<Vec<_>>());
+
+        let delays = identify_delays(&[0, 1, 2, 3, 4, 5, 6, 7], 4);
+        assert_eq!(delays, vec![0, 0, 0, 0, 5500, 5500, 5500, 5500].into_iter().map(Duration::from_millis).collect::<Vec<_>>());
+    }
+}
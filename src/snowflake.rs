@@ -23,6 +23,18 @@ pub trait Identifiable {
 #[derive(Serialize)]
 pub struct Snowflake(pub u64);
 
+/// First millisecond of 2015 from which the
+/// timestamps of the snowflakes are counted.
+const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+
+impl Snowflake {
+    /// Unix time in milliseconds at which the
+    /// object identified by the snowflake was created.
+    pub fn timestamp_millis(&self) -> u64 {
+        (self.0 >> 22) + DISCORD_EPOCH
+    }
+}
+
 struct SnowflakeVisitor;
 
 impl<'de> Visitor<'de> for SnowflakeVisitor {
@@ -91,3 +103,13 @@ impl DerefMut for Snowflake {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_timestamp() {
+        assert_eq!(Snowflake(175928847299117063).timestamp_millis(), 1_462_015_105_796);
+    }
+}
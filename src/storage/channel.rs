@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use crate::{Snowflake, Identifiable};
 use crate::gateway::*;
-use crate::storage::{Stored, Storage};
+use crate::storage::{Stored, Storage, Query};
 
 #[derive(Default, Debug, Clone)]
 pub struct ChannelStorage {
-    channels: HashMap<Snowflake, Channel>
+    channels: HashMap<Snowflake, Arc<Channel>>,
+    direct_channels: HashMap<Snowflake, Snowflake>,
+    guild_channels: HashMap<Snowflake, HashSet<Snowflake>>,
 }
 
 impl Storage for ChannelStorage {}
@@ -14,9 +17,17 @@ impl Stored for Channel {
     type Storage = ChannelStorage;
 }
 
+impl Query for ChannelStorage {
+    type Item = Channel;
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Channel> + '_> {
+        Box::new(self.channels.values().map(Arc::as_ref))
+    }
+}
+
 impl ChannelStorage {
     pub fn all(&self) -> Vec<&Channel> {
-        self.channels.values().collect()
+        self.channels.values().map(Arc::as_ref).collect()
     }
 
     pub fn get(&self, id: Snowflake) -> &Channel {
@@ -58,6 +69,13 @@ impl ChannelStorage {
         }
     }
 
+    pub fn thread(&self, id: Snowflake) -> &ThreadChannel {
+        match self.get_opt(id).unwrap() {
+            Channel::Thread(channel) => channel,
+            _ => panic!("Given channel is not a thread")
+        }
+    }
+
     pub fn direct(&self, id: Snowflake) -> &DirectChannel {
         match self.get_opt(id).unwrap() {
             Channel::Direct(channel) => channel,
@@ -65,6 +83,15 @@ impl ChannelStorage {
         }
     }
 
+    /// The direct channel with the given user if it was
+    /// opened by [Context::direct_message](automate::Context::direct_message).
+    pub fn direct_with(&self, user: Snowflake) -> Option<&DirectChannel> {
+        match self.get_opt(*self.direct_channels.get(&user)?) {
+            Some(Channel::Direct(channel)) => Some(channel),
+            _ => None
+        }
+    }
+
     pub fn group(&self, id: Snowflake) -> &GroupChannel {
         match self.get_opt(id).unwrap() {
             Channel::Group(channel) => channel,
@@ -72,19 +99,41 @@ impl ChannelStorage {
         }
     }
 
+    /// Channels and threads of the given guild.
+    pub fn guild(&self, guild: Snowflake) -> Vec<&Channel> {
+        self.guild_channels.get(&guild)
+            .map(|channels| channels.iter().filter_map(|c| self.get_opt(*c)).collect())
+            .unwrap_or_default()
+    }
+
     pub fn get_opt(&self, id: Snowflake) -> Option<&Channel> {
-        self.channels.get(&id)
+        self.channels.get(&id).map(Arc::as_ref)
     }
 
     pub(crate) fn get_mut(&mut self, id: Snowflake) -> Option<&mut Channel> {
-        self.channels.get_mut(&id)
+        self.channels.get_mut(&id).map(Arc::make_mut)
     }
 
     pub(crate) fn insert(&mut self, channel: Channel) {
-        self.channels.insert(channel.id(), channel);
+        if let Some(guild) = channel.guild_id() {
+            self.guild_channels.entry(guild).or_default().insert(channel.id());
+        }
+
+        self.channels.insert(channel.id(), Arc::new(channel));
+    }
+
+    pub(crate) fn insert_direct(&mut self, user: Snowflake, channel: DirectChannel) {
+        self.direct_channels.insert(user, channel.id);
+        self.insert(Channel::Direct(channel));
     }
 
     pub(crate) fn remove(&mut self, channel: Snowflake) {
-        self.channels.remove(&channel);
+        if let Some(guild) = self.channels.remove(&channel).and_then(|c| c.guild_id()) {
+            if let Some(channels) = self.guild_channels.get_mut(&guild) {
+                channels.remove(&channel);
+            }
+        }
+
+        self.direct_channels.retain(|_, direct| *direct != channel);
     }
 }
\ No newline at end of file
@@ -1,11 +1,16 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::Snowflake;
 use crate::gateway::*;
-use crate::storage::{Stored, Storage};
+use crate::storage::{Stored, Storage, Query};
 
+/// Guilds are kept behind an [Arc](std::sync::Arc) which
+/// makes cloning the storage (for example when taking a
+/// [snapshot](automate::storage::StorageContainer::snapshot))
+/// cheap since guilds themselves are not copied.
 #[derive(Default, Debug, Clone)]
 pub struct GuildStorage {
-    guilds: HashMap<Snowflake, Guild>
+    guilds: HashMap<Snowflake, Arc<Guild>>
 }
 
 impl Storage for GuildStorage {}
@@ -14,9 +19,17 @@ impl Stored for Guild {
     type Storage = GuildStorage;
 }
 
+impl Query for GuildStorage {
+    type Item = Guild;
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Guild> + '_> {
+        Box::new(self.guilds.values().map(Arc::as_ref))
+    }
+}
+
 impl GuildStorage {
     pub fn all(&self) -> Vec<&Guild> {
-        self.guilds.values().collect()
+        self.guilds.values().map(Arc::as_ref).collect()
     }
 
     pub fn get(&self, id: Snowflake) -> &Guild {
@@ -24,18 +37,20 @@ impl GuildStorage {
     }
 
     pub fn get_opt(&self, id: Snowflake) -> Option<&Guild> {
-        self.guilds.get(&id)
+        self.guilds.get(&id).map(Arc::as_ref)
     }
 
+    /// Mutable reference to the guild, the guild
+    /// is cloned if a snapshot still references it.
     pub(crate) fn get_mut(&mut self, id: Snowflake) -> Option<&mut Guild> {
-        self.guilds.get_mut(&id)
+        self.guilds.get_mut(&id).map(Arc::make_mut)
     }
 
     pub(crate) fn insert(&mut self, guild: Guild) {
-        self.guilds.insert(guild.id, guild);
+        self.guilds.insert(guild.id, Arc::new(guild));
     }
 
     pub(crate) fn remove(&mut self, guild: Snowflake) -> Option<Guild> {
-        self.guilds.remove(&guild)
+        self.guilds.remove(&guild).map(|g| Arc::try_unwrap(g).unwrap_or_else(|g| Guild::clone(&g)))
     }
-}
\ No newline at end of file
+}
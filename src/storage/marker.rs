@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use futures::lock::Mutex;
+use crate::Snowflake;
+use crate::storage::{Stored, Storage};
+
+/// A message sent by the bot and identified by a marker,
+/// used by [Context::upsert_message](automate::Context::upsert_message)
+/// to edit the same message instead of sending a new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkedMessage {
+    pub channel_id: Snowflake,
+    pub message_id: Snowflake,
+}
+
+/// Messages of the bot identified by a marker in each
+/// channel, kept across sessions of the shard.
+///
+/// The markers are only kept in memory: once the bot is
+/// restarted, [upsert_message](automate::Context::upsert_message)
+/// sends a new message instead of editing the one sent before
+/// the restart. Bots which need to edit the same message across
+/// restarts can [insert](automate::storage::MarkerStorage::insert)
+/// the markers they saved when the shard is ready.
+#[derive(Default, Debug, Clone)]
+pub struct MarkerStorage {
+    messages: HashMap<(Snowflake, String), Snowflake>,
+    locks: HashMap<(Snowflake, String), Arc<Mutex<()>>>,
+}
+
+impl Storage for MarkerStorage {}
+
+impl Stored for MarkedMessage {
+    type Storage = MarkerStorage;
+}
+
+impl MarkerStorage {
+    /// The message marked with the given marker in the channel.
+    pub fn get(&self, channel: Snowflake, marker: &str) -> Option<MarkedMessage> {
+        self.messages.get(&(channel, marker.to_owned())).map(|message| MarkedMessage {
+            channel_id: channel,
+            message_id: *message,
+        })
+    }
+
+    /// Marks the message and returns the message
+    /// previously marked with the same marker.
+    pub fn insert<M: Into<String>>(&mut self, channel: Snowflake, marker: M, message: Snowflake) -> Option<Snowflake> {
+        self.messages.insert((channel, marker.into()), message)
+    }
+
+    pub fn remove(&mut self, channel: Snowflake, marker: &str) -> Option<Snowflake> {
+        self.messages.remove(&(channel, marker.to_owned()))
+    }
+
+    /// The lock held while a message is found or sent
+    /// for the marker, so that concurrent upserts do
+    /// not send the message twice.
+    pub(crate) fn lock(&mut self, channel: Snowflake, marker: &str) -> Arc<Mutex<()>> {
+        //locks which are not held anymore are forgotten
+        self.locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+
+        let lock = self.locks.entry((channel, marker.to_owned())).or_default();
+        Arc::clone(lock)
+    }
+
+    /// Forgets the marker of a deleted message.
+    pub(crate) fn forget(&mut self, channel: Snowflake, message: Snowflake) {
+        self.messages.retain(|(c, _), m| *c != channel || *m != message);
+    }
+}
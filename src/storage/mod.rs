@@ -1,15 +1,32 @@
 mod guild;
 mod channel;
 mod user;
+mod presence;
+mod voice;
+mod persistent;
+mod policy;
+mod settings;
+mod marker;
+mod snapshot;
 
 pub use guild::*;
 pub use channel::*;
 pub use user::*;
+pub use presence::*;
+pub use voice::*;
+pub use persistent::*;
+pub use policy::*;
+pub use settings::*;
+pub use marker::*;
+pub use snapshot::Snapshot;
 
 use crate::gateway::*;
 use std::collections::HashMap;
 use std::any::{TypeId, Any};
+use std::time::Instant;
+use std::sync::Arc;
 use crate::{Identifiable, Snowflake};
+use snapshot::Generation;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 pub trait Stored {
@@ -18,9 +35,60 @@ pub trait Stored {
 
 pub trait Storage: Send + Sync {}
 
+/// Common way of searching the built-in storages
+/// without copying their content.
+///
+/// ```
+/// # use automate::listener;
+/// use automate::{Context, Error};
+/// use automate::gateway::{MessageCreateDispatch, Guild};
+/// use automate::storage::Query;
+///
+/// #[listener]
+/// async fn large_guilds(ctx: &Context, _: &MessageCreateDispatch) -> Result<(), Error> {
+///     let guilds = ctx.storage::<Guild>().await;
+///     let large = guilds.count_where(|g| g.members.len() > 1000);
+///     let rust = guilds.find(|g| g.name == "Rust");
+///
+///     Ok(())
+/// }
+/// ```
+pub trait Query: Storage {
+    type Item;
+
+    /// Iterates over the values of the storage.
+    fn iter(&self) -> Box<dyn Iterator<Item = &Self::Item> + '_>;
+
+    /// The first value matching the predicate.
+    fn find<F: Fn(&Self::Item) -> bool>(&self, predicate: F) -> Option<&Self::Item> {
+        self.iter().find(|item| predicate(item))
+    }
+
+    /// All the values matching the predicate.
+    fn filter<F: Fn(&Self::Item) -> bool>(&self, predicate: F) -> Vec<&Self::Item> {
+        self.iter().filter(|item| predicate(item)).collect()
+    }
+
+    /// Amount of values in the storage.
+    fn count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Amount of values matching the predicate.
+    fn count_where<F: Fn(&Self::Item) -> bool>(&self, predicate: F) -> usize {
+        self.iter().filter(|item| predicate(item)).count()
+    }
+}
+
 pub struct StorageContainer {
     init: Vec<Box<dyn Fn(&mut StorageContainer) + Send + Sync>>,
-    storages: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    storages: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    generations: HashMap<TypeId, Arc<Generation>>,
+    policy: CachePolicy,
+    last_eviction: Option<Instant>,
+    /// The user of the bot, never evicted from the
+    /// members of the guilds.
+    bot: Option<Snowflake>,
 }
 
 /// This implementation of clone is a bit special since
@@ -30,6 +98,7 @@ pub struct StorageContainer {
 impl Clone for StorageContainer {
     fn clone(&self) -> Self {
         let mut container = StorageContainer::for_use(self.init.len());
+        container.policy = self.policy.clone();
 
         for callback in &self.init {
             callback(&mut container);
@@ -44,6 +113,10 @@ impl StorageContainer {
         StorageContainer {
             init: Vec::with_capacity(5),
             storages: HashMap::new(),
+            generations: HashMap::new(),
+            policy: CachePolicy::default(),
+            last_eviction: None,
+            bot: None,
         }
     }
 
@@ -51,6 +124,10 @@ impl StorageContainer {
         StorageContainer {
             init: Vec::new(),
             storages: HashMap::with_capacity(capacity),
+            generations: HashMap::with_capacity(capacity),
+            policy: CachePolicy::default(),
+            last_eviction: None,
+            bot: None,
         }
     }
 
@@ -62,16 +139,41 @@ impl StorageContainer {
         self.init.push(Box::new(initializer));
     }
 
+    /// Container giving access to the same storages
+    /// as this one, unlike [clone](Clone::clone)
+    /// which creates new empty storages.
+    pub(crate) fn share(&self) -> StorageContainer {
+        StorageContainer {
+            init: Vec::new(),
+            storages: self.storages.clone(),
+            generations: self.generations.clone(),
+            policy: self.policy.clone(),
+            last_eviction: None,
+            bot: self.bot,
+        }
+    }
+
+    /// Whether the storage of the given type was initialized.
+    pub(crate) fn contains(&self, id: TypeId) -> bool {
+        self.storages.contains_key(&id)
+    }
+
+    pub(crate) fn set_policy(&mut self, policy: CachePolicy) {
+        self.policy = policy;
+    }
+
     /// Initialize the storage with a default
     /// empty storage.
     pub fn initialize<T: Stored + 'static>(&mut self) where T::Storage: Default {
-        self.storages.insert(TypeId::of::<T>(), Box::new(RwLock::new(T::Storage::default())));
+        self.storages.insert(TypeId::of::<T>(), Arc::new(RwLock::new(T::Storage::default())));
+        self.generations.insert(TypeId::of::<T>(), Arc::default());
     }
 
     /// Initialize the storage with the provided
     /// existing storage instance.
     pub fn existing<T: Stored + 'static>(&mut self, storage: T::Storage) {
-        self.storages.insert(TypeId::of::<T>(), Box::new(RwLock::new(storage)));
+        self.storages.insert(TypeId::of::<T>(), Arc::new(RwLock::new(storage)));
+        self.generations.insert(TypeId::of::<T>(), Arc::default());
     }
 
     pub async fn read<T: Stored + 'static>(&self) -> RwLockReadGuard<'_, T::Storage> {
@@ -82,10 +184,55 @@ impl StorageContainer {
     }
 
     pub async fn write<T: Stored + 'static>(&self) -> RwLockWriteGuard<'_, T::Storage> {
-        self.storages
+        let guard = self.storages
             .get(&TypeId::of::<T>()).expect("Storage has never been initialized")
             .downcast_ref::<RwLock<T::Storage>>().expect("Failed to downcast storage")
-            .write().await
+            .write().await;
+
+        //incremented while holding the guard so that a snapshot
+        //never gets tagged with the generation of a pending write
+        self.generations[&TypeId::of::<T>()].increment();
+
+        guard
+    }
+
+    /// Owned copy of the storage which does not hold
+    /// any lock once returned. The built-in storages keep
+    /// their entries behind an [Arc](std::sync::Arc) so
+    /// taking a snapshot only clones pointers.
+    ///
+    /// Prefer this over [read](automate::storage::StorageContainer::read)
+    /// when iterating over a storage while awaiting
+    /// other futures.
+    pub async fn snapshot<T: Stored + 'static>(&self) -> T::Storage where T::Storage: Clone {
+        self.read::<T>().await.clone()
+    }
+
+    /// Shared immutable copy of the storage. The copy is
+    /// only made when the storage was modified since the
+    /// last snapshot, listeners requesting a snapshot of an
+    /// unchanged storage get the same one.
+    pub async fn storage_snapshot<T: Stored + 'static>(&self) -> Snapshot<T::Storage> where T::Storage: Clone + 'static {
+        let storage = self.read::<T>().await;
+        self.generations[&TypeId::of::<T>()].snapshot(|| T::Storage::clone(&storage))
+    }
+
+    /// Sets the size gauges of the built-in storages
+    /// once they have been initialized.
+    #[cfg(feature = "metrics")]
+    pub(crate) async fn record_sizes(&self, metrics: &crate::metrics::Metrics, shard: u32) {
+        use crate::metrics::CACHE_SIZE;
+
+        if !self.storages.contains_key(&TypeId::of::<Guild>()) {
+            return;
+        }
+
+        let shard = shard.to_string();
+        metrics.set(CACHE_SIZE, &[("storage", "guilds"), ("shard", &shard)], self.read::<Guild>().await.all().len() as u64);
+        metrics.set(CACHE_SIZE, &[("storage", "channels"), ("shard", &shard)], self.read::<Channel>().await.all().len() as u64);
+        metrics.set(CACHE_SIZE, &[("storage", "users"), ("shard", &shard)], self.read::<User>().await.all().len() as u64);
+        metrics.set(CACHE_SIZE, &[("storage", "presences"), ("shard", &shard)], self.read::<PresenceUpdate>().await.all().len() as u64);
+        metrics.set(CACHE_SIZE, &[("storage", "voice_states"), ("shard", &shard)], self.read::<VoiceState>().await.all().len() as u64);
     }
 }
 
@@ -96,43 +243,93 @@ impl StorageContainer {
     /// the respective storages.
     #[inline]
     async fn insert_guild(&mut self, guild: &Guild) {
-        {
-            let mut guilds = self.write::<Guild>().await;
-            let mut new_guild = Clone::clone(guild);
+        let mut new_guild = Clone::clone(guild);
 
-            //channels and members are not sent for guild updates, so transfer them from
-            //the previous guild object
-            if let Some(guild) = guilds.remove(guild.id) {
-                new_guild.members = guild.members;
-                new_guild.channels = guild.channels;
-            }
+        if !self.policy.caches_channels(Some(guild.id)) {
+            new_guild.channels.clear();
+            new_guild.threads.clear();
+        }
 
-            guilds.insert(new_guild);
+        if let Some(max) = self.policy.max_members {
+            //keep the bot then the members with the lowest ids so
+            //that the same members are kept after a reconnection
+            let mut members: Vec<Snowflake> = new_guild.members.keys().copied().collect();
+            members.sort_by_key(|id| (Some(*id) != self.bot, id.0));
+
+            for member in members.into_iter().skip(max) {
+                new_guild.members.remove(&member);
+            }
         }
 
         {
             let mut channels = self.write::<Channel>().await;
-            for channel in guild.channels.values() {
+            for channel in new_guild.channels.values() {
                 channels.insert(Channel::from_guild(channel));
             }
+
+            for thread in new_guild.threads.values() {
+                channels.insert(Channel::Thread(Clone::clone(thread)));
+            }
         }
 
-        {
+        if self.policy.users {
             let mut users = self.write::<User>().await;
-            for member in guild.members.values() {
+            for member in new_guild.members.values() {
                 Self::insert_user(&mut users, &member.user, Some(guild.id))
             }
         }
+
+        //voice states and presences are only sent in guild create events
+        if self.policy.voice_states {
+            if let Some(states) = &guild.voice_states {
+                let mut voice_states = self.write::<VoiceState>().await;
+                for state in states {
+                    voice_states.insert(guild.id, VoiceState::from_partial(guild.id, state));
+                }
+            }
+        }
+
+        if self.policy.presences {
+            if let Some(presences) = &guild.presences {
+                let mut storage = self.write::<PresenceUpdate>().await;
+                for presence in presences {
+                    if let Some(presence) = PresenceUpdate::from_partial(guild.id, presence) {
+                        storage.insert(presence);
+                    }
+                }
+            }
+        }
+
+        {
+            let mut guilds = self.write::<Guild>().await;
+
+            //channels and members are not sent for guild updates, so transfer them from
+            //the previous guild object
+            if let Some(guild) = guilds.remove(guild.id) {
+                new_guild.members = guild.members;
+                new_guild.channels = guild.channels;
+                new_guild.threads = guild.threads;
+                new_guild.guild_scheduled_events = guild.guild_scheduled_events;
+            }
+
+            guilds.insert(new_guild);
+        }
+
+        self.evict_users().await;
     }
 
     /// Adds a channel and insert its recipients in
     /// the user storage if it is a group channel.
     #[inline]
     async fn insert_channel(&mut self, channel: &Channel) {
+        if !self.policy.caches_channels(channel.guild_id()) {
+            return;
+        }
+
         self.write::<Channel>().await.insert(Clone::clone(&channel));
 
         //insert group channel recipients
-        if let Channel::Group(channel) = &channel {
+        if let (Channel::Group(channel), true) = (&channel, self.policy.users) {
             let mut users = self.write::<User>().await;
             for user in channel.recipients.values() {
                 Self::insert_user(&mut users, user, None)
@@ -140,6 +337,61 @@ impl StorageContainer {
         }
     }
 
+    /// Adds a thread to its guild and to
+    /// the channel storage.
+    #[inline]
+    async fn insert_thread(&mut self, thread: &ThreadChannel) {
+        if !self.policy.caches_channels(thread.guild_id) {
+            return;
+        }
+
+        if let Some(guild) = thread.guild_id {
+            if let Some(guild) = self.write::<Guild>().await.get_mut(guild) {
+                guild.threads.insert(thread.id, Clone::clone(thread));
+            }
+        }
+
+        self.write::<Channel>().await.insert(Channel::Thread(Clone::clone(thread)));
+    }
+
+    /// Removes a thread from its guild and
+    /// from the channel storage.
+    #[inline]
+    async fn remove_thread(&mut self, thread: Snowflake, guild: Snowflake) {
+        if let Some(guild) = self.write::<Guild>().await.get_mut(guild) {
+            guild.threads.remove(&thread);
+        }
+
+        self.write::<Channel>().await.remove(thread);
+    }
+
+    /// Applies the modification to the thread
+    /// in both the guild and channel storages.
+    #[inline]
+    async fn update_thread<F: Fn(&mut ThreadChannel)>(&mut self, thread: Snowflake, guild: Snowflake, update: F) {
+        if let Some(guild) = self.write::<Guild>().await.get_mut(guild) {
+            if let Some(thread) = guild.threads.get_mut(&thread) {
+                update(thread);
+            }
+        }
+
+        if let Some(Channel::Thread(thread)) = self.write::<Channel>().await.get_mut(thread) {
+            update(thread);
+        }
+    }
+
+    /// Removes the users that expired according to the cache
+    /// policy, checks at most once every minute.
+    async fn evict_users(&mut self) {
+        if let Some(ttl) = self.policy.user_ttl {
+            if !matches!(self.last_eviction, Some(last) if last.elapsed().as_secs() < 60) {
+                self.last_eviction = Some(Instant::now());
+
+                self.write::<User>().await.evict(ttl);
+            }
+        }
+    }
+
     /// Adds a new role to its guild.
     #[inline]
     async fn insert_role(&mut self, role: &Role, guild: Snowflake) {
@@ -155,6 +407,8 @@ impl StorageContainer {
     /// the guild to the currently saved user.
     #[inline]
     fn insert_user(storage: &mut RwLockWriteGuard<'_, UserStorage>, user: &User, guild: Option<Snowflake>) {
+        storage.touch(user.id);
+
         if let Some(user) = storage.get_mut(user.id) {
             if let Some(guild) = guild {
                 user.guilds.insert(guild);
@@ -175,6 +429,17 @@ impl StorageContainer {
         self.initialize::<Guild>();
         self.initialize::<Channel>();
         self.initialize::<User>();
+        self.initialize::<PresenceUpdate>();
+        self.initialize::<VoiceState>();
+
+        //settings and markers are kept across sessions
+        if !self.contains(TypeId::of::<Settings>()) {
+            self.initialize::<Settings>();
+        }
+
+        if !self.contains(TypeId::of::<MarkedMessage>()) {
+            self.initialize::<MarkedMessage>();
+        }
 
         {
             let mut channels = self.write::<Channel>().await;
@@ -183,6 +448,7 @@ impl StorageContainer {
             }
         }
 
+        self.bot = Some(event.user.id);
         self.write::<User>().await.insert(event.user.clone());
     }
 
@@ -206,13 +472,84 @@ impl StorageContainer {
             Some(Channel::News(c)) => c.last_pin_timestamp = event.last_pin_timestamp.clone(),
             Some(Channel::Direct(c)) => c.last_pin_timestamp = event.last_pin_timestamp.clone(),
             Some(Channel::Group(c)) => c.last_pin_timestamp = event.last_pin_timestamp.clone(),
+            Some(Channel::Thread(c)) => c.last_pin_timestamp = event.last_pin_timestamp.clone(),
             None => (), //the DM was not loaded yet so we can't update it
             _ => panic!("Message-less channel received a pin update")
         };
     }
 
+    pub async fn on_thread_create(&mut self, event: &ThreadCreateDispatch) {
+        self.insert_thread(&event.0).await;
+    }
+
+    pub async fn on_thread_update(&mut self, event: &ThreadUpdateDispatch) {
+        self.insert_thread(&event.0).await;
+    }
+
+    pub async fn on_thread_delete(&mut self, event: &ThreadDeleteDispatch) {
+        self.remove_thread(event.id, event.guild_id).await;
+    }
+
+    /// Removes the threads of the synced channels that are
+    /// not active anymore and inserts the active ones.
+    pub async fn on_thread_list_sync(&mut self, event: &ThreadListSyncDispatch) {
+        let synced = |thread: &ThreadChannel| match (&event.channel_ids, thread.parent_id) {
+            (Some(channels), Some(parent)) => channels.contains(&parent),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+
+        let removed: Vec<Snowflake> = match self.read::<Guild>().await.get_opt(event.guild_id) {
+            Some(guild) => guild.threads.values()
+                .filter(|thread| synced(thread))
+                .map(|thread| thread.id)
+                .collect(),
+            None => Vec::new()
+        };
+
+        for thread in removed {
+            self.remove_thread(thread, event.guild_id).await;
+        }
+
+        for thread in &event.threads {
+            let mut thread = Clone::clone(thread);
+            thread.member = event.members.iter()
+                .find(|member| member.id == Some(thread.id))
+                .cloned();
+
+            self.insert_thread(&thread).await;
+        }
+    }
+
+    pub async fn on_thread_member_update(&mut self, event: &ThreadMemberUpdateDispatch) {
+        if let (Some(thread), Some(guild)) = (event.0.id, event.0.guild_id) {
+            self.update_thread(thread, guild, |t| t.member = Some(event.0.clone())).await;
+        }
+    }
+
+    pub async fn on_thread_members_update(&mut self, event: &ThreadMembersUpdateDispatch) {
+        self.update_thread(event.id, event.guild_id, |t| t.member_count = Some(event.member_count)).await;
+    }
+
     pub async fn on_guild_create(&mut self, event: &GuildCreateDispatch) {
         self.insert_guild(&event.0).await;
+
+        //the storage is not locked while the settings are loaded
+        let loader = self.write::<Settings>().await.loader(event.0.id);
+        if let Some(backend) = loader {
+            match backend.get(event.0.id).await {
+                Ok(settings) => {
+                    let save = self.write::<Settings>().await.loaded(event.0.id, settings);
+                    if let Err(err) = save.persist().await {
+                        error!("Failed to save the settings of guild {}: {}", event.0.id, err);
+                    }
+                }
+                Err(err) => {
+                    error!("Failed to load the settings of guild {}: {}", event.0.id, err);
+                    self.write::<Settings>().await.cancel_load(event.0.id);
+                }
+            }
+        }
     }
 
     pub async fn on_guild_update(&mut self, event: &GuildUpdateDispatch) {
@@ -221,16 +558,21 @@ impl StorageContainer {
 
     pub async fn on_guild_delete(&mut self, event: &GuildDeleteDispatch) {
         let id = event.id;
-        let guild: Guild = Guild::clone(self.read::<Guild>().await.get(id));
+        let guild: Guild = match self.read::<Guild>().await.get_opt(id) {
+            Some(guild) => Guild::clone(guild),
+            None => return, //the guild was never received, for example when left by the guild policy
+        };
 
         {
             let mut channels = self.write::<Channel>().await;
-            for channel in guild.channels.keys() {
+            for channel in guild.channels.keys().chain(guild.threads.keys()) {
                 channels.remove(*channel);
             }
         }
 
         self.write::<Guild>().await.remove(id);
+        self.write::<VoiceState>().await.remove_guild(id);
+        self.write::<PresenceUpdate>().await.remove_guild(id);
     }
 
     /// Removal of the user will be handled
@@ -248,20 +590,77 @@ impl StorageContainer {
 
     pub async fn on_guild_integrations_update(&mut self, _event: &GuildIntegrationsUpdateDispatch) {}
 
+    pub async fn on_guild_scheduled_event_create(&mut self, event: &GuildScheduledEventCreateDispatch) {
+        if let Some(guild) = self.write::<Guild>().await.get_mut(event.0.guild_id) {
+            guild.guild_scheduled_events.insert(event.0.id, event.0.clone());
+        }
+    }
+
+    pub async fn on_guild_scheduled_event_update(&mut self, event: &GuildScheduledEventUpdateDispatch) {
+        if let Some(guild) = self.write::<Guild>().await.get_mut(event.0.guild_id) {
+            guild.guild_scheduled_events.insert(event.0.id, event.0.clone());
+        }
+    }
+
+    pub async fn on_guild_scheduled_event_delete(&mut self, event: &GuildScheduledEventDeleteDispatch) {
+        if let Some(guild) = self.write::<Guild>().await.get_mut(event.0.guild_id) {
+            guild.guild_scheduled_events.remove(&event.0.id);
+        }
+    }
+
+    pub async fn on_guild_scheduled_event_user_add(&mut self, event: &GuildScheduledEventUserAddDispatch) {
+        if let Some(guild) = self.write::<Guild>().await.get_mut(event.guild_id) {
+            if let Some(scheduled) = guild.guild_scheduled_events.get_mut(&event.guild_scheduled_event_id) {
+                scheduled.user_count = scheduled.user_count.map(|count| count + 1);
+            }
+        }
+    }
+
+    pub async fn on_guild_scheduled_event_user_remove(&mut self, event: &GuildScheduledEventUserRemoveDispatch) {
+        if let Some(guild) = self.write::<Guild>().await.get_mut(event.guild_id) {
+            if let Some(scheduled) = guild.guild_scheduled_events.get_mut(&event.guild_scheduled_event_id) {
+                scheduled.user_count = scheduled.user_count.map(|count| count - 1);
+            }
+        }
+    }
+
+    pub async fn on_auto_moderation_rule_create(&mut self, _event: &AutoModerationRuleCreateDispatch) {}
+
+    pub async fn on_auto_moderation_rule_update(&mut self, _event: &AutoModerationRuleUpdateDispatch) {}
+
+    pub async fn on_auto_moderation_rule_delete(&mut self, _event: &AutoModerationRuleDeleteDispatch) {}
+
+    pub async fn on_auto_moderation_action_execution(&mut self, _event: &AutoModerationActionExecutionDispatch) {}
+    pub async fn on_interaction_create(&mut self, _event: &InteractionCreateDispatch) {}
+
     pub async fn on_guild_member_add(&mut self, event: &GuildMemberAddDispatch) {
-        Self::insert_user(&mut self.write::<User>().await, &event.member.user, Some(event.guild_id));
+        if self.policy.users {
+            Self::insert_user(&mut self.write::<User>().await, &event.member.user, Some(event.guild_id));
+        }
 
-        let mut guilds = self.write::<Guild>().await;
-        if let Some(guild) = guilds.get_mut(event.guild_id) {
-            guild.members.insert(event.member.user.id, event.member.clone());
+        {
+            let max = self.policy.max_members.unwrap_or(usize::MAX);
+
+            let mut guilds = self.write::<Guild>().await;
+            if let Some(guild) = guilds.get_mut(event.guild_id) {
+                if guild.members.len() < max {
+                    guild.members.insert(event.member.user.id, event.member.clone());
+                }
+            }
         }
+
+        self.evict_users().await;
     }
 
     pub async fn on_guild_member_remove(&mut self, event: &GuildMemberRemoveDispatch) {
-        let mut guilds = self.write::<Guild>().await;
-        if let Some(guild) = guilds.get_mut(event.guild_id) {
-            guild.members.remove(&event.user.id);
+        {
+            let mut guilds = self.write::<Guild>().await;
+            if let Some(guild) = guilds.get_mut(event.guild_id) {
+                guild.members.remove(&event.user.id);
+            }
         }
+
+        self.write::<PresenceUpdate>().await.remove(event.guild_id, event.user.id);
     }
 
     pub async fn on_guild_member_update(&mut self, event: &GuildMemberUpdateDispatch) {
@@ -271,7 +670,11 @@ impl StorageContainer {
                 member.user = event.user.clone();
                 member.nick = event.nick.clone();
                 member.roles = event.roles.clone();
-                member.premium_since = event.premium_since;
+
+                //the boost date is not always sent
+                if event.premium_since.is_some() {
+                    member.premium_since = event.premium_since;
+                }
             }
         }
     }
@@ -301,9 +704,17 @@ impl StorageContainer {
 
     pub async fn on_message_update(&mut self, _event: &MessageUpdateDispatch) {}
 
-    pub async fn on_message_delete(&mut self, _event: &MessageDeleteDispatch) {}
+    pub async fn on_message_delete(&mut self, event: &MessageDeleteDispatch) {
+        self.write::<MarkedMessage>().await.forget(event.channel_id, event.id);
+    }
+
+    pub async fn on_message_delete_bulk(&mut self, event: &MessageDeleteBulkDispatch) {
+        let mut markers = self.write::<MarkedMessage>().await;
 
-    pub async fn on_message_delete_bulk(&mut self, _event: &MessageDeleteBulkDispatch) {}
+        for id in &event.ids {
+            markers.forget(event.channel_id, *id);
+        }
+    }
 
     pub async fn on_reaction_add(&mut self, _event: &MessageReactionAddDispatch) {}
 
@@ -316,11 +727,23 @@ impl StorageContainer {
     pub async fn on_presence_update(&mut self, event: &PresenceUpdateDispatch) {
         let update = &event.0;
 
+        if self.policy.presences {
+            self.write::<PresenceUpdate>().await.insert(update.clone());
+        }
+
+        if self.policy.user_ttl.is_some() {
+            self.write::<User>().await.touch(update.user.id);
+            self.evict_users().await;
+        }
+
         let mut guilds = self.write::<Guild>().await;
         if let Some(guild) = guilds.get_mut(event.guild_id) {
             if let Some(member) = guild.members.get_mut(&update.user.id) {
                 member.roles = update.roles.clone();
-                member.premium_since = update.premium_since;
+
+                if update.premium_since.is_some() {
+                    member.premium_since = update.premium_since;
+                }
 
                 if let Some(nick) = &update.nick {
                     member.nick = nick.clone();
@@ -349,8 +772,7 @@ impl StorageContainer {
             }
         }
 
-        let mut users = self.write::<User>().await;
-        if let Some(current_user) = users.get_mut(user.id) {
+        self.write::<User>().await.update(user.id, |current_user| {
             current_user.username = user.username.clone();
             current_user.discriminator = user.discriminator.clone();
             current_user.avatar = user.avatar.clone();
@@ -361,12 +783,58 @@ impl StorageContainer {
             current_user.email = user.email.clone();
             current_user.flags = user.flags;
             current_user.premium_type = user.premium_type;
-        }
+        });
     }
 
-    pub async fn on_voice_state_update(&mut self, _event: &VoiceStateUpdateDispatch) {}
+    pub async fn on_voice_state_update(&mut self, event: &VoiceStateUpdateDispatch) {
+        if let (Some(guild), true) = (event.0.guild_id, self.policy.voice_states) {
+            self.write::<VoiceState>().await.insert(guild, event.0.clone());
+        }
+    }
 
     pub async fn on_voice_server_update(&mut self, _event: &VoiceServerUpdateDispatch) {}
 
     pub async fn on_webhooks_update(&mut self, _event: &WebhooksUpdateDispatch) {}
+
+    pub async fn on_raw(&mut self, _event: &RawDispatch) {}
+
+    pub async fn on_cache_resync(&mut self, _event: &CacheResyncDispatch) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_guild, fake_member};
+    use futures::executor::block_on;
+
+    #[test]
+    fn keeps_the_same_members() {
+        let mut storages = StorageContainer::for_use(5);
+        storages.policy = CachePolicy::default().max_members(2);
+
+        let ready: ReadyDispatch = serde_json::from_value(serde_json::json!({
+            "v": 8,
+            "user": {"id": "9", "username": "bot", "discriminator": "0001", "avatar": null},
+            "private_channels": [],
+            "guilds": [],
+            "session_id": "session",
+            "application": {"id": "9"},
+        })).unwrap();
+
+        let mut guild = fake_guild(1, 2, "guild");
+        for id in &[5, 1, 3, 9, 7] {
+            guild.members.insert(Snowflake(*id), fake_member(*id, vec![]));
+        }
+
+        block_on(async {
+            storages.on_ready(&ready).await;
+            storages.on_guild_create(&GuildCreateDispatch::from(guild)).await;
+
+            let guilds = storages.read::<Guild>().await;
+            let mut members: Vec<u64> = guilds.get(Snowflake(1)).members.keys().map(|id| id.0).collect();
+            members.sort();
+
+            assert_eq!(members, vec![1, 9]);
+        });
+    }
 }
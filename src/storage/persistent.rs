@@ -0,0 +1,82 @@
+use std::sync::Arc;
+use std::marker::PhantomData;
+use async_trait::async_trait;
+use crate::{Error, Snowflake};
+use crate::storage::Storage;
+
+/// A store living outside of the bot's memory such
+/// as a Redis or SQL database. Values stored in such a
+/// backend survive restarts and can be shared by shards
+/// running on different machines.
+///
+/// Implementations are responsible for serializing
+/// the values.
+#[async_trait]
+pub trait PersistentBackend<T>: Send + Sync {
+    /// Retrieves the value associated with the id.
+    async fn get(&self, id: Snowflake) -> Result<Option<T>, Error>;
+
+    /// Inserts or replaces the value associated with the id.
+    async fn put(&self, id: Snowflake, value: &T) -> Result<(), Error>;
+
+    /// Removes the value associated with the id.
+    async fn delete(&self, id: Snowflake) -> Result<(), Error>;
+
+    /// Retrieves all the values in the backend.
+    async fn scan(&self) -> Result<Vec<(Snowflake, T)>, Error>;
+}
+
+/// Storage that relays every call to a
+/// [PersistentBackend](automate::storage::PersistentBackend).
+///
+/// It holds no data itself and cloning it only clones a
+/// pointer to the backend, a [snapshot](automate::Context::snapshot)
+/// can thus be used to avoid holding the storage guard
+/// while waiting for the backend.
+///
+/// Registered using
+/// [Configuration::add_persistent_storage](automate::Configuration::add_persistent_storage).
+pub struct PersistentStorage<T> {
+    backend: Arc<dyn PersistentBackend<T>>,
+    _stored: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for PersistentStorage<T> {
+    fn clone(&self) -> Self {
+        PersistentStorage {
+            backend: Arc::clone(&self.backend),
+            _stored: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> Storage for PersistentStorage<T> {}
+
+impl<T> PersistentStorage<T> {
+    pub fn new(backend: Arc<dyn PersistentBackend<T>>) -> PersistentStorage<T> {
+        PersistentStorage {
+            backend,
+            _stored: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub async fn get(&self, id: Snowflake) -> Result<Option<T>, Error> {
+        self.backend.get(id).await
+    }
+
+    #[inline]
+    pub async fn put(&self, id: Snowflake, value: &T) -> Result<(), Error> {
+        self.backend.put(id, value).await
+    }
+
+    #[inline]
+    pub async fn delete(&self, id: Snowflake) -> Result<(), Error> {
+        self.backend.delete(id).await
+    }
+
+    #[inline]
+    pub async fn scan(&self) -> Result<Vec<(Snowflake, T)>, Error> {
+        self.backend.scan().await
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use crate::Snowflake;
+
+/// Defines what the caching storages keep in memory.
+/// By default, everything Discord sends is cached.
+///
+/// ```
+/// # use automate::Configuration;
+/// use automate::storage::CachePolicy;
+/// use std::time::Duration;
+///
+/// let config = Configuration::new("token")
+///     .cache_policy(CachePolicy::default()
+///         .presences(false)
+///         .max_members(1000)
+///         .user_ttl(Duration::from_secs(3600)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CachePolicy {
+    pub(crate) users: bool,
+    pub(crate) presences: bool,
+    pub(crate) voice_states: bool,
+    pub(crate) max_members: Option<usize>,
+    pub(crate) channel_guilds: Option<HashSet<Snowflake>>,
+    pub(crate) user_ttl: Option<Duration>,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy {
+            users: true,
+            presences: true,
+            voice_states: true,
+            max_members: None,
+            channel_guilds: None,
+            user_ttl: None,
+        }
+    }
+}
+
+impl CachePolicy {
+    /// Whether users should be cached in the user storage.
+    pub fn users(mut self, enabled: bool) -> Self {
+        self.users = enabled;
+        self
+    }
+
+    /// Whether presences should be cached in the presence storage.
+    pub fn presences(mut self, enabled: bool) -> Self {
+        self.presences = enabled;
+        self
+    }
+
+    /// Whether voice states should be cached in the voice state storage.
+    pub fn voice_states(mut self, enabled: bool) -> Self {
+        self.voice_states = enabled;
+        self
+    }
+
+    /// Maximum amount of members kept for each guild.
+    /// Members joining a guild that already reached
+    /// the limit are not cached. When a guild is received
+    /// with more members, the bot and the members with the
+    /// lowest ids are kept.
+    pub fn max_members(mut self, max: usize) -> Self {
+        self.max_members = Some(max);
+        self
+    }
+
+    /// Only caches the channels of the given guilds,
+    /// the channels of the other guilds are neither in
+    /// the channel storage nor in their guild object.
+    pub fn channels_for(mut self, guilds: Vec<Snowflake>) -> Self {
+        self.channel_guilds = Some(guilds.into_iter().collect());
+        self
+    }
+
+    /// Evicts the users that were not part of any
+    /// event for the given duration.
+    pub fn user_ttl(mut self, ttl: Duration) -> Self {
+        self.user_ttl = Some(ttl);
+        self
+    }
+
+    #[inline]
+    pub(crate) fn caches_channels(&self, guild: Option<Snowflake>) -> bool {
+        match (&self.channel_guilds, guild) {
+            (Some(guilds), Some(guild)) => guilds.contains(&guild),
+            _ => true
+        }
+    }
+}
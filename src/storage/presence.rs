@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::Snowflake;
+use crate::gateway::PresenceUpdate;
+use crate::storage::{Stored, Storage, Query};
+
+/// Last presence received for each user that is not
+/// offline in each guild shared with the bot. Requires
+/// the `GuildPresences` intent.
+#[derive(Default, Debug, Clone)]
+pub struct PresenceStorage {
+    presences: HashMap<(Snowflake, Snowflake), Arc<PresenceUpdate>>
+}
+
+impl Storage for PresenceStorage {}
+
+impl Stored for PresenceUpdate {
+    type Storage = PresenceStorage;
+}
+
+impl Query for PresenceStorage {
+    type Item = PresenceUpdate;
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PresenceUpdate> + '_> {
+        Box::new(self.presences.values().map(Arc::as_ref))
+    }
+}
+
+impl PresenceStorage {
+    pub fn all(&self) -> Vec<&PresenceUpdate> {
+        self.presences.values().map(Arc::as_ref).collect()
+    }
+
+    /// Presence of the user in the given guild or `None`
+    /// if the user is offline or was never seen.
+    pub fn get_opt(&self, guild: Snowflake, user: Snowflake) -> Option<&PresenceUpdate> {
+        self.presences.get(&(guild, user)).map(Arc::as_ref)
+    }
+
+    /// Status of the user in the given guild, `offline`
+    /// if the user is offline or was never seen.
+    pub fn status(&self, guild: Snowflake, user: Snowflake) -> &str {
+        self.get_opt(guild, user).map_or("offline", |p| p.status.as_str())
+    }
+
+    /// Whether the user is online in any guild.
+    #[inline]
+    pub fn is_online(&self, user: Snowflake) -> bool {
+        self.presences.keys().any(|(_, u)| *u == user)
+    }
+
+    /// Presences of the user in all the guilds.
+    pub fn user(&self, user: Snowflake) -> Vec<&PresenceUpdate> {
+        self.presences.iter()
+            .filter(|((_, u), _)| *u == user)
+            .map(|(_, p)| p.as_ref())
+            .collect()
+    }
+
+    /// Presences of the users in the given guild.
+    pub fn guild(&self, guild: Snowflake) -> Vec<&PresenceUpdate> {
+        self.presences.iter()
+            .filter(|((g, _), _)| *g == guild)
+            .map(|(_, p)| p.as_ref())
+            .collect()
+    }
+
+    /// Saves the presence or forgets the
+    /// user if they went offline.
+    pub(crate) fn insert(&mut self, presence: PresenceUpdate) {
+        let key = (presence.guild_id, presence.user.id);
+
+        if presence.status == "offline" {
+            self.presences.remove(&key);
+        } else {
+            self.presences.insert(key, Arc::new(presence));
+        }
+    }
+
+    pub(crate) fn remove(&mut self, guild: Snowflake, user: Snowflake) {
+        self.presences.remove(&(guild, user));
+    }
+
+    pub(crate) fn remove_guild(&mut self, guild: Snowflake) {
+        self.presences.retain(|(g, _), _| *g != guild);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fake_presence;
+
+    #[test]
+    fn presences_per_guild() {
+        let mut storage = PresenceStorage::default();
+        storage.insert(fake_presence(1, 10, "online"));
+        storage.insert(fake_presence(2, 10, "idle"));
+        storage.insert(fake_presence(2, 11, "dnd"));
+
+        assert_eq!(storage.status(Snowflake(1), Snowflake(10)), "online");
+        assert_eq!(storage.status(Snowflake(2), Snowflake(10)), "idle");
+        assert_eq!(storage.user(Snowflake(10)).len(), 2);
+
+        storage.insert(fake_presence(1, 10, "offline"));
+        assert_eq!(storage.status(Snowflake(1), Snowflake(10)), "offline");
+        assert!(storage.is_online(Snowflake(10)));
+
+        storage.remove_guild(Snowflake(2));
+        assert!(storage.all().is_empty());
+        assert!(!storage.is_online(Snowflake(10)));
+    }
+}
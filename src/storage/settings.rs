@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use crate::{Error, Snowflake};
+use crate::storage::{Stored, Storage, PersistentBackend};
+
+/// Configuration values of a guild such as the
+/// prefix of the commands or toggled features.
+/// Values are kept as JSON and deserialized
+/// when read.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    values: HashMap<String, Value>,
+}
+
+impl Settings {
+    /// The value of the key or `None` if it is not set
+    /// or can't be deserialized into `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.values.get(key).and_then(|v| serde_json::from_value(Value::clone(v)).ok())
+    }
+
+    /// The raw JSON value of the key.
+    pub fn get_raw(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+}
+
+/// A modification of the settings of a guild. The value
+/// is `None` when the key was removed.
+#[derive(Debug, Clone)]
+pub struct SettingChange {
+    pub guild_id: Snowflake,
+    pub key: String,
+    pub value: Option<Value>,
+}
+
+/// Settings of the guilds handled by the shard which can
+/// be modified at any time by the listeners.
+///
+/// When a [backend](automate::storage::PersistentBackend) is
+/// registered with
+/// [Configuration::persistent_settings](automate::Configuration::persistent_settings),
+/// the settings of a guild are loaded when the guild is
+/// received. Modifications return a [PendingSave](automate::storage::PendingSave)
+/// which saves the settings once the storage has been released.
+/// Modifications made while the settings of the guild are
+/// loading are only saved once the load is over.
+#[derive(Default)]
+pub struct SettingsStorage {
+    settings: HashMap<Snowflake, Settings>,
+    loading: HashMap<Snowflake, HashSet<String>>,
+    backend: Option<Arc<dyn PersistentBackend<Settings>>>,
+    subscribers: Vec<UnboundedSender<SettingChange>>,
+}
+
+impl Storage for SettingsStorage {}
+
+impl Stored for Settings {
+    type Storage = SettingsStorage;
+}
+
+impl SettingsStorage {
+    pub(crate) fn persistent(backend: Arc<dyn PersistentBackend<Settings>>) -> SettingsStorage {
+        SettingsStorage {
+            backend: Some(backend),
+            ..SettingsStorage::default()
+        }
+    }
+
+    /// All the settings of the guild.
+    pub fn guild(&self, guild: Snowflake) -> Option<&Settings> {
+        self.settings.get(&guild)
+    }
+
+    /// The value of the key in the given guild or `None` if
+    /// it is not set or can't be deserialized into `T`.
+    pub fn get<T: DeserializeOwned>(&self, guild: Snowflake, key: &str) -> Option<T> {
+        self.settings.get(&guild).and_then(|s| s.get(key))
+    }
+
+    /// The value of the key in the given guild or
+    /// the default value if it is not set.
+    pub fn get_or<T: DeserializeOwned>(&self, guild: Snowflake, key: &str, default: T) -> T {
+        self.get(guild, key).unwrap_or(default)
+    }
+
+    /// Sets the value of the key in the given guild. The
+    /// settings of the guild are saved in the backend
+    /// when the returned value is persisted.
+    pub fn set<K: Into<String>, T: Serialize>(&mut self, guild: Snowflake, key: K, value: T) -> Result<PendingSave, Error> {
+        let value = serde_json::to_value(value)?;
+        self.apply(guild, key.into(), Some(value));
+
+        Ok(self.pending(guild))
+    }
+
+    /// Removes the key from the settings of the guild. The
+    /// settings of the guild are saved in the backend
+    /// when the returned value is persisted.
+    pub fn remove(&mut self, guild: Snowflake, key: &str) -> PendingSave {
+        self.apply(guild, key.to_owned(), None);
+        self.pending(guild)
+    }
+
+    /// Stream of the modifications made to the
+    /// settings of the guilds of the shard.
+    pub fn subscribe(&mut self) -> UnboundedReceiver<SettingChange> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.subscribers.push(sender);
+
+        receiver
+    }
+
+    /// The backend to load the settings of the guild
+    /// from if they were not loaded yet. The guild is
+    /// considered loading until [loaded](SettingsStorage::loaded)
+    /// or [cancel_load](SettingsStorage::cancel_load) is called.
+    pub(crate) fn loader(&mut self, guild: Snowflake) -> Option<Arc<dyn PersistentBackend<Settings>>> {
+        if self.settings.contains_key(&guild) || self.loading.contains_key(&guild) {
+            return None;
+        }
+
+        let backend = self.backend.clone()?;
+        self.loading.insert(guild, HashSet::new());
+
+        Some(backend)
+    }
+
+    /// Adds the settings loaded from the backend, the keys
+    /// set or removed while they were loaded are kept as
+    /// they are. The returned value saves the merged settings
+    /// if they were modified during the load.
+    pub(crate) fn loaded(&mut self, guild: Snowflake, settings: Option<Settings>) -> PendingSave {
+        let modified = self.loading.remove(&guild).unwrap_or_default();
+        let current = self.settings.entry(guild).or_default();
+
+        for (key, value) in settings.map(|s| s.values).unwrap_or_default() {
+            if !modified.contains(&key) {
+                current.values.entry(key).or_insert(value);
+            }
+        }
+
+        if modified.is_empty() {
+            PendingSave {
+                guild,
+                save: None,
+            }
+        } else {
+            self.pending(guild)
+        }
+    }
+
+    /// Stops waiting for the settings of the guild when
+    /// they could not be loaded.
+    pub(crate) fn cancel_load(&mut self, guild: Snowflake) {
+        self.loading.remove(&guild);
+    }
+
+    fn apply(&mut self, guild: Snowflake, key: String, value: Option<Value>) {
+        let settings = self.settings.entry(guild).or_default();
+
+        if let Some(modified) = self.loading.get_mut(&guild) {
+            modified.insert(key.clone());
+        }
+
+        match &value {
+            Some(value) => settings.values.insert(key.clone(), Value::clone(value)),
+            None => settings.values.remove(&key),
+        };
+
+        let change = SettingChange {
+            guild_id: guild,
+            key,
+            value,
+        };
+
+        self.subscribers.retain(|s| s.unbounded_send(change.clone()).is_ok());
+    }
+
+    fn pending(&self, guild: Snowflake) -> PendingSave {
+        //saving before the load is over would replace
+        //the keys that were not loaded yet
+        let save = match (&self.backend, self.settings.get(&guild)) {
+            (Some(backend), Some(settings)) if !self.loading.contains_key(&guild) => Some((Arc::clone(backend), Settings::clone(settings))),
+            _ => None,
+        };
+
+        PendingSave {
+            guild,
+            save,
+        }
+    }
+}
+
+/// A copy of the settings of a guild after a modification,
+/// saved in the backend by [persist](automate::storage::PendingSave::persist)
+/// without keeping the storage locked.
+///
+/// ```
+/// # use automate::{Context, Error, Snowflake};
+/// # use automate::storage::Settings;
+/// async fn set_prefix(ctx: &Context<'_>, guild: Snowflake, prefix: &str) -> Result<(), Error> {
+///     let save = ctx.storage_mut::<Settings>().await.set(guild, "prefix", prefix)?;
+///     save.persist().await
+/// }
+/// ```
+#[must_use = "the settings are only saved in the backend once persisted"]
+pub struct PendingSave {
+    guild: Snowflake,
+    save: Option<(Arc<dyn PersistentBackend<Settings>>, Settings)>,
+}
+
+impl PendingSave {
+    /// Saves the settings in the backend, does
+    /// nothing if there is no backend.
+    pub async fn persist(self) -> Result<(), Error> {
+        match self.save {
+            Some((backend, settings)) => backend.put(self.guild, &settings).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Memory(Mutex<HashMap<Snowflake, Settings>>);
+
+    #[async_trait]
+    impl PersistentBackend<Settings> for Memory {
+        async fn get(&self, id: Snowflake) -> Result<Option<Settings>, Error> {
+            Ok(self.0.lock().unwrap().get(&id).cloned())
+        }
+
+        async fn put(&self, id: Snowflake, value: &Settings) -> Result<(), Error> {
+            self.0.lock().unwrap().insert(id, value.clone());
+            Ok(())
+        }
+
+        async fn delete(&self, id: Snowflake) -> Result<(), Error> {
+            self.0.lock().unwrap().remove(&id);
+            Ok(())
+        }
+
+        async fn scan(&self) -> Result<Vec<(Snowflake, Settings)>, Error> {
+            Ok(self.0.lock().unwrap().iter().map(|(id, s)| (*id, s.clone())).collect())
+        }
+    }
+
+    #[test]
+    fn persists_after_release() {
+        let guild = Snowflake(1);
+        let backend = Arc::new(Memory::default());
+        let mut storage = SettingsStorage::persistent(Arc::clone(&backend) as Arc<dyn PersistentBackend<Settings>>);
+
+        let save = storage.set(guild, "prefix", "!").unwrap();
+        assert!(backend.0.lock().unwrap().is_empty());
+
+        block_on(save.persist()).unwrap();
+        assert_eq!(backend.0.lock().unwrap()[&guild].get::<String>("prefix"), Some(String::from("!")));
+
+        //keys modified while the settings were loading are kept
+        //and only saved once the load is over
+        block_on(storage.set(guild, "color", "blue").unwrap().persist()).unwrap();
+
+        let mut storage = SettingsStorage::persistent(Arc::clone(&backend) as Arc<dyn PersistentBackend<Settings>>);
+        let loader = storage.loader(guild).unwrap();
+        assert!(storage.loader(guild).is_none());
+
+        block_on(storage.set(guild, "levels", true).unwrap().persist()).unwrap();
+        block_on(storage.remove(guild, "color").persist()).unwrap();
+        assert_eq!(backend.0.lock().unwrap()[&guild].get::<bool>("levels"), None);
+        assert_eq!(backend.0.lock().unwrap()[&guild].get::<String>("color"), Some(String::from("blue")));
+
+        let loaded = block_on(loader.get(guild)).unwrap();
+        let save = storage.loaded(guild, loaded);
+        assert_eq!(storage.get::<String>(guild, "prefix"), Some(String::from("!")));
+        assert_eq!(storage.get::<bool>(guild, "levels"), Some(true));
+        assert_eq!(storage.get::<String>(guild, "color"), None);
+        assert!(storage.loader(guild).is_none());
+
+        block_on(save.persist()).unwrap();
+        let saved = backend.0.lock().unwrap()[&guild].clone();
+        assert_eq!(saved.get::<String>("prefix"), Some(String::from("!")));
+        assert_eq!(saved.get::<bool>("levels"), Some(true));
+        assert_eq!(saved.get::<String>("color"), None);
+    }
+
+    #[test]
+    fn notifies_changes() {
+        let guild = Snowflake(1);
+        let mut storage = SettingsStorage::default();
+        let mut changes = storage.subscribe();
+
+        storage.apply(guild, String::from("prefix"), Some(Value::from("!")));
+        assert_eq!(storage.get::<String>(guild, "prefix"), Some(String::from("!")));
+        assert_eq!(storage.get::<u32>(guild, "prefix"), None);
+        assert!(!storage.get_or(guild, "levels", false));
+
+        storage.apply(guild, String::from("prefix"), None);
+        assert_eq!(storage.get::<String>(guild, "prefix"), None);
+
+        let change = block_on(changes.next()).unwrap();
+        assert_eq!(change.value, Some(Value::from("!")));
+
+        let change = block_on(changes.next()).unwrap();
+        assert_eq!((change.guild_id, change.key.as_str(), change.value), (guild, "prefix", None));
+    }
+}
@@ -0,0 +1,97 @@
+use std::any::Any;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Immutable copy of a storage shared by all the
+/// listeners which requested it while the storage
+/// was not modified.
+///
+/// Obtained with
+/// [Context::storage_snapshot](automate::Context::storage_snapshot)
+/// and dereferences to the storage.
+pub struct Snapshot<S> {
+    generation: u64,
+    storage: Arc<S>,
+}
+
+impl<S> Clone for Snapshot<S> {
+    fn clone(&self) -> Self {
+        Snapshot {
+            generation: self.generation,
+            storage: Arc::clone(&self.storage),
+        }
+    }
+}
+
+impl<S> Snapshot<S> {
+    /// Amount of times the storage had been modified
+    /// when the snapshot was taken. Two snapshots with
+    /// the same generation hold the same values.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl<S> Deref for Snapshot<S> {
+    type Target = S;
+
+    #[inline]
+    fn deref(&self) -> &S {
+        &self.storage
+    }
+}
+
+/// Counts the modifications of a storage and keeps
+/// the last snapshot taken to reuse it until the
+/// storage is modified again.
+#[derive(Default)]
+pub(crate) struct Generation {
+    counter: AtomicU64,
+    last: Mutex<Option<(u64, Arc<dyn Any + Send + Sync>)>>,
+}
+
+impl Generation {
+    pub(crate) fn increment(&self) {
+        self.counter.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// The last snapshot if the storage was not modified
+    /// since, else a new snapshot created from `copy`.
+    pub(crate) fn snapshot<S: Send + Sync + 'static, F: FnOnce() -> S>(&self, copy: F) -> Snapshot<S> {
+        let generation = self.counter.load(Ordering::Acquire);
+        let mut last = self.last.lock().unwrap();
+
+        if let Some((g, storage)) = &*last {
+            if *g == generation {
+                if let Ok(storage) = Arc::clone(storage).downcast::<S>() {
+                    return Snapshot { generation, storage };
+                }
+            }
+        }
+
+        let storage = Arc::new(copy());
+        *last = Some((generation, Arc::clone(&storage) as Arc<dyn Any + Send + Sync>));
+
+        Snapshot { generation, storage }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_snapshots() {
+        let generation = Generation::default();
+
+        let first = generation.snapshot(|| vec![1]);
+        let second = generation.snapshot(|| vec![2]);
+        assert_eq!((second.as_slice(), second.generation()), (&[1][..], 0));
+        assert!(Arc::ptr_eq(&first.storage, &second.storage));
+
+        generation.increment();
+        let third = generation.snapshot(|| vec![3]);
+        assert_eq!((third.as_slice(), third.generation()), (&[3][..], 1));
+    }
+}
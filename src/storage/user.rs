@@ -1,11 +1,15 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use crate::Snowflake;
 use crate::gateway::User;
-use crate::storage::{Stored, Storage};
+use crate::storage::{Stored, Storage, Query};
 
 #[derive(Default, Debug, Clone)]
 pub struct UserStorage {
-    users: HashMap<Snowflake, User>
+    users: HashMap<Snowflake, Arc<User>>,
+    seen: HashMap<Snowflake, Instant>,
+    tags: HashMap<String, Snowflake>,
 }
 
 impl Storage for UserStorage {}
@@ -14,9 +18,17 @@ impl Stored for User {
     type Storage = UserStorage;
 }
 
+impl Query for UserStorage {
+    type Item = User;
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &User> + '_> {
+        Box::new(self.users.values().map(Arc::as_ref))
+    }
+}
+
 impl UserStorage {
     pub fn all(&self) -> Vec<&User> {
-        self.users.values().collect()
+        self.users.values().map(Arc::as_ref).collect()
     }
 
     #[inline]
@@ -25,14 +37,120 @@ impl UserStorage {
     }
 
     pub fn get_opt(&self, id: Snowflake) -> Option<&User> {
-        self.users.get(&id)
+        self.users.get(&id).map(Arc::as_ref)
+    }
+
+    /// The user with the given name and
+    /// discriminator, such as `automate#0001`.
+    pub fn by_tag(&self, tag: &str) -> Option<&User> {
+        self.get_opt(*self.tags.get(tag)?)
     }
 
     pub(crate) fn get_mut(&mut self, id: Snowflake) -> Option<&mut User> {
-        self.users.get_mut(&id)
+        self.users.get_mut(&id).map(Arc::make_mut)
     }
 
     pub(crate) fn insert(&mut self, user: User) {
-        self.users.insert(user.id, user);
+        self.seen.insert(user.id, Instant::now());
+        self.tags.insert(user.tag(), user.id);
+
+        if let Some(previous) = self.users.insert(user.id, Arc::new(user)) {
+            self.untag(&previous);
+        }
+    }
+
+    /// Applies the modification to the user and
+    /// updates its tag if it changed.
+    pub(crate) fn update<F: FnOnce(&mut User)>(&mut self, id: Snowflake, update: F) {
+        if let Some(user) = self.users.get_mut(&id) {
+            let previous = user.tag();
+            let user = Arc::make_mut(user);
+            update(user);
+
+            let tag = user.tag();
+            if tag != previous {
+                if self.tags.get(&previous) == Some(&id) {
+                    self.tags.remove(&previous);
+                }
+
+                self.tags.insert(tag, id);
+            }
+        }
+    }
+
+    /// Removes the tag of the user from the index
+    /// unless another user took it since.
+    fn untag(&mut self, user: &User) {
+        let tag = user.tag();
+        if self.tags.get(&tag) == Some(&user.id) && self.users.get(&user.id).map_or(true, |u| u.tag() != tag) {
+            self.tags.remove(&tag);
+        }
+    }
+
+    /// Marks the user as seen in an event.
+    pub(crate) fn touch(&mut self, id: Snowflake) {
+        if self.users.contains_key(&id) {
+            self.seen.insert(id, Instant::now());
+        }
     }
-}
\ No newline at end of file
+
+    /// Removes the users that were not seen
+    /// since the given duration.
+    pub(crate) fn evict(&mut self, ttl: Duration) {
+        let seen = &mut self.seen;
+        seen.retain(|_, instant| instant.elapsed() < ttl);
+        self.users.retain(|id, _| seen.contains_key(id));
+
+        let users = &self.users;
+        self.tags.retain(|_, id| users.contains_key(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fake_user;
+
+    #[test]
+    fn tags_follow_renames() {
+        let mut storage = UserStorage::default();
+        storage.insert(fake_user(1, "alice"));
+        assert_eq!(storage.by_tag("alice#0001").unwrap().id, Snowflake(1));
+
+        storage.update(Snowflake(1), |user| user.username = String::from("bob"));
+        assert!(storage.by_tag("alice#0001").is_none());
+        assert_eq!(storage.by_tag("bob#0001").unwrap().id, Snowflake(1));
+
+        //the tag was taken by another user while the
+        //previous owner still had it in the storage
+        storage.insert(fake_user(2, "bob"));
+        storage.insert(fake_user(1, "carol"));
+        assert_eq!(storage.by_tag("bob#0001").unwrap().id, Snowflake(2));
+        assert_eq!(storage.by_tag("carol#0001").unwrap().id, Snowflake(1));
+
+        //inserting the same user again keeps its tag
+        storage.insert(fake_user(1, "carol"));
+        assert_eq!(storage.by_tag("carol#0001").unwrap().id, Snowflake(1));
+
+        storage.evict(Duration::from_secs(0));
+        assert!(storage.by_tag("bob#0001").is_none());
+        assert!(storage.tags.is_empty());
+    }
+
+    #[test]
+    fn queries() {
+        let mut storage = UserStorage::default();
+        storage.insert(fake_user(1, "alice"));
+        storage.insert(fake_user(2, "bob"));
+        storage.insert(fake_user(3, "bobby"));
+
+        assert_eq!(storage.count(), 3);
+        assert_eq!(storage.count_where(|user| user.username.starts_with("bob")), 2);
+        assert_eq!(storage.find(|user| user.username == "alice").unwrap().id, Snowflake(1));
+        assert!(storage.find(|user| user.username == "carol").is_none());
+
+        let mut ids: Vec<Snowflake> = storage.filter(|user| user.id != Snowflake(1)).iter().map(|user| user.id).collect();
+        ids.sort_by_key(|id| id.0);
+        assert_eq!(ids, vec![Snowflake(2), Snowflake(3)]);
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::Snowflake;
+use crate::gateway::VoiceState;
+use crate::storage::{Stored, Storage, Query};
+
+/// Voice states of the users connected to a voice
+/// channel. Requires the `GuildVoiceStates` intent.
+#[derive(Default, Debug, Clone)]
+pub struct VoiceStateStorage {
+    states: HashMap<(Snowflake, Snowflake), Arc<VoiceState>>
+}
+
+impl Storage for VoiceStateStorage {}
+
+impl Stored for VoiceState {
+    type Storage = VoiceStateStorage;
+}
+
+impl Query for VoiceStateStorage {
+    type Item = VoiceState;
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &VoiceState> + '_> {
+        Box::new(self.states.values().map(Arc::as_ref))
+    }
+}
+
+impl VoiceStateStorage {
+    pub fn all(&self) -> Vec<&VoiceState> {
+        self.states.values().map(Arc::as_ref).collect()
+    }
+
+    /// Voice state of the user in the given guild or `None`
+    /// if the user is not in a voice channel of the guild.
+    pub fn get_opt(&self, guild: Snowflake, user: Snowflake) -> Option<&VoiceState> {
+        self.states.get(&(guild, user)).map(Arc::as_ref)
+    }
+
+    /// Voice channel the user is connected
+    /// to in the given guild.
+    pub fn channel(&self, guild: Snowflake, user: Snowflake) -> Option<Snowflake> {
+        self.get_opt(guild, user).and_then(|s| s.channel_id)
+    }
+
+    /// Voice states of the users connected
+    /// to the given voice channel.
+    pub fn in_channel(&self, channel: Snowflake) -> Vec<&VoiceState> {
+        self.states.values()
+            .filter(|s| s.channel_id == Some(channel))
+            .map(Arc::as_ref)
+            .collect()
+    }
+
+    /// Voice states of the users connected to
+    /// a voice channel of the given guild.
+    pub fn guild(&self, guild: Snowflake) -> Vec<&VoiceState> {
+        self.states.iter()
+            .filter(|((g, _), _)| *g == guild)
+            .map(|(_, s)| s.as_ref())
+            .collect()
+    }
+
+    /// Saves the voice state or removes it if
+    /// the user left the voice channel.
+    pub(crate) fn insert(&mut self, guild: Snowflake, state: VoiceState) {
+        if state.channel_id.is_some() {
+            self.states.insert((guild, state.user_id), Arc::new(state));
+        } else {
+            self.states.remove(&(guild, state.user_id));
+        }
+    }
+
+    pub(crate) fn remove_guild(&mut self, guild: Snowflake) {
+        self.states.retain(|(g, _), _| *g != guild);
+    }
+}
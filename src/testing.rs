@@ -0,0 +1,334 @@
+//! Tools to call listeners directly in unit tests.
+//!
+//! The [ContextBuilder](automate::testing::ContextBuilder)
+//! creates the [Context](automate::Context) given to the
+//! listeners and records the commands they send to the
+//! gateway. Requests to the HTTP API can be intercepted
+//! with an [HttpClient](automate::http::HttpClient).
+//!
+//! ```
+//! use automate::{listener, Context, Error};
+//! use automate::gateway::{MessageCreateDispatch, UpdateStatus, StatusType};
+//! use automate::testing::{ContextBuilder, fake_message, fake_user};
+//!
+//! #[listener]
+//! async fn go_idle(ctx: &Context, data: &MessageCreateDispatch) -> Result<(), Error> {
+//!     if data.0.content == "!idle" {
+//!         ctx.update_status(UpdateStatus {
+//!             since: None,
+//!             game: None,
+//!             status: StatusType::Idle,
+//!             afk: true,
+//!         }).await?;
+//!     }
+//!
+//!     Ok(())
+//! }
+//!
+//! # automate::tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let mut builder = ContextBuilder::new();
+//! let message = fake_message(1, 2, fake_user(3, "user"), "!idle");
+//!
+//! go_idle(&builder.build(), &MessageCreateDispatch(message)).await.unwrap();
+//! assert_eq!(builder.commands().len(), 1);
+//! # });
+//! ```
+
+use crate::{Configuration, Context, HttpAPI, Snowflake};
+use crate::gateway::{Instruction, InstructionSender, InstructionReceiver, CommandBudget, User, Message, Guild, GuildChannel, Channel, ChannelType, Role, GuildMember, PresenceUpdate};
+use tktungstenite::tungstenite::Message as TkMessage;
+use serde_json::{json, Value};
+
+#[cfg(feature = "storage")]
+use crate::gateway::{GuildCreateDispatch, VoiceState};
+#[cfg(feature = "storage")]
+use crate::storage::{Settings, MarkedMessage};
+#[cfg(feature = "storage")]
+use std::any::TypeId;
+
+/// Owns what the [Context](automate::Context) given to
+/// the listeners borrows and records the commands sent
+/// to the gateway through it.
+pub struct ContextBuilder {
+    config: Configuration,
+    http: HttpAPI,
+    bot: User,
+    sender: InstructionSender,
+    receiver: InstructionReceiver,
+    budget: CommandBudget,
+}
+
+impl ContextBuilder {
+    /// Creates a builder with a bot named `bot`
+    /// and an HTTP API sending requests to Discord.
+    pub fn new() -> ContextBuilder {
+        ContextBuilder::with_config(Configuration::new("token"))
+    }
+
+    /// Creates a builder with the storages and the
+    /// HTTP API defined in the given configuration.
+    pub fn with_config(config: Configuration) -> ContextBuilder {
+        #[allow(unused_mut)]
+        let mut config = config.clone();
+
+        #[cfg(feature = "storage")]
+        {
+            config.storages.initialize::<Guild>();
+            config.storages.initialize::<Channel>();
+            config.storages.initialize::<User>();
+            config.storages.initialize::<PresenceUpdate>();
+            config.storages.initialize::<VoiceState>();
+
+            if !config.storages.contains(TypeId::of::<Settings>()) {
+                config.storages.initialize::<Settings>();
+            }
+
+            if !config.storages.contains(TypeId::of::<MarkedMessage>()) {
+                config.storages.initialize::<MarkedMessage>();
+            }
+        }
+
+        let (sender, receiver) = InstructionSender::channel(config.command_queue_capacity, config.backpressure);
+
+        ContextBuilder {
+            http: HttpAPI::for_config(&config),
+            config,
+            bot: fake_user(0, "bot"),
+            sender,
+            receiver,
+            budget: CommandBudget::default(),
+        }
+    }
+
+    /// Sets the user returned by [Context::bot](automate::Context::bot).
+    pub fn bot(mut self, bot: User) -> Self {
+        self.bot = bot;
+        self
+    }
+
+    /// Sets the HTTP API used by the context, usually one
+    /// created with [HttpAPI::with_client](automate::HttpAPI::with_client).
+    pub fn http(mut self, http: HttpAPI) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Adds the guild, its channels and its members
+    /// to the storages as if it had been received
+    /// from the gateway.
+    #[cfg(feature = "storage")]
+    pub async fn guild(&mut self, guild: Guild) {
+        self.config.storages.on_guild_create(&GuildCreateDispatch::from(guild)).await;
+    }
+
+    pub fn build(&self) -> Context<'_> {
+        Context {
+            sender: &self.sender,
+            budget: &self.budget,
+            #[cfg(feature = "storage")]
+            storage: &self.config.storages,
+            http: &self.http,
+            metrics: &self.config.metrics,
+            collectors: &self.config.collectors,
+            shard: [self.config.shard_id.unwrap_or(0), self.config.total_shards.unwrap_or(1)],
+            bot: &self.bot,
+        }
+    }
+
+    /// The JSON payloads of the commands sent to the
+    /// gateway since the last call to this function.
+    pub fn commands(&mut self) -> Vec<String> {
+        let mut commands = Vec::new();
+
+        while let Some(instruction) = self.receiver.try_recv() {
+            match instruction {
+                Instruction::Send(TkMessage::Text(payload), _) => commands.push(payload),
+                Instruction::Presence(TkMessage::Text(payload)) => commands.push(payload),
+                _ => (),
+            }
+        }
+
+        commands
+    }
+}
+
+impl Default for ContextBuilder {
+    fn default() -> Self {
+        ContextBuilder::new()
+    }
+}
+
+pub fn fake_user<S: Into<Snowflake>>(id: S, username: &str) -> User {
+    serde_json::from_value(json!({
+        "id": id.into().0.to_string(),
+        "username": username,
+        "discriminator": "0001",
+        "avatar": null,
+    })).expect("Failed to create fake user")
+}
+
+/// A message sent in a guild channel by the given author.
+pub fn fake_message<S: Into<Snowflake>>(id: S, channel: S, author: User, content: &str) -> Message {
+    let mut message: Message = serde_json::from_value(message_json(id, channel, content)).expect("Failed to create fake message");
+
+    message.author = author;
+    message
+}
+
+/// The JSON sent by Discord for a [fake_message](automate::testing::fake_message)
+/// sent by a user with the id 0.
+pub(crate) fn message_json<S: Into<Snowflake>>(id: S, channel: S, content: &str) -> Value {
+    json!({
+        "id": id.into().0.to_string(),
+        "channel_id": channel.into().0.to_string(),
+        "author": {"id": "0", "username": "", "discriminator": "0000", "avatar": null},
+        "content": content,
+        "timestamp": "2021-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+/// A channel of a guild without any permission overwrite,
+/// threads and private channels are not supported.
+pub fn fake_channel<S: Into<Snowflake>>(id: S, guild: S, kind: ChannelType, parent: Option<S>, position: i32) -> GuildChannel {
+    let channel: Channel = serde_json::from_value(channel_json(id, guild, kind, parent, position)).expect("Failed to create fake channel");
+    GuildChannel::from_channel(&channel).unwrap()
+}
+
+/// The JSON sent by Discord for a [fake_channel](automate::testing::fake_channel).
+pub(crate) fn channel_json<S: Into<Snowflake>>(id: S, guild: S, kind: ChannelType, parent: Option<S>, position: i32) -> Value {
+    let mut channel = json!({
+        "id": id.into().0.to_string(),
+        "type": kind as u8,
+        "guild_id": guild.into().0.to_string(),
+        "position": position,
+        "name": "channel",
+        "permission_overwrites": [],
+    });
+
+    let fields = match kind {
+        ChannelType::GuildCategory => json!({}),
+        ChannelType::GuildText => json!({"topic": null, "last_message_id": null, "rate_limit_per_user": 0}),
+        ChannelType::GuildNews => json!({"topic": null, "last_message_id": null}),
+        ChannelType::GuildVoice | ChannelType::GuildStageVoice => json!({"bitrate": 64000, "user_limit": 0}),
+        ChannelType::GuildStore => json!({}),
+        _ => panic!("Fake channels can not be threads or private channels"),
+    };
+
+    let object = channel.as_object_mut().unwrap();
+    object.extend(fields.as_object().unwrap().clone());
+
+    if !matches!(kind, ChannelType::GuildCategory) {
+        object.insert(String::from("parent_id"), json!(parent.map(|p| p.into().0.to_string())));
+    }
+
+    channel
+}
+
+/// A guild without any role, channel or member.
+pub fn fake_guild<S: Into<Snowflake>>(id: S, owner: S, name: &str) -> Guild {
+    serde_json::from_value(guild_json(id, owner, name)).expect("Failed to create fake guild")
+}
+
+/// The JSON sent by Discord for a [fake_guild](automate::testing::fake_guild).
+pub(crate) fn guild_json<S: Into<Snowflake>>(id: S, owner: S, name: &str) -> Value {
+    json!({
+        "id": id.into().0.to_string(),
+        "name": name,
+        "icon": null,
+        "splash": null,
+        "discovery_splash": null,
+        "owner_id": owner.into().0.to_string(),
+        "region": "europe",
+        "afk_channel_id": null,
+        "afk_timeout": 300,
+        "verification_level": 0,
+        "default_message_notifications": 0,
+        "explicit_content_filter": 0,
+        "roles": [],
+        "emojis": [],
+        "features": [],
+        "mfa_level": 0,
+        "application_id": null,
+        "vanity_url_code": null,
+        "description": null,
+        "banner": null,
+        "premium_tier": 0,
+        "preferred_locale": "en-US",
+        "rules_channel_id": null,
+        "public_updates_channel_id": null,
+    })
+}
+
+/// A role without any tag or icon.
+pub fn fake_role<S: Into<Snowflake>>(id: S, position: i32, permissions: u32) -> Role {
+    serde_json::from_value(json!({
+        "id": id.into().0.to_string(),
+        "name": "role",
+        "color": 0,
+        "hoist": false,
+        "position": position,
+        "permissions": permissions,
+        "managed": false,
+        "mentionable": false,
+    })).expect("Failed to create fake role")
+}
+
+/// A member named `user` with the given roles.
+pub fn fake_member<S: Into<Snowflake>>(id: S, roles: Vec<S>) -> GuildMember {
+    let roles: Vec<String> = roles.into_iter().map(|role| role.into().0.to_string()).collect();
+
+    serde_json::from_value(json!({
+        "user": {"id": id.into().0.to_string(), "username": "user", "discriminator": "0001", "avatar": null},
+        "roles": roles,
+        "joined_at": "2020-01-01T00:00:00+00:00",
+        "deaf": false,
+        "mute": false,
+    })).expect("Failed to create fake member")
+}
+
+/// The presence of a user in a guild without any activity.
+pub fn fake_presence<S: Into<Snowflake>>(guild: S, user: S, status: &str) -> PresenceUpdate {
+    serde_json::from_value(json!({
+        "user": {"id": user.into().0.to_string()},
+        "roles": [],
+        "game": null,
+        "guild_id": guild.into().0.to_string(),
+        "status": status,
+        "activities": [],
+        "client_status": {},
+    })).expect("Failed to create fake presence")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fakes_deserialize() {
+        let message = fake_message(1, 2, fake_user(3, "user"), "hello");
+        assert_eq!(message.author.username, "user");
+        assert_eq!(message.content, "hello");
+        assert!(message.content_available());
+        assert!(!fake_message(1, 2, fake_user(3, "user"), "").content_available());
+
+        let guild = fake_guild(4, 3, "guild");
+        assert_eq!(guild.owner_id, Snowflake(3));
+
+        let voice = fake_channel(5, 4, ChannelType::GuildVoice, Some(6), 2);
+        assert!(matches!(voice, GuildChannel::Voice(_)));
+        assert_eq!((voice.parent_id(), voice.position()), (Some(Snowflake(6)), Some(2)));
+
+        assert_eq!(fake_role(7, 1, 8).permissions, 8);
+        assert_eq!(fake_member(3, vec![7]).roles, vec![Snowflake(7)]);
+        assert_eq!(fake_presence(4, 3, "idle").guild_id, Snowflake(4));
+    }
+}